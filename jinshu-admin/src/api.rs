@@ -0,0 +1,232 @@
+use crate::role::Role;
+use jinshu_database::user::Model as UserModel;
+use jinshu_utils::secret::Secret;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// HTTP 路由
+pub mod route {
+    /// 管理员登录
+    pub const SIGN_IN: &str = "/sign_in";
+    /// 管理员登出
+    pub const SIGN_OUT: &str = "/sign_out";
+    /// 用户列表及搜索
+    pub const USERS: &str = "/users";
+    /// 用户详情
+    pub const USER: &str = "/users/:id";
+    /// 用户会话
+    pub const USER_SESSION: &str = "/users/:id/session";
+    /// 踢人
+    pub const USER_KICK: &str = "/users/:id/kick";
+    /// 封禁/解封
+    pub const USER_BAN: &str = "/users/:id/ban";
+    /// 最近消息
+    pub const MESSAGES: &str = "/messages";
+    /// 内容审核标记的消息
+    pub const FLAGGED_MESSAGES: &str = "/messages/flagged";
+    /// 复审完成，删除标记
+    pub const FLAGGED_MESSAGE: &str = "/messages/flagged/:id";
+    /// 已注册的服务
+    pub const SERVICES: &str = "/services";
+    /// 管理员列表及创建
+    pub const ADMINS: &str = "/admins";
+    /// 删除管理员
+    pub const ADMIN: &str = "/admins/:id";
+    /// 应用列表及创建
+    pub const APPS: &str = "/apps";
+    /// 修改/删除应用
+    pub const APP: &str = "/apps/:id";
+    /// 重置应用密钥
+    pub const APP_SECRET: &str = "/apps/:id/secret";
+}
+
+/// 管理员登录请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SignInParam {
+    /// 用户名
+    pub username: String,
+    /// 密码
+    pub password: Secret,
+}
+
+/// 管理员登录返回结果
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SignInResult {
+    /// 令牌，请求时放在 `Authorization: Bearer <token>` 中
+    pub token: Uuid,
+    /// 角色
+    pub role: Role,
+    /// 过期时间
+    pub expire: u64,
+}
+
+fn default_offset() -> u64 {
+    0
+}
+
+fn default_limit() -> u64 {
+    20
+}
+
+/// 用户列表查询参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserQuery {
+    /// 应用 ID，为空时查询所有应用的用户
+    pub app_id: Option<String>,
+    /// 按锦书用户 ID 或外系统用户 ID 模糊搜索
+    pub keyword: Option<String>,
+    /// 偏移
+    #[serde(default = "default_offset")]
+    pub offset: u64,
+    /// 个数
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+}
+
+/// 用户详情
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserDetail {
+    /// 用户信息
+    pub user: UserModel,
+    /// 用户所在的 Comet 服务，不在线时为 `None`
+    pub session: Option<String>,
+    /// 封禁信息，未封禁时为 `None`
+    pub ban: Option<BanInfo>,
+}
+
+/// 用户会话
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SessionInfo {
+    /// 锦书用户 ID
+    pub user_id: Uuid,
+    /// 用户所在的 Comet 服务，不在线时为 `None`
+    pub comet: Option<String>,
+}
+
+/// 踢人返回结果
+#[derive(Debug, Deserialize, Serialize)]
+pub struct KickResult {
+    /// 用户是否在线并已被断开连接
+    pub kicked: bool,
+}
+
+/// 封禁请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BanParam {
+    /// 封禁原因
+    pub reason: String,
+    /// 封禁时长（秒），为空表示永久封禁
+    pub duration_sec: Option<usize>,
+}
+
+/// 封禁信息
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BanInfo {
+    /// 封禁原因
+    pub reason: String,
+    /// 操作的管理员
+    pub operator: String,
+    /// 封禁时间
+    pub create_time: u64,
+    /// 解封时间，为空表示永久封禁
+    pub expire: Option<u64>,
+}
+
+/// 消息查询参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MessageQuery {
+    /// 应用 ID，为空时查询所有应用的消息
+    pub app_id: Option<String>,
+    /// 发送方或接收方的锦书用户 ID，为空时查询所有消息
+    pub user_id: Option<Uuid>,
+    /// 个数
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+}
+
+/// 标记消息查询参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FlaggedQuery {
+    /// 偏移
+    #[serde(default = "default_offset")]
+    pub offset: u64,
+    /// 个数
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+}
+
+/// 服务信息
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ServiceInfo {
+    /// 服务名
+    pub name: String,
+    /// 服务实例
+    pub instances: Vec<ServiceInstance>,
+}
+
+/// 服务实例
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ServiceInstance {
+    /// 注册键
+    pub key: String,
+    /// 服务地址
+    pub uri: String,
+}
+
+/// 创建管理员请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateAdminParam {
+    /// 用户名
+    pub username: String,
+    /// 密码
+    pub password: Secret,
+    /// 角色
+    pub role: Role,
+}
+
+/// 管理员信息
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminInfo {
+    /// 管理员 ID
+    pub id: Uuid,
+    /// 用户名
+    pub username: String,
+    /// 角色
+    pub role: Role,
+}
+
+/// 创建应用请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateAppParam {
+    /// 应用名
+    pub name: String,
+}
+
+/// 修改应用请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateAppParam {
+    /// 应用名，为空时不修改
+    pub name: Option<String>,
+    /// 是否启用，为空时不修改
+    pub enabled: Option<bool>,
+}
+
+/// 应用信息
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AppInfo {
+    /// 应用 ID
+    pub id: String,
+    /// 应用名
+    pub name: String,
+    /// 是否启用
+    pub enabled: bool,
+}
+
+/// 应用凭证，只在创建应用及重置密钥时返回
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AppCredential {
+    /// 应用 ID
+    pub id: String,
+    /// 应用密钥
+    pub secret: Secret,
+}
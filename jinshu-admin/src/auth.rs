@@ -0,0 +1,155 @@
+use crate::role::Role;
+use axum::async_trait;
+use axum::extract::{Extension, FromRequest, RequestParts};
+use axum::http::header::AUTHORIZATION;
+use deadpool_redis::redis::{self, AsyncCommands};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use uuid::Uuid;
+
+/// 构造存储管理员令牌时使用的键
+pub fn get_admin_token_key<D: Display>(token: D) -> String {
+    format!("admin:token:{}", token)
+}
+
+/// 构造存储管理员所有登录令牌的集合时使用的键
+pub fn get_admin_tokens_key(id: Uuid) -> String {
+    format!("admin:tokens:{}", id.as_simple())
+}
+
+/// 保存管理员的登录令牌，并将令牌加入该管理员的令牌集合
+pub async fn save_admin_token(
+    conn: &mut deadpool_redis::Connection,
+    admin: &AdminUser,
+    validity_sec: usize,
+) -> crate::Result<()> {
+    let tokens_key = get_admin_tokens_key(admin.id);
+    let _: () = redis::pipe()
+        .atomic()
+        .set_ex(
+            get_admin_token_key(admin.token.as_simple()),
+            serde_json::to_string(admin)?,
+            validity_sec,
+        )
+        .ignore()
+        .sadd(&tokens_key, admin.token.as_simple().to_string())
+        .ignore()
+        .expire(&tokens_key, validity_sec)
+        .ignore()
+        .query_async(conn)
+        .await?;
+    Ok(())
+}
+
+/// 删除管理员的登录令牌
+pub async fn remove_admin_token(
+    conn: &mut deadpool_redis::Connection,
+    admin: &AdminUser,
+) -> crate::Result<()> {
+    let _: () = redis::pipe()
+        .atomic()
+        .del(get_admin_token_key(admin.token.as_simple()))
+        .ignore()
+        .srem(
+            get_admin_tokens_key(admin.id),
+            admin.token.as_simple().to_string(),
+        )
+        .ignore()
+        .query_async(conn)
+        .await?;
+    Ok(())
+}
+
+/// 吊销管理员的所有登录令牌，返回吊销的令牌数
+pub async fn revoke_admin_tokens(
+    conn: &mut deadpool_redis::Connection,
+    id: Uuid,
+) -> crate::Result<usize> {
+    let tokens_key = get_admin_tokens_key(id);
+    let tokens: Vec<String> = conn.smembers(&tokens_key).await?;
+
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    for token in &tokens {
+        pipe.del(get_admin_token_key(token)).ignore();
+    }
+    let _: () = pipe.del(&tokens_key).ignore().query_async(conn).await?;
+
+    Ok(tokens.len())
+}
+
+/// 已登录的管理员
+///
+/// 从请求头 `Authorization: Bearer <token>` 中读取令牌，并从 Redis 中查询对应的管理员
+///
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AdminUser {
+    /// 管理员 ID
+    pub id: Uuid,
+    /// 用户名
+    pub username: String,
+    /// 角色
+    pub role: Role,
+    /// 登录令牌
+    pub token: Uuid,
+}
+
+impl AdminUser {
+    /// 检查管理员是否拥有 `role` 的权限
+    pub fn require(&self, role: Role) -> crate::Result<()> {
+        if self.role >= role {
+            Ok(())
+        } else {
+            tracing::warn!(admin = %self.username, role = %self.role, required = %role, "Permission denied");
+            Err(crate::Error::Forbidden)
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for AdminUser {
+    type Rejection = crate::Error;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Extension(redis) = Extension::<deadpool_redis::Pool>::from_request(req)
+            .await
+            .map_err(|e| crate::Error::Other(e.to_string().into()))?;
+
+        let token: Uuid = req
+            .headers()
+            .and_then(|headers| headers.get(AUTHORIZATION))
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .and_then(|v| v.trim().parse().ok())
+            .ok_or(crate::Error::Unauthorized)?;
+
+        let mut conn = redis.get().await?;
+        let value: Option<String> = conn.get(get_admin_token_key(token.as_simple())).await?;
+
+        match value {
+            Some(value) => Ok(serde_json::from_str(&value)?),
+            None => Err(crate::Error::Unauthorized),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AdminUser;
+    use crate::role::Role;
+    use uuid::Uuid;
+
+    #[test]
+    fn require() {
+        let admin = AdminUser {
+            id: Uuid::new_v4(),
+            username: "operator".into(),
+            role: Role::Operator,
+            token: Uuid::new_v4(),
+        };
+
+        assert!(admin.require(Role::Viewer).is_ok());
+        assert!(admin.require(Role::Operator).is_ok());
+        assert!(admin.require(Role::Admin).is_err());
+    }
+}
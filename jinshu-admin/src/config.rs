@@ -0,0 +1,88 @@
+use jinshu_utils::secret::Secret;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Admin 的配置
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminConfig {
+    /// 监听的 IP 地址
+    pub ip: IpAddr,
+
+    /// 监听的端口号
+    pub port: u16,
+
+    /// Comet 服务名，用于踢人
+    pub comet_name: String,
+
+    /// 在服务查询接口中展示的服务名
+    pub service_names: Vec<String>,
+
+    /// 管理员令牌有效期（秒）
+    pub token_validity_sec: usize,
+
+    /// 初始管理员，没有任何管理员账号时自动创建
+    pub root: RootAdminConfig,
+
+    /// 初始应用，没有任何应用时自动创建
+    #[serde(default)]
+    pub app: Option<InitialAppConfig>,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            ip: [0u8, 0, 0, 0].into(),
+            port: 9800,
+            comet_name: "comet".into(),
+            service_names: vec![
+                "receiver".into(),
+                "authorizer".into(),
+                "comet".into(),
+                "distributor".into(),
+                "timer".into(),
+            ],
+            token_validity_sec: 3600,
+            root: RootAdminConfig::default(),
+            app: None,
+        }
+    }
+}
+
+/// 初始管理员配置
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RootAdminConfig {
+    /// 用户名
+    pub username: String,
+    /// 密码
+    pub password: Secret,
+}
+
+impl Default for RootAdminConfig {
+    fn default() -> Self {
+        Self {
+            username: "admin".into(),
+            password: Secret::new("1qaz2wsx"),
+        }
+    }
+}
+
+/// 初始应用配置
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InitialAppConfig {
+    /// 应用 ID
+    pub id: String,
+    /// 应用名
+    pub name: String,
+    /// 应用密钥
+    pub secret: Secret,
+}
+
+#[cfg(test)]
+mod test {
+    use super::AdminConfig;
+
+    #[test]
+    fn default() {
+        AdminConfig::default();
+    }
+}
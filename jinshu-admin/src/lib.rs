@@ -1 +1,19 @@
+#![deny(missing_docs, unsafe_code)]
+//! # Admin
+//!
+//! 管理端，提供用户管理、会话查询、踢人及封禁、消息查询、服务查询等运维接口
+//!
+//! 管理员账号与锦书用户相互独立，按角色控制权限
+//!
 
+mod api;
+/// 管理员鉴权
+pub mod auth;
+/// 配置
+pub mod config;
+mod error;
+/// 管理员角色
+pub mod role;
+
+pub use api::*;
+pub use error::*;
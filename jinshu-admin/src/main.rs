@@ -1,3 +1,809 @@
-fn main() {
-    println!("Hello, world!");
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::SaltString;
+use argon2::{PasswordHash, PasswordHasher, PasswordVerifier};
+use axum::extract::{Extension, Json, Path, Query};
+use axum::http::StatusCode;
+use axum::routing::{delete, get, post, put};
+use axum::Router;
+use deadpool_redis::redis::AsyncCommands;
+use jinshu_admin::auth::{remove_admin_token, revoke_admin_tokens, save_admin_token, AdminUser};
+use jinshu_admin::config::AdminConfig;
+use jinshu_admin::role::Role;
+use jinshu_admin::{
+    route, AdminInfo, AppCredential, AppInfo, BanInfo, BanParam, CreateAdminParam, CreateAppParam,
+    Error, FlaggedQuery, KickResult, MessageQuery, ServiceInfo, ServiceInstance, SessionInfo,
+    SignInParam, SignInResult, UpdateAppParam, UserDetail, UserQuery,
+};
+use jinshu_common::Config;
+use jinshu_database::config::DatabaseConfig;
+use jinshu_database::message::Model as MessageModel;
+use jinshu_database::prelude::*;
+use jinshu_database::user::Model as UserModel;
+use jinshu_database::{admin, app, message, user};
+use jinshu_redis::config::RedisConfig;
+use jinshu_redis::moderation::{FlaggedMessage, FlaggedStore};
+use jinshu_redis::revocation::{RevocationStore, DEFAULT_RETENTION_MS};
+use jinshu_redis::session::SessionStore;
+use jinshu_redis::{get_ban_key, get_refresh_key, get_sign_in_key};
+use jinshu_rpc::comet::comet_client::CometClient;
+use jinshu_rpc::comet::KickRequest;
+use jinshu_rpc::registry::etcd::{EtcdConfig, EtcdRegistry};
+use jinshu_rpc::registry::Registry;
+use jinshu_tracing::config::TracingConfig;
+use jinshu_utils::secret::Secret;
+use jinshu_utils::{current_millisecond, shutdown_signal};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, Database, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::transport::Uri;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+struct Conf {
+    tracing: TracingConfig,
+    etcd: EtcdConfig,
+    admin: AdminConfig,
+    database: DatabaseConfig,
+    redis: RedisConfig,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let conf = Conf::from_cli()?;
+
+    let _tracer = conf.tracing.init("admin")?;
+
+    let Conf {
+        etcd,
+        admin,
+        database,
+        redis,
+        ..
+    } = conf;
+
+    tracing::info!(?etcd);
+    let registry = Arc::new(EtcdRegistry::new(&etcd).await?);
+
+    tracing::info!(?database);
+    let database = Database::connect(database).await?;
+
+    tracing::info!(?redis);
+    let redis_config: deadpool_redis::Config = redis.into();
+    let redis = redis_config.builder()?.build()?;
+    let session_store = SessionStore::from_pool(redis.clone());
+    let flagged_store = FlaggedStore::from_pool(redis.clone());
+
+    create_root_admin(&database, &admin).await?;
+    create_initial_app(&database, &admin).await?;
+
+    let addr = SocketAddr::new(admin.ip, admin.port);
+
+    let app = Router::new()
+        .route(route::SIGN_IN, post(sign_in))
+        .route(route::SIGN_OUT, post(sign_out))
+        .route(route::USERS, get(list_users))
+        .route(route::USER, get(retrieve_user))
+        .route(route::USER_SESSION, get(retrieve_session))
+        .route(route::USER_KICK, post(kick_user))
+        .route(route::USER_BAN, post(ban_user).delete(unban_user))
+        .route(route::MESSAGES, get(list_messages))
+        .route(route::FLAGGED_MESSAGES, get(list_flagged_messages))
+        .route(route::FLAGGED_MESSAGE, delete(review_flagged_message))
+        .route(route::SERVICES, get(list_services))
+        .route(route::ADMINS, get(list_admins).post(create_admin))
+        .route(route::ADMIN, delete(delete_admin))
+        .route(route::APPS, get(list_apps).post(create_app))
+        .route(route::APP, put(update_app).delete(delete_app))
+        .route(route::APP_SECRET, post(reset_app_secret))
+        .layer(Extension(database))
+        .layer(Extension(redis))
+        .layer(Extension(session_store))
+        .layer(Extension(flagged_store))
+        .layer(Extension(registry))
+        .layer(Extension(Arc::new(admin)))
+        .layer(tower_http::trace::TraceLayer::new_for_http());
+
+    tracing::info!(%addr, "jinshu-admin is started.");
+
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    Ok(())
+}
+
+async fn hash_password(password: Secret) -> Result<String, Error> {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        argon2::Argon2::default()
+            .hash_password(password.expose().as_bytes(), &salt)
+            .map(|h| h.to_string())
+    })
+    .await
+    .map_err(|e| Error::Other(e.to_string().into()))?
+    .map_err(|e| Error::Other(e.to_string().into()))
+}
+
+/// 没有任何管理员账号时，使用配置创建初始管理员
+async fn create_root_admin(db: &DatabaseConnection, config: &AdminConfig) -> anyhow::Result<()> {
+    if Admin::find().one(db).await?.is_some() {
+        return Ok(());
+    }
+
+    let model = admin::ActiveModel {
+        id: Set(Uuid::new_v4().as_simple().to_string()),
+        username: Set(config.root.username.clone()),
+        password: Set(hash_password(config.root.password.clone()).await?),
+        role: Set(Role::Admin.to_string()),
+        ..Default::default()
+    };
+    model.insert(db).await?;
+
+    tracing::info!(username = %config.root.username, "Root admin is created.");
+    Ok(())
+}
+
+/// 没有任何应用时，使用配置创建初始应用
+async fn create_initial_app(db: &DatabaseConnection, config: &AdminConfig) -> anyhow::Result<()> {
+    let initial = match &config.app {
+        Some(initial) => initial,
+        None => return Ok(()),
+    };
+
+    if App::find().one(db).await?.is_some() {
+        return Ok(());
+    }
+
+    let model = app::ActiveModel {
+        id: Set(initial.id.clone()),
+        name: Set(initial.name.clone()),
+        secret: Set(initial.secret.expose().to_string()),
+        enabled: Set(true),
+        ..Default::default()
+    };
+    model.insert(db).await?;
+
+    tracing::info!(id = %initial.id, "Initial app is created.");
+    Ok(())
+}
+
+/// 生成应用密钥
+fn generate_app_secret() -> Secret {
+    Secret::new(format!(
+        "{}{}",
+        Uuid::new_v4().as_simple(),
+        Uuid::new_v4().as_simple()
+    ))
+}
+
+fn app_info(model: app::Model) -> AppInfo {
+    AppInfo {
+        id: model.id,
+        name: model.name,
+        enabled: model.enabled,
+    }
+}
+
+fn admin_info(model: admin::Model) -> Result<AdminInfo, Error> {
+    Ok(AdminInfo {
+        id: model
+            .id
+            .parse()
+            .map_err(|e: uuid::Error| Error::Other(e.to_string().into()))?,
+        username: model.username,
+        role: model.role.parse()?,
+    })
+}
+
+#[tracing::instrument(skip_all)]
+async fn sign_in(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(redis): Extension<deadpool_redis::Pool>,
+    Extension(config): Extension<Arc<AdminConfig>>,
+    Json(param): Json<SignInParam>,
+) -> Result<Json<SignInResult>, Error> {
+    tracing::info!(?param);
+
+    let model = Admin::find()
+        .filter(admin::Column::Username.eq(param.username.as_str()))
+        .one(&db)
+        .await?
+        .ok_or(Error::Unauthorized)?;
+
+    let hash = model.password.clone();
+    let password = param.password;
+    let valid = tokio::task::spawn_blocking(move || match PasswordHash::new(&hash) {
+        Ok(hash) => argon2::Argon2::default()
+            .verify_password(password.expose().as_bytes(), &hash)
+            .is_ok(),
+        Err(e) => {
+            tracing::error!(%e, "Invalid password hash");
+            false
+        }
+    })
+    .await
+    .map_err(|e| Error::Other(e.to_string().into()))?;
+
+    if !valid {
+        return Err(Error::Unauthorized);
+    }
+
+    let info = admin_info(model)?;
+    let token = Uuid::new_v4();
+    let admin = AdminUser {
+        id: info.id,
+        username: info.username,
+        role: info.role,
+        token,
+    };
+
+    let mut conn = redis.get().await?;
+    save_admin_token(&mut conn, &admin, config.token_validity_sec).await?;
+
+    tracing::info!(username = %admin.username, "Admin signed in");
+
+    Ok(Json(SignInResult {
+        token,
+        role: admin.role,
+        expire: current_millisecond() + config.token_validity_sec as u64 * 1000,
+    }))
+}
+
+#[tracing::instrument(skip_all)]
+async fn sign_out(
+    Extension(redis): Extension<deadpool_redis::Pool>,
+    admin: AdminUser,
+) -> Result<StatusCode, Error> {
+    let mut conn = redis.get().await?;
+    remove_admin_token(&mut conn, &admin).await?;
+    tracing::info!(username = %admin.username, "Admin signed out");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[tracing::instrument(skip_all)]
+async fn list_users(
+    Extension(db): Extension<DatabaseConnection>,
+    admin: AdminUser,
+    Query(query): Query<UserQuery>,
+) -> Result<Json<Vec<UserModel>>, Error> {
+    admin.require(Role::Viewer)?;
+    tracing::info!(?query);
+
+    let mut select = User::find();
+    if let Some(app_id) = query.app_id.as_deref() {
+        select = select.filter(user::Column::AppId.eq(app_id));
+    }
+    if let Some(keyword) = query.keyword.as_deref().filter(|k| !k.is_empty()) {
+        select = select.filter(
+            Condition::any()
+                .add(user::Column::Id.contains(keyword))
+                .add(user::Column::ExternalId.contains(keyword)),
+        );
+    }
+
+    let users = select
+        .order_by_desc(user::Column::CreateTime)
+        .offset(query.offset)
+        .limit(query.limit.min(1000))
+        .all(&db)
+        .await?;
+
+    Ok(Json(users))
+}
+
+/// 查询用户所属的应用 ID
+async fn user_app_id(db: &DatabaseConnection, user_id: Uuid) -> Result<String, Error> {
+    Ok(User::find_by_id(user_id.as_simple().to_string())
+        .one(db)
+        .await?
+        .ok_or_else(|| Error::NotFound("User".into()))?
+        .app_id)
+}
+
+async fn load_ban(
+    redis: &deadpool_redis::Pool,
+    app_id: &str,
+    user_id: Uuid,
+) -> Result<Option<BanInfo>, Error> {
+    let mut conn = redis.get().await?;
+    let value: Option<String> = conn.get(get_ban_key(app_id, user_id.as_simple())).await?;
+    Ok(match value {
+        Some(value) => Some(serde_json::from_str(&value)?),
+        None => None,
+    })
+}
+
+#[tracing::instrument(skip_all)]
+async fn retrieve_user(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(redis): Extension<deadpool_redis::Pool>,
+    Extension(session_store): Extension<SessionStore>,
+    admin: AdminUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<UserDetail>, Error> {
+    admin.require(Role::Viewer)?;
+    tracing::info!(%user_id);
+
+    let user = User::find_by_id(user_id.as_simple().to_string())
+        .one(&db)
+        .await?
+        .ok_or_else(|| Error::NotFound("User".into()))?;
+
+    let session = session_store.load(&user.app_id, user_id).await?;
+    let ban = load_ban(&redis, &user.app_id, user_id).await?;
+    Ok(Json(UserDetail { user, session, ban }))
+}
+
+#[tracing::instrument(skip_all)]
+async fn retrieve_session(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(session_store): Extension<SessionStore>,
+    admin: AdminUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<SessionInfo>, Error> {
+    admin.require(Role::Viewer)?;
+    tracing::info!(%user_id);
+
+    let app_id = user_app_id(&db, user_id).await?;
+    Ok(Json(SessionInfo {
+        user_id,
+        comet: session_store.load(&app_id, user_id).await?,
+    }))
+}
+
+/// 使令牌失效，并断开用户在 Comet 上的连接
+async fn kick(
+    redis: &deadpool_redis::Pool,
+    session_store: &SessionStore,
+    registry: &EtcdRegistry,
+    comet_name: &str,
+    app_id: &str,
+    user_id: Uuid,
+) -> Result<bool, Error> {
+    let mut conn = redis.get().await?;
+    let _: () = conn
+        .del(&[
+            get_sign_in_key(app_id, user_id.as_simple()),
+            get_refresh_key(app_id, user_id.as_simple()),
+        ])
+        .await?;
+
+    // 同时吊销该用户已签发的签名令牌
+    let now = current_millisecond();
+    RevocationStore::from_pool(redis.clone())
+        .revoke_user(app_id, user_id, now, now + DEFAULT_RETENTION_MS)
+        .await?;
+
+    let key = match session_store.load(app_id, user_id).await? {
+        Some(key) => key,
+        None => return Ok(false),
+    };
+
+    let endpoints = registry
+        .discover::<Vec<(String, Uri)>>(comet_name)
+        .await
+        .map_err(|e| Error::Other(e.to_string().into()))?;
+
+    let uri = match endpoints.into_iter().find(|(k, _)| *k == key) {
+        Some((_, uri)) => uri,
+        None => {
+            tracing::warn!(%user_id, %key, "Comet is offline, remove the session");
+            session_store.remove(app_id, user_id).await?;
+            return Ok(false);
+        }
+    };
+
+    let mut client = CometClient::connect(uri)
+        .await
+        .map_err(|e| Error::Other(e.to_string().into()))?;
+    let result = client
+        .kick(KickRequest {
+            user_id: user_id.as_bytes().to_vec(),
+            app_id: app_id.to_string(),
+        })
+        .await?
+        .into_inner();
+
+    Ok(result.ok)
+}
+
+#[tracing::instrument(skip_all)]
+async fn kick_user(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(redis): Extension<deadpool_redis::Pool>,
+    Extension(session_store): Extension<SessionStore>,
+    Extension(registry): Extension<Arc<EtcdRegistry>>,
+    Extension(config): Extension<Arc<AdminConfig>>,
+    admin: AdminUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<KickResult>, Error> {
+    admin.require(Role::Operator)?;
+
+    let app_id = user_app_id(&db, user_id).await?;
+    let kicked = kick(
+        &redis,
+        &session_store,
+        &registry,
+        &config.comet_name,
+        &app_id,
+        user_id,
+    )
+    .await?;
+
+    tracing::info!(admin = %admin.username, %user_id, kicked, "Kick user");
+    Ok(Json(KickResult { kicked }))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip_all)]
+async fn ban_user(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(redis): Extension<deadpool_redis::Pool>,
+    Extension(session_store): Extension<SessionStore>,
+    Extension(registry): Extension<Arc<EtcdRegistry>>,
+    Extension(config): Extension<Arc<AdminConfig>>,
+    admin: AdminUser,
+    Path(user_id): Path<Uuid>,
+    Json(param): Json<BanParam>,
+) -> Result<Json<BanInfo>, Error> {
+    admin.require(Role::Operator)?;
+    tracing::info!(%user_id, ?param);
+
+    let now = current_millisecond();
+    let ban = BanInfo {
+        reason: param.reason,
+        operator: admin.username.clone(),
+        create_time: now,
+        expire: param.duration_sec.map(|sec| now + sec as u64 * 1000),
+    };
+
+    let app_id = user_app_id(&db, user_id).await?;
+    let key = get_ban_key(&app_id, user_id.as_simple());
+    let value = serde_json::to_string(&ban)?;
+    let mut conn = redis.get().await?;
+    let _: () = match param.duration_sec {
+        Some(sec) => conn.set_ex(key, value, sec).await?,
+        None => conn.set(key, value).await?,
+    };
+
+    // 签名令牌模式下 Authorizer 不检查封禁名单，由踢下线时写入的吊销记录拒绝已签发的令牌
+    let kicked = kick(
+        &redis,
+        &session_store,
+        &registry,
+        &config.comet_name,
+        &app_id,
+        user_id,
+    )
+    .await?;
+
+    tracing::info!(admin = %admin.username, %user_id, kicked, "Ban user");
+    Ok(Json(ban))
+}
+
+#[tracing::instrument(skip_all)]
+async fn unban_user(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(redis): Extension<deadpool_redis::Pool>,
+    admin: AdminUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    admin.require(Role::Operator)?;
+
+    let app_id = user_app_id(&db, user_id).await?;
+    let mut conn = redis.get().await?;
+    let removed: usize = conn.del(get_ban_key(&app_id, user_id.as_simple())).await?;
+
+    tracing::info!(admin = %admin.username, %user_id, removed, "Unban user");
+    if removed > 0 {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound("Ban".into()))
+    }
+}
+
+#[tracing::instrument(skip_all)]
+async fn list_messages(
+    Extension(db): Extension<DatabaseConnection>,
+    admin: AdminUser,
+    Query(query): Query<MessageQuery>,
+) -> Result<Json<Vec<MessageModel>>, Error> {
+    admin.require(Role::Viewer)?;
+    tracing::info!(?query);
+
+    let mut select = Message::find();
+    if let Some(app_id) = query.app_id.as_deref() {
+        select = select.filter(message::Column::AppId.eq(app_id));
+    }
+    if let Some(user_id) = query.user_id {
+        let user_id = user_id.as_simple().to_string();
+        select = select.filter(
+            Condition::any()
+                .add(message::Column::From.eq(user_id.as_str()))
+                .add(message::Column::To.eq(user_id.as_str())),
+        );
+    }
+
+    let messages = select
+        .order_by_desc(message::Column::Timestamp)
+        .limit(query.limit.min(1000))
+        .all(&db)
+        .await?;
+
+    Ok(Json(messages))
+}
+
+#[tracing::instrument(skip_all)]
+async fn list_flagged_messages(
+    Extension(flagged_store): Extension<FlaggedStore>,
+    admin: AdminUser,
+    Query(query): Query<FlaggedQuery>,
+) -> Result<Json<Vec<FlaggedMessage>>, Error> {
+    admin.require(Role::Viewer)?;
+    tracing::info!(?query);
+
+    let messages = flagged_store
+        .list(query.offset as usize, query.limit.min(1000) as usize)
+        .await?;
+
+    Ok(Json(messages))
+}
+
+#[tracing::instrument(skip_all)]
+async fn review_flagged_message(
+    Extension(flagged_store): Extension<FlaggedStore>,
+    admin: AdminUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    admin.require(Role::Operator)?;
+
+    let removed = flagged_store.remove(id).await?;
+
+    tracing::info!(admin = %admin.username, %id, removed, "Review flagged message");
+    if removed {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound("Flagged message".into()))
+    }
+}
+
+#[tracing::instrument(skip_all)]
+async fn list_services(
+    Extension(registry): Extension<Arc<EtcdRegistry>>,
+    Extension(config): Extension<Arc<AdminConfig>>,
+    admin: AdminUser,
+) -> Result<Json<Vec<ServiceInfo>>, Error> {
+    admin.require(Role::Viewer)?;
+
+    let mut services = Vec::with_capacity(config.service_names.len());
+    for name in &config.service_names {
+        let endpoints = registry
+            .discover::<Vec<(String, Uri)>>(name)
+            .await
+            .map_err(|e| Error::Other(e.to_string().into()))?;
+
+        services.push(ServiceInfo {
+            name: name.clone(),
+            instances: endpoints
+                .into_iter()
+                .map(|(key, uri)| ServiceInstance {
+                    key,
+                    uri: uri.to_string(),
+                })
+                .collect(),
+        });
+    }
+
+    Ok(Json(services))
+}
+
+#[tracing::instrument(skip_all)]
+async fn list_admins(
+    Extension(db): Extension<DatabaseConnection>,
+    admin: AdminUser,
+) -> Result<Json<Vec<AdminInfo>>, Error> {
+    admin.require(Role::Admin)?;
+
+    let admins = Admin::find()
+        .order_by_asc(admin::Column::CreateTime)
+        .all(&db)
+        .await?
+        .into_iter()
+        .map(admin_info)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(admins))
+}
+
+#[tracing::instrument(skip_all)]
+async fn create_admin(
+    Extension(db): Extension<DatabaseConnection>,
+    admin: AdminUser,
+    Json(param): Json<CreateAdminParam>,
+) -> Result<(StatusCode, Json<AdminInfo>), Error> {
+    admin.require(Role::Admin)?;
+    tracing::info!(?param);
+
+    if param.username.is_empty() {
+        return Err(Error::BadRequest("Username is empty".into()));
+    }
+
+    let exists = Admin::find()
+        .filter(admin::Column::Username.eq(param.username.as_str()))
+        .one(&db)
+        .await?
+        .is_some();
+    if exists {
+        return Err(Error::BadRequest("Username already exists".into()));
+    }
+
+    let id = Uuid::new_v4();
+    let model = admin::ActiveModel {
+        id: Set(id.as_simple().to_string()),
+        username: Set(param.username.clone()),
+        password: Set(hash_password(param.password).await?),
+        role: Set(param.role.to_string()),
+        ..Default::default()
+    };
+    model.insert(&db).await?;
+
+    tracing::info!(admin = %admin.username, username = %param.username, role = %param.role, "Admin created");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(AdminInfo {
+            id,
+            username: param.username,
+            role: param.role,
+        }),
+    ))
+}
+
+#[tracing::instrument(skip_all)]
+async fn delete_admin(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(redis): Extension<deadpool_redis::Pool>,
+    admin: AdminUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    admin.require(Role::Admin)?;
+
+    if id == admin.id {
+        return Err(Error::BadRequest("Can not delete yourself".into()));
+    }
+
+    let result = Admin::delete_by_id(id.as_simple().to_string())
+        .exec(&db)
+        .await?;
+
+    // 已删除的管理员不能继续使用之前登录获得的令牌
+    let mut conn = redis.get().await?;
+    let revoked = revoke_admin_tokens(&mut conn, id).await?;
+
+    tracing::info!(admin = %admin.username, %id, rows = result.rows_affected, revoked, "Admin deleted");
+    if result.rows_affected > 0 {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound("Admin".into()))
+    }
+}
+
+#[tracing::instrument(skip_all)]
+async fn list_apps(
+    Extension(db): Extension<DatabaseConnection>,
+    admin: AdminUser,
+) -> Result<Json<Vec<AppInfo>>, Error> {
+    admin.require(Role::Admin)?;
+
+    let apps = App::find()
+        .order_by_asc(app::Column::CreateTime)
+        .all(&db)
+        .await?
+        .into_iter()
+        .map(app_info)
+        .collect();
+
+    Ok(Json(apps))
+}
+
+#[tracing::instrument(skip_all)]
+async fn create_app(
+    Extension(db): Extension<DatabaseConnection>,
+    admin: AdminUser,
+    Json(param): Json<CreateAppParam>,
+) -> Result<(StatusCode, Json<AppCredential>), Error> {
+    admin.require(Role::Admin)?;
+    tracing::info!(?param);
+
+    if param.name.is_empty() {
+        return Err(Error::BadRequest("App name is empty".into()));
+    }
+
+    let id = Uuid::new_v4().as_simple().to_string();
+    let secret = generate_app_secret();
+    let model = app::ActiveModel {
+        id: Set(id.clone()),
+        name: Set(param.name.clone()),
+        secret: Set(secret.expose().to_string()),
+        enabled: Set(true),
+        ..Default::default()
+    };
+    model.insert(&db).await?;
+
+    tracing::info!(admin = %admin.username, %id, name = %param.name, "App created");
+
+    Ok((StatusCode::CREATED, Json(AppCredential { id, secret })))
+}
+
+async fn find_app(db: &DatabaseConnection, id: &str) -> Result<app::Model, Error> {
+    App::find_by_id(id.to_string())
+        .one(db)
+        .await?
+        .ok_or_else(|| Error::NotFound("App".into()))
+}
+
+#[tracing::instrument(skip_all)]
+async fn update_app(
+    Extension(db): Extension<DatabaseConnection>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+    Json(param): Json<UpdateAppParam>,
+) -> Result<Json<AppInfo>, Error> {
+    admin.require(Role::Admin)?;
+    tracing::info!(%id, ?param);
+
+    let mut model: app::ActiveModel = find_app(&db, &id).await?.into();
+    if let Some(name) = param.name {
+        if name.is_empty() {
+            return Err(Error::BadRequest("App name is empty".into()));
+        }
+        model.name = Set(name);
+    }
+    if let Some(enabled) = param.enabled {
+        model.enabled = Set(enabled);
+    }
+    let model = model.update(&db).await?;
+
+    tracing::info!(admin = %admin.username, %id, enabled = model.enabled, "App updated");
+    Ok(Json(app_info(model)))
+}
+
+#[tracing::instrument(skip_all)]
+async fn delete_app(
+    Extension(db): Extension<DatabaseConnection>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, Error> {
+    admin.require(Role::Admin)?;
+
+    let result = App::delete_by_id(id.clone()).exec(&db).await?;
+
+    tracing::info!(admin = %admin.username, %id, rows = result.rows_affected, "App deleted");
+    if result.rows_affected > 0 {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound("App".into()))
+    }
+}
+
+#[tracing::instrument(skip_all)]
+async fn reset_app_secret(
+    Extension(db): Extension<DatabaseConnection>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<Json<AppCredential>, Error> {
+    admin.require(Role::Admin)?;
+
+    let secret = generate_app_secret();
+    let mut model: app::ActiveModel = find_app(&db, &id).await?.into();
+    model.secret = Set(secret.expose().to_string());
+    model.update(&db).await?;
+
+    tracing::info!(admin = %admin.username, %id, "App secret reset");
+    Ok(Json(AppCredential { id, secret }))
 }
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// 管理员角色，权限由低到高排列，高权限角色拥有低权限角色的全部权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// 只读，可以查询用户、会话、消息及服务
+    Viewer,
+    /// 运维，可以踢人及封禁用户
+    Operator,
+    /// 超级管理员，可以管理管理员账号
+    Admin,
+}
+
+impl Role {
+    /// 角色名
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Role::Viewer),
+            "operator" => Ok(Role::Operator),
+            "admin" => Ok(Role::Admin),
+            _ => Err(crate::Error::BadRequest(
+                format!("Invalid role: {}", s).into(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Role;
+
+    #[test]
+    fn role() {
+        assert!(Role::Admin > Role::Operator);
+        assert!(Role::Operator > Role::Viewer);
+
+        for role in [Role::Viewer, Role::Operator, Role::Admin] {
+            assert_eq!(role.to_string().parse::<Role>().unwrap(), role);
+        }
+        assert!("root".parse::<Role>().is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use jinshu_database::message::Model as MessageModel;
+use sea_orm::JsonValue;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// HTTP 路由
+pub mod route {
+    /// 个人资料
+    pub const PROFILE: &str = "/profile";
+    /// 会话列表
+    pub const CONVERSATIONS: &str = "/conversations";
+    /// 会话的历史消息
+    pub const CONVERSATION_MESSAGES: &str = "/conversations/:peer_id/messages";
+    /// 标记会话已读
+    pub const CONVERSATION_READ: &str = "/conversations/:peer_id/read";
+    /// 会话设置（免打扰、置顶）
+    pub const CONVERSATION_SETTING: &str = "/conversations/:peer_id/setting";
+    /// 未读数
+    pub const UNREAD: &str = "/unread";
+    /// 用户设置
+    pub const SETTINGS: &str = "/settings";
+    /// 发布自己的身份公钥
+    pub const IDENTITY_KEYS: &str = "/keys";
+    /// 删除自己的身份公钥
+    pub const IDENTITY_KEY: &str = "/keys/:key_id";
+    /// 查询同一应用内用户的身份公钥
+    pub const USER_IDENTITY_KEYS: &str = "/users/:id/keys";
+}
+
+pub use jinshu_common::header;
+
+fn default_offset() -> u64 {
+    0
+}
+
+fn default_limit() -> u64 {
+    20
+}
+
+/// 更新个人资料请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateProfileParam {
+    /// 扩展字段
+    pub extension: Option<JsonValue>,
+}
+
+/// 分页查询参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PageQuery {
+    /// 偏移
+    #[serde(default = "default_offset")]
+    pub offset: u64,
+    /// 个数
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+}
+
+/// 会话信息
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ConversationInfo {
+    /// 对方的锦书用户 ID 或群组 ID
+    pub peer_id: Uuid,
+    /// 是否为群组会话
+    pub group: bool,
+    /// 最后一条消息
+    pub last_message: Option<MessageModel>,
+    /// 未读数
+    pub unread: u64,
+    /// 是否免打扰
+    pub muted: bool,
+    /// 是否置顶
+    pub pinned: bool,
+}
+
+/// 历史消息查询参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HistoryQuery {
+    /// 只查询该时间（毫秒）之前的消息，为空时从最新的消息开始查询
+    pub before: Option<u64>,
+    /// 个数
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+}
+
+/// 标记已读请求参数
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ReadParam {
+    /// 已读到该时间（毫秒），为空时使用当前时间
+    pub timestamp: Option<u64>,
+}
+
+/// 会话设置
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ConversationSetting {
+    /// 是否免打扰，为空时不修改
+    pub muted: Option<bool>,
+    /// 是否置顶，为空时不修改
+    pub pinned: Option<bool>,
+}
+
+/// 未读数
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UnreadCount {
+    /// 总未读数，不包含免打扰的会话
+    pub total: u64,
+    /// 各会话的未读数，只包含有未读消息的会话
+    pub conversations: HashMap<Uuid, u64>,
+}
+
+/// 发布身份公钥请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PublishKeyParam {
+    /// 公钥 ID，同一用户内唯一，已存在时覆盖
+    pub key_id: String,
+    /// 密钥算法标识，如 `x25519`
+    pub algorithm: String,
+    /// 公钥，服务端不解析，通常为 Base64 编码
+    pub public_key: String,
+}
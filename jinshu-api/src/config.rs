@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Api 的配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiConfig {
+    /// 监听的 IP 地址
+    pub ip: IpAddr,
+
+    /// 监听的端口号
+    pub port: u16,
+
+    /// 要消费的 Authorizer 服务名
+    pub authorizer_name: String,
+
+    /// 单次查询的最大条数
+    pub max_limit: u64,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            ip: [0u8, 0, 0, 0].into(),
+            port: 9500,
+            authorizer_name: "authorizer".into(),
+            max_limit: 100,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ApiConfig;
+
+    #[test]
+    fn default() {
+        let config = ApiConfig::default();
+        assert_eq!(config.port, 9500);
+        assert_eq!(config.authorizer_name, "authorizer");
+        assert!(config.max_limit > 0);
+    }
+}
@@ -0,0 +1,71 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::borrow::Cow;
+
+/// Api 服务错误
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// 数据库错误
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+    /// JSON 错误
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// 未登录或令牌无效
+    #[error("Unauthorized")]
+    Unauthorized,
+    /// 没有权限
+    #[error("Forbidden")]
+    Forbidden,
+    /// 资源不存在
+    #[error("{0} not found")]
+    NotFound(Cow<'static, str>),
+    /// 请求参数不合法
+    #[error("{0}")]
+    BadRequest(Cow<'static, str>),
+    /// 其他错误
+    #[error("{0}")]
+    Other(Cow<'static, str>),
+}
+
+impl Error {
+    /// 错误对应的 HTTP 状态码
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Error::Unauthorized => StatusCode::UNAUTHORIZED,
+            Error::Forbidden => StatusCode::FORBIDDEN,
+            Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::BadRequest(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        (self.status_code(), self.to_string()).into_response()
+    }
+}
+
+/// Api 服务结果
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod test {
+    use super::Error;
+    use axum::http::StatusCode;
+
+    #[test]
+    fn status_code() {
+        assert_eq!(Error::Unauthorized.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(Error::Forbidden.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            Error::NotFound("user".into()).status_code(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            Error::Other("".into()).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -1 +1,15 @@
+#![deny(missing_docs, unsafe_code)]
+//! # Api
+//!
+//! 面向客户端的 HTTP 接口，使用登录令牌鉴权，提供个人资料、会话列表、历史消息、未读数及用户设置
+//!
 
+mod api;
+/// 配置
+pub mod config;
+mod error;
+/// 接口实现
+pub mod service;
+
+pub use api::*;
+pub use error::*;
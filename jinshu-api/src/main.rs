@@ -1,3 +1,228 @@
-fn main() {
-    println!("Hello, world!");
+use axum::extract::{Extension, Json, Path, Query};
+use axum::http::StatusCode;
+use axum::routing::{delete, get, post, put};
+use axum::Router;
+use jinshu_api::config::ApiConfig;
+use jinshu_api::service::ApiService;
+use jinshu_api::{
+    route, ConversationInfo, ConversationSetting, HistoryQuery, PageQuery, PublishKeyParam,
+    ReadParam, Result, UnreadCount, UpdateProfileParam,
+};
+use jinshu_common::auth::AuthorizedUser;
+use jinshu_common::Config;
+use jinshu_database::config::DatabaseConfig;
+use jinshu_database::identity_key::Model as IdentityKeyModel;
+use jinshu_database::message::Model as MessageModel;
+use jinshu_database::user::Model as UserModel;
+use jinshu_rpc::authorizer::authorizer_client::AuthorizerClient;
+use jinshu_rpc::registry::etcd::{EtcdConfig, EtcdRegistry};
+use jinshu_rpc::registry::Registry;
+use jinshu_tracing::config::TracingConfig;
+use jinshu_utils::{current_millisecond, shutdown_signal};
+use sea_orm::{Database, JsonValue};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+struct Conf {
+    tracing: TracingConfig,
+    etcd: EtcdConfig,
+    database: DatabaseConfig,
+    api: ApiConfig,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let conf = Conf::from_cli()?;
+
+    let _tracer = conf.tracing.init("api")?;
+
+    let Conf {
+        etcd,
+        database,
+        api,
+        ..
+    } = conf;
+
+    tracing::info!(?etcd);
+    let registry = EtcdRegistry::new(&etcd).await?;
+
+    let (authorizer_channel, ak) = registry.discover_channel(&api.authorizer_name).await?;
+    let authorizer = AuthorizerClient::new(authorizer_channel);
+
+    tracing::info!(?database);
+    let database = Database::connect(database).await?;
+    let service = ApiService::new(&api, database);
+
+    let app = Router::new()
+        .route(route::PROFILE, get(profile).put(update_profile))
+        .route(route::CONVERSATIONS, get(conversations))
+        .route(route::CONVERSATION_MESSAGES, get(history))
+        .route(route::CONVERSATION_READ, post(read))
+        .route(
+            route::CONVERSATION_SETTING,
+            put(update_conversation_setting),
+        )
+        .route(route::UNREAD, get(unread))
+        .route(route::SETTINGS, get(settings).put(update_settings))
+        .route(route::IDENTITY_KEYS, put(publish_identity_key))
+        .route(route::IDENTITY_KEY, delete(delete_identity_key))
+        .route(route::USER_IDENTITY_KEYS, get(identity_keys))
+        .layer(Extension(service))
+        .layer(Extension(authorizer))
+        .layer(tower_http::trace::TraceLayer::new_for_http());
+
+    let addr = SocketAddr::new(api.ip, api.port);
+
+    tracing::info!(%addr, "jinshu-api is started.");
+
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    ak.close().await??;
+    tracing::info!("Service keeper closed.");
+
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+async fn profile(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+) -> Result<Json<UserModel>> {
+    tracing::info!(%user_id);
+    Ok(Json(service.profile(user_id).await?))
+}
+
+#[tracing::instrument(skip_all)]
+async fn update_profile(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+    Json(param): Json<UpdateProfileParam>,
+) -> Result<Json<UserModel>> {
+    tracing::info!(%user_id, ?param);
+    Ok(Json(
+        service.update_profile(user_id, param.extension).await?,
+    ))
+}
+
+#[tracing::instrument(skip_all)]
+async fn conversations(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<Vec<ConversationInfo>>> {
+    tracing::info!(%user_id, ?query);
+    Ok(Json(
+        service
+            .conversations(user_id, query.offset, query.limit)
+            .await?,
+    ))
+}
+
+#[tracing::instrument(skip_all)]
+async fn history(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+    Path(peer_id): Path<Uuid>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<MessageModel>>> {
+    tracing::info!(%user_id, %peer_id, ?query);
+    Ok(Json(
+        service
+            .history(user_id, peer_id, query.before, query.limit)
+            .await?,
+    ))
+}
+
+#[tracing::instrument(skip_all)]
+async fn read(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+    Path(peer_id): Path<Uuid>,
+    param: Option<Json<ReadParam>>,
+) -> Result<StatusCode> {
+    let Json(param) = param.unwrap_or_default();
+    tracing::info!(%user_id, %peer_id, ?param);
+
+    let timestamp = param.timestamp.unwrap_or_else(current_millisecond);
+    service.read(user_id, peer_id, timestamp).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[tracing::instrument(skip_all)]
+async fn update_conversation_setting(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+    Path(peer_id): Path<Uuid>,
+    Json(setting): Json<ConversationSetting>,
+) -> Result<Json<ConversationSetting>> {
+    tracing::info!(%user_id, %peer_id, ?setting);
+    Ok(Json(
+        service
+            .update_conversation_setting(user_id, peer_id, setting)
+            .await?,
+    ))
+}
+
+#[tracing::instrument(skip_all)]
+async fn unread(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+) -> Result<Json<UnreadCount>> {
+    tracing::info!(%user_id);
+    Ok(Json(service.unread(user_id).await?))
+}
+
+#[tracing::instrument(skip_all)]
+async fn settings(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+) -> Result<Json<JsonValue>> {
+    tracing::info!(%user_id);
+    Ok(Json(service.settings(user_id).await?))
+}
+
+#[tracing::instrument(skip_all)]
+async fn update_settings(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+    Json(setting): Json<JsonValue>,
+) -> Result<Json<JsonValue>> {
+    tracing::info!(%user_id);
+    Ok(Json(service.update_settings(user_id, setting).await?))
+}
+
+#[tracing::instrument(skip_all)]
+async fn identity_keys(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+    Path(peer_id): Path<Uuid>,
+) -> Result<Json<Vec<IdentityKeyModel>>> {
+    tracing::info!(%user_id, %peer_id);
+    Ok(Json(service.identity_keys(user_id, peer_id).await?))
+}
+
+#[tracing::instrument(skip_all)]
+async fn publish_identity_key(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+    Json(param): Json<PublishKeyParam>,
+) -> Result<Json<IdentityKeyModel>> {
+    tracing::info!(%user_id, key_id = %param.key_id, algorithm = %param.algorithm);
+    Ok(Json(service.publish_identity_key(user_id, param).await?))
+}
+
+#[tracing::instrument(skip_all)]
+async fn delete_identity_key(
+    Extension(service): Extension<ApiService>,
+    AuthorizedUser { user_id, .. }: AuthorizedUser,
+    Path(key_id): Path<String>,
+) -> Result<StatusCode> {
+    tracing::info!(%user_id, %key_id);
+    service.delete_identity_key(user_id, key_id).await?;
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -0,0 +1,557 @@
+use crate::config::ApiConfig;
+use crate::{ConversationInfo, ConversationSetting, Error, PublishKeyParam, UnreadCount};
+use jinshu_database::identity_key::Model as IdentityKeyModel;
+use jinshu_database::message::Model as MessageModel;
+use jinshu_database::prelude::*;
+use jinshu_database::user::Model as UserModel;
+use jinshu_database::{conversation, group_member, identity_key, message, user, user_setting};
+use jinshu_utils::current_millisecond;
+use sea_orm::prelude::TimeDateTimeWithTimeZone;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
+    FromQueryResult, JsonValue, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// 允许的最大毫秒时间戳，即 9999-12-31T23:59:59.999Z
+pub const MAX_TIMESTAMP_MS: u64 = 253_402_300_799_999;
+
+/// 毫秒时间戳转换为数据库中的时间，超过 [`MAX_TIMESTAMP_MS`] 时返回 [`Error::BadRequest`]
+pub fn to_datetime(ms: u64) -> crate::Result<TimeDateTimeWithTimeZone> {
+    let secs = Some(ms)
+        .filter(|ms| *ms <= MAX_TIMESTAMP_MS)
+        .and_then(|ms| i64::try_from(ms / 1000).ok())
+        .ok_or_else(|| Error::BadRequest(format!("Invalid timestamp: {}", ms).into()))?;
+    Ok(TimeDateTimeWithTimeZone::from_unix_timestamp(secs) + Duration::from_millis(ms % 1000))
+}
+
+/// 数据库中的时间转换为毫秒时间戳
+pub fn to_millisecond(datetime: TimeDateTimeWithTimeZone) -> u64 {
+    datetime.unix_timestamp() as u64 * 1000 + datetime.nanosecond() as u64 / 1_000_000
+}
+
+/// 消息所属会话的对方 ID：发往群组的消息属于该群组，否则属于单聊的另一方
+fn peer_of<'a>(user_id: &str, from: &'a str, to: &'a str, groups: &HashSet<String>) -> &'a str {
+    if groups.contains(to) || from == user_id {
+        to
+    } else {
+        from
+    }
+}
+
+/// 查询会话中消息的条件
+fn conversation_condition(user_id: &str, peer_id: &str, group: bool) -> Condition {
+    if group {
+        Condition::all().add(message::Column::To.eq(peer_id))
+    } else {
+        Condition::any()
+            .add(
+                Condition::all()
+                    .add(message::Column::From.eq(user_id))
+                    .add(message::Column::To.eq(peer_id)),
+            )
+            .add(
+                Condition::all()
+                    .add(message::Column::From.eq(peer_id))
+                    .add(message::Column::To.eq(user_id)),
+            )
+    }
+}
+
+/// 会话的对方及最后一条消息的时间
+#[derive(Debug)]
+struct Peer {
+    id: String,
+    group: bool,
+    last_time: TimeDateTimeWithTimeZone,
+    setting: Option<conversation::Model>,
+}
+
+impl Peer {
+    fn pinned(&self) -> bool {
+        self.setting.as_ref().map(|s| s.pinned).unwrap_or_default()
+    }
+
+    fn muted(&self) -> bool {
+        self.setting.as_ref().map(|s| s.muted).unwrap_or_default()
+    }
+
+    fn read_time(&self) -> Option<TimeDateTimeWithTimeZone> {
+        self.setting.as_ref().and_then(|s| s.read_time)
+    }
+}
+
+/// 客户端 HTTP 接口的实现
+#[derive(Clone)]
+pub struct ApiService {
+    db: DatabaseConnection,
+    max_limit: u64,
+}
+
+impl ApiService {
+    /// 使用配置及数据库连接构造
+    pub fn new(config: &ApiConfig, db: DatabaseConnection) -> Self {
+        Self {
+            db,
+            max_limit: config.max_limit.max(1),
+        }
+    }
+
+    fn limit(&self, limit: u64) -> u64 {
+        limit.clamp(1, self.max_limit)
+    }
+
+    /// 查询个人资料
+    pub async fn profile(&self, user_id: Uuid) -> crate::Result<UserModel> {
+        User::find_by_id(user_id.as_simple().to_string())
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| Error::NotFound("User".into()))
+    }
+
+    /// 更新个人资料的扩展字段
+    pub async fn update_profile(
+        &self,
+        user_id: Uuid,
+        extension: Option<JsonValue>,
+    ) -> crate::Result<UserModel> {
+        let mut model: user::ActiveModel = self.profile(user_id).await?.into();
+        model.extension = Set(extension);
+        Ok(model.update(&self.db).await?)
+    }
+
+    /// 用户所在的群组
+    async fn groups(&self, user_id: &str) -> crate::Result<HashSet<String>> {
+        Ok(GroupMember::find()
+            .filter(group_member::Column::UserId.eq(user_id))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|m| m.group_id)
+            .collect())
+    }
+
+    /// 判断对方是否为群组，用户不在该群组中时返回 `Forbidden`
+    async fn is_group(&self, user_id: &str, peer_id: &str) -> crate::Result<bool> {
+        if Group::find_by_id(peer_id.to_string())
+            .one(&self.db)
+            .await?
+            .is_none()
+        {
+            return Ok(false);
+        }
+
+        match GroupMember::find_by_id((peer_id.to_string(), user_id.to_string()))
+            .one(&self.db)
+            .await?
+        {
+            Some(_) => Ok(true),
+            None => Err(Error::Forbidden),
+        }
+    }
+
+    /// 用户的所有会话，置顶的在前，其余按最后一条消息的时间倒序排列
+    async fn peers(&self, user_id: &str) -> crate::Result<Vec<Peer>> {
+        #[derive(Debug, FromQueryResult)]
+        struct LastTime {
+            from: String,
+            to: String,
+            last_time: TimeDateTimeWithTimeZone,
+        }
+
+        let groups = self.groups(user_id).await?;
+
+        let mut condition = Condition::any()
+            .add(message::Column::From.eq(user_id))
+            .add(message::Column::To.eq(user_id));
+        if !groups.is_empty() {
+            condition = condition.add(message::Column::To.is_in(groups.iter().cloned()));
+        }
+
+        let rows = Message::find()
+            .select_only()
+            .column(message::Column::From)
+            .column(message::Column::To)
+            .column_as(Expr::col(message::Column::Timestamp).max(), "last_time")
+            .filter(condition)
+            .group_by(message::Column::From)
+            .group_by(message::Column::To)
+            .into_model::<LastTime>()
+            .all(&self.db)
+            .await?;
+
+        let mut last_times: HashMap<String, TimeDateTimeWithTimeZone> = HashMap::new();
+        for row in rows {
+            let peer = peer_of(user_id, &row.from, &row.to, &groups);
+            let last_time = last_times.entry(peer.to_string()).or_insert(row.last_time);
+            if row.last_time > *last_time {
+                *last_time = row.last_time;
+            }
+        }
+
+        let mut settings: HashMap<String, conversation::Model> = Conversation::find()
+            .filter(conversation::Column::UserId.eq(user_id))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|m| (m.peer_id.clone(), m))
+            .collect();
+
+        let mut peers: Vec<Peer> = last_times
+            .into_iter()
+            .map(|(id, last_time)| Peer {
+                group: groups.contains(&id),
+                setting: settings.remove(&id),
+                id,
+                last_time,
+            })
+            .collect();
+
+        peers.sort_by(|a, b| {
+            b.pinned()
+                .cmp(&a.pinned())
+                .then_with(|| b.last_time.cmp(&a.last_time))
+        });
+
+        Ok(peers)
+    }
+
+    /// 会话中对方发送的、已读时间之后的消息数
+    async fn unread_of(&self, user_id: &str, peer: &Peer) -> crate::Result<u64> {
+        let mut select = Message::find()
+            .filter(conversation_condition(user_id, &peer.id, peer.group))
+            .filter(message::Column::From.ne(user_id));
+        if let Some(read_time) = peer.read_time() {
+            select = select.filter(message::Column::Timestamp.gt(read_time));
+        }
+        Ok(select.count(&self.db).await? as u64)
+    }
+
+    /// 查询会话列表
+    pub async fn conversations(
+        &self,
+        user_id: Uuid,
+        offset: u64,
+        limit: u64,
+    ) -> crate::Result<Vec<ConversationInfo>> {
+        let user_id = user_id.as_simple().to_string();
+        let peers = self.peers(&user_id).await?;
+
+        let mut conversations = Vec::new();
+        for peer in peers
+            .iter()
+            .skip(offset as usize)
+            .take(self.limit(limit) as usize)
+        {
+            let peer_id = match peer.id.parse() {
+                Ok(peer_id) => peer_id,
+                Err(e) => {
+                    tracing::warn!(peer_id = %peer.id, %e, "Invalid peer id, skipped");
+                    continue;
+                }
+            };
+
+            let last_message = Message::find()
+                .filter(conversation_condition(&user_id, &peer.id, peer.group))
+                .order_by_desc(message::Column::Timestamp)
+                .one(&self.db)
+                .await?;
+
+            conversations.push(ConversationInfo {
+                peer_id,
+                group: peer.group,
+                last_message,
+                unread: self.unread_of(&user_id, peer).await?,
+                muted: peer.muted(),
+                pinned: peer.pinned(),
+            });
+        }
+
+        Ok(conversations)
+    }
+
+    /// 查询会话的历史消息，按时间倒序排列
+    pub async fn history(
+        &self,
+        user_id: Uuid,
+        peer_id: Uuid,
+        before: Option<u64>,
+        limit: u64,
+    ) -> crate::Result<Vec<MessageModel>> {
+        let user_id = user_id.as_simple().to_string();
+        let peer_id = peer_id.as_simple().to_string();
+        let group = self.is_group(&user_id, &peer_id).await?;
+
+        let mut select = Message::find().filter(conversation_condition(&user_id, &peer_id, group));
+        if let Some(before) = before {
+            select = select.filter(message::Column::Timestamp.lt(to_datetime(before)?));
+        }
+
+        Ok(select
+            .order_by_desc(message::Column::Timestamp)
+            .limit(self.limit(limit))
+            .all(&self.db)
+            .await?)
+    }
+
+    /// 查询未读数
+    pub async fn unread(&self, user_id: Uuid) -> crate::Result<UnreadCount> {
+        let user_id = user_id.as_simple().to_string();
+
+        let mut unread = UnreadCount::default();
+        for peer in self.peers(&user_id).await? {
+            let count = self.unread_of(&user_id, &peer).await?;
+            if count == 0 {
+                continue;
+            }
+
+            let peer_id = match peer.id.parse() {
+                Ok(peer_id) => peer_id,
+                Err(_) => continue,
+            };
+
+            if !peer.muted() {
+                unread.total += count;
+            }
+            unread.conversations.insert(peer_id, count);
+        }
+
+        Ok(unread)
+    }
+
+    /// 修改会话设置，不存在时创建
+    async fn update_conversation<F>(
+        &self,
+        user_id: String,
+        peer_id: String,
+        update: F,
+    ) -> crate::Result<conversation::Model>
+    where
+        F: FnOnce(&mut conversation::ActiveModel),
+    {
+        let now = to_datetime(current_millisecond())?;
+        match Conversation::find_by_id((user_id.clone(), peer_id.clone()))
+            .one(&self.db)
+            .await?
+        {
+            Some(model) => {
+                let mut model: conversation::ActiveModel = model.into();
+                update(&mut model);
+                model.update_time = Set(now);
+                Ok(model.update(&self.db).await?)
+            }
+            None => {
+                let mut model = conversation::ActiveModel {
+                    user_id: Set(user_id),
+                    peer_id: Set(peer_id),
+                    read_time: Set(None),
+                    muted: Set(false),
+                    pinned: Set(false),
+                    update_time: Set(now),
+                };
+                update(&mut model);
+                Ok(model.insert(&self.db).await?)
+            }
+        }
+    }
+
+    /// 将会话标记为已读到 `timestamp`，已读时间只会向后移动
+    pub async fn read(&self, user_id: Uuid, peer_id: Uuid, timestamp: u64) -> crate::Result<()> {
+        let user_id = user_id.as_simple().to_string();
+        let peer_id = peer_id.as_simple().to_string();
+        self.is_group(&user_id, &peer_id).await?;
+
+        let read_time = to_datetime(timestamp)?;
+        self.update_conversation(user_id, peer_id, |model| {
+            let current = match &model.read_time {
+                ActiveValue::Set(t) | ActiveValue::Unchanged(t) => *t,
+                ActiveValue::NotSet => None,
+            };
+            if current.map(|t| t < read_time).unwrap_or(true) {
+                model.read_time = Set(Some(read_time));
+            }
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// 修改会话的免打扰、置顶设置，返回修改后的设置
+    pub async fn update_conversation_setting(
+        &self,
+        user_id: Uuid,
+        peer_id: Uuid,
+        setting: ConversationSetting,
+    ) -> crate::Result<ConversationSetting> {
+        let user_id = user_id.as_simple().to_string();
+        let peer_id = peer_id.as_simple().to_string();
+        self.is_group(&user_id, &peer_id).await?;
+
+        let model = self
+            .update_conversation(user_id, peer_id, |model| {
+                if let Some(muted) = setting.muted {
+                    model.muted = Set(muted);
+                }
+                if let Some(pinned) = setting.pinned {
+                    model.pinned = Set(pinned);
+                }
+            })
+            .await?;
+
+        Ok(ConversationSetting {
+            muted: Some(model.muted),
+            pinned: Some(model.pinned),
+        })
+    }
+
+    /// 查询用户设置，未设置时返回空对象
+    pub async fn settings(&self, user_id: Uuid) -> crate::Result<JsonValue> {
+        Ok(UserSetting::find_by_id(user_id.as_simple().to_string())
+            .one(&self.db)
+            .await?
+            .map(|m| m.setting)
+            .unwrap_or_else(|| JsonValue::Object(Default::default())))
+    }
+
+    /// 保存用户设置，设置必须是 JSON 对象
+    pub async fn update_settings(
+        &self,
+        user_id: Uuid,
+        setting: JsonValue,
+    ) -> crate::Result<JsonValue> {
+        if !setting.is_object() {
+            return Err(Error::BadRequest("Settings must be a JSON object".into()));
+        }
+
+        let user_id = user_id.as_simple().to_string();
+        let now = to_datetime(current_millisecond())?;
+        let model = match UserSetting::find_by_id(user_id.clone())
+            .one(&self.db)
+            .await?
+        {
+            Some(model) => {
+                let mut model: user_setting::ActiveModel = model.into();
+                model.setting = Set(setting);
+                model.update_time = Set(now);
+                model.update(&self.db).await?
+            }
+            None => {
+                user_setting::ActiveModel {
+                    user_id: Set(user_id),
+                    setting: Set(setting),
+                    update_time: Set(now),
+                }
+                .insert(&self.db)
+                .await?
+            }
+        };
+
+        Ok(model.setting)
+    }
+
+    /// 查询用户 `peer_id` 的身份公钥，只能查询与自己同一应用的用户
+    pub async fn identity_keys(
+        &self,
+        user_id: Uuid,
+        peer_id: Uuid,
+    ) -> crate::Result<Vec<IdentityKeyModel>> {
+        let app_id = self.profile(user_id).await?.app_id;
+        Ok(IdentityKey::find()
+            .filter(identity_key::Column::UserId.eq(peer_id.as_simple().to_string()))
+            .filter(identity_key::Column::AppId.eq(app_id))
+            .order_by_desc(identity_key::Column::CreateTime)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// 发布自己的身份公钥，公钥 ID 已存在时覆盖
+    pub async fn publish_identity_key(
+        &self,
+        user_id: Uuid,
+        param: PublishKeyParam,
+    ) -> crate::Result<IdentityKeyModel> {
+        if param.key_id.is_empty() || param.algorithm.is_empty() || param.public_key.is_empty() {
+            return Err(Error::BadRequest(
+                "Key id, algorithm and public key must not be empty".into(),
+            ));
+        }
+
+        let app_id = self.profile(user_id).await?.app_id;
+        let user_id = user_id.as_simple().to_string();
+        let now = to_datetime(current_millisecond())?;
+        let model = match IdentityKey::find_by_id((user_id.clone(), param.key_id.clone()))
+            .one(&self.db)
+            .await?
+        {
+            Some(model) => {
+                let mut model: identity_key::ActiveModel = model.into();
+                model.algorithm = Set(param.algorithm);
+                model.public_key = Set(param.public_key);
+                model.create_time = Set(now);
+                model.update(&self.db).await?
+            }
+            None => {
+                identity_key::ActiveModel {
+                    user_id: Set(user_id),
+                    key_id: Set(param.key_id),
+                    app_id: Set(app_id),
+                    algorithm: Set(param.algorithm),
+                    public_key: Set(param.public_key),
+                    create_time: Set(now),
+                }
+                .insert(&self.db)
+                .await?
+            }
+        };
+
+        Ok(model)
+    }
+
+    /// 删除自己的身份公钥
+    pub async fn delete_identity_key(&self, user_id: Uuid, key_id: String) -> crate::Result<()> {
+        let result = IdentityKey::delete_by_id((user_id.as_simple().to_string(), key_id))
+            .exec(&self.db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(Error::NotFound("Identity key".into()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{peer_of, to_datetime, to_millisecond, MAX_TIMESTAMP_MS};
+    use crate::Error;
+    use std::collections::HashSet;
+
+    #[test]
+    fn datetime() {
+        for ms in [0, 999, 1_000, 1_650_000_000_123] {
+            assert_eq!(to_millisecond(to_datetime(ms).unwrap()), ms);
+        }
+
+        assert_eq!(
+            to_millisecond(to_datetime(MAX_TIMESTAMP_MS).unwrap()),
+            MAX_TIMESTAMP_MS
+        );
+        assert!(matches!(
+            to_datetime(MAX_TIMESTAMP_MS + 1),
+            Err(Error::BadRequest(_))
+        ));
+        assert!(matches!(to_datetime(u64::MAX), Err(Error::BadRequest(_))));
+    }
+
+    #[test]
+    fn peer() {
+        let groups: HashSet<String> = ["group".to_string()].into_iter().collect();
+
+        assert_eq!(peer_of("me", "me", "other", &groups), "other");
+        assert_eq!(peer_of("me", "other", "me", &groups), "other");
+        assert_eq!(peer_of("me", "me", "group", &groups), "group");
+        assert_eq!(peer_of("me", "other", "group", &groups), "group");
+    }
+}
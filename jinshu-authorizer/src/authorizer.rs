@@ -1,65 +1,168 @@
 use deadpool_redis::redis::AsyncCommands;
-use jinshu_redis::get_sign_in_key;
+use jinshu_redis::get_ban_key;
 use jinshu_rpc::authorizer::{authorizer_server, SignInResult, Token};
 use jinshu_rpc::{internal, invalid_argument};
-use serde::Deserialize;
+use serde_json::Value as JsonValue;
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 
-/// 基于 Redis 的授权服务
-pub struct Authorizer {
-    redis: deadpool_redis::Pool,
+/// 登录验证
+///
+/// 验证通过时返回登录时携带的扩展字段，不通过时返回 `None`
+///
+#[tonic::async_trait]
+pub trait Authorizer: Send + Sync + 'static {
+    /// 验证应用 `app_id` 的用户的登录令牌
+    async fn authorize(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        token: &str,
+    ) -> crate::Result<Option<JsonValue>>;
+
+    /// 清除用户缓存的验证结果，用户被封禁时调用
+    async fn forget(&self, _app_id: &str, _user_id: Uuid) {}
+}
+
+#[tonic::async_trait]
+impl<A: Authorizer + ?Sized> Authorizer for Box<A> {
+    async fn authorize(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        token: &str,
+    ) -> crate::Result<Option<JsonValue>> {
+        (**self).authorize(app_id, user_id, token).await
+    }
+
+    async fn forget(&self, app_id: &str, user_id: Uuid) {
+        (**self).forget(app_id, user_id).await
+    }
+}
+
+/// 封禁名单
+#[tonic::async_trait]
+pub trait BanList: Send + Sync + 'static {
+    /// 应用 `app_id` 的用户是否被封禁
+    async fn is_banned(&self, app_id: &str, user_id: Uuid) -> crate::Result<bool>;
 }
 
-impl Authorizer {
-    /// 使用 Redis Pool 创建授权服务
-    pub fn new(redis: deadpool_redis::Pool) -> Self {
-        Self { redis }
+#[tonic::async_trait]
+impl BanList for deadpool_redis::Pool {
+    async fn is_banned(&self, app_id: &str, user_id: Uuid) -> crate::Result<bool> {
+        let mut conn = self.get().await?;
+        Ok(conn
+            .exists(get_ban_key(app_id, user_id.as_simple()))
+            .await?)
     }
 }
 
+/// 不配置封禁名单时不检查封禁
 #[tonic::async_trait]
-impl authorizer_server::Authorizer for Authorizer {
+impl<B: BanList> BanList for Option<B> {
+    async fn is_banned(&self, app_id: &str, user_id: Uuid) -> crate::Result<bool> {
+        match self {
+            Some(bans) => bans.is_banned(app_id, user_id).await,
+            None => Ok(false),
+        }
+    }
+}
+
+/// 使用 [`Authorizer`] 实现的授权服务
+///
+/// 先检查封禁名单，被封禁的用户不能登录；
+/// 签名令牌模式下不配置封禁名单，封禁用户时吊销其令牌，由吊销列表拒绝登录
+///
+pub struct AuthorizerService<A, B> {
+    authorizer: A,
+    bans: B,
+}
+
+impl<A: Authorizer, B: BanList> AuthorizerService<A, B> {
+    /// 使用登录验证实现及封禁名单构造
+    pub fn new(authorizer: A, bans: B) -> Self {
+        Self { authorizer, bans }
+    }
+
+    /// 验证应用 `app_id` 的用户的登录令牌，用户被封禁时清除其缓存的验证结果并返回 `None`
+    pub async fn authorize(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        token: &str,
+    ) -> crate::Result<Option<JsonValue>> {
+        if self.bans.is_banned(app_id, user_id).await? {
+            tracing::info!(%app_id, %user_id, "User is banned");
+            self.authorizer.forget(app_id, user_id).await;
+            return Ok(None);
+        }
+
+        self.authorizer.authorize(app_id, user_id, token).await
+    }
+}
+
+#[tonic::async_trait]
+impl<A: Authorizer, B: BanList> authorizer_server::Authorizer for AuthorizerService<A, B> {
     #[tracing::instrument(skip(self, request))]
     async fn sign_in(&self, request: Request<Token>) -> Result<Response<SignInResult>, Status> {
-        let Token { user_id, token } = request.into_inner();
-        tracing::info!(user_id = %user_id, token = %token);
+        let Token {
+            user_id,
+            token,
+            app_id,
+        } = request.into_inner();
+        tracing::info!(%app_id, %user_id);
 
         let user_id: Uuid = user_id.parse().map_err(invalid_argument)?;
-        let token: Uuid = token.parse().map_err(invalid_argument)?;
-
-        let mut conn = self.redis.get().await.map_err(internal)?;
+        if app_id.is_empty() {
+            return Err(Status::invalid_argument("App id is empty"));
+        }
 
-        let key = get_sign_in_key(user_id.as_simple());
+        let extension = self
+            .authorize(&app_id, user_id, &token)
+            .await
+            .map_err(internal)?;
 
-        let value: Option<String> = conn.get(&key).await.map_err(internal)?;
+        tracing::info!(%app_id, %user_id, ok = extension.is_some());
 
-        tracing::info!(%key, ?value);
+        Ok(Response::new(SignInResult {
+            ok: extension.is_some(),
+            extension: extension.map(|e| e.to_string()),
+        }))
+    }
+}
 
-        #[derive(Debug, Deserialize)]
-        struct SignIn {
-            user_id: Uuid,
-            token: Uuid,
-            extension: serde_json::Value,
-        }
+#[cfg(test)]
+pub(crate) mod test {
+    use super::BanList;
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
 
-        let sign_in: SignIn = match value {
-            None => {
-                return Ok(Response::new(SignInResult {
-                    ok: false,
-                    extension: None,
-                }));
-            }
-            Some(value) => serde_json::from_str(&value).map_err(internal)?,
-        };
+    /// 内存中的封禁名单，用于测试，克隆的名单共享数据
+    #[derive(Default, Clone)]
+    pub(crate) struct MemoryBanList(Arc<Mutex<HashSet<(String, Uuid)>>>);
 
-        tracing::info!(?sign_in);
+    impl MemoryBanList {
+        pub(crate) fn ban(&self, app_id: &str, user_id: Uuid) {
+            self.0.lock().unwrap().insert((app_id.to_string(), user_id));
+        }
 
-        let result = SignInResult {
-            ok: sign_in.user_id == user_id && sign_in.token == token,
-            extension: Some(sign_in.extension.to_string()),
-        };
+        pub(crate) fn unban(&self, app_id: &str, user_id: Uuid) {
+            self.0
+                .lock()
+                .unwrap()
+                .remove(&(app_id.to_string(), user_id));
+        }
+    }
 
-        Ok(Response::new(result))
+    #[tonic::async_trait]
+    impl BanList for MemoryBanList {
+        async fn is_banned(&self, app_id: &str, user_id: Uuid) -> crate::Result<bool> {
+            Ok(self
+                .0
+                .lock()
+                .unwrap()
+                .contains(&(app_id.to_string(), user_id)))
+        }
     }
 }
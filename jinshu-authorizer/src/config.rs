@@ -1,5 +1,7 @@
 use jinshu_rpc::config::ServiceConfig;
+use jinshu_utils::secret::Secret;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Authorizer 的配置
 #[derive(Debug, Deserialize, Serialize)]
@@ -8,6 +10,14 @@ pub struct AuthorizerConfig {
     /// Authorizer 服务配置
     #[serde(flatten)]
     pub service: ServiceConfig,
+
+    /// 签名令牌模式下同步吊销列表的间隔（毫秒）
+    #[serde(default = "default_revocation_sync_ms")]
+    pub revocation_sync_ms: u64,
+
+    /// 将登录验证委托给应用服务器时的 Webhook 配置，配置后不再使用令牌模式对应的验证方式
+    #[serde(default)]
+    pub webhook: Option<WebhookConfig>,
 }
 
 impl Default for AuthorizerConfig {
@@ -19,16 +29,92 @@ impl Default for AuthorizerConfig {
                 listen_ip: [0u8, 0, 0, 0].into(),
                 listen_port: 9300,
             },
+            revocation_sync_ms: default_revocation_sync_ms(),
+            webhook: None,
         }
     }
 }
 
+fn default_revocation_sync_ms() -> u64 {
+    1000
+}
+
+/// Webhook 的配置
+///
+/// 各应用可以使用自己的应用服务器验证登录，未单独配置的应用使用默认的地址
+///
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// 未单独配置的应用使用的验证地址，为空时这些应用的登录验证不通过
+    pub url: Option<String>,
+    /// 未单独配置的应用签名请求使用的密钥，为空时不签名
+    pub secret: Option<Secret>,
+    /// 各应用的 Webhook，键为应用 ID
+    pub apps: HashMap<String, AppWebhookConfig>,
+    /// 请求超时时间（毫秒）
+    pub timeout_ms: u64,
+    /// 验证结果的缓存时间（毫秒），为 0 时不缓存
+    pub cache_ttl_ms: u64,
+    /// 最多缓存的验证结果数
+    pub cache_capacity: usize,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: Some("http://127.0.0.1:8765/jinshu/authorize".into()),
+            secret: None,
+            apps: HashMap::new(),
+            timeout_ms: 3000,
+            cache_ttl_ms: 60000,
+            cache_capacity: 100000,
+        }
+    }
+}
+
+/// 应用的 Webhook 配置
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AppWebhookConfig {
+    /// 应用服务器的验证地址
+    pub url: String,
+    /// 签名请求使用的密钥，应用服务器用它验证请求来自锦书
+    pub secret: Secret,
+}
+
 #[cfg(test)]
 mod test {
-    use super::AuthorizerConfig;
+    use super::{AuthorizerConfig, WebhookConfig};
 
     #[test]
     fn default() {
         AuthorizerConfig::default();
+        WebhookConfig::default();
+    }
+
+    #[test]
+    fn legacy() {
+        let config: AuthorizerConfig = serde_json::from_str(
+            r#"{"service_name": "authorizer", "public_host": "0.0.0.0", "listen_ip": "0.0.0.0", "listen_port": 9300}"#,
+        )
+        .unwrap();
+        assert_eq!(config.revocation_sync_ms, 1000);
+        assert!(config.webhook.is_none());
+    }
+
+    #[test]
+    fn webhook() {
+        // 旧版本只配置一个地址
+        let config: WebhookConfig =
+            serde_json::from_str(r#"{"url": "http://127.0.0.1/authorize"}"#).unwrap();
+        assert_eq!(config.url.as_deref(), Some("http://127.0.0.1/authorize"));
+        assert!(config.secret.is_none() && config.apps.is_empty());
+
+        let config: WebhookConfig = serde_json::from_str(
+            r#"{"url": null, "apps": {"app": {"url": "http://app/authorize", "secret": "s"}}}"#,
+        )
+        .unwrap();
+        assert!(config.url.is_none());
+        assert_eq!(config.apps["app"].secret.expose(), "s");
     }
 }
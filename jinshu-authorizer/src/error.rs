@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+/// 授权服务的错误
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Redis 连接池错误
+    #[error(transparent)]
+    Pool(#[from] deadpool_redis::PoolError),
+    /// Redis 错误
+    #[error(transparent)]
+    Redis(#[from] deadpool_redis::redis::RedisError),
+    /// JSON 错误
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// HTTP 请求错误
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// 非法的 Webhook 地址
+    #[error(transparent)]
+    Url(#[from] url::ParseError),
+    /// Webhook 返回了无法处理的状态码
+    #[error("Unexpected webhook status: {0}")]
+    Webhook(reqwest::StatusCode),
+}
+
+/// 授权服务的结果
+pub type Result<T> = std::result::Result<T, Error>;
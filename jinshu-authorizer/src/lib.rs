@@ -4,8 +4,21 @@
 //! 授权服务，用户登录验证
 //!
 
-/// 授权服务实现
+/// 登录验证抽象及授权服务实现
 pub mod authorizer;
 
 /// 配置
 pub mod config;
+
+mod error;
+
+/// 基于 Redis 的登录验证
+pub mod redis;
+
+/// 基于签名令牌的登录验证
+pub mod signed;
+
+/// 委托应用服务器的登录验证
+pub mod webhook;
+
+pub use error::*;
@@ -1,19 +1,27 @@
-use jinshu_authorizer::authorizer::Authorizer;
+use jinshu_authorizer::authorizer::{Authorizer, AuthorizerService};
 use jinshu_authorizer::config::AuthorizerConfig;
+use jinshu_authorizer::redis::RedisAuthorizer;
+use jinshu_authorizer::signed::SignedAuthorizer;
+use jinshu_authorizer::webhook::WebhookAuthorizer;
+use jinshu_common::token::{TokenConfig, TokenMode};
 use jinshu_common::Config;
 use jinshu_redis::config::RedisConfig;
+use jinshu_redis::revocation::RevocationStore;
 use jinshu_rpc::authorizer::authorizer_server::AuthorizerServer;
 use jinshu_rpc::registry::etcd::{EtcdConfig, EtcdRegistry};
 use jinshu_rpc::registry::Registry;
 use jinshu_tracing::config::TracingConfig;
 use jinshu_utils::shutdown_signal;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Debug, Deserialize, Serialize)]
 struct Conf {
     tracing: TracingConfig,
     etcd: EtcdConfig,
     redis: RedisConfig,
+    #[serde(default)]
+    token: TokenConfig,
     authorizer: AuthorizerConfig,
 }
 
@@ -26,7 +34,13 @@ async fn main() -> anyhow::Result<()> {
     let Conf {
         etcd,
         redis,
-        authorizer: AuthorizerConfig { service },
+        token,
+        authorizer:
+            AuthorizerConfig {
+                service,
+                revocation_sync_ms,
+                webhook,
+            },
         ..
     } = conf;
 
@@ -37,7 +51,28 @@ async fn main() -> anyhow::Result<()> {
     let redis_config: deadpool_redis::Config = redis.into();
     let redis = redis_config.builder()?.build()?;
 
-    let authorizer = AuthorizerServer::new(Authorizer::new(redis));
+    // 签名令牌模式下不访问 Redis，封禁用户时会吊销其令牌
+    let (authorizer, bans): (Box<dyn Authorizer>, _) = match (webhook, token.mode) {
+        (Some(webhook), _) => {
+            tracing::info!(?webhook, "Authorize by webhook");
+            (Box::new(WebhookAuthorizer::new(&webhook)?), Some(redis))
+        }
+        (None, TokenMode::Random) => {
+            tracing::info!("Authorize by redis");
+            (Box::new(RedisAuthorizer::new(redis.clone())), Some(redis))
+        }
+        (None, TokenMode::Signed) => {
+            tracing::info!("Authorize by signed token");
+            let authorizer = SignedAuthorizer::new(token.verifier()?);
+            authorizer.sync_revocation(
+                RevocationStore::from_pool(redis),
+                Duration::from_millis(revocation_sync_ms),
+            );
+            (Box::new(authorizer), None)
+        }
+    };
+
+    let authorizer = AuthorizerServer::new(AuthorizerService::new(authorizer, bans));
 
     let (uri, handle) = registry
         .run_service(service, authorizer, shutdown_signal())
@@ -0,0 +1,103 @@
+use crate::authorizer::Authorizer;
+use deadpool_redis::redis::AsyncCommands;
+use jinshu_redis::get_sign_in_key;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
+
+/// 基于 Redis 的登录验证
+pub struct RedisAuthorizer {
+    redis: deadpool_redis::Pool,
+}
+
+impl RedisAuthorizer {
+    /// 使用 Redis Pool 创建
+    pub fn new(redis: deadpool_redis::Pool) -> Self {
+        Self { redis }
+    }
+}
+
+#[tonic::async_trait]
+impl Authorizer for RedisAuthorizer {
+    async fn authorize(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        token: &str,
+    ) -> crate::Result<Option<JsonValue>> {
+        let mut conn = self.redis.get().await?;
+
+        let key = get_sign_in_key(app_id, user_id.as_simple());
+
+        let value: Option<String> = conn.get(&key).await?;
+
+        tracing::info!(%key, ?value);
+
+        #[derive(Debug, Deserialize)]
+        struct SignIn {
+            user_id: Uuid,
+            token: String,
+            extension: JsonValue,
+        }
+
+        let sign_in: SignIn = match value {
+            None => return Ok(None),
+            Some(value) => serde_json::from_str(&value)?,
+        };
+
+        tracing::info!(?sign_in);
+
+        Ok(
+            (sign_in.user_id == user_id && same_token(&sign_in.token, token))
+                .then_some(sign_in.extension),
+        )
+    }
+}
+
+/// 比较令牌，UUID 令牌按值比较，旧客户端可能发送带连字符的 UUID
+fn same_token(stored: &str, token: &str) -> bool {
+    if stored == token {
+        return true;
+    }
+
+    match (Uuid::try_parse(stored), Uuid::try_parse(token)) {
+        (Ok(stored), Ok(token)) => stored == token,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{same_token, RedisAuthorizer};
+    use crate::authorizer::test::MemoryBanList;
+    use crate::authorizer::AuthorizerService;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn banned() -> anyhow::Result<()> {
+        let bans = MemoryBanList::default();
+        // 无法连接的 Redis，只有未被封禁时才会访问
+        let redis = deadpool_redis::Config::from_url("redis://127.0.0.1:1")
+            .builder()?
+            .build()?;
+        let service = AuthorizerService::new(RedisAuthorizer::new(redis), bans.clone());
+
+        let user_id = Uuid::new_v4();
+        assert!(service.authorize("app", user_id, "token").await.is_err());
+
+        bans.ban("app", user_id);
+        assert!(service.authorize("app", user_id, "token").await?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_token() {
+        let token = Uuid::new_v4();
+        let stored = token.as_simple().to_string();
+        assert!(same_token(&stored, &stored));
+        assert!(same_token(&stored, &token.to_string()));
+        assert!(!same_token(&stored, &Uuid::new_v4().to_string()));
+        assert!(!same_token(&stored, "token"));
+    }
+}
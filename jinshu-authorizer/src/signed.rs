@@ -0,0 +1,214 @@
+use crate::authorizer::Authorizer;
+use jinshu_common::token::TokenVerifier;
+use jinshu_redis::revocation::{RevocationList, RevocationStore};
+use serde_json::Value as JsonValue;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// 基于签名令牌的登录验证
+///
+/// 只验证令牌的签名、有效期及内存中的吊销列表，不访问 Redis；
+/// 吊销列表由 [`SignedAuthorizer::sync_revocation`] 定期从 Redis 同步
+///
+pub struct SignedAuthorizer {
+    verifier: TokenVerifier,
+    revocation: Arc<RwLock<RevocationList>>,
+}
+
+impl SignedAuthorizer {
+    /// 使用令牌验证器创建
+    pub fn new(verifier: TokenVerifier) -> Self {
+        Self {
+            verifier,
+            revocation: Default::default(),
+        }
+    }
+
+    /// 启动定期同步吊销列表的任务
+    pub fn sync_revocation(&self, store: RevocationStore, interval: Duration) -> JoinHandle<()> {
+        let revocation = self.revocation.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                match store.load().await {
+                    Ok(list) => {
+                        if let Ok(mut revocation) = revocation.write() {
+                            *revocation = list;
+                        }
+                    }
+                    Err(e) => tracing::error!(error = %e, "Failed to load revocation list"),
+                }
+            }
+        })
+    }
+
+    fn is_revoked(&self, app_id: &str, user_id: Uuid, jti: Uuid, issued_at: u64) -> bool {
+        self.revocation
+            .read()
+            .map(|list| list.is_revoked(app_id, user_id, jti, issued_at))
+            .unwrap_or_default()
+    }
+}
+
+#[tonic::async_trait]
+impl Authorizer for SignedAuthorizer {
+    async fn authorize(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        token: &str,
+    ) -> crate::Result<Option<JsonValue>> {
+        let claims = match self.verifier.verify(token) {
+            Ok(claims) => claims,
+            Err(e) => {
+                tracing::info!(%user_id, error = %e, "Invalid token");
+                return Ok(None);
+            }
+        };
+
+        if claims.app != app_id {
+            tracing::info!(%user_id, %app_id, app = %claims.app, "App mismatch");
+            return Ok(None);
+        }
+
+        if claims.sub != user_id {
+            tracing::info!(%user_id, subject = %claims.sub, "Subject mismatch");
+            return Ok(None);
+        }
+
+        if self.is_revoked(app_id, user_id, claims.jti, claims.issued_at_ms()) {
+            tracing::info!(%user_id, jti = %claims.jti, "Token is revoked");
+            return Ok(None);
+        }
+
+        Ok(Some(claims.ext))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SignedAuthorizer;
+    use crate::authorizer::test::MemoryBanList;
+    use crate::authorizer::{Authorizer, AuthorizerService};
+    use jinshu_common::token::{TokenConfig, TokenKey, TokenMode};
+    use jinshu_utils::current_millisecond;
+    use jinshu_utils::secret::Secret;
+    use uuid::Uuid;
+
+    fn config() -> TokenConfig {
+        TokenConfig {
+            mode: TokenMode::Signed,
+            key_id: "k1".into(),
+            keys: vec![TokenKey {
+                id: "k1".into(),
+                secret: Some(Secret::new("secret")),
+                private_key: None,
+                public_key: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    async fn sign_in(authorizer: &SignedAuthorizer, user_id: Uuid, token: &str) -> bool {
+        authorizer
+            .authorize("app", user_id, token)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    #[tokio::test]
+    async fn signed() -> anyhow::Result<()> {
+        let config = config();
+        let authorizer = SignedAuthorizer::new(config.verifier()?);
+
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let (token, claims) = config
+            .signer()?
+            .sign("app", alice, Default::default(), 60)?;
+
+        assert!(sign_in(&authorizer, alice, &token).await);
+        assert!(!sign_in(&authorizer, bob, &token).await);
+        assert!(!sign_in(&authorizer, alice, "invalid").await);
+        assert!(authorizer
+            .authorize("other", alice, &token)
+            .await
+            .unwrap()
+            .is_none());
+
+        *authorizer.revocation.write().unwrap() =
+            [format!("token:{}", claims.jti.as_simple()).as_str()]
+                .into_iter()
+                .collect();
+        assert!(!sign_in(&authorizer, alice, &token).await);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sign_out_and_sign_in_again() -> anyhow::Result<()> {
+        let config = config();
+        let signer = config.signer()?;
+        let authorizer = SignedAuthorizer::new(config.verifier()?);
+
+        let user_id = Uuid::new_v4();
+        let (old_token, _) = signer.sign("app", user_id, Default::default(), 60)?;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        // 登出时吊销此前签发的令牌，随后立即重新登录，新令牌通常与登出在同一秒内签发
+        let now = current_millisecond();
+        *authorizer.revocation.write().unwrap() =
+            [format!("user:app:{}:{}", user_id.as_simple(), now).as_str()]
+                .into_iter()
+                .collect();
+        let (new_token, _) = signer.sign("app", user_id, Default::default(), 60)?;
+
+        assert!(!sign_in(&authorizer, user_id, &old_token).await);
+        assert!(sign_in(&authorizer, user_id, &new_token).await);
+
+        // 吊销记录按应用隔离
+        let (other_token, _) = signer.sign("other", user_id, Default::default(), 60)?;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        *authorizer.revocation.write().unwrap() =
+            [format!("user:app:{}:{}", user_id.as_simple(), current_millisecond()).as_str()]
+                .into_iter()
+                .collect();
+        assert!(authorizer
+            .authorize("other", user_id, &other_token)
+            .await?
+            .is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn banned() -> anyhow::Result<()> {
+        let config = config();
+        let signer = config.signer()?;
+        let authorizer = SignedAuthorizer::new(config.verifier()?);
+        let revocation = authorizer.revocation.clone();
+        // 签名令牌模式下不检查封禁名单
+        let service = AuthorizerService::new(authorizer, None::<MemoryBanList>);
+
+        let user_id = Uuid::new_v4();
+        let (token, _) = signer.sign("app", user_id, Default::default(), 60)?;
+        assert!(service.authorize("app", user_id, &token).await?.is_some());
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        // 封禁用户时吊销其令牌，签名仍然有效的令牌也不能登录
+        *revocation.write().unwrap() =
+            [format!("user:app:{}:{}", user_id.as_simple(), current_millisecond()).as_str()]
+                .into_iter()
+                .collect();
+        assert!(service.authorize("app", user_id, &token).await?.is_none());
+
+        // 解封后网关签发的新令牌可以登录
+        let (token, _) = signer.sign("app", user_id, Default::default(), 60)?;
+        assert!(service.authorize("app", user_id, &token).await?.is_some());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,468 @@
+use crate::authorizer::Authorizer;
+use crate::config::WebhookConfig;
+use jinshu_common::header;
+use jinshu_common::signature::signature;
+use jinshu_utils::current_millisecond;
+use jinshu_utils::secret::Secret;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Webhook 请求体
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WebhookRequest {
+    /// 应用 ID
+    pub app_id: String,
+    /// 锦书用户 ID
+    pub user_id: Uuid,
+    /// 登录令牌
+    pub token: String,
+}
+
+/// Webhook 响应体
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WebhookResponse {
+    /// 是否验证通过
+    pub ok: bool,
+    /// 扩展字段
+    #[serde(default)]
+    pub extension: JsonValue,
+}
+
+/// 将登录验证委托给应用服务器的实现
+///
+/// 向应用配置的地址 POST [`WebhookRequest`]，应用服务器返回 2xx 及 [`WebhookResponse`]；
+/// 返回 401、403 或 404 视为验证不通过，其他状态码视为错误；没有配置地址的应用验证不通过。
+/// 配置了密钥时按 [`jinshu_common::signature::signature`] 签名请求，签名放在请求头中，
+/// 与应用服务器请求 Gateway 时的签名方式相同。
+/// 验证结果（包括不通过）在配置的时间内缓存，错误不缓存
+///
+pub struct WebhookAuthorizer {
+    http: Client,
+    default: Option<Endpoint>,
+    apps: HashMap<String, Endpoint>,
+    cache: ResultCache,
+}
+
+/// 应用服务器的验证地址及签名密钥
+struct Endpoint {
+    url: Url,
+    secret: Option<Secret>,
+}
+
+impl Endpoint {
+    fn new(url: &str, secret: Option<Secret>) -> crate::Result<Self> {
+        Ok(Self {
+            url: url.parse()?,
+            secret,
+        })
+    }
+
+    fn path_and_query(&self) -> String {
+        match self.url.query() {
+            Some(query) => format!("{}?{}", self.url.path(), query),
+            None => self.url.path().to_string(),
+        }
+    }
+}
+
+impl WebhookAuthorizer {
+    /// 使用配置构造
+    pub fn new(config: &WebhookConfig) -> crate::Result<Self> {
+        let default = match &config.url {
+            Some(url) => {
+                if config.secret.is_none() {
+                    tracing::warn!(%url, "Default webhook requests are not signed");
+                }
+                Some(Endpoint::new(url, config.secret.clone())?)
+            }
+            None => None,
+        };
+        let apps = config
+            .apps
+            .iter()
+            .map(|(app_id, app)| {
+                Ok((
+                    app_id.clone(),
+                    Endpoint::new(&app.url, Some(app.secret.clone()))?,
+                ))
+            })
+            .collect::<crate::Result<_>>()?;
+
+        Ok(Self {
+            http: Client::builder()
+                .timeout(Duration::from_millis(config.timeout_ms))
+                .build()?,
+            default,
+            apps,
+            cache: ResultCache::new(
+                Duration::from_millis(config.cache_ttl_ms),
+                config.cache_capacity,
+            ),
+        })
+    }
+
+    async fn request(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        token: &str,
+    ) -> crate::Result<Option<JsonValue>> {
+        let endpoint = match self.apps.get(app_id).or(self.default.as_ref()) {
+            Some(endpoint) => endpoint,
+            None => {
+                tracing::warn!(%app_id, "No webhook is configured for the app");
+                return Ok(None);
+            }
+        };
+
+        let body = serde_json::to_vec(&WebhookRequest {
+            app_id: app_id.to_string(),
+            user_id,
+            token: token.to_string(),
+        })?;
+
+        let mut request = self
+            .http
+            .post(endpoint.url.clone())
+            .header(header::APP_ID, app_id)
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(secret) = &endpoint.secret {
+            let timestamp = current_millisecond();
+            let nonce = Uuid::new_v4().as_simple().to_string();
+            let signature = signature(
+                secret.expose(),
+                "POST",
+                &endpoint.path_and_query(),
+                timestamp,
+                &nonce,
+                &body,
+            );
+            request = request
+                .header(header::TIMESTAMP, timestamp)
+                .header(header::NONCE, nonce)
+                .header(header::SIGNATURE, signature);
+        }
+        let response = request.body(body).send().await?;
+
+        match response.status() {
+            status if status.is_success() => {
+                let WebhookResponse { ok, extension } = response.json().await?;
+                Ok(ok.then_some(extension))
+            }
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => Ok(None),
+            status => Err(crate::Error::Webhook(status)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Authorizer for WebhookAuthorizer {
+    async fn authorize(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        token: &str,
+    ) -> crate::Result<Option<JsonValue>> {
+        let key = (app_id.to_string(), user_id, token.to_string());
+        if let Some(result) = self.cache.get(&key) {
+            tracing::debug!(%app_id, %user_id, "Webhook result cache hit");
+            return Ok(result);
+        }
+
+        let result = self.request(app_id, user_id, token).await?;
+        self.cache.insert(key, result.clone());
+        Ok(result)
+    }
+
+    async fn forget(&self, app_id: &str, user_id: Uuid) {
+        self.cache.remove_user(app_id, user_id);
+    }
+}
+
+/// 缓存的键，应用 ID、用户 ID 及令牌
+type CacheKey = (String, Uuid, String);
+
+/// 带有效期的验证结果缓存
+struct ResultCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<CacheKey, (Instant, Option<JsonValue>)>>,
+}
+
+impl ResultCache {
+    fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: Default::default(),
+        }
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<Option<JsonValue>> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(key)
+            .filter(|(expire, _)| *expire > Instant::now())
+            .map(|(_, result)| result.clone())
+    }
+
+    /// 删除用户的所有缓存结果
+    fn remove_user(&self, app_id: &str, user_id: Uuid) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|(app, user, _), _| app != app_id || *user != user_id);
+        }
+    }
+
+    fn insert(&self, key: CacheKey, result: Option<JsonValue>) {
+        if self.ttl.is_zero() || self.capacity == 0 {
+            return;
+        }
+
+        if let Ok(mut entries) = self.entries.lock() {
+            let now = Instant::now();
+            if entries.len() >= self.capacity {
+                entries.retain(|_, (expire, _)| *expire > now);
+            }
+            // 清理过期结果后仍然已满时不再缓存
+            if entries.len() < self.capacity {
+                entries.insert(key, (now + self.ttl, result));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ResultCache, WebhookAuthorizer, WebhookRequest, WebhookResponse};
+    use crate::authorizer::test::MemoryBanList;
+    use crate::authorizer::{Authorizer, AuthorizerService};
+    use crate::config::{AppWebhookConfig, WebhookConfig};
+    use axum::body::Bytes;
+    use axum::extract::{Extension, Json};
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use jinshu_common::header;
+    use jinshu_common::signature::verify;
+    use jinshu_utils::secret::Secret;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    #[test]
+    fn cache() {
+        let cache = ResultCache::new(Duration::from_millis(50), 1);
+        let (a, b) = (
+            ("app".into(), Uuid::new_v4(), "a".into()),
+            ("app".into(), Uuid::new_v4(), "b".into()),
+        );
+
+        cache.insert(a.clone(), None);
+        cache.insert(b.clone(), Some(Default::default()));
+        assert_eq!(cache.get(&a), Some(None));
+        assert_eq!(cache.get(&b), None);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get(&a), None);
+        cache.insert(b.clone(), Some(Default::default()));
+        assert_eq!(cache.get(&b), Some(Some(Default::default())));
+    }
+
+    const SECRET: &str = "app-secret";
+
+    /// 模拟的应用服务器，验证请求签名
+    async fn app_server(
+        Extension(calls): Extension<Arc<AtomicUsize>>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<Json<WebhookResponse>, StatusCode> {
+        calls.fetch_add(1, Ordering::SeqCst);
+        let value = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or(StatusCode::UNAUTHORIZED)
+        };
+        let timestamp = value(header::TIMESTAMP)?
+            .parse()
+            .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        let (nonce, sig) = (value(header::NONCE)?, value(header::SIGNATURE)?);
+        if !verify(SECRET, "POST", "/authorize", timestamp, nonce, &body, sig) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+
+        let request: WebhookRequest =
+            serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+        if value(header::APP_ID)? != request.app_id {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        match request.token.as_str() {
+            "valid" => Ok(Json(WebhookResponse {
+                ok: true,
+                extension: serde_json::json!({ "app_id": request.app_id, "user_id": request.user_id }),
+            })),
+            "invalid" => Ok(Json(WebhookResponse {
+                ok: false,
+                extension: Default::default(),
+            })),
+            "unknown" => Err(StatusCode::UNAUTHORIZED),
+            _ => Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
+    /// 启动模拟的应用服务器，返回验证地址及调用次数
+    fn start_app_server() -> (String, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route("/authorize", post(app_server))
+            .layer(Extension(calls.clone()));
+
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        (format!("http://{}/authorize", addr), calls)
+    }
+
+    fn app_webhook(url: &str, secret: &str) -> AppWebhookConfig {
+        AppWebhookConfig {
+            url: url.to_string(),
+            secret: Secret::new(secret),
+        }
+    }
+
+    /// 只为应用 `app` 配置模拟的应用服务器，返回使用它的验证器及调用次数
+    fn webhook_authorizer() -> anyhow::Result<(WebhookAuthorizer, Arc<AtomicUsize>)> {
+        let (url, calls) = start_app_server();
+        let authorizer = WebhookAuthorizer::new(&WebhookConfig {
+            url: None,
+            apps: [("app".to_string(), app_webhook(&url, SECRET))].into(),
+            ..Default::default()
+        })?;
+
+        Ok((authorizer, calls))
+    }
+
+    #[tokio::test]
+    async fn webhook() -> anyhow::Result<()> {
+        let (authorizer, calls) = webhook_authorizer()?;
+
+        let user_id = Uuid::new_v4();
+        let extension = authorizer.authorize("app", user_id, "valid").await?;
+        assert_eq!(
+            extension,
+            Some(serde_json::json!({ "app_id": "app", "user_id": user_id }))
+        );
+        assert!(authorizer
+            .authorize("app", user_id, "invalid")
+            .await?
+            .is_none());
+        assert!(authorizer
+            .authorize("app", user_id, "unknown")
+            .await?
+            .is_none());
+        assert!(authorizer.authorize("app", user_id, "error").await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // 结果被缓存，错误不缓存
+        assert!(authorizer
+            .authorize("app", user_id, "valid")
+            .await?
+            .is_some());
+        assert!(authorizer
+            .authorize("app", user_id, "unknown")
+            .await?
+            .is_none());
+        assert!(authorizer.authorize("app", user_id, "error").await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn per_app() -> anyhow::Result<()> {
+        let (url, calls) = start_app_server();
+        let authorizer = WebhookAuthorizer::new(&WebhookConfig {
+            url: None,
+            apps: [
+                ("app".to_string(), app_webhook(&url, SECRET)),
+                ("forged".to_string(), app_webhook(&url, "other-secret")),
+            ]
+            .into(),
+            ..Default::default()
+        })?;
+
+        let user_id = Uuid::new_v4();
+        assert!(authorizer
+            .authorize("app", user_id, "valid")
+            .await?
+            .is_some());
+        // 签名密钥不对，应用服务器拒绝
+        assert!(authorizer
+            .authorize("forged", user_id, "valid")
+            .await?
+            .is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        // 没有配置地址的应用不请求应用服务器
+        assert!(authorizer
+            .authorize("other", user_id, "valid")
+            .await?
+            .is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // 未单独配置的应用使用默认地址
+        let authorizer = WebhookAuthorizer::new(&WebhookConfig {
+            url: Some(url.clone()),
+            secret: Some(Secret::new(SECRET)),
+            ..Default::default()
+        })?;
+        assert_eq!(
+            authorizer.authorize("other", user_id, "valid").await?,
+            Some(serde_json::json!({ "app_id": "other", "user_id": user_id }))
+        );
+
+        // 默认地址没有配置密钥时不签名
+        let authorizer = WebhookAuthorizer::new(&WebhookConfig {
+            url: Some(url),
+            ..Default::default()
+        })?;
+        assert!(authorizer
+            .authorize("other", user_id, "valid")
+            .await?
+            .is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn banned() -> anyhow::Result<()> {
+        let bans = MemoryBanList::default();
+        let (authorizer, calls) = webhook_authorizer()?;
+        let service = AuthorizerService::new(authorizer, bans.clone());
+
+        let user_id = Uuid::new_v4();
+        assert!(service.authorize("app", user_id, "valid").await?.is_some());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 封禁后不使用缓存的结果，并清除缓存
+        bans.ban("app", user_id);
+        assert!(service.authorize("app", user_id, "valid").await?.is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        bans.unban("app", user_id);
+        assert!(service.authorize("app", user_id, "valid").await?.is_some());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+}
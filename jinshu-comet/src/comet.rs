@@ -1,9 +1,10 @@
 use crate::connection::ConnectionManager;
 use async_trait::async_trait;
-use jinshu_protocol::Message;
-use jinshu_rpc::comet::PushResult;
+use jinshu_protocol::{Capability, Message};
+use jinshu_rpc::comet::{KickRequest, KickResult, PushResult};
 use jinshu_rpc::{internal, invalid_argument};
 use tonic::{Request, Response, Status};
+use uuid::Uuid;
 
 /// 长链接保持服务
 #[derive(Clone)]
@@ -25,8 +26,16 @@ impl jinshu_rpc::comet::comet_server::Comet for Comet {
         request: Request<jinshu_rpc::domain::message::Message>,
     ) -> Result<Response<PushResult>, Status> {
         let message = request.into_inner();
+        let app_id = message.app_id.clone();
         let message = Message::try_from(&message).map_err(invalid_argument)?;
-        if let Some(mut r) = self.manager.get(message.to) {
+        if let Some(mut r) = self.manager.get(&app_id, message.to) {
+            if message.content.is_encrypted() && !r.capabilities().contains(Capability::Encryption)
+            {
+                return Err(Status::failed_precondition(format!(
+                    "user {} of app {} does not support encrypted messages.",
+                    message.to, app_id
+                )));
+            }
             r.push(message).await.map_err(internal)?;
 
             Ok(Response::new(PushResult {
@@ -34,7 +43,18 @@ impl jinshu_rpc::comet::comet_server::Comet for Comet {
                 result: None,
             }))
         } else {
-            Err(Status::not_found(format!("user {} not found.", message.to)))
+            Err(Status::not_found(format!(
+                "user {} of app {} not found.",
+                message.to, app_id
+            )))
         }
     }
+
+    async fn kick(&self, request: Request<KickRequest>) -> Result<Response<KickResult>, Status> {
+        let KickRequest { user_id, app_id } = request.into_inner();
+        let user_id = Uuid::from_slice(&user_id).map_err(invalid_argument)?;
+        let ok = self.manager.kick(&app_id, user_id);
+        tracing::info!(%app_id, %user_id, ok, "Kick user");
+        Ok(Response::new(KickResult { ok }))
+    }
 }
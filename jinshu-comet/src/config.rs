@@ -1,4 +1,6 @@
-use jinshu_protocol::Codec;
+use crate::limiter::RateLimitConfig;
+use crate::moderation::ModerationConfig;
+use jinshu_protocol::{Codec, Compression, PduCodec};
 use jinshu_rpc::config::ServiceConfig;
 use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
@@ -13,7 +15,7 @@ pub struct CometConfig {
     /// 监听的端口号
     pub port: u16,
 
-    /// 使用的编码, 0.json | 1.msgpack | 2.cbor | 3. flexbuffers
+    /// 使用的编码, 0.json | 1.msgpack | 2.cbor | 3. flexbuffers | 4.protobuf
     pub codec: Codec,
 
     /// 服务配置
@@ -24,6 +26,45 @@ pub struct CometConfig {
 
     /// 要消费的 Authorizer 服务名
     pub authorizer_name: String,
+
+    /// 发送消息的限流配置
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+
+    /// 内容审核配置
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+
+    /// 报文压缩配置
+    #[serde(default)]
+    pub compression: CompressionConfig,
+
+    /// 每个连接收发报文的最大长度（字节），超过 16MB 的报文使用扩展报文头
+    #[serde(default = "default_max_frame_len")]
+    pub max_frame_len: usize,
+}
+
+fn default_max_frame_len() -> usize {
+    PduCodec::DEFAULT_MAX_FRAME_LEN
+}
+
+/// 报文压缩配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    /// 支持的压缩算法，登录时选择客户端列表中第一个支持的算法，为空时不压缩
+    pub supported: Vec<Compression>,
+    /// 压缩阈值，小于该长度（字节）的报文不压缩
+    pub threshold: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            supported: Compression::ALL.to_vec(),
+            threshold: PduCodec::DEFAULT_COMPRESS_THRESHOLD,
+        }
+    }
 }
 
 impl Default for CometConfig {
@@ -40,16 +81,21 @@ impl Default for CometConfig {
             },
             receiver_name: "receiver".into(),
             authorizer_name: "authorizer".into(),
+            rate_limit: RateLimitConfig::default(),
+            moderation: ModerationConfig::default(),
+            compression: CompressionConfig::default(),
+            max_frame_len: default_max_frame_len(),
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::CometConfig;
+    use super::{CometConfig, CompressionConfig};
 
     #[test]
     fn default() {
         CometConfig::default();
+        CompressionConfig::default();
     }
 }
@@ -1,40 +1,64 @@
+use crate::config::CompressionConfig;
+use crate::limiter::{BucketConfig, RateLimiter, TokenBucket};
+use crate::moderation::{Moderator, Outcome};
 use dashmap::mapref::one::RefMut;
 use dashmap::DashMap;
 use futures::SinkExt;
 use jinshu_protocol::{
-    Body, Codec, Message, Pdu, PduCodec, Request, Response, TransactionIdGenerator,
+    negotiate_version, Body, Capabilities, Capability, Codec, Compression, Message, Pdu, PduCodec,
+    Request, Response, TransactionIdGenerator, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
 use jinshu_redis::session::SessionStore;
 use jinshu_rpc::authorizer::authorizer_client::AuthorizerClient;
 use jinshu_rpc::authorizer::{SignInResult, Token};
 use jinshu_rpc::domain::message::Message as RpcMessage;
 use jinshu_rpc::receiver::receiver_client::ReceiverClient;
+use jinshu_utils::current_millisecond;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::net::TcpStream;
 use tokio::sync::mpsc::{channel, Sender};
+use tokio::sync::Notify;
 use tokio_stream::StreamExt;
 use tokio_util::codec::{FramedRead, FramedWrite};
 use tonic::transport::Channel;
 use uuid::Uuid;
 
+/// 连接的键，应用 ID 及用户 ID
+pub type ConnectionKey = (String, Uuid);
+
 /// 连接管理器
+///
+/// 连接按应用隔离，同一用户 ID 在不同应用中的连接互不影响
+///
 #[derive(Clone)]
 pub struct ConnectionManager {
     service_uri: String,
-    connections: Arc<DashMap<Uuid, Connection>>,
+    connections: Arc<DashMap<ConnectionKey, Connection>>,
     receiver: ReceiverClient<Channel>,
     authorizer: AuthorizerClient<Channel>,
     session_store: SessionStore,
+    user_limiter: Option<Arc<dyn RateLimiter>>,
+    connection_limit: Option<BucketConfig>,
+    moderator: Option<Moderator>,
+    compression: CompressionConfig,
+    max_frame_len: usize,
 }
 
 impl ConnectionManager {
     /// 构造连接管理器
+    ///
+    /// `user_limiter` 及 `connection_limit` 分别限制每个用户及每个连接发送消息的频率，为空时不限制；
+    /// `moderator` 为空时不审核消息内容
+    ///
     pub fn new(
         service_uri: &str,
         receiver: ReceiverClient<Channel>,
         authorizer: AuthorizerClient<Channel>,
         session_store: SessionStore,
+        user_limiter: Option<Arc<dyn RateLimiter>>,
+        connection_limit: Option<BucketConfig>,
+        moderator: Option<Moderator>,
     ) -> Self {
         Self {
             service_uri: service_uri.to_owned(),
@@ -42,40 +66,129 @@ impl ConnectionManager {
             receiver,
             authorizer,
             session_store,
+            user_limiter,
+            connection_limit,
+            moderator,
+            compression: Default::default(),
+            max_frame_len: PduCodec::DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    /// 设置报文压缩配置，默认支持所有压缩算法
+    pub fn with_compression(mut self, compression: CompressionConfig) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// 设置每个连接收发报文的最大长度，默认为 [`PduCodec::DEFAULT_MAX_FRAME_LEN`]
+    pub fn with_max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    /// 为一次发送取出令牌，先检查连接的令牌桶再检查用户的令牌桶，被限流时返回需要等待的毫秒数
+    ///
+    /// 用户限流器出错时不限制发送
+    ///
+    async fn acquire(
+        user_limiter: Option<&dyn RateLimiter>,
+        connection_bucket: Option<&mut TokenBucket>,
+        app_id: &str,
+        user_id: Uuid,
+    ) -> Option<u64> {
+        let now = current_millisecond();
+        if let Some(wait) = connection_bucket.and_then(|bucket| bucket.acquire(now)) {
+            return Some(wait);
+        }
+
+        match user_limiter?.acquire(app_id, user_id, now).await {
+            Ok(wait) => wait,
+            Err(error) => {
+                tracing::warn!(%error, %app_id, %user_id, "Failed to acquire rate limit token");
+                None
+            }
         }
     }
 
     /// 尝试接收一个用户登录
     pub async fn accept(&mut self, stream: TcpStream, codec: Codec) -> anyhow::Result<()> {
-        let codec = PduCodec::new(codec);
+        let mut codec = PduCodec::new(codec);
+        codec.set_compress_threshold(self.compression.threshold);
+        codec.set_max_frame_len(self.max_frame_len);
         let (reader, writer) = stream.into_split();
 
         let mut writer = FramedWrite::new(writer, codec);
         let mut reader = FramedRead::new(reader, codec);
 
-        let user_id = match reader.next().await {
+        let (app_id, user_id, capabilities) = match reader.next().await {
             Some(Ok(Pdu {
-                body: Body::Req(Request::SignIn { user_id, token }),
+                body:
+                    Body::Req(Request::SignIn {
+                        app_id,
+                        user_id,
+                        token,
+                        compressions,
+                        version,
+                        capabilities,
+                    }),
                 id,
             })) => {
+                let version = match negotiate_version(version) {
+                    Some(version) => version,
+                    None => {
+                        writer
+                            .send(
+                                Response::UnsupportedVersion {
+                                    min_version: MIN_PROTOCOL_VERSION,
+                                    max_version: PROTOCOL_VERSION,
+                                }
+                                .to_pdu(id),
+                            )
+                            .await
+                            .unwrap_or_default(); // do nothing
+                        anyhow::bail!("Sign in error: unsupported protocol version {}", version);
+                    }
+                };
+                let capabilities = if version == 0 {
+                    Capabilities::legacy()
+                } else {
+                    Capabilities::all().intersection(&capabilities)
+                };
+
                 let request = tonic::Request::new(Token {
                     user_id: user_id.simple().to_string(),
-                    token: token.simple().to_string(),
+                    token,
+                    app_id: app_id.clone(),
                 });
                 match self.authorizer.sign_in(request).await {
                     Ok(resp) => {
                         let SignInResult { ok, extension } = resp.into_inner();
                         if ok {
+                            let compression = if capabilities.contains(Capability::Compression) {
+                                Compression::negotiate(&compressions, &self.compression.supported)
+                            } else {
+                                None
+                            };
                             writer
                                 .send(
                                     Response::SignedIn {
                                         extension: extension
                                             .and_then(|s| serde_json::Value::from_str(&s).ok()),
+                                        compression,
+                                        version,
+                                        capabilities: capabilities.clone(),
+                                        codecs: Codec::ALL.to_vec(),
+                                        compressions: self.compression.supported.clone(),
                                     }
                                     .to_pdu(id),
                                 )
                                 .await?;
-                            user_id // get user ID
+                            // 登录响应不压缩，之后发送的报文使用协商的压缩算法
+                            writer.encoder_mut().set_compression(compression);
+                            writer
+                                .encoder_mut()
+                                .set_binary_data(capabilities.contains(Capability::BinaryData));
+                            (app_id, user_id, capabilities) // get app ID, user ID and agreed capabilities
                         } else {
                             writer
                                 .send(Response::InvalidToken { user_id }.to_pdu(id))
@@ -114,7 +227,7 @@ impl ConnectionManager {
             None => anyhow::bail!("Connection closed"),
         };
 
-        tracing::info!(%user_id, "user sign in [OK]");
+        tracing::info!(%app_id, %user_id, ?capabilities, "user sign in [OK]");
 
         let (client_writer, mut transfer) = channel::<Pdu>(32);
         tokio::spawn(async move {
@@ -144,15 +257,44 @@ impl ConnectionManager {
         });
 
         let pusher = client_writer.clone();
-        self.connections
-            .insert(user_id, Connection::new(user_id, pusher));
-        self.session_store.store(user_id, &self.service_uri).await?;
+        let kicked = Arc::new(Notify::new());
+        let connection_id = Uuid::new_v4();
+        self.connections.insert(
+            (app_id.clone(), user_id),
+            Connection::new(
+                connection_id,
+                app_id.clone(),
+                user_id,
+                pusher,
+                kicked.clone(),
+                capabilities.clone(),
+            ),
+        );
+        self.session_store
+            .store(&app_id, user_id, &self.service_uri)
+            .await?;
 
         let ss = self.session_store.clone();
+        let service_uri = self.service_uri.clone();
         let mut receiver = self.receiver.clone();
         let connections = self.connections.clone();
+        let user_limiter = self.user_limiter.clone();
+        let moderator = self.moderator.clone();
+        let mut connection_bucket = self
+            .connection_limit
+            .map(|config| TokenBucket::new(config, current_millisecond()));
         tokio::spawn(async move {
-            while let Some(pdu) = client_reader.recv().await {
+            loop {
+                let pdu = tokio::select! {
+                    pdu = client_reader.recv() => match pdu {
+                        Some(pdu) => pdu,
+                        None => break,
+                    },
+                    _ = kicked.notified() => {
+                        tracing::info!(%user_id, "User is kicked");
+                        break;
+                    }
+                };
                 tracing::info!("receive pdu: {:?}", pdu);
                 let Pdu { id: req_id, body } = pdu;
                 if let Body::Req(req) = body {
@@ -164,14 +306,93 @@ impl ConnectionManager {
                                 break;
                             }
                         }
-                        Request::Send { message } => {
-                            let rpc_message = RpcMessage::try_from(&message)?;
+                        Request::Send { mut message } => {
+                            if message.content.is_encrypted()
+                                && !capabilities.contains(Capability::Encryption)
+                            {
+                                if let Err(e) = client_writer
+                                    .send(
+                                        Response::Rejected {
+                                            id: message.id,
+                                            error: "Encryption is not negotiated".to_string(),
+                                        }
+                                        .to_pdu(req_id),
+                                    )
+                                    .await
+                                {
+                                    tracing::error!("Failed to send response to client: {:?}", e.0);
+                                    break;
+                                }
+                                continue;
+                            }
+
+                            if let Some(retry_after_ms) = Self::acquire(
+                                user_limiter.as_deref(),
+                                connection_bucket.as_mut(),
+                                &app_id,
+                                user_id,
+                            )
+                            .await
+                            {
+                                tracing::debug!(%app_id, %user_id, retry_after_ms, "Rate limited");
+                                let response = if capabilities.contains(Capability::RateLimit) {
+                                    Response::RateLimited {
+                                        id: message.id,
+                                        retry_after_ms,
+                                    }
+                                } else {
+                                    Response::Rejected {
+                                        id: message.id,
+                                        error: format!(
+                                            "Rate limited, retry after {}ms",
+                                            retry_after_ms
+                                        ),
+                                    }
+                                };
+                                if let Err(e) = client_writer.send(response.to_pdu(req_id)).await {
+                                    tracing::error!("Failed to send response to client: {:?}", e.0);
+                                    break;
+                                }
+                                continue;
+                            }
+
+                            if let Some(moderator) = &moderator {
+                                if let Outcome::Rejected(reason) =
+                                    moderator.moderate(&app_id, &mut message).await
+                                {
+                                    tracing::info!(%app_id, %user_id, %reason, "Message is rejected by moderation");
+                                    if let Err(e) = client_writer
+                                        .send(
+                                            Response::Rejected {
+                                                id: message.id,
+                                                error: reason,
+                                            }
+                                            .to_pdu(req_id),
+                                        )
+                                        .await
+                                    {
+                                        tracing::error!(
+                                            "Failed to send response to client: {:?}",
+                                            e.0
+                                        );
+                                        break;
+                                    }
+                                    continue;
+                                }
+                            }
+
+                            let mut rpc_message = RpcMessage::try_from(&message)?;
+                            // 消息只能发往连接所属的应用
+                            rpc_message.app_id = app_id.clone();
                             let req = tonic::Request::new(rpc_message);
                             match receiver.enqueue(req).await {
                                 Ok(resp) => {
                                     let result = resp.into_inner();
                                     tracing::info!("enqueue result: {}", result.ok);
 
+                                    if !capabilities.contains(Capability::Receipt) {
+                                        continue;
+                                    }
                                     if let Err(e) = client_writer
                                         .send(Response::Queued { id: message.id }.to_pdu(req_id))
                                         .await
@@ -211,13 +432,21 @@ impl ConnectionManager {
                 }
             }
 
-            if let Err(error) = ss.remove(user_id).await {
-                tracing::warn!(%error, "Failed to remove session");
+            // 用户重连后旧连接才关闭时，已登录的是新连接，不能删除新连接及其会话
+            if !remove_connection(&connections, &app_id, user_id, connection_id) {
+                tracing::info!(%app_id, %user_id, "Stale connection closed, user is reconnected");
+                return Ok(());
+            }
+            tracing::info!(%app_id, %user_id, "User connection removed");
+
+            if let Some(limiter) = &user_limiter {
+                limiter
+                    .release(&app_id, user_id, current_millisecond())
+                    .await;
             }
 
-            match connections.remove(&user_id) {
-                None => tracing::warn!(%user_id, "Failed to remove connection"),
-                Some(_) => tracing::info!(%user_id, "User connection removed"),
+            if let Err(error) = ss.remove_if(&app_id, user_id, &service_uri).await {
+                tracing::warn!(%error, "Failed to remove session");
             }
 
             Ok::<_, anyhow::Error>(())
@@ -226,48 +455,146 @@ impl ConnectionManager {
         Ok(())
     }
 
-    /// 根据用户ID获取对应的连接对象
-    pub fn get(&self, user_id: Uuid) -> Option<RefMut<Uuid, Connection>> {
-        self.connections.get_mut(&user_id)
+    /// 根据应用ID及用户ID获取对应的连接对象
+    pub fn get(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+    ) -> Option<RefMut<'_, ConnectionKey, Connection>> {
+        self.connections.get_mut(&(app_id.to_string(), user_id))
+    }
+
+    /// 删除应用ID及用户ID对应的连接对象
+    pub fn remove(&self, app_id: &str, user_id: Uuid) -> Option<(ConnectionKey, Connection)> {
+        self.connections.remove(&(app_id.to_string(), user_id))
     }
 
-    /// 删除用户ID对应的连接对象
-    pub fn remove(&self, user_id: Uuid) -> Option<(Uuid, Connection)> {
-        self.connections.remove(&user_id)
+    /// 断开用户连接，用户不在线时返回 `false`
+    pub fn kick(&self, app_id: &str, user_id: Uuid) -> bool {
+        match self.connections.get(&(app_id.to_string(), user_id)) {
+            Some(connection) => {
+                connection.kick();
+                true
+            }
+            None => false,
+        }
     }
 }
 
+/// 只在映射中仍是 ID 为 `connection_id` 的连接时删除，返回是否删除
+fn remove_connection(
+    connections: &DashMap<ConnectionKey, Connection>,
+    app_id: &str,
+    user_id: Uuid,
+    connection_id: Uuid,
+) -> bool {
+    connections
+        .remove_if(&(app_id.to_string(), user_id), |_, connection| {
+            connection.id == connection_id
+        })
+        .is_some()
+}
+
 /// 用户连接
 pub struct Connection {
+    id: Uuid,
+    app_id: String,
     user_id: Uuid,
     pusher: Sender<Pdu>,
     id_gen: TransactionIdGenerator,
+    kicked: Arc<Notify>,
+    capabilities: Capabilities,
 }
 
 impl Connection {
     /// 构造用户连接
-    fn new(user_id: Uuid, pusher: Sender<Pdu>) -> Self {
+    fn new(
+        id: Uuid,
+        app_id: String,
+        user_id: Uuid,
+        pusher: Sender<Pdu>,
+        kicked: Arc<Notify>,
+        capabilities: Capabilities,
+    ) -> Self {
         Self {
+            id,
+            app_id,
             user_id,
             pusher,
             id_gen: TransactionIdGenerator::default(),
+            kicked,
+            capabilities,
         }
     }
 
+    /// 断开连接
+    pub fn kick(&self) {
+        self.kicked.notify_one();
+    }
+
+    /// 连接所属的应用ID
+    pub fn app_id(&self) -> &str {
+        &self.app_id
+    }
+
     /// 连接的用户ID
     pub fn user_id(&self) -> &Uuid {
         &self.user_id
     }
 
+    /// 登录时协商的能力
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
     /// 推送消息
     pub async fn push(&mut self, message: Message) -> anyhow::Result<()> {
         let id = self.id_gen.next_id();
-        Ok(self
-            .pusher
+        self.pusher
             .send(Request::Push { message }.to_pdu(id))
             .await
             .map_err(|e| {
                 anyhow::anyhow!("Connection closed, Failed to send pdu to client: {:?}", e.0)
-            })?)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{remove_connection, Connection, ConnectionKey};
+    use dashmap::DashMap;
+    use jinshu_protocol::Capabilities;
+    use tokio::sync::mpsc::channel;
+    use uuid::Uuid;
+
+    fn connection(id: Uuid, user_id: Uuid) -> Connection {
+        Connection::new(
+            id,
+            "app".into(),
+            user_id,
+            channel(1).0,
+            Default::default(),
+            Capabilities::default(),
+        )
+    }
+
+    #[test]
+    fn stale_connection() {
+        let connections: DashMap<ConnectionKey, Connection> = DashMap::new();
+        let user_id = Uuid::new_v4();
+        let key = ("app".to_string(), user_id);
+        let (stale, live) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // 用户重连，新连接替换旧连接
+        connections.insert(key.clone(), connection(stale, user_id));
+        connections.insert(key.clone(), connection(live, user_id));
+
+        // 旧连接关闭时不删除新连接
+        assert!(!remove_connection(&connections, "app", user_id, stale));
+        assert!(connections.get(&key).map(|c| c.id == live).unwrap());
+
+        assert!(remove_connection(&connections, "app", user_id, live));
+        assert!(connections.is_empty());
+        assert!(!remove_connection(&connections, "app", user_id, live));
     }
 }
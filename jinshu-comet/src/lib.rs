@@ -12,3 +12,9 @@ pub mod comet;
 
 /// 连接管理
 pub mod connection;
+
+/// 发送消息限流
+pub mod limiter;
+
+/// 内容审核
+pub mod moderation;
@@ -0,0 +1,341 @@
+use dashmap::DashMap;
+use deadpool_redis::redis::Script;
+use jinshu_redis::get_rate_limit_key;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 令牌桶的配置
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct BucketConfig {
+    /// 桶的容量，即允许的突发发送数
+    pub capacity: u32,
+    /// 每秒补充的令牌数
+    pub refill_per_sec: f64,
+}
+
+impl BucketConfig {
+    fn refill_per_ms(&self) -> f64 {
+        self.refill_per_sec / 1000.0
+    }
+}
+
+/// 发送消息的限流配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// 每个用户的令牌桶，为空时不限制
+    pub user: Option<BucketConfig>,
+    /// 每个连接的令牌桶，为空时不限制
+    pub connection: Option<BucketConfig>,
+    /// 用户的令牌桶是否存储在 Redis 中，以便在多个 Comet 节点间共享
+    pub distributed: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            user: Some(BucketConfig {
+                capacity: 100,
+                refill_per_sec: 50.0,
+            }),
+            connection: Some(BucketConfig {
+                capacity: 50,
+                refill_per_sec: 20.0,
+            }),
+            distributed: false,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// 构造用户限流器，未配置用户令牌桶时返回 `None`
+    pub fn user_limiter(&self, redis: deadpool_redis::Pool) -> Option<Arc<dyn RateLimiter>> {
+        let config = self.user?;
+        Some(if self.distributed {
+            Arc::new(RedisRateLimiter::new(redis, config))
+        } else {
+            Arc::new(LocalRateLimiter::new(config))
+        })
+    }
+}
+
+/// 令牌桶
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_ms: f64,
+    tokens: f64,
+    last_ms: u64,
+}
+
+impl TokenBucket {
+    /// 构造一个装满令牌的桶
+    pub fn new(config: BucketConfig, now_ms: u64) -> Self {
+        let capacity = config.capacity as f64;
+        Self {
+            capacity,
+            refill_per_ms: config.refill_per_ms(),
+            tokens: capacity,
+            last_ms: now_ms,
+        }
+    }
+
+    fn refill(&mut self, now_ms: u64) {
+        if now_ms > self.last_ms {
+            let elapsed = (now_ms - self.last_ms) as f64;
+            self.tokens = self
+                .capacity
+                .min(self.tokens + elapsed * self.refill_per_ms);
+            self.last_ms = now_ms;
+        }
+    }
+
+    /// 尝试取出一个令牌，令牌不足时返回需要等待的毫秒数
+    pub fn acquire(&mut self, now_ms: u64) -> Option<u64> {
+        self.refill(now_ms);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else if self.refill_per_ms > 0.0 {
+            Some(((1.0 - self.tokens) / self.refill_per_ms).ceil() as u64)
+        } else {
+            Some(u64::MAX)
+        }
+    }
+
+    /// 桶是否已经装满
+    pub fn is_full(&mut self, now_ms: u64) -> bool {
+        self.refill(now_ms);
+        self.tokens >= self.capacity
+    }
+}
+
+/// 用户限流器
+#[async_trait::async_trait]
+pub trait RateLimiter: Send + Sync + 'static {
+    /// 为应用 `app_id` 的用户发送一条消息取出一个令牌，被限流时返回需要等待的毫秒数
+    async fn acquire(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        now_ms: u64,
+    ) -> anyhow::Result<Option<u64>>;
+
+    /// 用户断开连接时调用，用于回收资源
+    async fn release(&self, _app_id: &str, _user_id: Uuid, _now_ms: u64) {}
+}
+
+/// 清理已装满的令牌桶的间隔毫秒数
+const SWEEP_INTERVAL_MS: u64 = 60_000;
+
+/// 单节点的用户限流器，令牌桶保存在内存中
+#[derive(Debug)]
+pub struct LocalRateLimiter {
+    config: BucketConfig,
+    buckets: DashMap<(String, Uuid), TokenBucket>,
+    last_sweep_ms: AtomicU64,
+}
+
+impl LocalRateLimiter {
+    /// 使用令牌桶配置构造
+    pub fn new(config: BucketConfig) -> Self {
+        Self {
+            config,
+            buckets: DashMap::new(),
+            last_sweep_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// 每隔 [`SWEEP_INTERVAL_MS`] 移除已装满的令牌桶，装满的桶与新建的桶等价，
+    /// 移除后不影响限流，断开连接时未装满而保留的桶由此回收
+    fn sweep(&self, now_ms: u64) {
+        let last = self.last_sweep_ms.load(Ordering::Relaxed);
+        if now_ms.saturating_sub(last) < SWEEP_INTERVAL_MS
+            || self
+                .last_sweep_ms
+                .compare_exchange(last, now_ms, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+        self.buckets.retain(|_, bucket| !bucket.is_full(now_ms));
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimiter for LocalRateLimiter {
+    async fn acquire(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        now_ms: u64,
+    ) -> anyhow::Result<Option<u64>> {
+        self.sweep(now_ms);
+        Ok(self
+            .buckets
+            .entry((app_id.to_string(), user_id))
+            .or_insert_with(|| TokenBucket::new(self.config, now_ms))
+            .acquire(now_ms))
+    }
+
+    async fn release(&self, app_id: &str, user_id: Uuid, now_ms: u64) {
+        // 桶未装满时保留，避免用户通过重连绕过限流，装满后由 `sweep` 回收
+        self.buckets
+            .remove_if_mut(&(app_id.to_string(), user_id), |_, bucket| {
+                bucket.is_full(now_ms)
+            });
+    }
+}
+
+/// 令牌桶脚本，返回需要等待的毫秒数，为 0 时表示取出令牌成功
+const TOKEN_BUCKET_SCRIPT: &str = r"
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'time')
+local tokens = tonumber(state[1]) or capacity
+local time = tonumber(state[2]) or now
+if now > time then
+  tokens = math.min(capacity, tokens + (now - time) * rate)
+  time = now
+end
+local wait = 0
+if tokens >= 1 then
+  tokens = tokens - 1
+elseif rate > 0 then
+  wait = math.ceil((1 - tokens) / rate)
+else
+  wait = -1
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'time', tostring(time))
+if rate > 0 then
+  redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / rate) + 1000)
+end
+return wait
+";
+
+/// 分布式的用户限流器，令牌桶保存在 Redis 中，多个 Comet 节点共享同一用户的令牌桶
+#[derive(Clone)]
+pub struct RedisRateLimiter {
+    redis: deadpool_redis::Pool,
+    config: BucketConfig,
+    script: Script,
+}
+
+impl RedisRateLimiter {
+    /// 使用 Redis 连接池及令牌桶配置构造
+    pub fn new(redis: deadpool_redis::Pool, config: BucketConfig) -> Self {
+        Self {
+            redis,
+            config,
+            script: Script::new(TOKEN_BUCKET_SCRIPT),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimiter for RedisRateLimiter {
+    async fn acquire(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        now_ms: u64,
+    ) -> anyhow::Result<Option<u64>> {
+        let mut conn = self.redis.get().await?;
+        let wait: i64 = self
+            .script
+            .key(get_rate_limit_key(app_id, user_id.as_simple()))
+            .arg(self.config.capacity)
+            .arg(self.config.refill_per_ms())
+            .arg(now_ms)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(match wait {
+            0 => None,
+            w if w < 0 => Some(u64::MAX),
+            w => Some(w as u64),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        BucketConfig, LocalRateLimiter, RateLimitConfig, RateLimiter, TokenBucket,
+        SWEEP_INTERVAL_MS,
+    };
+    use uuid::Uuid;
+
+    const CONFIG: BucketConfig = BucketConfig {
+        capacity: 2,
+        refill_per_sec: 10.0,
+    };
+
+    #[test]
+    fn default() {
+        RateLimitConfig::default();
+    }
+
+    #[test]
+    fn bucket() {
+        let mut bucket = TokenBucket::new(CONFIG, 1000);
+        assert_eq!(bucket.acquire(1000), None);
+        assert_eq!(bucket.acquire(1000), None);
+        assert_eq!(bucket.acquire(1000), Some(100));
+        assert_eq!(bucket.acquire(1040), Some(60));
+        assert_eq!(bucket.acquire(1100), None);
+        assert!(!bucket.is_full(1100));
+        assert!(bucket.is_full(1300));
+        assert_eq!(bucket.acquire(10000), None);
+        assert_eq!(bucket.acquire(10000), None);
+        assert_eq!(bucket.acquire(10000), Some(100));
+    }
+
+    #[test]
+    fn no_refill() {
+        let mut bucket = TokenBucket::new(
+            BucketConfig {
+                capacity: 1,
+                refill_per_sec: 0.0,
+            },
+            0,
+        );
+        assert_eq!(bucket.acquire(0), None);
+        assert_eq!(bucket.acquire(u64::MAX), Some(u64::MAX));
+    }
+
+    #[tokio::test]
+    async fn local() {
+        let limiter = LocalRateLimiter::new(CONFIG);
+        let user = Uuid::new_v4();
+        assert_eq!(limiter.acquire("a", user, 0).await.unwrap(), None);
+        assert_eq!(limiter.acquire("a", user, 0).await.unwrap(), None);
+        assert_eq!(limiter.acquire("a", user, 0).await.unwrap(), Some(100));
+        // 不同应用的用户互不影响
+        assert_eq!(limiter.acquire("b", user, 0).await.unwrap(), None);
+
+        // 未装满的桶在断开连接后保留
+        limiter.release("a", user, 0).await;
+        assert_eq!(limiter.acquire("a", user, 0).await.unwrap(), Some(100));
+        limiter.release("a", user, 1000).await;
+        assert_eq!(limiter.buckets.len(), 1);
+        assert!(!limiter.buckets.contains_key(&("a".to_string(), user)));
+    }
+    #[tokio::test]
+    async fn sweep() {
+        let limiter = LocalRateLimiter::new(CONFIG);
+        let (idle, busy) = (Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(limiter.acquire("a", idle, 1).await.unwrap(), None);
+        // 断开连接时桶未装满，保留
+        limiter.release("a", idle, 1).await;
+        assert_eq!(limiter.buckets.len(), 1);
+
+        // 装满的桶在下次清理时移除，仍在发送的用户的桶保留
+        let now = SWEEP_INTERVAL_MS + 1;
+        assert_eq!(limiter.acquire("a", busy, now).await.unwrap(), None);
+        assert_eq!(limiter.buckets.len(), 1);
+        assert!(limiter.buckets.contains_key(&("a".to_string(), busy)));
+    }
+}
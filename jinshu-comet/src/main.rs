@@ -1,11 +1,14 @@
 use jinshu_comet::comet::Comet;
 use jinshu_comet::config::CometConfig;
 use jinshu_comet::connection::ConnectionManager;
+use jinshu_comet::moderation::{KeywordFilter, Moderator};
 use jinshu_common::Config;
 use jinshu_redis::config::RedisConfig;
+use jinshu_redis::moderation::FlaggedStore;
 use jinshu_redis::session::SessionStore;
 use jinshu_rpc::authorizer::authorizer_client;
 use jinshu_rpc::comet::comet_server::CometServer;
+use jinshu_rpc::moderator::moderator_client::ModeratorClient;
 use jinshu_rpc::receiver::receiver_client;
 use jinshu_rpc::registry::etcd::{EtcdConfig, EtcdRegistry};
 use jinshu_rpc::registry::Registry;
@@ -40,6 +43,10 @@ async fn main() -> anyhow::Result<()> {
                 service,
                 receiver_name,
                 authorizer_name,
+                rate_limit,
+                moderation,
+                compression,
+                max_frame_len,
             },
         ..
     } = conf;
@@ -63,11 +70,45 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!(?redis);
     let redis_config: deadpool_redis::Config = redis.into();
     let redis = redis_config.builder()?.build()?;
-    let session_store = SessionStore::from_pool(redis);
+    let session_store = SessionStore::from_pool(redis.clone());
+
+    tracing::info!(?rate_limit);
+    let user_limiter = rate_limit.user_limiter(redis.clone());
+
+    tracing::info!(?moderation);
+    let mut moderator_keeper = None;
+    let moderator = if moderation.enabled {
+        let filter = KeywordFilter::new(&moderation.rules, moderation.mask)?;
+        let remote = match &moderation.service_name {
+            Some(name) => {
+                let (channel, keeper) = registry.discover_channel(name).await?;
+                moderator_keeper = Some(keeper);
+                Some(ModeratorClient::new(channel))
+            }
+            None => None,
+        };
+        Some(Moderator::new(
+            filter,
+            remote,
+            moderation.fail_open,
+            FlaggedStore::from_pool(redis),
+        ))
+    } else {
+        None
+    };
 
     let register_key = registry.get_register_key(&service.service_name, &service_uri);
-    let connection_manager =
-        ConnectionManager::new(&register_key, receiver, authorizer, session_store);
+    let connection_manager = ConnectionManager::new(
+        &register_key,
+        receiver,
+        authorizer,
+        session_store,
+        user_limiter,
+        rate_limit.connection,
+        moderator,
+    )
+    .with_compression(compression)
+    .with_max_frame_len(max_frame_len);
 
     let comet = Comet::new(connection_manager.clone());
     let mut handle = registry
@@ -109,6 +150,9 @@ async fn main() -> anyhow::Result<()> {
 
     rk.close().await??;
     ak.close().await??;
+    if let Some(mk) = moderator_keeper {
+        mk.close().await??;
+    }
     tracing::info!("Service keeper closed.");
 
     Ok(())
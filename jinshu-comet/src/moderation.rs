@@ -0,0 +1,371 @@
+use jinshu_protocol::{Content, Message};
+use jinshu_redis::moderation::{FlaggedMessage, FlaggedStore};
+use jinshu_rpc::domain::message::Message as RpcMessage;
+use jinshu_rpc::moderator::moderation_result::Action as RemoteAction;
+use jinshu_rpc::moderator::moderator_client::ModeratorClient;
+use jinshu_utils::current_millisecond;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tonic::transport::Channel;
+
+/// 命中规则后的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// 拒绝发送
+    Reject,
+    /// 将命中的内容替换为掩码后发送
+    Mask,
+    /// 发送并标记为待复审
+    Flag,
+}
+
+/// 审核规则
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RuleConfig {
+    /// 关键词，`regex` 为 `true` 时为正则表达式
+    pub pattern: String,
+    /// 是否为正则表达式
+    #[serde(default)]
+    pub regex: bool,
+    /// 处理方式
+    pub action: Action,
+}
+
+/// 内容审核配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ModerationConfig {
+    /// 是否启用审核
+    pub enabled: bool,
+    /// 本地关键词及正则规则，关键词不区分大小写
+    pub rules: Vec<RuleConfig>,
+    /// 替换命中内容使用的字符
+    pub mask: char,
+    /// 外部审核服务名，为空时只使用本地规则
+    pub service_name: Option<String>,
+    /// 外部审核服务不可用时是否放行
+    pub fail_open: bool,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: Vec::new(),
+            mask: '*',
+            service_name: None,
+            fail_open: true,
+        }
+    }
+}
+
+/// 审核结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// 通过，消息内容可能已被替换
+    Pass,
+    /// 拒绝
+    Rejected(String),
+    /// 通过并标记为待复审
+    Flagged(Vec<String>),
+}
+
+impl Outcome {
+    fn merge(self, other: Outcome) -> Outcome {
+        match (self, other) {
+            (r @ Outcome::Rejected(_), _) | (_, r @ Outcome::Rejected(_)) => r,
+            (Outcome::Flagged(mut a), Outcome::Flagged(b)) => {
+                a.extend(b);
+                Outcome::Flagged(a)
+            }
+            (f @ Outcome::Flagged(_), Outcome::Pass) | (Outcome::Pass, f @ Outcome::Flagged(_)) => {
+                f
+            }
+            (Outcome::Pass, Outcome::Pass) => Outcome::Pass,
+        }
+    }
+}
+
+/// 本地关键词及正则过滤器，只检查文本消息及链接消息的地址，端到端加密消息直接通过
+#[derive(Debug)]
+pub struct KeywordFilter {
+    rules: Vec<(Regex, Action, String)>,
+    mask: char,
+}
+
+impl KeywordFilter {
+    /// 使用规则构造，正则表达式非法时返回错误
+    pub fn new(rules: &[RuleConfig], mask: char) -> Result<Self, regex::Error> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let pattern = if rule.regex {
+                    rule.pattern.clone()
+                } else {
+                    regex::escape(&rule.pattern)
+                };
+                let regex = RegexBuilder::new(&pattern)
+                    .case_insensitive(!rule.regex)
+                    .build()?;
+                Ok((regex, rule.action, rule.pattern.clone()))
+            })
+            .collect::<Result<_, regex::Error>>()?;
+        Ok(Self { rules, mask })
+    }
+
+    /// 检查消息内容，需要替换时直接修改消息内容
+    pub fn apply(&self, content: &mut Content) -> Outcome {
+        match content {
+            Content::Data { mime, bytes } if mime.type_() == mime::TEXT => {
+                let mut text = match std::str::from_utf8(bytes) {
+                    Ok(text) => text.to_string(),
+                    Err(_) => return Outcome::Pass,
+                };
+
+                let mut outcome = Outcome::Pass;
+                let mut masked = false;
+                for (regex, action, pattern) in &self.rules {
+                    if !regex.is_match(&text) {
+                        continue;
+                    }
+                    match action {
+                        Action::Reject => return Outcome::Rejected(reason(pattern)),
+                        Action::Flag => {
+                            outcome = outcome.merge(Outcome::Flagged(vec![reason(pattern)]))
+                        }
+                        Action::Mask => {
+                            let mask = self.mask;
+                            text = regex
+                                .replace_all(&text, |c: &regex::Captures| {
+                                    c[0].chars().map(|_| mask).collect::<String>()
+                                })
+                                .into_owned();
+                            masked = true;
+                        }
+                    }
+                }
+
+                if masked {
+                    *bytes = text.into();
+                }
+                outcome
+            }
+            Content::Link { url } => {
+                let mut outcome = Outcome::Pass;
+                for (regex, action, pattern) in &self.rules {
+                    if !regex.is_match(url.as_str()) {
+                        continue;
+                    }
+                    // 链接无法部分替换，命中替换规则时标记
+                    match action {
+                        Action::Reject => return Outcome::Rejected(reason(pattern)),
+                        Action::Mask | Action::Flag => {
+                            outcome = outcome.merge(Outcome::Flagged(vec![reason(pattern)]))
+                        }
+                    }
+                }
+                outcome
+            }
+            _ => Outcome::Pass,
+        }
+    }
+}
+
+fn reason(pattern: &str) -> String {
+    format!("Matched rule: {}", pattern)
+}
+
+/// 内容审核，先使用本地规则过滤，再调用外部审核服务；标记的消息保存到 [`FlaggedStore`] 供管理端复审
+#[derive(Clone)]
+pub struct Moderator {
+    filter: Arc<KeywordFilter>,
+    remote: Option<ModeratorClient<Channel>>,
+    fail_open: bool,
+    flagged: FlaggedStore,
+}
+
+impl Moderator {
+    /// 构造内容审核
+    pub fn new(
+        filter: KeywordFilter,
+        remote: Option<ModeratorClient<Channel>>,
+        fail_open: bool,
+        flagged: FlaggedStore,
+    ) -> Self {
+        Self {
+            filter: Arc::new(filter),
+            remote,
+            fail_open,
+            flagged,
+        }
+    }
+
+    /// 审核应用 `app_id` 的消息，需要替换时直接修改消息内容
+    pub async fn moderate(&self, app_id: &str, message: &mut Message) -> Outcome {
+        let original = summary(&message.content);
+
+        let mut outcome = self.filter.apply(&mut message.content);
+        if let (Outcome::Pass | Outcome::Flagged(_), Some(remote)) = (&outcome, &self.remote) {
+            outcome = outcome.merge(self.moderate_remote(remote.clone(), app_id, message).await);
+        }
+
+        if let Outcome::Flagged(reasons) = &outcome {
+            tracing::info!(%app_id, id = %message.id, ?reasons, "Message is flagged");
+            let flagged = FlaggedMessage {
+                id: message.id,
+                app_id: app_id.to_string(),
+                from: message.from,
+                to: message.to,
+                timestamp: message.timestamp,
+                content: original,
+                reasons: reasons.clone(),
+                flag_time: current_millisecond(),
+            };
+            if let Err(error) = self.flagged.flag(&flagged).await {
+                tracing::error!(%error, id = %message.id, "Failed to save flagged message");
+            }
+        }
+
+        outcome
+    }
+
+    async fn moderate_remote(
+        &self,
+        mut remote: ModeratorClient<Channel>,
+        app_id: &str,
+        message: &mut Message,
+    ) -> Outcome {
+        let mut request = match RpcMessage::try_from(&*message) {
+            Ok(request) => request,
+            Err(e) => return Outcome::Rejected(e.to_string()),
+        };
+        request.app_id = app_id.to_string();
+
+        let result = match remote.moderate(request).await {
+            Ok(result) => result.into_inner(),
+            Err(status) => {
+                tracing::warn!(%status, "Failed to call moderation service");
+                return if self.fail_open {
+                    Outcome::Pass
+                } else {
+                    Outcome::Rejected("Moderation service is unavailable".into())
+                };
+            }
+        };
+
+        match result.action() {
+            RemoteAction::Pass => Outcome::Pass,
+            RemoteAction::Reject => Outcome::Rejected(result.reason),
+            RemoteAction::Flag => Outcome::Flagged(vec![result.reason]),
+            RemoteAction::Mask => match Content::try_from(result.content.as_slice()) {
+                Ok(content) => {
+                    message.content = content;
+                    Outcome::Pass
+                }
+                Err(e) => {
+                    tracing::warn!(%e, "Invalid masked content from moderation service");
+                    Outcome::Rejected("Invalid masked content".into())
+                }
+            },
+        }
+    }
+}
+
+/// 用于复审的消息内容摘要
+fn summary(content: &Content) -> String {
+    match content {
+        Content::Data { mime, bytes } if mime.type_() == mime::TEXT => {
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+        Content::Data { mime, bytes } => format!("<{}, {} bytes>", mime, bytes.len()),
+        Content::Link { url } => url.to_string(),
+        Content::Encrypted {
+            algorithm,
+            ciphertext,
+            ..
+        } => format!("<encrypted: {}, {} bytes>", algorithm, ciphertext.len()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Action, KeywordFilter, ModerationConfig, Outcome, RuleConfig};
+    use jinshu_protocol::Content;
+
+    fn rule(pattern: &str, regex: bool, action: Action) -> RuleConfig {
+        RuleConfig {
+            pattern: pattern.into(),
+            regex,
+            action,
+        }
+    }
+
+    fn text(content: &Content) -> &str {
+        match content {
+            Content::Data { bytes, .. } => std::str::from_utf8(bytes).unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn default() {
+        ModerationConfig::default();
+    }
+
+    #[test]
+    fn filter() {
+        let filter = KeywordFilter::new(
+            &[
+                rule("spam", false, Action::Reject),
+                rule("damn", false, Action::Mask),
+                rule(r"\d{11}", true, Action::Flag),
+            ],
+            '*',
+        )
+        .unwrap();
+
+        let mut content = Content::string("hello");
+        assert_eq!(filter.apply(&mut content), Outcome::Pass);
+        assert_eq!(text(&content), "hello");
+
+        let mut content = Content::string("Buy SPAM now");
+        assert!(matches!(filter.apply(&mut content), Outcome::Rejected(_)));
+
+        let mut content = Content::string("Damn it, damn");
+        assert_eq!(filter.apply(&mut content), Outcome::Pass);
+        assert_eq!(text(&content), "**** it, ****");
+
+        let mut content = Content::string("damn, call 13800000000");
+        assert!(matches!(filter.apply(&mut content), Outcome::Flagged(r) if r.len() == 1));
+        assert_eq!(text(&content), "****, call 13800000000");
+
+        let mut content = Content::link(url::Url::parse("https://spam.example.com").unwrap());
+        assert!(matches!(filter.apply(&mut content), Outcome::Rejected(_)));
+
+        let mut content = Content::data(mime::IMAGE_PNG, b"spam".to_vec());
+        assert_eq!(filter.apply(&mut content), Outcome::Pass);
+    }
+
+    #[test]
+    fn invalid_regex() {
+        assert!(KeywordFilter::new(&[rule("(", true, Action::Reject)], '*').is_err());
+        assert!(KeywordFilter::new(&[rule("(", false, Action::Reject)], '*').is_ok());
+    }
+
+    #[test]
+    fn merge() {
+        let flagged = |r: &str| Outcome::Flagged(vec![r.into()]);
+        assert_eq!(Outcome::Pass.merge(Outcome::Pass), Outcome::Pass);
+        assert_eq!(Outcome::Pass.merge(flagged("a")), flagged("a"));
+        assert_eq!(
+            flagged("a").merge(flagged("b")),
+            Outcome::Flagged(vec!["a".into(), "b".into()])
+        );
+        assert_eq!(
+            flagged("a").merge(Outcome::Rejected("b".into())),
+            Outcome::Rejected("b".into())
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use crate::header;
+use axum::async_trait;
+use axum::extract::{Extension, FromRequest, RequestParts};
+use axum::http::header::AUTHORIZATION;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use jinshu_rpc::authorizer::authorizer_client::AuthorizerClient;
+use jinshu_rpc::authorizer::{SignInResult, Token};
+use tonic::transport::Channel;
+use uuid::Uuid;
+
+/// 通过 Authorizer 服务验证的锦书用户
+///
+/// 从请求头 `x-jinshu-app-id`、`x-jinshu-user-id` 及 `Authorization: Bearer <token>` 中读取应用 ID、用户 ID 及令牌
+///
+#[derive(Debug, Clone)]
+pub struct AuthorizedUser {
+    /// 应用 ID
+    pub app_id: String,
+    /// 用户 ID
+    pub user_id: Uuid,
+}
+
+/// 验证锦书用户失败
+#[derive(Debug, thiserror::Error)]
+pub enum AuthorizeError {
+    /// 未登录或令牌无效
+    #[error("Unauthorized")]
+    Unauthorized,
+    /// 其他错误，如未配置 Authorizer 客户端
+    #[error("{0}")]
+    Other(String),
+}
+
+impl IntoResponse for AuthorizeError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            AuthorizeError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AuthorizeError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for AuthorizedUser {
+    type Rejection = AuthorizeError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Extension(mut authorizer) = Extension::<AuthorizerClient<Channel>>::from_request(req)
+            .await
+            .map_err(|e| AuthorizeError::Other(e.to_string()))?;
+
+        let headers = req.headers().ok_or(AuthorizeError::Unauthorized)?;
+
+        let app_id = headers
+            .get(header::APP_ID)
+            .and_then(|v| v.to_str().ok())
+            .ok_or(AuthorizeError::Unauthorized)?
+            .to_string();
+
+        let user_id: Uuid = headers
+            .get(header::USER_ID)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .ok_or(AuthorizeError::Unauthorized)?;
+
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or(AuthorizeError::Unauthorized)?
+            .trim()
+            .to_string();
+
+        let SignInResult { ok, .. } = authorizer
+            .sign_in(tonic::Request::new(Token {
+                app_id: app_id.clone(),
+                user_id: user_id.simple().to_string(),
+                token,
+            }))
+            .await
+            .map_err(|status| {
+                tracing::warn!(%status, "Failed to call authorizer");
+                AuthorizeError::Unauthorized
+            })?
+            .into_inner();
+
+        if ok {
+            Ok(Self { app_id, user_id })
+        } else {
+            Err(AuthorizeError::Unauthorized)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AuthorizeError, AuthorizedUser};
+    use crate::header;
+    use axum::extract::{FromRequest, RequestParts};
+    use axum::http::{Request, StatusCode};
+    use axum::response::IntoResponse;
+    use jinshu_rpc::authorizer::authorizer_client::AuthorizerClient;
+    use tonic::transport::Channel;
+    use uuid::Uuid;
+
+    #[test]
+    fn status_code() {
+        assert_eq!(
+            AuthorizeError::Unauthorized.into_response().status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            AuthorizeError::Other("test".into())
+                .into_response()
+                .status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_headers() {
+        let authorizer =
+            AuthorizerClient::new(Channel::from_static("http://127.0.0.1:1").connect_lazy());
+
+        let request = Request::builder().body(()).unwrap();
+        let mut parts = RequestParts::new(request);
+        // 未配置 Authorizer 客户端
+        assert!(matches!(
+            AuthorizedUser::from_request(&mut parts).await,
+            Err(AuthorizeError::Other(_))
+        ));
+
+        let request = Request::builder()
+            .header(header::APP_ID, "app")
+            .header(header::USER_ID, Uuid::new_v4().to_string())
+            .extension(authorizer)
+            .body(())
+            .unwrap();
+        let mut parts = RequestParts::new(request);
+        // 缺少令牌时不调用 Authorizer
+        assert!(matches!(
+            AuthorizedUser::from_request(&mut parts).await,
+            Err(AuthorizeError::Unauthorized)
+        ));
+    }
+}
@@ -200,6 +200,7 @@ mod tests {
         let mut file = OpenOptions::new()
             .write(true)
             .create(true)
+            .truncate(true)
             .open(&fullpath)?;
         serde_json::to_writer(&mut file, value).map_err(|_e| std::io::ErrorKind::Other)?;
         Ok(())
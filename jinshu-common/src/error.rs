@@ -9,6 +9,9 @@ pub enum Error {
     /// 配置错误
     #[error(transparent)]
     Config(#[from] config::ConfigError),
+    /// 令牌错误
+    #[error(transparent)]
+    Token(#[from] jsonwebtoken::errors::Error),
     /// 其他错误
     #[error("{0}")]
     Other(Cow<'static, str>),
@@ -0,0 +1,10 @@
+/// 用户所属的应用 ID
+pub const APP_ID: &str = "x-jinshu-app-id";
+/// 锦书用户 ID，与 `Authorization: Bearer <token>` 一起用于鉴权
+pub const USER_ID: &str = "x-jinshu-user-id";
+/// 请求时间戳（毫秒），用于请求签名
+pub const TIMESTAMP: &str = "x-jinshu-timestamp";
+/// 随机数，用于请求签名，防止重放
+pub const NONCE: &str = "x-jinshu-nonce";
+/// 请求签名，十六进制字符串，见 [`crate::signature::signature`]
+pub const SIGNATURE: &str = "x-jinshu-signature";
@@ -4,8 +4,17 @@
 //! 服务端模块公共库
 //!
 
+/// axum 服务共用的锦书用户鉴权
+#[cfg(feature = "axum")]
+pub mod auth;
 mod conf;
 mod error;
+/// HTTP 头
+pub mod header;
+/// 应用请求签名
+pub mod signature;
+/// 签名令牌
+pub mod token;
 
 pub use conf::*;
 pub use error::*;
@@ -0,0 +1,100 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 计算请求签名，十六进制字符串
+///
+/// 签名为 `HMAC-SHA256(secret, "{method}\n{path_and_query}\n{timestamp}\n{nonce}\n{hex(SHA256(body))}")`，
+/// `timestamp` 为毫秒时间戳
+///
+pub fn signature(
+    secret: &str,
+    method: &str,
+    path_and_query: &str,
+    timestamp: u64,
+    nonce: &str,
+    body: &[u8],
+) -> String {
+    hex::encode(
+        mac(secret, method, path_and_query, timestamp, nonce, body)
+            .finalize()
+            .into_bytes(),
+    )
+}
+
+/// 校验请求签名
+pub fn verify(
+    secret: &str,
+    method: &str,
+    path_and_query: &str,
+    timestamp: u64,
+    nonce: &str,
+    body: &[u8],
+    signature: &str,
+) -> bool {
+    match hex::decode(signature) {
+        Ok(bytes) => mac(secret, method, path_and_query, timestamp, nonce, body)
+            .verify_slice(&bytes)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn mac(
+    secret: &str,
+    method: &str,
+    path_and_query: &str,
+    timestamp: u64,
+    nonce: &str,
+    body: &[u8],
+) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("impossible: HMAC can take key of any size");
+    mac.update(
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            method,
+            path_and_query,
+            timestamp,
+            nonce,
+            hex::encode(Sha256::digest(body))
+        )
+        .as_bytes(),
+    );
+    mac
+}
+
+#[cfg(test)]
+mod test {
+    use super::{signature, verify};
+
+    #[test]
+    fn sign() {
+        let sig = signature("secret", "POST", "/sign_in", 1000, "nonce", b"{}");
+        assert!(verify(
+            "secret", "POST", "/sign_in", 1000, "nonce", b"{}", &sig
+        ));
+        assert!(!verify(
+            "other", "POST", "/sign_in", 1000, "nonce", b"{}", &sig
+        ));
+        assert!(!verify(
+            "secret", "DELETE", "/sign_in", 1000, "nonce", b"{}", &sig
+        ));
+        assert!(!verify(
+            "secret", "POST", "/user", 1000, "nonce", b"{}", &sig
+        ));
+        assert!(!verify(
+            "secret", "POST", "/sign_in", 1001, "nonce", b"{}", &sig
+        ));
+        assert!(!verify(
+            "secret", "POST", "/sign_in", 1000, "other", b"{}", &sig
+        ));
+        assert!(!verify(
+            "secret", "POST", "/sign_in", 1000, "nonce", b"[]", &sig
+        ));
+        assert!(!verify(
+            "secret", "POST", "/sign_in", 1000, "nonce", b"{}", "not hex"
+        ));
+    }
+}
@@ -8,6 +8,8 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
     pub id: String,
+    #[sea_orm(column_type = "Text")]
+    pub app_id: String,
     pub timestamp: TimeDateTimeWithTimeZone,
     #[sea_orm(column_type = "Text")]
     pub from: String,
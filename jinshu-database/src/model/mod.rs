@@ -3,9 +3,14 @@
 
 pub mod prelude;
 
+pub mod admin;
+pub mod app;
 pub mod block;
+pub mod conversation;
 pub mod friend;
 pub mod group;
 pub mod group_member;
+pub mod identity_key;
 pub mod message;
 pub mod user;
+pub mod user_setting;
@@ -1,8 +1,13 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.7.0
 
+pub use super::admin::Entity as Admin;
+pub use super::app::Entity as App;
 pub use super::block::Entity as Block;
+pub use super::conversation::Entity as Conversation;
 pub use super::friend::Entity as Friend;
 pub use super::group::Entity as Group;
 pub use super::group_member::Entity as GroupMember;
+pub use super::identity_key::Entity as IdentityKey;
 pub use super::message::Entity as Message;
 pub use super::user::Entity as User;
+pub use super::user_setting::Entity as UserSetting;
@@ -9,6 +9,8 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
     pub id: String,
     #[sea_orm(column_type = "Text")]
+    pub app_id: String,
+    #[sea_orm(column_type = "Text")]
     pub external_id: String,
     pub extension: Option<Json>,
     pub create_time: TimeDateTimeWithTimeZone,
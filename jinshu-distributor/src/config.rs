@@ -0,0 +1,72 @@
+use jinshu_queue::distributed_topic;
+use jinshu_rpc::config::ServiceConfig;
+use serde::{Deserialize, Serialize};
+
+/// Distributor 的配置
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DistributorConfig {
+    /// 分发服务配置，注册后用于健康检查
+    #[serde(flatten)]
+    pub service: ServiceConfig,
+
+    /// 分发给 Pusher 的主题名，实际主题为 `<入队主题>.<pusher_name>`
+    pub pusher_name: String,
+
+    /// 分发给 Storage 的主题名，实际主题为 `<入队主题>.<storage_name>`
+    pub storage_name: String,
+
+    /// 是否将发往群组的消息展开为发给每个群成员的消息再推送
+    pub expand_group: bool,
+
+    /// 直接丢弃的消息类型
+    pub deny_mimes: Vec<String>,
+
+    /// 只推送、不存储的消息类型，如正在输入提示
+    pub transient_mimes: Vec<String>,
+
+    /// 只存储、不推送的消息类型
+    pub silent_mimes: Vec<String>,
+}
+
+impl DistributorConfig {
+    /// 分发给 Pusher 的主题
+    pub fn pusher_topic(&self, topic: &str) -> String {
+        distributed_topic(topic, &self.pusher_name)
+    }
+
+    /// 分发给 Storage 的主题
+    pub fn storage_topic(&self, topic: &str) -> String {
+        distributed_topic(topic, &self.storage_name)
+    }
+}
+
+impl Default for DistributorConfig {
+    fn default() -> Self {
+        Self {
+            service: ServiceConfig {
+                service_name: "distributor".into(),
+                public_host: "0.0.0.0".into(),
+                listen_ip: [0u8, 0, 0, 0].into(),
+                listen_port: 9900,
+            },
+            pusher_name: "pusher".into(),
+            storage_name: "storage".into(),
+            expand_group: true,
+            deny_mimes: vec![],
+            transient_mimes: vec![],
+            silent_mimes: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DistributorConfig;
+
+    #[test]
+    fn default() {
+        let config = DistributorConfig::default();
+        assert_eq!(config.pusher_topic("jinshu.dev"), "jinshu.dev.pusher");
+        assert_eq!(config.storage_topic("jinshu.dev"), "jinshu.dev.storage");
+    }
+}
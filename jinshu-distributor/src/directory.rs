@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use jinshu_database::prelude::*;
+use jinshu_database::{block, group, group_member, user};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// 分发时需要的用户及群组关系
+#[async_trait]
+pub trait Directory: Send + Sync + 'static {
+    /// 判断 `id` 是否为应用 `app_id` 的用户或群组
+    async fn belongs_to(&self, app_id: &str, id: Uuid) -> crate::Result<bool>;
+
+    /// 查询应用 `app_id` 的群组成员，`id` 不是该应用的群组 ID 时返回 `None`
+    async fn group_members(&self, app_id: &str, id: Uuid) -> crate::Result<Option<Vec<Uuid>>>;
+
+    /// 返回 `users` 中屏蔽了 `sender` 的用户
+    async fn blocked_by(&self, sender: Uuid, users: &[Uuid]) -> crate::Result<HashSet<Uuid>>;
+}
+
+#[async_trait]
+impl Directory for DatabaseConnection {
+    async fn belongs_to(&self, app_id: &str, id: Uuid) -> crate::Result<bool> {
+        let id = id.as_simple().to_string();
+        let users = User::find()
+            .filter(user::Column::Id.eq(id.as_str()))
+            .filter(user::Column::AppId.eq(app_id))
+            .count(self)
+            .await?;
+        if users > 0 {
+            return Ok(true);
+        }
+
+        let groups = Group::find()
+            .filter(group::Column::Id.eq(id.as_str()))
+            .filter(group::Column::AppId.eq(app_id))
+            .count(self)
+            .await?;
+        Ok(groups > 0)
+    }
+
+    async fn group_members(&self, app_id: &str, id: Uuid) -> crate::Result<Option<Vec<Uuid>>> {
+        let group_id = id.as_simple().to_string();
+        if Group::find_by_id(group_id.clone())
+            .filter(group::Column::AppId.eq(app_id))
+            .one(self)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        let members = GroupMember::find()
+            .filter(group_member::Column::GroupId.eq(group_id))
+            .all(self)
+            .await?
+            .into_iter()
+            .map(|m| m.user_id.parse())
+            .collect::<Result<Vec<Uuid>, _>>()?;
+
+        Ok(Some(members))
+    }
+
+    async fn blocked_by(&self, sender: Uuid, users: &[Uuid]) -> crate::Result<HashSet<Uuid>> {
+        if users.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        Ok(Block::find()
+            .filter(block::Column::BlockId.eq(sender.as_simple().to_string()))
+            .filter(block::Column::UserId.is_in(users.iter().map(|u| u.as_simple().to_string())))
+            .all(self)
+            .await?
+            .into_iter()
+            .map(|b| b.user_id.parse())
+            .collect::<Result<HashSet<Uuid>, _>>()?)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::Directory;
+    use async_trait::async_trait;
+    use std::collections::{HashMap, HashSet};
+    use uuid::Uuid;
+
+    /// 内存中的关系，用于测试
+    #[derive(Debug, Default)]
+    pub struct MemoryDirectory {
+        /// 用户或群组所属的应用
+        pub apps: HashMap<Uuid, String>,
+        pub groups: HashMap<Uuid, Vec<Uuid>>,
+        /// (用户, 被屏蔽的用户)
+        pub blocks: HashSet<(Uuid, Uuid)>,
+    }
+
+    impl MemoryDirectory {
+        /// 将用户或群组加入应用
+        pub fn join(&mut self, app_id: &str, ids: &[Uuid]) {
+            for id in ids {
+                self.apps.insert(*id, app_id.to_string());
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Directory for MemoryDirectory {
+        async fn belongs_to(&self, app_id: &str, id: Uuid) -> crate::Result<bool> {
+            Ok(self.apps.get(&id).map(String::as_str) == Some(app_id))
+        }
+
+        async fn group_members(&self, app_id: &str, id: Uuid) -> crate::Result<Option<Vec<Uuid>>> {
+            if !self.belongs_to(app_id, id).await? {
+                return Ok(None);
+            }
+            Ok(self.groups.get(&id).cloned())
+        }
+
+        async fn blocked_by(&self, sender: Uuid, users: &[Uuid]) -> crate::Result<HashSet<Uuid>> {
+            Ok(users
+                .iter()
+                .filter(|u| self.blocks.contains(&(**u, sender)))
+                .copied()
+                .collect())
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use crate::config::DistributorConfig;
+use crate::directory::Directory;
+use crate::filter::MessageFilter;
+use crate::Error;
+use jinshu_protocol::Content;
+use jinshu_queue::{HandleResult, QueuedMessage, QueuedMessageHandler, QueuedMessageProducer};
+use uuid::Uuid;
+
+/// 一条消息的分发结果
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Distribution {
+    /// 分发给 Pusher 的消息数
+    pub pushed: usize,
+    /// 是否分发给 Storage
+    pub stored: bool,
+}
+
+/// 消息分发器
+///
+/// 接收者不是消息所属应用的用户或群组时直接丢弃，消息不会跨应用传递；
+/// 消息按类型决定是否推送及存储；发往群组的消息原样存储，推送时展开为发给每个群成员（发送者除外）的消息；
+/// 单聊消息的接收者屏蔽了发送者时直接丢弃，群成员屏蔽了发送者时不推送给该成员
+///
+pub struct Distributor<D, P> {
+    directory: D,
+    producer: P,
+    filter: MessageFilter,
+    expand_group: bool,
+    pusher_topic: String,
+    storage_topic: String,
+}
+
+impl<D: Directory, P: QueuedMessageProducer> Distributor<D, P> {
+    /// 使用配置、入队主题、关系查询及生产者构造
+    pub fn new(config: &DistributorConfig, topic: &str, directory: D, producer: P) -> Self {
+        Self {
+            directory,
+            producer,
+            filter: MessageFilter::new(config),
+            expand_group: config.expand_group,
+            pusher_topic: config.pusher_topic(topic),
+            storage_topic: config.storage_topic(topic),
+        }
+    }
+
+    /// 分发消息
+    pub async fn distribute(&self, message: &QueuedMessage) -> crate::Result<Distribution> {
+        let inner = message.inner();
+        let content = Content::try_from(inner.content.as_ref())?;
+
+        let route = self.filter.route(&content);
+        if route.is_none() {
+            tracing::info!("Message is denied by filter");
+            return Ok(Distribution::default());
+        }
+
+        let from = Uuid::from_slice(&inner.from)?;
+        let to = Uuid::from_slice(&inner.to)?;
+
+        let app_id = inner.app_id.as_str();
+        if !self.directory.belongs_to(app_id, to).await? {
+            tracing::info!(%app_id, %to, "Receiver is not in the app");
+            return Ok(Distribution::default());
+        }
+
+        let recipients = match self.group_members(app_id, to).await? {
+            Some(members) => {
+                let members: Vec<Uuid> = members.into_iter().filter(|m| *m != from).collect();
+                let blocked = self.directory.blocked_by(from, &members).await?;
+                members
+                    .into_iter()
+                    .filter(|m| !blocked.contains(m))
+                    .collect()
+            }
+            None => {
+                if !self.directory.blocked_by(from, &[to]).await?.is_empty() {
+                    tracing::info!(%from, %to, "Sender is blocked by receiver");
+                    return Ok(Distribution::default());
+                }
+                vec![to]
+            }
+        };
+
+        let mut distribution = Distribution::default();
+
+        if route.store {
+            self.producer.produce(&self.storage_topic, message).await?;
+            distribution.stored = true;
+        }
+
+        if route.push {
+            for recipient in recipients {
+                if recipient == to {
+                    self.producer.produce(&self.pusher_topic, message).await?;
+                } else {
+                    let mut expanded = inner.clone();
+                    expanded.to = recipient.as_bytes().to_vec();
+                    self.producer
+                        .produce(&self.pusher_topic, &QueuedMessage::new(expanded))
+                        .await?;
+                }
+                distribution.pushed += 1;
+            }
+        }
+
+        Ok(distribution)
+    }
+
+    async fn group_members(&self, app_id: &str, id: Uuid) -> crate::Result<Option<Vec<Uuid>>> {
+        if self.expand_group {
+            self.directory.group_members(app_id, id).await
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<D: Directory, P: QueuedMessageProducer> QueuedMessageHandler for Distributor<D, P> {
+    async fn handle(&self, topic: &str, message: &QueuedMessage) -> HandleResult {
+        match self.distribute(message).await {
+            Ok(Distribution { pushed, stored }) => {
+                tracing::info!(%topic, pushed, stored, "Message is distributed");
+                HandleResult::Ok
+            }
+            // 队列或数据库不可用时停止消费，避免提交未分发的消息
+            Err(e @ Error::Queue(_)) | Err(e @ Error::Database(_)) => {
+                HandleResult::Error(e.to_string().into())
+            }
+            Err(e) => HandleResult::Failure(e.to_string().into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Distribution, Distributor};
+    use crate::config::DistributorConfig;
+    use crate::directory::test::MemoryDirectory;
+    use jinshu_protocol::Content;
+    use jinshu_queue::{QueuedMessage, QueuedMessageProducer};
+    use jinshu_rpc::domain::message::Message as RpcMessage;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    #[derive(Default)]
+    struct MemoryProducer(Mutex<Vec<(String, RpcMessage)>>);
+
+    #[async_trait::async_trait]
+    impl QueuedMessageProducer for MemoryProducer {
+        async fn produce(
+            &self,
+            topic: &str,
+            message: &QueuedMessage,
+        ) -> jinshu_queue::error::Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((topic.to_string(), message.inner().clone()));
+            Ok(())
+        }
+    }
+
+    impl MemoryProducer {
+        fn receivers(&self, topic: &str) -> Vec<Uuid> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(t, _)| t == topic)
+                .map(|(_, m)| Uuid::from_slice(&m.to).unwrap())
+                .collect()
+        }
+    }
+
+    fn message(from: Uuid, to: Uuid, content: Content) -> QueuedMessage {
+        QueuedMessage::new(RpcMessage {
+            id: Uuid::new_v4().as_bytes().to_vec(),
+            timestamp: 0,
+            from: from.as_bytes().to_vec(),
+            to: to.as_bytes().to_vec(),
+            content: Vec::try_from(&content).unwrap().into(),
+            app_id: "app".into(),
+        })
+    }
+
+    fn config() -> DistributorConfig {
+        DistributorConfig {
+            transient_mimes: vec!["application/x-typing".into()],
+            ..Default::default()
+        }
+    }
+
+    const PUSHER: &str = "jinshu.pusher";
+    const STORAGE: &str = "jinshu.storage";
+
+    #[tokio::test]
+    async fn direct() -> crate::Result<()> {
+        let (alice, bob, mallory) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut directory = MemoryDirectory::default();
+        directory.join("app", &[alice, bob]);
+        directory.join("other", &[mallory]);
+        directory.blocks.insert((bob, alice));
+
+        let distributor =
+            Distributor::new(&config(), "jinshu", directory, MemoryProducer::default());
+
+        let result = distributor
+            .distribute(&message(bob, alice, Content::string("hi")))
+            .await?;
+        assert_eq!(
+            result,
+            Distribution {
+                pushed: 1,
+                stored: true
+            }
+        );
+        assert_eq!(distributor.producer.receivers(PUSHER), vec![alice]);
+        assert_eq!(distributor.producer.receivers(STORAGE), vec![alice]);
+
+        // bob 屏蔽了 alice
+        let result = distributor
+            .distribute(&message(alice, bob, Content::string("hi")))
+            .await?;
+        assert_eq!(result, Distribution::default());
+
+        let typing = Content::data("application/x-typing".parse().unwrap(), Vec::new());
+        let result = distributor.distribute(&message(bob, alice, typing)).await?;
+        assert_eq!(
+            result,
+            Distribution {
+                pushed: 1,
+                stored: false
+            }
+        );
+        assert_eq!(distributor.producer.receivers(STORAGE).len(), 1);
+
+        // mallory 属于其他应用
+        let result = distributor
+            .distribute(&message(alice, mallory, Content::string("hi")))
+            .await?;
+        assert_eq!(result, Distribution::default());
+        assert_eq!(distributor.producer.receivers(PUSHER).len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn group() -> crate::Result<()> {
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let group = Uuid::new_v4();
+
+        let mut directory = MemoryDirectory::default();
+        directory.join("app", &[alice, bob, carol, group]);
+        directory.groups.insert(group, vec![alice, bob, carol]);
+        directory.blocks.insert((carol, alice));
+
+        let distributor =
+            Distributor::new(&config(), "jinshu", directory, MemoryProducer::default());
+
+        let result = distributor
+            .distribute(&message(alice, group, Content::string("hi")))
+            .await?;
+        assert_eq!(
+            result,
+            Distribution {
+                pushed: 1,
+                stored: true
+            }
+        );
+        assert_eq!(distributor.producer.receivers(PUSHER), vec![bob]);
+        assert_eq!(distributor.producer.receivers(STORAGE), vec![group]);
+
+        let mut directory = MemoryDirectory::default();
+        directory.join("app", &[group]);
+        let distributor = Distributor::new(
+            &DistributorConfig {
+                expand_group: false,
+                ..config()
+            },
+            "jinshu",
+            directory,
+            MemoryProducer::default(),
+        );
+        distributor
+            .distribute(&message(alice, group, Content::string("hi")))
+            .await?;
+        assert_eq!(distributor.producer.receivers(PUSHER), vec![group]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,19 @@
+/// 分发服务错误
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// 消息队列错误
+    #[error(transparent)]
+    Queue(#[from] jinshu_queue::error::Error),
+    /// 数据库错误
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+    /// 不合法的 ID
+    #[error(transparent)]
+    InvalidId(#[from] uuid::Error),
+    /// 不合法的消息内容
+    #[error(transparent)]
+    InvalidContent(#[from] jinshu_protocol::InvalidContentFormat),
+}
+
+/// 分发服务结果
+pub type Result<T> = std::result::Result<T, Error>;
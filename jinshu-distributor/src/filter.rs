@@ -0,0 +1,158 @@
+use crate::config::DistributorConfig;
+use jinshu_protocol::Content;
+
+/// 链接消息匹配时使用的类型
+const LINK_MIME: &str = "text/uri-list";
+
+/// 端到端加密消息匹配时使用的类型，加密消息的真实类型对服务端不可见
+pub const ENCRYPTED_MIME: &str = "application/x-jinshu-encrypted";
+
+/// 消息的分发路由
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Route {
+    /// 是否分发给 Pusher
+    pub push: bool,
+    /// 是否分发给 Storage
+    pub store: bool,
+}
+
+impl Route {
+    /// 推送并存储
+    pub const ALL: Route = Route {
+        push: true,
+        store: true,
+    };
+
+    /// 丢弃
+    pub const NONE: Route = Route {
+        push: false,
+        store: false,
+    };
+
+    /// 是否丢弃
+    pub fn is_none(&self) -> bool {
+        !self.push && !self.store
+    }
+}
+
+/// 判断类型 `essence`（不含参数，如 `text/plain`）是否匹配 `pattern`，支持 `*/*` 及 `type/*`，不区分大小写
+pub fn mime_matches(pattern: &str, essence: &str) -> bool {
+    match pattern.split_once('/') {
+        Some(("*", "*")) => true,
+        Some((ty, "*")) => essence
+            .split_once('/')
+            .map(|(t, _)| ty.eq_ignore_ascii_case(t))
+            .unwrap_or_default(),
+        _ => pattern.eq_ignore_ascii_case(essence),
+    }
+}
+
+/// 按消息类型决定分发路由的过滤器
+///
+/// 链接消息按 `text/uri-list` 类型匹配
+///
+#[derive(Debug, Default)]
+pub struct MessageFilter {
+    deny: Vec<String>,
+    transient: Vec<String>,
+    silent: Vec<String>,
+}
+
+impl MessageFilter {
+    /// 使用配置构造
+    pub fn new(config: &DistributorConfig) -> Self {
+        Self {
+            deny: config.deny_mimes.clone(),
+            transient: config.transient_mimes.clone(),
+            silent: config.silent_mimes.clone(),
+        }
+    }
+
+    /// 决定消息的分发路由
+    pub fn route(&self, content: &Content) -> Route {
+        let essence = match content {
+            Content::Data { mime, .. } => mime.essence_str(),
+            Content::Link { .. } => LINK_MIME,
+            Content::Encrypted { .. } => ENCRYPTED_MIME,
+        };
+
+        let matches = |patterns: &[String]| patterns.iter().any(|p| mime_matches(p, essence));
+
+        if matches(&self.deny) {
+            Route::NONE
+        } else if matches(&self.transient) {
+            Route {
+                push: true,
+                store: false,
+            }
+        } else if matches(&self.silent) {
+            Route {
+                push: false,
+                store: true,
+            }
+        } else {
+            Route::ALL
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{mime_matches, MessageFilter, Route};
+    use crate::config::DistributorConfig;
+    use jinshu_protocol::Content;
+
+    #[test]
+    fn mime() {
+        assert!(mime_matches("*/*", "image/png"));
+        assert!(mime_matches("image/*", "image/png"));
+        assert!(mime_matches("IMAGE/PNG", "image/png"));
+        assert!(mime_matches(
+            "text/plain",
+            mime::TEXT_PLAIN_UTF_8.essence_str()
+        ));
+        assert!(!mime_matches("text/*", "image/png"));
+        assert!(!mime_matches("image/jpeg", "image/png"));
+    }
+
+    #[test]
+    fn route() {
+        let filter = MessageFilter::new(&DistributorConfig {
+            deny_mimes: vec!["application/x-spam".into()],
+            transient_mimes: vec!["application/x-typing".into()],
+            silent_mimes: vec!["text/uri-list".into()],
+            ..Default::default()
+        });
+
+        assert_eq!(filter.route(&Content::string("hello")), Route::ALL);
+        assert_eq!(
+            filter.route(&Content::encrypted("alg", "a", "b", [], [])),
+            Route::ALL
+        );
+        assert!(filter
+            .route(&Content::data(
+                "application/x-spam".parse().unwrap(),
+                Vec::new()
+            ))
+            .is_none());
+        assert_eq!(
+            filter.route(&Content::data(
+                "application/x-typing".parse().unwrap(),
+                Vec::new()
+            )),
+            Route {
+                push: true,
+                store: false
+            }
+        );
+        assert_eq!(
+            filter.route(&Content::link(
+                "http://localhost/".parse::<url::Url>().unwrap()
+            )),
+            Route {
+                push: false,
+                store: true
+            }
+        );
+    }
+}
@@ -0,0 +1,19 @@
+#![deny(missing_docs, unsafe_code)]
+//! # Distributor
+//!
+//! 消息分发服务，消费入队的消息，按消息类型分发到 Pusher 及 Storage 的主题
+//!
+//! 分发时会展开群组消息并过滤被屏蔽的消息
+//!
+
+/// 配置
+pub mod config;
+/// 用户及群组关系查询
+pub mod directory;
+/// 消息分发
+pub mod distributor;
+mod error;
+/// 按消息类型过滤
+pub mod filter;
+
+pub use error::*;
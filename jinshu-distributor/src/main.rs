@@ -0,0 +1,77 @@
+use jinshu_common::Config;
+use jinshu_database::config::DatabaseConfig;
+use jinshu_distributor::config::DistributorConfig;
+use jinshu_distributor::distributor::Distributor;
+use jinshu_queue::config::{consume_with_handler, create_producer, QueueConfig};
+use jinshu_queue::kafka::{KafkaConsumerConfig, KafkaProducerConfig};
+use jinshu_queue::pulsar::{PulsarConsumerConfig, PulsarProducerConfig};
+use jinshu_rpc::registry::etcd::{EtcdConfig, EtcdRegistry};
+use jinshu_rpc::registry::Registry;
+use jinshu_tracing::config::TracingConfig;
+use jinshu_utils::shutdown_signal;
+use sea_orm::Database;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct Conf {
+    tracing: TracingConfig,
+    etcd: EtcdConfig,
+    database: DatabaseConfig,
+    distributor: DistributorConfig,
+
+    /// 使用的消息队列中间件，消费入队的消息
+    #[serde(flatten)]
+    queue: QueueConfig<KafkaConsumerConfig, PulsarConsumerConfig>,
+}
+
+/// 与消费使用同一份队列配置，用于分发消息
+#[derive(Debug, Deserialize)]
+struct ProducerConf {
+    #[serde(flatten)]
+    queue: QueueConfig<KafkaProducerConfig, PulsarProducerConfig>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let conf = Conf::from_cli()?;
+    let ProducerConf { queue: producer } = ProducerConf::from_cli()?;
+
+    let _tracer = conf.tracing.init("distributor")?;
+
+    tracing::info!(?conf);
+
+    let Conf {
+        etcd,
+        database,
+        distributor,
+        queue,
+        ..
+    } = conf;
+
+    let topic = queue.topic().to_string();
+
+    tracing::info!(?database);
+    let database = Database::connect(database).await?;
+
+    let producer = create_producer(&producer).await?;
+
+    let handler = Distributor::new(&distributor, &topic, database, producer);
+    tracing::info!(
+        %topic,
+        pusher_topic = %distributor.pusher_topic(&topic),
+        storage_topic = %distributor.storage_topic(&topic),
+        "Distribute messages"
+    );
+
+    let registry = EtcdRegistry::new(&etcd).await?;
+    let (_reporter, health) = tonic_health::server::health_reporter();
+    let (uri, handle) = registry
+        .run_service(distributor.service, health, shutdown_signal())
+        .await?;
+    tracing::info!(%uri, "Distributor service is running.");
+
+    consume_with_handler(queue, handler, shutdown_signal()).await?;
+    handle.await?;
+
+    Ok(())
+}
@@ -0,0 +1,18 @@
+/// 生成的头文件写入 `OUT_DIR`，构建时不修改源码目录；
+/// 提交的 `include/jinshu.h` 由测试 `header` 检查及更新
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is not set");
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+        .expect("Failed to read cbindgen.toml");
+
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("Failed to generate C header")
+        .write_to_file(format!("{}/jinshu.h", out_dir));
+
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
@@ -0,0 +1,140 @@
+#ifndef JINSHU_H
+#define JINSHU_H
+
+/* 由 cbindgen 生成，不要手动修改 */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// 连接状态，见 [`ConnectionState`]
+typedef enum JinshuConnectionState {
+  // 正在连接并登录
+  JINSHU_CONNECTION_STATE_CONNECTING = 0,
+  // 已登录
+  JINSHU_CONNECTION_STATE_CONNECTED = 1,
+  // 连接断开，等待重连
+  JINSHU_CONNECTION_STATE_RECONNECTING = 2,
+  // 连接关闭，不再重连
+  JINSHU_CONNECTION_STATE_CLOSED = 3,
+} JinshuConnectionState;
+
+// 接口调用结果
+typedef enum JinshuStatus {
+  // 成功
+  JINSHU_STATUS_OK = 0,
+  // 参数不合法，如空指针、非 UTF-8 字符串、格式错误的 JSON 或 UUID
+  JINSHU_STATUS_INVALID_ARGUMENT = 1,
+  // 登录失败
+  JINSHU_STATUS_LOGIN_FAILED = 2,
+  // 连接已关闭
+  JINSHU_STATUS_CONNECTION_CLOSED = 3,
+  // 等待响应超时
+  JINSHU_STATUS_TIMEOUT = 4,
+  // 其他错误
+  JINSHU_STATUS_ERROR = 5,
+} JinshuStatus;
+
+// 客户端句柄
+typedef struct JinshuClient JinshuClient;
+
+// 用户代理句柄
+typedef struct JinshuUserAgent JinshuUserAgent;
+
+// 事件回调，不需要的回调可以为空
+typedef struct JinshuCallbacks {
+  // 传给每个回调的用户数据
+  void *user_data;
+  // 收到消息，`message` 为消息的 JSON
+  void (*on_message)(void *user_data, const char *message);
+  // 连接状态变化，`attempt` 为重连次数，只在 [`JinshuConnectionState::Reconnecting`] 时有效
+  void (*on_state)(void *user_data, enum JinshuConnectionState state, uint32_t attempt);
+  // [`jinshu_send`] 的结果，成功时 `payload` 为响应的 JSON，失败时为错误信息
+  void (*on_response)(void *user_data,
+                      uint64_t request_id,
+                      enum JinshuStatus status,
+                      const char *payload);
+} JinshuCallbacks;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// 当前线程最近一次接口调用的错误信息，成功时返回空指针
+//
+// 返回的字符串在当前线程下一次调用接口前有效
+//
+const char *jinshu_last_error(void);
+
+// 使用 JSON 格式的配置（见 `ClientConfig`）构造客户端，`config` 为空指针时使用默认配置
+//
+// # Safety
+//
+// `config` 为空指针或以 `\0` 结尾的字符串，`client` 为有效的指针；
+// 成功后 `*client` 需要使用 [`jinshu_client_free`] 释放
+//
+enum JinshuStatus jinshu_client_new(const char *config,
+                                    struct JinshuClient **client);
+
+// 释放客户端，需要在释放该客户端登录的所有用户代理之后调用
+//
+// # Safety
+//
+// `client` 为空指针或 [`jinshu_client_new`] 返回且未释放的指针
+//
+void jinshu_client_free(struct JinshuClient *client);
+
+// 使用锦书用户 ID 及令牌登录，阻塞直到登录成功或失败
+//
+// # Safety
+//
+// `client` 为有效的客户端，`user_id` 及 `token` 为以 `\0` 结尾的字符串，`user_agent` 为有效的指针；
+// 成功后 `*user_agent` 需要使用 [`jinshu_user_agent_free`] 释放
+//
+enum JinshuStatus jinshu_sign_in(const struct JinshuClient *client,
+                                 const char *user_id,
+                                 const char *token,
+                                 struct JinshuCallbacks callbacks,
+                                 struct JinshuUserAgent **user_agent);
+
+// 使用 JSON 格式的登录凭证（见 `Credential`）登录，阻塞直到登录成功或失败，登录后自动刷新令牌
+//
+// # Safety
+//
+// 同 [`jinshu_sign_in`]，`credential` 为以 `\0` 结尾的字符串
+//
+enum JinshuStatus jinshu_sign_in_with_credential(const struct JinshuClient *client,
+                                                 const char *credential,
+                                                 struct JinshuCallbacks callbacks,
+                                                 struct JinshuUserAgent **user_agent);
+
+// 发送消息给用户 `to`，立即返回，结果通过 [`JinshuCallbacks::on_response`] 回调
+//
+// `mime` 为内容的 MIME 类型，如 `text/plain`，`request_id` 由调用者指定，回调时原样返回
+//
+// # Safety
+//
+// `user_agent` 为有效的用户代理，`to` 及 `mime` 为以 `\0` 结尾的字符串，
+// `data` 指向至少 `len` 字节的数据，`len` 为 0 时可以为空指针
+//
+enum JinshuStatus jinshu_send(const struct JinshuUserAgent *user_agent,
+                              const char *to,
+                              const char *mime,
+                              const uint8_t *data,
+                              uintptr_t len,
+                              uint64_t request_id);
+
+// 释放用户代理并关闭连接
+//
+// # Safety
+//
+// `user_agent` 为空指针或登录返回且未释放的指针
+//
+void jinshu_user_agent_free(struct JinshuUserAgent *user_agent);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* JINSHU_H */
@@ -0,0 +1,639 @@
+#![deny(missing_docs)]
+//! # FFI
+//!
+//! 锦书客户端 SDK 的 C 语言接口，头文件 `include/jinshu.h` 由 cbindgen 在构建时生成
+//!
+//! * 传入及传出的字符串均为以 `\0` 结尾的 UTF-8 字符串，配置、登录凭证、消息及响应使用 JSON 表示
+//! * 失败的调用返回非 [`JinshuStatus::Ok`] 的状态，错误信息可通过 [`jinshu_last_error`] 获取
+//! * 事件通过 [`JinshuCallbacks`] 回调，回调在 SDK 内部的线程中调用，传入的字符串只在回调期间有效
+//!
+
+use jinshu_protocol::{Content, Message, Response};
+use jinshu_sdk::{
+    Client, ClientConfig, ConnectionState, Credential, LoginError, MessageSender, UserAgent,
+};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::fmt::Display;
+use std::os::raw::{c_char, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use tokio::runtime::{Handle, Runtime};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// 接口调用结果
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum JinshuStatus {
+    /// 成功
+    Ok = 0,
+    /// 参数不合法，如空指针、非 UTF-8 字符串、格式错误的 JSON 或 UUID
+    InvalidArgument = 1,
+    /// 登录失败
+    LoginFailed = 2,
+    /// 连接已关闭
+    ConnectionClosed = 3,
+    /// 等待响应超时
+    Timeout = 4,
+    /// 其他错误
+    Error = 5,
+}
+
+/// 连接状态，见 [`ConnectionState`]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum JinshuConnectionState {
+    /// 正在连接并登录
+    Connecting = 0,
+    /// 已登录
+    Connected = 1,
+    /// 连接断开，等待重连
+    Reconnecting = 2,
+    /// 连接关闭，不再重连
+    Closed = 3,
+}
+
+/// 事件回调，不需要的回调可以为空
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct JinshuCallbacks {
+    /// 传给每个回调的用户数据
+    pub user_data: *mut c_void,
+    /// 收到消息，`message` 为消息的 JSON
+    pub on_message: Option<extern "C" fn(user_data: *mut c_void, message: *const c_char)>,
+    /// 连接状态变化，`attempt` 为重连次数，只在 [`JinshuConnectionState::Reconnecting`] 时有效
+    pub on_state:
+        Option<extern "C" fn(user_data: *mut c_void, state: JinshuConnectionState, attempt: u32)>,
+    /// [`jinshu_send`] 的结果，成功时 `payload` 为响应的 JSON，失败时为错误信息
+    pub on_response: Option<
+        extern "C" fn(
+            user_data: *mut c_void,
+            request_id: u64,
+            status: JinshuStatus,
+            payload: *const c_char,
+        ),
+    >,
+}
+
+// 调用者保证回调及用户数据可以在任意线程中使用
+#[allow(unsafe_code)]
+unsafe impl Send for JinshuCallbacks {}
+#[allow(unsafe_code)]
+unsafe impl Sync for JinshuCallbacks {}
+
+impl JinshuCallbacks {
+    fn message(&self, message: &Message) {
+        if let Some(on_message) = self.on_message {
+            match serde_json::to_string(message) {
+                Ok(json) => on_message(self.user_data, c_string(json).as_ptr()),
+                Err(e) => log::error!("Failed to serialize message {}: {}", message.id, e),
+            }
+        }
+    }
+
+    fn state(&self, state: ConnectionState) {
+        if let Some(on_state) = self.on_state {
+            let (state, attempt) = match state {
+                ConnectionState::Connecting => (JinshuConnectionState::Connecting, 0),
+                ConnectionState::Connected => (JinshuConnectionState::Connected, 0),
+                ConnectionState::Reconnecting { attempt, .. } => {
+                    (JinshuConnectionState::Reconnecting, attempt)
+                }
+                ConnectionState::Closed => (JinshuConnectionState::Closed, 0),
+            };
+            on_state(self.user_data, state, attempt);
+        }
+    }
+
+    fn response(&self, request_id: u64, result: jinshu_sdk::Result<Response>) {
+        if let Some(on_response) = self.on_response {
+            let (status, payload) = match result.map(|response| serde_json::to_string(&response)) {
+                Ok(Ok(json)) => (JinshuStatus::Ok, json),
+                Ok(Err(e)) => (JinshuStatus::Error, e.to_string()),
+                Err(e) => {
+                    let FfiError(status, message) = e.into();
+                    (status, message)
+                }
+            };
+            on_response(
+                self.user_data,
+                request_id,
+                status,
+                c_string(payload).as_ptr(),
+            );
+        }
+    }
+}
+
+/// 客户端句柄
+pub struct JinshuClient {
+    runtime: Runtime,
+    client: Client,
+}
+
+/// 用户代理句柄
+pub struct JinshuUserAgent {
+    user_id: Uuid,
+    handle: Handle,
+    sender: MessageSender,
+    callbacks: JinshuCallbacks,
+    events: JoinHandle<()>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// 接口内部的错误
+struct FfiError(JinshuStatus, String);
+
+impl FfiError {
+    fn invalid(message: impl Display) -> Self {
+        Self(JinshuStatus::InvalidArgument, message.to_string())
+    }
+}
+
+impl From<jinshu_sdk::Error> for FfiError {
+    fn from(e: jinshu_sdk::Error) -> Self {
+        let status = match &e {
+            jinshu_sdk::Error::ConnectionClosed => JinshuStatus::ConnectionClosed,
+            jinshu_sdk::Error::Timeout => JinshuStatus::Timeout,
+            jinshu_sdk::Error::Login(_) => JinshuStatus::LoginFailed,
+            _ => JinshuStatus::Error,
+        };
+        Self(status, e.to_string())
+    }
+}
+
+impl From<LoginError> for FfiError {
+    fn from(e: LoginError) -> Self {
+        Self(JinshuStatus::LoginFailed, e.to_string())
+    }
+}
+
+/// 不含 `\0` 的字符串转换为 C 字符串
+fn c_string(s: String) -> CString {
+    CString::new(s).unwrap_or_default()
+}
+
+/// 执行接口调用，记录错误信息并将 panic 转换为错误
+fn ffi(f: impl FnOnce() -> Result<(), FfiError>) -> JinshuStatus {
+    let result = catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| Err(FfiError(JinshuStatus::Error, "Rust panicked".into())));
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = result
+            .as_ref()
+            .err()
+            .map(|FfiError(_, message)| c_string(message.clone()));
+    });
+    match result {
+        Ok(()) => JinshuStatus::Ok,
+        Err(FfiError(status, _)) => status,
+    }
+}
+
+/// 读取字符串参数
+#[allow(unsafe_code)]
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::invalid(format_args!("{} is null", name)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|e| FfiError::invalid(format_args!("{} is not UTF-8: {}", name, e)))
+}
+
+/// 读取 UUID 参数
+#[allow(unsafe_code)]
+unsafe fn uuid_arg(ptr: *const c_char, name: &str) -> Result<Uuid, FfiError> {
+    str_arg(ptr, name)?
+        .parse()
+        .map_err(|e| FfiError::invalid(format_args!("{} is not a UUID: {}", name, e)))
+}
+
+/// 当前线程最近一次接口调用的错误信息，成功时返回空指针
+///
+/// 返回的字符串在当前线程下一次调用接口前有效
+///
+#[no_mangle]
+pub extern "C" fn jinshu_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// 使用 JSON 格式的配置（见 `ClientConfig`）构造客户端，`config` 为空指针时使用默认配置
+///
+/// # Safety
+///
+/// `config` 为空指针或以 `\0` 结尾的字符串，`client` 为有效的指针；
+/// 成功后 `*client` 需要使用 [`jinshu_client_free`] 释放
+///
+#[no_mangle]
+#[allow(unsafe_code)]
+pub unsafe extern "C" fn jinshu_client_new(
+    config: *const c_char,
+    client: *mut *mut JinshuClient,
+) -> JinshuStatus {
+    ffi(|| {
+        if client.is_null() {
+            return Err(FfiError::invalid("client is null"));
+        }
+
+        let config: ClientConfig = if config.is_null() {
+            ClientConfig::default()
+        } else {
+            serde_json::from_str(str_arg(config, "config")?)
+                .map_err(|e| FfiError::invalid(format_args!("Invalid config: {}", e)))?
+        };
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| FfiError(JinshuStatus::Error, e.to_string()))?;
+        let handle = Box::new(JinshuClient {
+            runtime,
+            client: Client::new(config)?,
+        });
+        *client = Box::into_raw(handle);
+        Ok(())
+    })
+}
+
+/// 释放客户端，需要在释放该客户端登录的所有用户代理之后调用
+///
+/// # Safety
+///
+/// `client` 为空指针或 [`jinshu_client_new`] 返回且未释放的指针
+///
+#[no_mangle]
+#[allow(unsafe_code)]
+pub unsafe extern "C" fn jinshu_client_free(client: *mut JinshuClient) {
+    if !client.is_null() {
+        let client = Box::from_raw(client);
+        client.runtime.shutdown_background();
+    }
+}
+
+/// 使用锦书用户 ID 及令牌登录，阻塞直到登录成功或失败
+///
+/// # Safety
+///
+/// `client` 为有效的客户端，`user_id` 及 `token` 为以 `\0` 结尾的字符串，`user_agent` 为有效的指针；
+/// 成功后 `*user_agent` 需要使用 [`jinshu_user_agent_free`] 释放
+///
+#[no_mangle]
+#[allow(unsafe_code)]
+pub unsafe extern "C" fn jinshu_sign_in(
+    client: *const JinshuClient,
+    user_id: *const c_char,
+    token: *const c_char,
+    callbacks: JinshuCallbacks,
+    user_agent: *mut *mut JinshuUserAgent,
+) -> JinshuStatus {
+    ffi(|| {
+        let client = client
+            .as_ref()
+            .ok_or_else(|| FfiError::invalid("client is null"))?;
+        let user_id = uuid_arg(user_id, "user_id")?;
+        let token = str_arg(token, "token")?;
+        if user_agent.is_null() {
+            return Err(FfiError::invalid("user_agent is null"));
+        }
+
+        let signed_in = client
+            .runtime
+            .block_on(client.client.sign_in(user_id, token))?;
+        *user_agent = start(client, user_id, signed_in, callbacks);
+        Ok(())
+    })
+}
+
+/// 使用 JSON 格式的登录凭证（见 `Credential`）登录，阻塞直到登录成功或失败，登录后自动刷新令牌
+///
+/// # Safety
+///
+/// 同 [`jinshu_sign_in`]，`credential` 为以 `\0` 结尾的字符串
+///
+#[no_mangle]
+#[allow(unsafe_code)]
+pub unsafe extern "C" fn jinshu_sign_in_with_credential(
+    client: *const JinshuClient,
+    credential: *const c_char,
+    callbacks: JinshuCallbacks,
+    user_agent: *mut *mut JinshuUserAgent,
+) -> JinshuStatus {
+    ffi(|| {
+        let client = client
+            .as_ref()
+            .ok_or_else(|| FfiError::invalid("client is null"))?;
+        let credential: Credential = serde_json::from_str(str_arg(credential, "credential")?)
+            .map_err(|e| FfiError::invalid(format_args!("Invalid credential: {}", e)))?;
+        if user_agent.is_null() {
+            return Err(FfiError::invalid("user_agent is null"));
+        }
+
+        let user_id = credential.user_id;
+        let signed_in = client
+            .runtime
+            .block_on(client.client.sign_in_with_credential(credential))?;
+        *user_agent = start(client, user_id, signed_in, callbacks);
+        Ok(())
+    })
+}
+
+/// 在后台分发用户代理的事件
+fn start(
+    client: &JinshuClient,
+    user_id: Uuid,
+    user_agent: UserAgent,
+    callbacks: JinshuCallbacks,
+) -> *mut JinshuUserAgent {
+    let sender = user_agent.sender();
+    let events = client.runtime.spawn(dispatch(user_agent, callbacks));
+    Box::into_raw(Box::new(JinshuUserAgent {
+        user_id,
+        handle: client.runtime.handle().clone(),
+        sender,
+        callbacks,
+        events,
+    }))
+}
+
+/// 回调收到的消息及连接状态的变化，连接关闭后结束
+async fn dispatch(mut user_agent: UserAgent, callbacks: JinshuCallbacks) {
+    let mut state = user_agent.watch_state();
+    loop {
+        tokio::select! {
+            message = user_agent.receive() => match message {
+                Ok(message) => callbacks.message(&message),
+                Err(_) => break,
+            },
+            changed = state.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let current = *state.borrow_and_update();
+                callbacks.state(current);
+                if current == ConnectionState::Closed {
+                    return;
+                }
+            }
+        }
+    }
+    callbacks.state(ConnectionState::Closed);
+}
+
+/// 发送消息给用户 `to`，立即返回，结果通过 [`JinshuCallbacks::on_response`] 回调
+///
+/// `mime` 为内容的 MIME 类型，如 `text/plain`，`request_id` 由调用者指定，回调时原样返回
+///
+/// # Safety
+///
+/// `user_agent` 为有效的用户代理，`to` 及 `mime` 为以 `\0` 结尾的字符串，
+/// `data` 指向至少 `len` 字节的数据，`len` 为 0 时可以为空指针
+///
+#[no_mangle]
+#[allow(unsafe_code)]
+pub unsafe extern "C" fn jinshu_send(
+    user_agent: *const JinshuUserAgent,
+    to: *const c_char,
+    mime: *const c_char,
+    data: *const u8,
+    len: usize,
+    request_id: u64,
+) -> JinshuStatus {
+    ffi(|| {
+        let user_agent = user_agent
+            .as_ref()
+            .ok_or_else(|| FfiError::invalid("user_agent is null"))?;
+        let to = uuid_arg(to, "to")?;
+        let mime: mime::Mime = str_arg(mime, "mime")?
+            .parse()
+            .map_err(|e| FfiError::invalid(format_args!("Invalid mime: {}", e)))?;
+        let data = match len {
+            0 => Vec::new(),
+            _ if data.is_null() => return Err(FfiError::invalid("data is null")),
+            _ => std::slice::from_raw_parts(data, len).to_vec(),
+        };
+
+        let message = Message::new(user_agent.user_id, to, Content::data(mime, data));
+        let sender = user_agent.sender.clone();
+        let callbacks = user_agent.callbacks;
+        user_agent.handle.spawn(async move {
+            callbacks.response(request_id, sender.send(message).await);
+        });
+        Ok(())
+    })
+}
+
+/// 释放用户代理并关闭连接
+///
+/// # Safety
+///
+/// `user_agent` 为空指针或登录返回且未释放的指针
+///
+#[no_mangle]
+#[allow(unsafe_code)]
+pub unsafe extern "C" fn jinshu_user_agent_free(user_agent: *mut JinshuUserAgent) {
+    if !user_agent.is_null() {
+        let user_agent = Box::from_raw(user_agent);
+        user_agent.events.abort();
+    }
+}
+
+#[cfg(test)]
+#[allow(unsafe_code)]
+mod test {
+    use super::{
+        jinshu_client_free, jinshu_client_new, jinshu_last_error, jinshu_send, jinshu_sign_in,
+        jinshu_user_agent_free, JinshuCallbacks, JinshuConnectionState, JinshuStatus,
+    };
+    use futures::{SinkExt, StreamExt};
+    use jinshu_protocol::{
+        Body, Capabilities, Content, Message, PduCodec, Request, Response, TransactionIdGenerator,
+        PROTOCOL_VERSION,
+    };
+    use jinshu_sdk::ClientConfig;
+    use std::ffi::{CStr, CString};
+    use std::os::raw::{c_char, c_void};
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_util::codec::Framed;
+    use uuid::Uuid;
+
+    #[test]
+    fn invalid_config() {
+        let config = CString::new("{").unwrap();
+        let mut client = std::ptr::null_mut();
+        let status = unsafe { jinshu_client_new(config.as_ptr(), &mut client) };
+        assert_eq!(status, JinshuStatus::InvalidArgument);
+        assert!(client.is_null());
+        let error = unsafe { CStr::from_ptr(jinshu_last_error()) };
+        assert!(error.to_str().unwrap().starts_with("Invalid config"));
+
+        let status = unsafe { jinshu_client_new(std::ptr::null(), &mut client) };
+        assert_eq!(status, JinshuStatus::Ok);
+        assert!(jinshu_last_error().is_null());
+        unsafe { jinshu_client_free(client) };
+    }
+
+    extern "C" fn on_message(user_data: *mut c_void, message: *const c_char) {
+        let events = unsafe { &*(user_data as *const Sender<String>) };
+        let message = unsafe { CStr::from_ptr(message) }.to_str().unwrap();
+        let _ = events.send(format!("message {}", message));
+    }
+
+    extern "C" fn on_state(user_data: *mut c_void, state: JinshuConnectionState, _attempt: u32) {
+        let events = unsafe { &*(user_data as *const Sender<String>) };
+        let _ = events.send(format!("state {:?}", state));
+    }
+
+    extern "C" fn on_response(
+        user_data: *mut c_void,
+        request_id: u64,
+        status: JinshuStatus,
+        payload: *const c_char,
+    ) {
+        let events = unsafe { &*(user_data as *const Sender<String>) };
+        let payload = unsafe { CStr::from_ptr(payload) }.to_str().unwrap();
+        let _ = events.send(format!("response {} {:?} {}", request_id, status, payload));
+    }
+
+    /// 跳过连接状态变化，返回下一个消息或响应
+    fn next_event(received: &Receiver<String>) -> String {
+        loop {
+            let event = received.recv_timeout(Duration::from_secs(5)).unwrap();
+            if !event.starts_with("state") {
+                return event;
+            }
+        }
+    }
+
+    #[test]
+    fn sign_in_and_send() {
+        let comet = tokio::runtime::Runtime::new().unwrap();
+        let listener = comet.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let config = ClientConfig {
+            comet_host: "127.0.0.1".into(),
+            comet_port: listener.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+
+        let user_id = Uuid::new_v4();
+        let peer = Uuid::new_v4();
+        let pushed = Message::new(peer, user_id, Content::string("hello"));
+        let pushed_id = pushed.id;
+        comet.spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut transport = Framed::new(socket, PduCodec::default());
+            let pdu = transport.next().await.unwrap().unwrap();
+            transport
+                .send(
+                    Response::SignedIn {
+                        extension: None,
+                        compression: None,
+                        version: PROTOCOL_VERSION,
+                        capabilities: Capabilities::all(),
+                        codecs: vec![],
+                        compressions: vec![],
+                    }
+                    .to_pdu(pdu.id),
+                )
+                .await
+                .unwrap();
+
+            let pdu = transport.next().await.unwrap().unwrap();
+            let id = match pdu.body {
+                Body::Req(Request::Send { message }) => {
+                    assert_eq!(message.to, peer);
+                    message.id
+                }
+                body => panic!("unexpected body: {:?}", body),
+            };
+            transport
+                .send(Response::Queued { id }.to_pdu(pdu.id))
+                .await
+                .unwrap();
+
+            let mut id_gen = TransactionIdGenerator::default();
+            transport
+                .send(Request::Push { message: pushed }.to_pdu(id_gen.next_id()))
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        });
+
+        let (events, received) = channel::<String>();
+        let callbacks = JinshuCallbacks {
+            user_data: &events as *const Sender<String> as *mut c_void,
+            on_message: Some(on_message),
+            on_state: Some(on_state),
+            on_response: Some(on_response),
+        };
+
+        let config = CString::new(serde_json::to_string(&config).unwrap()).unwrap();
+        let user_id = CString::new(user_id.to_string()).unwrap();
+        let token = CString::new("token").unwrap();
+        let to = CString::new(peer.to_string()).unwrap();
+        let mime = CString::new("text/plain").unwrap();
+        let text = b"hi";
+        unsafe {
+            let mut client = std::ptr::null_mut();
+            assert_eq!(
+                jinshu_client_new(config.as_ptr(), &mut client),
+                JinshuStatus::Ok
+            );
+            let mut user_agent = std::ptr::null_mut();
+            assert_eq!(
+                jinshu_sign_in(
+                    client,
+                    user_id.as_ptr(),
+                    token.as_ptr(),
+                    callbacks,
+                    &mut user_agent
+                ),
+                JinshuStatus::Ok
+            );
+            assert_eq!(
+                jinshu_send(
+                    user_agent,
+                    to.as_ptr(),
+                    mime.as_ptr(),
+                    text.as_ptr(),
+                    text.len(),
+                    42
+                ),
+                JinshuStatus::Ok
+            );
+
+            let response = next_event(&received);
+            assert!(response.starts_with("response 42 Ok"), "{}", response);
+            assert!(response.contains("Queued"), "{}", response);
+
+            let message = next_event(&received);
+            assert!(message.contains(&pushed_id.to_string()), "{}", message);
+
+            jinshu_user_agent_free(user_agent);
+            jinshu_client_free(client);
+        }
+    }
+
+    /// 提交的头文件需与构建时生成的一致，设置环境变量 `JINSHU_FFI_UPDATE_HEADER` 运行该测试以更新
+    #[test]
+    fn header() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/jinshu.h"));
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/include/jinshu.h");
+        if std::env::var_os("JINSHU_FFI_UPDATE_HEADER").is_some() {
+            std::fs::write(path, generated).expect("Failed to update header");
+        }
+
+        assert!(
+            std::fs::read_to_string(path).expect("Failed to read header") == generated,
+            "include/jinshu.h is outdated, run `JINSHU_FFI_UPDATE_HEADER=1 cargo test -p jinshu-ffi header` to update it"
+        );
+    }
+}
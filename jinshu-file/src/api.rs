@@ -0,0 +1,118 @@
+use mime::Mime;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use uuid::Uuid;
+
+/// HTTP 路由
+pub mod route {
+    /// 上传文件
+    pub const FILE: &str = "/file";
+    /// 下载文件
+    pub const FILE_ID: &str = "/file/:id";
+    /// 获取文件信息及签名链接
+    pub const FILE_URL: &str = "/file/:id/url";
+    /// 分享文件给会话参与者
+    pub const FILE_SHARE: &str = "/file/:id/share";
+    /// 下载文件缩略图
+    pub const FILE_THUMBNAIL: &str = "/file/:id/thumbnail";
+    /// 创建分片上传任务
+    pub const UPLOAD: &str = "/upload";
+    /// 查询/完成/取消分片上传任务
+    pub const UPLOAD_ID: &str = "/upload/:id";
+    /// 上传分片
+    pub const UPLOAD_CHUNK: &str = "/upload/:id/:index";
+}
+
+pub use jinshu_common::header;
+
+/// 文件信息
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FileInfo {
+    /// 文件 ID
+    pub id: String,
+    /// 服务端计算的文件内容 SHA-256 十六进制字符串
+    pub sha256: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 文件类型
+    #[serde(with = "serde_shims::mime")]
+    pub mime: Mime,
+    /// 签名链接，可直接用于 `Content::link`
+    pub url: Url,
+    /// 签名链接过期时间（秒）
+    pub expires: u64,
+    /// 图片、音视频的媒体元数据
+    pub media: Option<MediaInfo>,
+    /// 缩略图，仅图片文件有
+    pub thumbnail: Option<Thumbnail>,
+}
+
+/// 媒体元数据，无法提取的字段为 `None`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MediaInfo {
+    /// 宽度（像素）
+    pub width: Option<u32>,
+    /// 高度（像素）
+    pub height: Option<u32>,
+    /// 时长（毫秒）
+    pub duration_ms: Option<u64>,
+}
+
+/// 缩略图信息
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Thumbnail {
+    /// 签名链接，可直接用于 `Content::link`
+    pub url: Url,
+    /// 宽度（像素）
+    pub width: u32,
+    /// 高度（像素）
+    pub height: u32,
+}
+
+/// 分享文件请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ShareParam {
+    /// 可以访问该文件的会话参与者
+    pub users: Vec<Uuid>,
+}
+
+/// 创建分片上传任务请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateUploadParam {
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 文件类型
+    #[serde(with = "serde_shims::mime")]
+    pub mime: Mime,
+    /// 文件内容的 SHA-256 十六进制字符串，当前用户上传过该文件时无需再上传
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+/// 创建分片上传任务返回结果
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateUploadResult {
+    /// 上传任务 ID
+    pub upload_id: Uuid,
+    /// 分片大小（字节）
+    pub chunk_size: u64,
+    /// 分片个数
+    pub chunk_count: u64,
+    /// 文件已存在时直接返回文件信息
+    pub file: Option<FileInfo>,
+}
+
+/// 分片上传任务状态，用于断点续传
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UploadStatus {
+    /// 上传任务 ID
+    pub upload_id: Uuid,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 分片大小（字节）
+    pub chunk_size: u64,
+    /// 分片个数
+    pub chunk_count: u64,
+    /// 已上传的分片序号
+    pub received: Vec<u64>,
+}
@@ -0,0 +1,72 @@
+use crate::Error;
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use std::fmt::Display;
+
+/// 读取请求体，超过 `limit` 字节时返回 [`Error::TooLarge`]
+///
+/// 先检查 `Content-Length`，读取过程中累计长度超过上限时立即停止，不会缓存超出上限的数据
+///
+pub async fn read_limited<S, E>(
+    mut body: S,
+    content_length: Option<u64>,
+    limit: u64,
+) -> crate::Result<Bytes>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Display,
+{
+    if let Some(length) = content_length {
+        if length > limit {
+            return Err(Error::TooLarge(length));
+        }
+    }
+
+    let mut data = BytesMut::with_capacity(content_length.unwrap_or_default() as usize);
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| Error::BadRequest(e.to_string().into()))?;
+        let length = (data.len() + chunk.len()) as u64;
+        if length > limit {
+            return Err(Error::TooLarge(length));
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(data.freeze())
+}
+
+#[cfg(test)]
+mod test {
+    use super::read_limited;
+    use crate::Error;
+    use bytes::Bytes;
+    use futures::stream;
+
+    fn body(chunks: &[&'static [u8]]) -> impl futures::Stream<Item = Result<Bytes, Error>> {
+        stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok(Bytes::from_static(chunk)))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[tokio::test]
+    async fn limited() -> crate::Result<()> {
+        let data = read_limited(body(&[b"hello", b", ", b"jinshu"]), None, 13).await?;
+        assert_eq!(data, Bytes::from_static(b"hello, jinshu"));
+
+        assert!(matches!(
+            read_limited(body(&[b"hello", b", ", b"jinshu"]), None, 12).await,
+            Err(Error::TooLarge(13))
+        ));
+
+        // 声明的长度超过上限时不读取
+        assert!(matches!(
+            read_limited(body(&[]), Some(1024), 13).await,
+            Err(Error::TooLarge(1024))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+use jinshu_utils::secret::Secret;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use url::Url;
+
+/// File 的配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FileConfig {
+    /// 监听的 IP 地址
+    pub ip: IpAddr,
+
+    /// 监听的端口号
+    pub port: u16,
+
+    /// 外部访问文件服务使用的 URL，用于生成签名链接
+    pub public_url: Url,
+
+    /// 要消费的 Authorizer 服务名
+    pub authorizer_name: String,
+
+    /// 文件大小上限（字节）
+    pub max_file_size: u64,
+
+    /// 分片上传时的分片大小（字节）
+    pub chunk_size: u64,
+
+    /// 分片上传任务的有效期（秒），过期后不能继续上传，并由清理任务删除
+    #[serde(default = "default_upload_ttl_sec")]
+    pub upload_ttl_sec: u64,
+
+    /// 清理过期分片上传任务的间隔（秒）
+    #[serde(default = "default_upload_cleanup_interval_sec")]
+    pub upload_cleanup_interval_sec: u64,
+
+    /// 签名链接配置
+    pub sign: SignConfig,
+
+    /// 缩略图配置
+    pub thumbnail: ThumbnailConfig,
+
+    /// 存储后端配置
+    pub storage: StorageConfig,
+}
+
+impl Default for FileConfig {
+    fn default() -> Self {
+        Self {
+            ip: [0u8, 0, 0, 0].into(),
+            port: 9600,
+            public_url: "http://localhost:9600"
+                .parse()
+                .expect("impossible: public_url parse error"),
+            authorizer_name: "authorizer".into(),
+            max_file_size: 512 * 1024 * 1024,
+            chunk_size: 4 * 1024 * 1024,
+            upload_ttl_sec: default_upload_ttl_sec(),
+            upload_cleanup_interval_sec: default_upload_cleanup_interval_sec(),
+            sign: SignConfig::default(),
+            thumbnail: ThumbnailConfig::default(),
+            storage: StorageConfig::Local(LocalStorageConfig::default()),
+        }
+    }
+}
+
+fn default_upload_ttl_sec() -> u64 {
+    24 * 3600
+}
+
+fn default_upload_cleanup_interval_sec() -> u64 {
+    3600
+}
+
+/// 签名链接配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SignConfig {
+    /// 签名密钥
+    pub secret: Secret,
+    /// 签名链接有效期（秒）
+    pub validity_sec: u64,
+}
+
+impl Default for SignConfig {
+    fn default() -> Self {
+        Self {
+            secret: Secret::new("1qaz2wsx"),
+            validity_sec: 3600,
+        }
+    }
+}
+
+/// 缩略图配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ThumbnailConfig {
+    /// 是否为图片生成缩略图
+    pub enable: bool,
+    /// 缩略图最大宽度（像素）
+    pub max_width: u32,
+    /// 缩略图最大高度（像素）
+    pub max_height: u32,
+    /// 缩略图 JPEG 编码质量（1-100）
+    pub quality: u8,
+    /// 超过该大小（字节）的图片不生成缩略图
+    pub max_source_size: u64,
+}
+
+impl Default for ThumbnailConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            max_width: 256,
+            max_height: 256,
+            quality: 80,
+            max_source_size: 32 * 1024 * 1024,
+        }
+    }
+}
+
+/// 存储后端配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum StorageConfig {
+    /// 本地文件系统
+    #[serde(rename = "local")]
+    Local(
+        /// 配置值
+        LocalStorageConfig,
+    ),
+    /// S3 兼容的对象存储，例如 MinIO
+    #[serde(rename = "s3")]
+    S3(
+        /// 配置值
+        S3StorageConfig,
+    ),
+}
+
+/// 本地文件系统存储配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LocalStorageConfig {
+    /// 文件保存的根目录
+    pub path: PathBuf,
+}
+
+impl Default for LocalStorageConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("./data/file"),
+        }
+    }
+}
+
+/// S3 兼容的对象存储配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct S3StorageConfig {
+    /// 服务地址，例如 `http://localhost:9000`
+    pub endpoint: String,
+    /// 区域
+    pub region: String,
+    /// 桶名
+    pub bucket: String,
+    /// Access Key
+    pub access_key: String,
+    /// Secret Key
+    pub secret_key: Secret,
+    /// 是否使用路径风格的访问地址（MinIO 需要开启）
+    pub path_style: bool,
+}
+
+impl Default for S3StorageConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://localhost:9000".into(),
+            region: "us-east-1".into(),
+            bucket: "jinshu".into(),
+            access_key: "jinshu".into(),
+            secret_key: Secret::new("1qaz2wsx"),
+            path_style: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FileConfig, S3StorageConfig};
+
+    #[test]
+    fn default() {
+        FileConfig::default();
+        S3StorageConfig::default();
+    }
+}
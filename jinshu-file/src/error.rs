@@ -0,0 +1,92 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::borrow::Cow;
+
+/// 文件服务错误
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// I/O 错误
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// JSON 错误
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// S3 错误
+    #[error(transparent)]
+    S3(#[from] s3::error::S3Error),
+    /// S3 凭证错误
+    #[error(transparent)]
+    S3Credentials(#[from] s3::creds::error::CredentialsError),
+    /// 未登录或令牌无效
+    #[error("Unauthorized")]
+    Unauthorized,
+    /// 没有权限
+    #[error("Forbidden")]
+    Forbidden,
+    /// 文件或上传任务不存在
+    #[error("{0} not found")]
+    NotFound(Cow<'static, str>),
+    /// 上传任务已过期
+    #[error("{0} expired")]
+    Expired(Cow<'static, str>),
+    /// 请求参数不合法
+    #[error("{0}")]
+    BadRequest(Cow<'static, str>),
+    /// 文件过大
+    #[error("The size of file ({0} bytes) exceeds the maximum size")]
+    TooLarge(u64),
+    /// 其他错误
+    #[error("{0}")]
+    Other(Cow<'static, str>),
+}
+
+impl Error {
+    /// 错误对应的 HTTP 状态码
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Error::Unauthorized => StatusCode::UNAUTHORIZED,
+            Error::Forbidden => StatusCode::FORBIDDEN,
+            Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::Expired(_) => StatusCode::GONE,
+            Error::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Error::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        (self.status_code(), self.to_string()).into_response()
+    }
+}
+
+/// 文件服务结果
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod test {
+    use super::Error;
+    use axum::http::StatusCode;
+
+    #[test]
+    fn status_code() {
+        assert_eq!(Error::Unauthorized.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            Error::NotFound("file".into()).status_code(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            Error::Expired("upload session".into()).status_code(),
+            StatusCode::GONE
+        );
+        assert_eq!(
+            Error::TooLarge(0).status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert_eq!(
+            Error::Other("".into()).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -1 +1,23 @@
+#![deny(missing_docs, unsafe_code)]
+//! # File
+//!
+//! 文件存取模块，提供文件的上传、分片续传、去重存储、缩略图生成及签名链接下载
+//!
 
+mod api;
+/// 请求体读取
+pub mod body;
+/// 配置
+pub mod config;
+mod error;
+/// 媒体元数据提取及缩略图生成
+pub mod media;
+/// 文件服务实现
+pub mod service;
+/// 签名链接
+pub mod sign;
+/// 文件存储后端
+pub mod storage;
+
+pub use api::*;
+pub use error::*;
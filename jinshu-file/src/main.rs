@@ -1,3 +1,254 @@
-fn main() {
-    println!("Hello, world!");
+use axum::extract::{BodyStream, Extension, Json, Path, Query, TypedHeader};
+use axum::headers::{ContentLength, ContentType};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{get, post, put};
+use axum::Router;
+use jinshu_common::auth::AuthorizedUser;
+use jinshu_common::Config;
+use jinshu_file::body::read_limited;
+use jinshu_file::config::FileConfig;
+use jinshu_file::media::THUMBNAIL_MIME;
+use jinshu_file::service::FileService;
+use jinshu_file::sign::Signature;
+use jinshu_file::{
+    route, CreateUploadParam, CreateUploadResult, Error, FileInfo, ShareParam, UploadStatus,
+};
+use jinshu_rpc::authorizer::authorizer_client::AuthorizerClient;
+use jinshu_rpc::registry::etcd::{EtcdConfig, EtcdRegistry};
+use jinshu_rpc::registry::Registry;
+use jinshu_tracing::config::TracingConfig;
+use jinshu_utils::shutdown_signal;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+struct Conf {
+    tracing: TracingConfig,
+    etcd: EtcdConfig,
+    file: FileConfig,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let conf = Conf::from_cli()?;
+
+    let _tracer = conf.tracing.init("file")?;
+
+    let Conf { etcd, file, .. } = conf;
+
+    tracing::info!(?etcd);
+    let registry = EtcdRegistry::new(&etcd).await?;
+
+    let (authorizer_channel, ak) = registry.discover_channel(&file.authorizer_name).await?;
+    let authorizer = AuthorizerClient::new(authorizer_channel);
+
+    tracing::info!(storage = ?file.storage);
+    let store = file.storage.clone().build().await?;
+    let service = FileService::new(&file, store);
+    service.spawn_cleanup(Duration::from_secs(file.upload_cleanup_interval_sec));
+
+    let app = Router::new()
+        .route(route::FILE, post(upload))
+        .route(route::FILE_ID, get(download))
+        .route(route::FILE_URL, get(file_url))
+        .route(route::FILE_THUMBNAIL, get(download_thumbnail))
+        .route(route::FILE_SHARE, post(share))
+        .route(route::UPLOAD, post(create_upload))
+        .route(
+            route::UPLOAD_ID,
+            get(upload_status)
+                .post(complete_upload)
+                .delete(abort_upload),
+        )
+        .route(route::UPLOAD_CHUNK, put(upload_chunk))
+        .layer(Extension(service))
+        .layer(Extension(authorizer))
+        .layer(tower_http::trace::TraceLayer::new_for_http());
+
+    let addr = SocketAddr::new(file.ip, file.port);
+
+    tracing::info!(%addr, "jinshu-file is started.");
+
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    ak.close().await??;
+    tracing::info!("Service keeper closed.");
+
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+async fn upload(
+    Extension(service): Extension<FileService>,
+    AuthorizedUser { app_id, user_id }: AuthorizedUser,
+    content_type: Option<TypedHeader<ContentType>>,
+    content_length: Option<TypedHeader<ContentLength>>,
+    body: BodyStream,
+) -> Result<(StatusCode, Json<FileInfo>), Error> {
+    let mime = content_type
+        .map(|TypedHeader(c)| c.into())
+        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+    let length = content_length.map(|TypedHeader(ContentLength(length))| length);
+    let body = read_limited(body, length, service.max_file_size()).await?;
+    tracing::info!(%user_id, %mime, size = body.len());
+
+    let info = service.upload(&app_id, user_id, mime, body).await?;
+    Ok((StatusCode::CREATED, Json(info)))
+}
+
+/// 未携带有效签名时，检查通过鉴权的用户能否访问该文件
+async fn authorize(
+    service: &FileService,
+    id: &str,
+    signed: bool,
+    user: Option<AuthorizedUser>,
+) -> Result<(), Error> {
+    match user {
+        _ if signed => Ok(()),
+        Some(AuthorizedUser { app_id, user_id }) => {
+            service.check_access(&app_id, id, user_id).await
+        }
+        None => Err(Error::Forbidden),
+    }
+}
+
+/// 携带有效签名、或者为上传者及被分享的用户时可以下载
+#[tracing::instrument(skip_all)]
+async fn download(
+    Extension(service): Extension<FileService>,
+    Path(id): Path<String>,
+    signature: Option<Query<Signature>>,
+    user: Option<AuthorizedUser>,
+) -> Result<impl IntoResponse, Error> {
+    tracing::info!(%id);
+    let signed = signature
+        .map(|Query(signature)| service.verify(&id, &signature))
+        .unwrap_or(false);
+    authorize(&service, &id, signed, user).await?;
+
+    let (meta, data) = service.download(&id).await?;
+    Ok((file_headers(&meta.mime)?, data))
+}
+
+/// 携带有效签名、或者为上传者及被分享的用户时可以下载
+#[tracing::instrument(skip_all)]
+async fn download_thumbnail(
+    Extension(service): Extension<FileService>,
+    Path(id): Path<String>,
+    signature: Option<Query<Signature>>,
+    user: Option<AuthorizedUser>,
+) -> Result<impl IntoResponse, Error> {
+    tracing::info!(%id);
+    let signed = signature
+        .map(|Query(signature)| service.verify_thumbnail(&id, &signature))
+        .unwrap_or(false);
+    authorize(&service, &id, signed, user).await?;
+
+    let data = service.download_thumbnail(&id).await?;
+    Ok((file_headers(&THUMBNAIL_MIME)?, data))
+}
+
+/// 文件内容不可变，允许客户端长期缓存
+fn file_headers(mime: &mime::Mime) -> Result<HeaderMap, Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(mime.as_ref()).map_err(|e| Error::Other(e.to_string().into()))?,
+    );
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, max-age=31536000, immutable"),
+    );
+    Ok(headers)
+}
+
+#[tracing::instrument(skip_all)]
+async fn file_url(
+    Extension(service): Extension<FileService>,
+    AuthorizedUser { app_id, user_id }: AuthorizedUser,
+    Path(id): Path<String>,
+) -> Result<Json<FileInfo>, Error> {
+    tracing::info!(%user_id, %id);
+    service.check_access(&app_id, &id, user_id).await?;
+    Ok(Json(service.info(&id).await?))
+}
+
+#[tracing::instrument(skip_all)]
+async fn share(
+    Extension(service): Extension<FileService>,
+    AuthorizedUser { app_id, user_id }: AuthorizedUser,
+    Path(id): Path<String>,
+    Json(param): Json<ShareParam>,
+) -> Result<StatusCode, Error> {
+    tracing::info!(%user_id, %id, ?param);
+    service.share(&app_id, user_id, &id, &param.users).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[tracing::instrument(skip_all)]
+async fn create_upload(
+    Extension(service): Extension<FileService>,
+    AuthorizedUser { app_id, user_id }: AuthorizedUser,
+    Json(param): Json<CreateUploadParam>,
+) -> Result<(StatusCode, Json<CreateUploadResult>), Error> {
+    tracing::info!(%user_id, ?param);
+    let result = service.create_upload(&app_id, user_id, param).await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+#[tracing::instrument(skip_all)]
+async fn upload_status(
+    Extension(service): Extension<FileService>,
+    AuthorizedUser { app_id, user_id }: AuthorizedUser,
+    Path(upload_id): Path<Uuid>,
+) -> Result<Json<UploadStatus>, Error> {
+    tracing::info!(%user_id, %upload_id);
+    Ok(Json(
+        service.upload_status(&app_id, user_id, upload_id).await?,
+    ))
+}
+
+#[tracing::instrument(skip_all)]
+async fn upload_chunk(
+    Extension(service): Extension<FileService>,
+    AuthorizedUser { app_id, user_id }: AuthorizedUser,
+    Path((upload_id, index)): Path<(Uuid, u64)>,
+    content_length: Option<TypedHeader<ContentLength>>,
+    body: BodyStream,
+) -> Result<StatusCode, Error> {
+    let length = content_length.map(|TypedHeader(ContentLength(length))| length);
+    let body = read_limited(body, length, service.chunk_size()).await?;
+    tracing::info!(%user_id, %upload_id, index, size = body.len());
+    service
+        .upload_chunk(&app_id, user_id, upload_id, index, body)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[tracing::instrument(skip_all)]
+async fn complete_upload(
+    Extension(service): Extension<FileService>,
+    AuthorizedUser { app_id, user_id }: AuthorizedUser,
+    Path(upload_id): Path<Uuid>,
+) -> Result<(StatusCode, Json<FileInfo>), Error> {
+    tracing::info!(%user_id, %upload_id);
+    let info = service.complete_upload(&app_id, user_id, upload_id).await?;
+    Ok((StatusCode::CREATED, Json(info)))
+}
+
+#[tracing::instrument(skip_all)]
+async fn abort_upload(
+    Extension(service): Extension<FileService>,
+    AuthorizedUser { app_id, user_id }: AuthorizedUser,
+    Path(upload_id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    tracing::info!(%user_id, %upload_id);
+    service.abort_upload(&app_id, user_id, upload_id).await?;
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -0,0 +1,162 @@
+use crate::config::ThumbnailConfig;
+use crate::MediaInfo;
+use bytes::Bytes;
+use image::codecs::jpeg::JpegEncoder;
+use image::io::Reader as ImageReader;
+use image::DynamicImage;
+use mime::Mime;
+use std::io::Cursor;
+
+/// 生成的缩略图
+#[derive(Debug, Clone)]
+pub struct ThumbnailImage {
+    /// 宽度（像素）
+    pub width: u32,
+    /// 高度（像素）
+    pub height: u32,
+    /// JPEG 编码的图片数据
+    pub bytes: Bytes,
+}
+
+/// 缩略图的文件类型
+pub const THUMBNAIL_MIME: Mime = mime::IMAGE_JPEG;
+
+/// 根据文件内容的魔数推测文件类型，无法识别时返回 `None`
+pub fn sniff(data: &[u8]) -> Option<Mime> {
+    infer::get(data).and_then(|t| t.mime_type().parse().ok())
+}
+
+/// 提取媒体元数据，不是图片或音视频、或者无法解析时返回 `None`
+///
+/// 图片只读取文件头获取宽高，音视频目前支持 MP4 / MOV 容器
+///
+pub fn probe(mime: &Mime, data: &[u8]) -> Option<MediaInfo> {
+    match mime.type_() {
+        mime::IMAGE => probe_image(data),
+        mime::VIDEO | mime::AUDIO => probe_mp4(data),
+        _ => None,
+    }
+}
+
+fn probe_image(data: &[u8]) -> Option<MediaInfo> {
+    let (width, height) = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+
+    Some(MediaInfo {
+        width: Some(width),
+        height: Some(height),
+        duration_ms: None,
+    })
+}
+
+fn probe_mp4(data: &[u8]) -> Option<MediaInfo> {
+    let reader = mp4::Mp4Reader::read_header(Cursor::new(data), data.len() as u64).ok()?;
+
+    let video = reader
+        .tracks()
+        .values()
+        .find(|track| matches!(track.track_type(), Ok(mp4::TrackType::Video)));
+
+    Some(MediaInfo {
+        width: video.map(|track| track.width() as u32),
+        height: video.map(|track| track.height() as u32),
+        duration_ms: Some(reader.duration().as_millis() as u64),
+    })
+}
+
+/// 为图片生成 JPEG 缩略图，保持宽高比
+///
+/// 未开启、不是图片、图片过大或无法解码时返回 `None`，该函数会占用较多 CPU，
+/// 在异步环境中应在 `spawn_blocking` 中调用
+///
+pub fn thumbnail(config: &ThumbnailConfig, mime: &Mime, data: &[u8]) -> Option<ThumbnailImage> {
+    if !config.enable || mime.type_() != mime::IMAGE || data.len() as u64 > config.max_source_size {
+        return None;
+    }
+
+    let image = match ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(image::ImageError::from)
+        .and_then(|reader| reader.decode())
+    {
+        Ok(image) => image,
+        Err(e) => {
+            tracing::warn!(%e, "Failed to decode image");
+            return None;
+        }
+    };
+
+    let thumbnail = DynamicImage::ImageRgb8(
+        image
+            .thumbnail(config.max_width, config.max_height)
+            .to_rgb8(),
+    );
+
+    let mut bytes = Vec::new();
+    if let Err(e) =
+        JpegEncoder::new_with_quality(&mut bytes, config.quality).encode_image(&thumbnail)
+    {
+        tracing::warn!(%e, "Failed to encode thumbnail");
+        return None;
+    }
+
+    Some(ThumbnailImage {
+        width: thumbnail.width(),
+        height: thumbnail.height(),
+        bytes: bytes.into(),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{probe, sniff, thumbnail};
+    use crate::config::ThumbnailConfig;
+    use image::{DynamicImage, ImageOutputFormat, RgbImage};
+    use std::io::Cursor;
+
+    pub(crate) fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(RgbImage::new(width, height));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+            .expect("failed to encode png");
+        bytes
+    }
+
+    #[test]
+    fn image() {
+        let data = png(640, 320);
+
+        let mime = sniff(&data).expect("failed to sniff png");
+        assert_eq!(mime, mime::IMAGE_PNG);
+
+        let info = probe(&mime, &data).expect("failed to probe png");
+        assert_eq!(info.width, Some(640));
+        assert_eq!(info.height, Some(320));
+        assert_eq!(info.duration_ms, None);
+
+        let config = ThumbnailConfig::default();
+        let thumb = thumbnail(&config, &mime, &data).expect("failed to create thumbnail");
+        assert_eq!((thumb.width, thumb.height), (256, 128));
+        assert_eq!(sniff(&thumb.bytes), Some(mime::IMAGE_JPEG));
+
+        let disabled = ThumbnailConfig {
+            enable: false,
+            ..Default::default()
+        };
+        assert!(thumbnail(&disabled, &mime, &data).is_none());
+    }
+
+    #[test]
+    fn other() {
+        let data = b"hello, jinshu";
+        assert!(sniff(data).is_none());
+        assert!(probe(&mime::TEXT_PLAIN, data).is_none());
+        assert!(probe(&mime::IMAGE_PNG, data).is_none());
+        assert!(probe(&"video/mp4".parse().unwrap(), data).is_none());
+        assert!(thumbnail(&ThumbnailConfig::default(), &mime::IMAGE_PNG, data).is_none());
+    }
+}
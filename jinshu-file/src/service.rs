@@ -0,0 +1,1162 @@
+use crate::config::{FileConfig, ThumbnailConfig};
+use crate::media::{self, ThumbnailImage};
+use crate::sign::{Signature, UrlSigner};
+use crate::storage::BlobStore;
+use crate::{
+    CreateUploadParam, CreateUploadResult, Error, FileInfo, MediaInfo, Thumbnail, UploadStatus,
+};
+use bytes::Bytes;
+use jinshu_utils::current_second;
+use mime::Mime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use url::Url;
+use uuid::Uuid;
+
+/// 文件内容的元数据，相同内容的文件共用
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FileMeta {
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 文件类型
+    #[serde(with = "serde_shims::mime")]
+    pub mime: Mime,
+    /// 创建时间（秒）
+    pub create_time: u64,
+    /// 媒体元数据
+    #[serde(default)]
+    pub media: Option<MediaInfo>,
+    /// 缩略图宽高（像素）
+    #[serde(default)]
+    pub thumbnail: Option<(u32, u32)>,
+}
+
+/// 用户上传的文件，每次上传生成一条记录，指向服务端计算出的内容 SHA-256
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FileRecord {
+    /// 文件 ID
+    pub id: Uuid,
+    /// 上传用户所属的应用 ID
+    pub app_id: String,
+    /// 文件内容的 SHA-256 十六进制字符串
+    pub sha256: String,
+    /// 上传用户
+    pub owner: Uuid,
+    /// 创建时间（秒）
+    pub create_time: u64,
+}
+
+/// 分片上传任务
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UploadSession {
+    /// 上传任务 ID
+    pub id: Uuid,
+    /// 上传用户所属的应用 ID
+    pub app_id: String,
+    /// 上传用户
+    pub owner: Uuid,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 文件类型
+    #[serde(with = "serde_shims::mime")]
+    pub mime: Mime,
+    /// 分片大小（字节）
+    pub chunk_size: u64,
+    /// 创建时间（秒）
+    pub create_time: u64,
+}
+
+impl UploadSession {
+    /// 分片个数
+    pub fn chunk_count(&self) -> u64 {
+        self.size.div_ceil(self.chunk_size)
+    }
+
+    /// 第 `index` 个分片的大小，序号超出范围时返回 `None`
+    pub fn chunk_len(&self, index: u64) -> Option<u64> {
+        if index >= self.chunk_count() {
+            return None;
+        }
+
+        Some(self.chunk_size.min(self.size - index * self.chunk_size))
+    }
+
+    /// 创建超过 `ttl_sec` 秒后过期
+    pub fn is_expired(&self, ttl_sec: u64, now: u64) -> bool {
+        now >= self.create_time.saturating_add(ttl_sec)
+    }
+}
+
+fn blob_key(app_id: &str, sha256: &str) -> String {
+    format!("app/{}/blob/{}", app_id, sha256)
+}
+
+fn meta_key(app_id: &str, sha256: &str) -> String {
+    format!("app/{}/meta/{}", app_id, sha256)
+}
+
+fn thumbnail_key(app_id: &str, sha256: &str) -> String {
+    format!("app/{}/thumbnail/{}", app_id, sha256)
+}
+
+fn record_key(id: Uuid) -> String {
+    format!("file/{}", id.as_simple())
+}
+
+/// 用户已上传内容的索引，用于同一用户的去重
+fn owner_key(app_id: &str, owner: Uuid, sha256: &str) -> String {
+    format!("app/{}/owner/{}/{}", app_id, owner.as_simple(), sha256)
+}
+
+/// 文件分享给的用户
+fn share_key(app_id: &str, id: Uuid, user: Uuid) -> String {
+    format!(
+        "app/{}/share/{}/{}",
+        app_id,
+        id.as_simple(),
+        user.as_simple()
+    )
+}
+
+/// 缩略图签名链接使用的资源路径
+fn thumbnail_path(id: &str) -> String {
+    format!("{}/thumbnail", id)
+}
+
+fn session_key(upload_id: Uuid) -> String {
+    format!("upload/{}/session", upload_id.as_simple())
+}
+
+fn chunk_key(upload_id: Uuid, index: u64) -> String {
+    format!("upload/{}/{}", upload_id.as_simple(), index)
+}
+
+/// 解析文件 ID
+fn parse_file_id(id: &str) -> crate::Result<Uuid> {
+    id.parse()
+        .map_err(|_| Error::BadRequest(format!("Invalid file id: {}", id).into()))
+}
+
+/// 判断是否为合法的 SHA-256 十六进制字符串
+fn check_sha256(sha256: &str) -> crate::Result<()> {
+    if sha256.len() == 64 && sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(Error::BadRequest(
+            format!("Invalid SHA-256: {}", sha256).into(),
+        ))
+    }
+}
+
+/// 文件服务
+///
+/// 文件内容以服务端计算出的 SHA-256 存储，同一应用内相同内容只保存一份；每次上传生成独立的文件 ID，
+/// 去重只在同一用户上传的文件之间进行，不会把其他用户的文件返回给客户端。
+/// 文件内容、去重索引及分享记录都按应用隔离，用户只能访问所属应用的文件
+///
+#[derive(Clone)]
+pub struct FileService {
+    store: Arc<dyn BlobStore>,
+    signer: UrlSigner,
+    public_url: Url,
+    max_file_size: u64,
+    chunk_size: u64,
+    upload_ttl_sec: u64,
+    thumbnail: Arc<ThumbnailConfig>,
+}
+
+impl FileService {
+    /// 使用配置及存储后端构造
+    pub fn new(config: &FileConfig, store: Arc<dyn BlobStore>) -> Self {
+        Self {
+            store,
+            signer: UrlSigner::new(config.sign.clone()),
+            public_url: config.public_url.clone(),
+            max_file_size: config.max_file_size,
+            chunk_size: config.chunk_size,
+            upload_ttl_sec: config.upload_ttl_sec,
+            thumbnail: Arc::new(config.thumbnail.clone()),
+        }
+    }
+
+    /// 校验签名链接
+    pub fn verify(&self, id: &str, signature: &Signature) -> bool {
+        self.signer.verify(id, signature, current_second())
+    }
+
+    /// 校验缩略图签名链接
+    pub fn verify_thumbnail(&self, id: &str, signature: &Signature) -> bool {
+        self.signer
+            .verify(&thumbnail_path(id), signature, current_second())
+    }
+
+    /// 文件大小上限（字节）
+    pub fn max_file_size(&self) -> u64 {
+        self.max_file_size
+    }
+
+    /// 分片大小（字节）
+    pub fn chunk_size(&self) -> u64 {
+        self.chunk_size
+    }
+
+    fn check_size(&self, size: u64) -> crate::Result<()> {
+        if size == 0 {
+            return Err(Error::BadRequest("Empty file".into()));
+        }
+
+        if size > self.max_file_size {
+            return Err(Error::TooLarge(size));
+        }
+
+        Ok(())
+    }
+
+    fn file_info(&self, record: &FileRecord, meta: &FileMeta) -> crate::Result<FileInfo> {
+        let now = current_second();
+        let id = record.id.as_simple().to_string();
+        let (url, signature) = self.signer.url(&self.public_url, &id, now)?;
+        let thumbnail = match meta.thumbnail {
+            Some((width, height)) => {
+                let (url, _) = self
+                    .signer
+                    .url(&self.public_url, &thumbnail_path(&id), now)?;
+                Some(Thumbnail { url, width, height })
+            }
+            None => None,
+        };
+
+        Ok(FileInfo {
+            id,
+            sha256: record.sha256.clone(),
+            size: meta.size,
+            mime: meta.mime.clone(),
+            url,
+            expires: signature.expires,
+            media: meta.media.clone(),
+            thumbnail,
+        })
+    }
+
+    async fn load_meta(&self, app_id: &str, sha256: &str) -> crate::Result<Option<FileMeta>> {
+        match self.store.get(&meta_key(app_id, sha256)).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn load_record(&self, id: Uuid) -> crate::Result<Option<FileRecord>> {
+        match self.store.get(&record_key(id)).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 加载文件记录及内容元数据
+    async fn load_file(&self, id: &str) -> crate::Result<(FileRecord, FileMeta)> {
+        let id = parse_file_id(id)?;
+        let record = self
+            .load_record(id)
+            .await?
+            .ok_or_else(|| Error::NotFound("File".into()))?;
+        let meta = self
+            .load_meta(&record.app_id, &record.sha256)
+            .await?
+            .ok_or_else(|| Error::NotFound("File".into()))?;
+        Ok((record, meta))
+    }
+
+    /// 加载应用内的文件记录及内容元数据，其他应用的文件视为不存在
+    async fn load_app_file(&self, app_id: &str, id: &str) -> crate::Result<(FileRecord, FileMeta)> {
+        let (record, meta) = self.load_file(id).await?;
+        if record.app_id != app_id {
+            return Err(Error::NotFound("File".into()));
+        }
+
+        Ok((record, meta))
+    }
+
+    /// 查找用户上传过的相同内容的文件
+    async fn find_owned(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        sha256: &str,
+    ) -> crate::Result<Option<FileInfo>> {
+        let id = match self.store.get(&owner_key(app_id, owner, sha256)).await? {
+            Some(bytes) => parse_file_id(&String::from_utf8_lossy(&bytes))?,
+            None => return Ok(None),
+        };
+
+        match (
+            self.load_record(id).await?,
+            self.load_meta(app_id, sha256).await?,
+        ) {
+            (Some(record), Some(meta)) => Ok(Some(self.file_info(&record, &meta)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// 为用户创建文件记录
+    async fn create_record(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        sha256: String,
+        meta: &FileMeta,
+    ) -> crate::Result<FileInfo> {
+        let record = FileRecord {
+            id: Uuid::new_v4(),
+            app_id: app_id.to_string(),
+            sha256,
+            owner,
+            create_time: current_second(),
+        };
+
+        self.store
+            .put(&record_key(record.id), serde_json::to_vec(&record)?.into())
+            .await?;
+        self.store
+            .put(
+                &owner_key(app_id, owner, &record.sha256),
+                record.id.as_simple().to_string().into(),
+            )
+            .await?;
+
+        tracing::info!(id = %record.id, %app_id, sha256 = %record.sha256, %owner, "File record created");
+        self.file_info(&record, meta)
+    }
+
+    /// 复用已存储的内容，`sha256` 必须由服务端根据上传的数据计算
+    ///
+    /// 用户上传过相同内容时直接返回该文件，同一应用的其他用户上传过相同内容时只复用存储的内容，
+    /// 内容不存在时返回 `None`
+    ///
+    async fn reuse(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        sha256: &str,
+    ) -> crate::Result<Option<FileInfo>> {
+        if let Some(info) = self.find_owned(app_id, owner, sha256).await? {
+            tracing::info!(id = %info.id, "File already uploaded by the owner");
+            return Ok(Some(info));
+        }
+
+        match self.load_meta(app_id, sha256).await? {
+            Some(meta) => {
+                tracing::info!(%sha256, "File content already exists");
+                let info = self
+                    .create_record(app_id, owner, sha256.to_string(), &meta)
+                    .await?;
+                Ok(Some(info))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// 保存文件内容并为用户创建文件记录
+    async fn store_file(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        mime: Mime,
+        sha256: String,
+        data: Bytes,
+    ) -> crate::Result<FileInfo> {
+        if let Some(info) = self.reuse(app_id, owner, &sha256).await? {
+            return Ok(info);
+        }
+
+        let size = data.len() as u64;
+        self.store
+            .put(&blob_key(app_id, &sha256), data.clone())
+            .await?;
+        self.save_meta(app_id, owner, mime, sha256, size, data.clone(), Some(data))
+            .await
+    }
+
+    /// 内容写入后识别文件类型、提取媒体元数据、生成缩略图，保存元数据并为用户创建文件记录
+    ///
+    /// `head` 为文件开头的数据，用于识别类型；`content` 为完整内容，为 `None` 时不提取媒体元数据
+    ///
+    #[allow(clippy::too_many_arguments)]
+    async fn save_meta(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        mime: Mime,
+        sha256: String,
+        size: u64,
+        head: Bytes,
+        content: Option<Bytes>,
+    ) -> crate::Result<FileInfo> {
+        // 解码图片及音视频比较耗时，放到阻塞线程中执行
+        let config = self.thumbnail.clone();
+        let (mime, media, thumbnail) = tokio::task::spawn_blocking(move || {
+            // 以文件内容识别出的类型为准
+            let mime = media::sniff(&head).unwrap_or(mime);
+            let media = content
+                .as_ref()
+                .and_then(|content| media::probe(&mime, content));
+            let thumbnail = content
+                .as_ref()
+                .and_then(|content| media::thumbnail(&config, &mime, content));
+            (mime, media, thumbnail)
+        })
+        .await
+        .map_err(|e| Error::Other(e.to_string().into()))?;
+
+        let meta = FileMeta {
+            size,
+            mime,
+            create_time: current_second(),
+            media,
+            thumbnail: thumbnail.as_ref().map(|t| (t.width, t.height)),
+        };
+
+        // 先写内容再写元数据，元数据存在即代表文件完整
+        if let Some(ThumbnailImage { bytes, .. }) = thumbnail {
+            self.store
+                .put(&thumbnail_key(app_id, &sha256), bytes)
+                .await?;
+        }
+        self.store
+            .put(
+                &meta_key(app_id, &sha256),
+                serde_json::to_vec(&meta)?.into(),
+            )
+            .await?;
+
+        tracing::info!(%app_id, %sha256, size = meta.size, "File content stored");
+        self.create_record(app_id, owner, sha256, &meta).await
+    }
+
+    /// 直接上传整个文件
+    pub async fn upload(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        mime: Mime,
+        data: Bytes,
+    ) -> crate::Result<FileInfo> {
+        self.check_size(data.len() as u64)?;
+        let sha256 = hex::encode(Sha256::digest(&data));
+        self.store_file(app_id, owner, mime, sha256, data).await
+    }
+
+    /// 获取文件信息及新的签名链接，调用前需通过签名或 [`FileService::check_access`] 校验权限
+    pub async fn info(&self, id: &str) -> crate::Result<FileInfo> {
+        let (record, meta) = self.load_file(id).await?;
+        self.file_info(&record, &meta)
+    }
+
+    /// 检查用户能否访问文件，只有同一应用内的上传者及上传者分享给的会话参与者可以访问
+    pub async fn check_access(&self, app_id: &str, id: &str, user: Uuid) -> crate::Result<()> {
+        let (record, _) = self.load_app_file(app_id, id).await?;
+        if record.owner == user
+            || self
+                .store
+                .exists(&share_key(app_id, record.id, user))
+                .await?
+        {
+            Ok(())
+        } else {
+            Err(Error::Forbidden)
+        }
+    }
+
+    /// 将文件分享给会话参与者，只有上传者可以分享
+    pub async fn share(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        id: &str,
+        users: &[Uuid],
+    ) -> crate::Result<()> {
+        let (record, _) = self.load_app_file(app_id, id).await?;
+        if record.owner != owner {
+            return Err(Error::Forbidden);
+        }
+
+        for user in users {
+            self.store
+                .put(&share_key(app_id, record.id, *user), Bytes::new())
+                .await?;
+        }
+
+        tracing::info!(id = %record.id, count = users.len(), "File shared");
+        Ok(())
+    }
+
+    /// 下载文件
+    pub async fn download(&self, id: &str) -> crate::Result<(FileMeta, Bytes)> {
+        let (record, meta) = self.load_file(id).await?;
+        let data = self
+            .store
+            .get(&blob_key(&record.app_id, &record.sha256))
+            .await?
+            .ok_or_else(|| Error::NotFound("File".into()))?;
+        Ok((meta, data))
+    }
+
+    /// 下载文件缩略图，缩略图为 JPEG 格式
+    pub async fn download_thumbnail(&self, id: &str) -> crate::Result<Bytes> {
+        let (record, _) = self.load_file(id).await?;
+        self.store
+            .get(&thumbnail_key(&record.app_id, &record.sha256))
+            .await?
+            .ok_or_else(|| Error::NotFound("Thumbnail".into()))
+    }
+
+    /// 创建分片上传任务
+    ///
+    /// 如果用户上传过参数中的 SHA-256 对应的文件，直接返回该文件信息；
+    /// 其他用户上传的文件不会因此返回，内容相同时在完成上传后由服务端校验并复用
+    ///
+    pub async fn create_upload(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        param: CreateUploadParam,
+    ) -> crate::Result<CreateUploadResult> {
+        self.check_size(param.size)?;
+
+        let session = UploadSession {
+            id: Uuid::new_v4(),
+            app_id: app_id.to_string(),
+            owner,
+            size: param.size,
+            mime: param.mime,
+            chunk_size: self.chunk_size,
+            create_time: current_second(),
+        };
+
+        if let Some(sha256) = &param.sha256 {
+            let sha256 = sha256.to_ascii_lowercase();
+            check_sha256(&sha256)?;
+            if let Some(info) = self.find_owned(app_id, owner, &sha256).await? {
+                tracing::info!(id = %info.id, "File already uploaded by the owner, skip uploading");
+                return Ok(CreateUploadResult {
+                    upload_id: session.id,
+                    chunk_size: session.chunk_size,
+                    chunk_count: session.chunk_count(),
+                    file: Some(info),
+                });
+            }
+        }
+
+        self.store
+            .put(
+                &session_key(session.id),
+                serde_json::to_vec(&session)?.into(),
+            )
+            .await?;
+
+        tracing::info!(upload_id = %session.id, size = session.size, "Upload session created");
+
+        Ok(CreateUploadResult {
+            upload_id: session.id,
+            chunk_size: session.chunk_size,
+            chunk_count: session.chunk_count(),
+            file: None,
+        })
+    }
+
+    async fn load_session(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        upload_id: Uuid,
+    ) -> crate::Result<UploadSession> {
+        let session: UploadSession = match self.store.get(&session_key(upload_id)).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)?,
+            None => return Err(Error::NotFound("Upload session".into())),
+        };
+
+        if session.app_id != app_id || session.owner != owner {
+            return Err(Error::Forbidden);
+        }
+
+        Ok(session)
+    }
+
+    /// 加载未过期的分片上传任务
+    async fn load_live_session(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        upload_id: Uuid,
+    ) -> crate::Result<UploadSession> {
+        let session = self.load_session(app_id, owner, upload_id).await?;
+        if session.is_expired(self.upload_ttl_sec, current_second()) {
+            return Err(Error::Expired("Upload session".into()));
+        }
+
+        Ok(session)
+    }
+
+    /// 查询分片上传任务状态
+    pub async fn upload_status(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        upload_id: Uuid,
+    ) -> crate::Result<UploadStatus> {
+        let session = self.load_live_session(app_id, owner, upload_id).await?;
+
+        let mut received = Vec::new();
+        for index in 0..session.chunk_count() {
+            if self.store.exists(&chunk_key(upload_id, index)).await? {
+                received.push(index);
+            }
+        }
+
+        Ok(UploadStatus {
+            upload_id,
+            size: session.size,
+            chunk_size: session.chunk_size,
+            chunk_count: session.chunk_count(),
+            received,
+        })
+    }
+
+    /// 上传第 `index` 个分片，重复上传会覆盖之前的分片
+    pub async fn upload_chunk(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        upload_id: Uuid,
+        index: u64,
+        data: Bytes,
+    ) -> crate::Result<()> {
+        let session = self.load_live_session(app_id, owner, upload_id).await?;
+
+        match session.chunk_len(index) {
+            Some(len) if len == data.len() as u64 => {}
+            Some(len) => {
+                return Err(Error::BadRequest(
+                    format!(
+                        "Invalid length of chunk {}: {}, expected: {}",
+                        index,
+                        data.len(),
+                        len
+                    )
+                    .into(),
+                ))
+            }
+            None => {
+                return Err(Error::BadRequest(
+                    format!("Invalid chunk index: {}", index).into(),
+                ))
+            }
+        }
+
+        self.store.put(&chunk_key(upload_id, index), data).await
+    }
+
+    /// 合并所有分片完成上传
+    ///
+    /// 分片逐个读取计算 SHA-256，再由存储后端流式合并，内存中最多只保留一个分片；
+    /// 超过缩略图源文件大小上限的文件只识别类型，不提取媒体元数据
+    ///
+    pub async fn complete_upload(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        upload_id: Uuid,
+    ) -> crate::Result<FileInfo> {
+        let session = self.load_live_session(app_id, owner, upload_id).await?;
+
+        let parts: Vec<String> = (0..session.chunk_count())
+            .map(|index| chunk_key(upload_id, index))
+            .collect();
+        let mut hasher = Sha256::new();
+        let mut head = None;
+        for (index, part) in parts.iter().enumerate() {
+            match self.store.get(part).await? {
+                Some(chunk) => {
+                    hasher.update(&chunk);
+                    head.get_or_insert(chunk);
+                }
+                None => {
+                    return Err(Error::BadRequest(
+                        format!("Chunk {} is missing", index).into(),
+                    ))
+                }
+            }
+        }
+
+        let sha256 = hex::encode(hasher.finalize());
+        let info = match self.reuse(app_id, owner, &sha256).await? {
+            Some(info) => info,
+            None => {
+                let key = blob_key(app_id, &sha256);
+                self.store.compose(&key, &parts).await?;
+                let content = if session.size <= self.thumbnail.max_source_size {
+                    self.store.get(&key).await?
+                } else {
+                    None
+                };
+                self.save_meta(
+                    app_id,
+                    owner,
+                    session.mime.clone(),
+                    sha256,
+                    session.size,
+                    head.unwrap_or_default(),
+                    content,
+                )
+                .await?
+            }
+        };
+
+        self.remove_session(&session).await?;
+
+        Ok(info)
+    }
+
+    /// 取消分片上传任务，过期的任务也可以取消
+    pub async fn abort_upload(
+        &self,
+        app_id: &str,
+        owner: Uuid,
+        upload_id: Uuid,
+    ) -> crate::Result<()> {
+        let session = self.load_session(app_id, owner, upload_id).await?;
+        self.remove_session(&session).await
+    }
+
+    async fn remove_session(&self, session: &UploadSession) -> crate::Result<()> {
+        for index in 0..session.chunk_count() {
+            self.store.delete(&chunk_key(session.id, index)).await?;
+        }
+
+        self.store.delete(&session_key(session.id)).await
+    }
+
+    /// 删除过期的分片上传任务及其分片，返回删除的任务个数
+    ///
+    /// 没有任务记录的分片是任务完成或取消时并发上传遗留的，一并删除
+    ///
+    pub async fn remove_expired_uploads(&self, now: u64) -> crate::Result<usize> {
+        let mut uploads: HashMap<Uuid, Vec<String>> = HashMap::new();
+        for key in self.store.list("upload").await? {
+            let upload_id = key
+                .strip_prefix("upload/")
+                .and_then(|key| key.split_once('/'))
+                .and_then(|(id, _)| id.parse().ok());
+            if let Some(upload_id) = upload_id {
+                uploads.entry(upload_id).or_default().push(key);
+            }
+        }
+
+        let mut removed = 0;
+        for (upload_id, keys) in uploads {
+            let session_key = session_key(upload_id);
+            let expired = match self.store.get(&session_key).await? {
+                Some(bytes) => serde_json::from_slice::<UploadSession>(&bytes)
+                    .map(|session| session.is_expired(self.upload_ttl_sec, now))
+                    .unwrap_or(true),
+                None => true,
+            };
+            if !expired {
+                continue;
+            }
+
+            // 最后删除任务记录，中途失败时下次清理仍能找到该任务
+            for key in keys.iter().filter(|key| **key != session_key) {
+                self.store.delete(key).await?;
+            }
+            self.store.delete(&session_key).await?;
+            tracing::info!(%upload_id, "Expired upload session removed");
+            removed += 1;
+        }
+
+        Ok(removed)
+    }
+
+    /// 启动定期删除过期分片上传任务的任务
+    pub fn spawn_cleanup(&self, interval: Duration) -> JoinHandle<()> {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.remove_expired_uploads(current_second()).await {
+                    tracing::error!(error = %e, "Failed to remove expired upload sessions");
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FileService, UploadSession};
+    use crate::config::{FileConfig, LocalStorageConfig};
+    use crate::media::sniff;
+    use crate::media::test::png;
+    use crate::sign::Signature;
+    use crate::storage::{BlobStore, LocalStore};
+    use crate::{CreateUploadParam, Error};
+    use bytes::Bytes;
+    use jinshu_utils::current_second;
+    use sha2::{Digest, Sha256};
+    use std::sync::Arc;
+    use temp_dir::TempDir;
+    use uuid::Uuid;
+
+    const APP: &str = "app";
+
+    async fn service(d: &TempDir) -> crate::Result<FileService> {
+        let store = LocalStore::new(LocalStorageConfig {
+            path: d.path().to_path_buf(),
+        })
+        .await?;
+
+        let config = FileConfig {
+            chunk_size: 4,
+            max_file_size: 64 * 1024,
+            ..Default::default()
+        };
+
+        Ok(FileService::new(&config, Arc::new(store)))
+    }
+
+    #[test]
+    fn session() {
+        let session = UploadSession {
+            id: Uuid::new_v4(),
+            app_id: APP.to_string(),
+            owner: Uuid::new_v4(),
+            size: 10,
+            mime: mime::TEXT_PLAIN,
+            chunk_size: 4,
+            create_time: 0,
+        };
+
+        assert_eq!(session.chunk_count(), 3);
+        assert_eq!(session.chunk_len(0), Some(4));
+        assert_eq!(session.chunk_len(2), Some(2));
+        assert_eq!(session.chunk_len(3), None);
+    }
+
+    #[tokio::test]
+    async fn upload() -> crate::Result<()> {
+        let d = TempDir::new()?;
+        let service = service(&d).await?;
+        let owner = Uuid::new_v4();
+
+        let data = Bytes::from_static(b"hello, jinshu");
+        let info = service
+            .upload(APP, owner, mime::TEXT_PLAIN, data.clone())
+            .await?;
+        assert_eq!(info.sha256, hex::encode(Sha256::digest(&data)));
+        assert_eq!(info.size, data.len() as u64);
+
+        // 同一用户去重，其他用户上传相同内容得到新的文件
+        let again = service
+            .upload(APP, owner, mime::TEXT_PLAIN, data.clone())
+            .await?;
+        assert_eq!(again.id, info.id);
+        let other = service
+            .upload(APP, Uuid::new_v4(), mime::TEXT_PLAIN, data.clone())
+            .await?;
+        assert_ne!(other.id, info.id);
+        assert_eq!(other.sha256, info.sha256);
+
+        let (meta, downloaded) = service.download(&info.id).await?;
+        assert_eq!(meta.mime, mime::TEXT_PLAIN);
+        assert_eq!(downloaded, data);
+        assert_eq!(service.download(&other.id).await?.1, data);
+
+        assert!(service
+            .upload(APP, owner, mime::TEXT_PLAIN, Bytes::new())
+            .await
+            .is_err());
+        assert!(service
+            .upload(
+                APP,
+                owner,
+                mime::TEXT_PLAIN,
+                vec![0u8; 64 * 1024 + 1].into()
+            )
+            .await
+            .is_err());
+        let participant = Uuid::new_v4();
+        assert!(service.check_access(APP, &info.id, owner).await.is_ok());
+        assert!(matches!(
+            service.check_access(APP, &info.id, participant).await,
+            Err(Error::Forbidden)
+        ));
+        assert!(matches!(
+            service
+                .share(APP, participant, &info.id, &[participant])
+                .await,
+            Err(Error::Forbidden)
+        ));
+        service.share(APP, owner, &info.id, &[participant]).await?;
+        assert!(service
+            .check_access(APP, &info.id, participant)
+            .await
+            .is_ok());
+        assert!(service
+            .check_access(APP, &other.id, participant)
+            .await
+            .is_err());
+
+        assert!(service.download("../meta").await.is_err());
+        assert!(service.download(&info.sha256).await.is_err());
+        assert!(service.download_thumbnail(&info.id).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn image() -> crate::Result<()> {
+        let d = TempDir::new()?;
+        let service = service(&d).await?;
+
+        let data = Bytes::from(png(640, 320));
+        let info = service
+            .upload(APP, Uuid::new_v4(), mime::APPLICATION_OCTET_STREAM, data)
+            .await?;
+        assert_eq!(info.mime, mime::IMAGE_PNG);
+
+        let media = info.media.expect("no media info");
+        assert_eq!((media.width, media.height), (Some(640), Some(320)));
+
+        let thumbnail = info.thumbnail.expect("no thumbnail");
+        assert_eq!((thumbnail.width, thumbnail.height), (256, 128));
+        assert_eq!(thumbnail.url.path(), format!("/file/{}/thumbnail", info.id));
+
+        let signature = thumbnail
+            .url
+            .query_pairs()
+            .find(|(k, _)| k == "signature")
+            .map(|(_, v)| v.to_string())
+            .expect("no signature");
+        let signature = Signature {
+            expires: info.expires,
+            signature,
+        };
+        assert!(service.verify_thumbnail(&info.id, &signature));
+        assert!(!service.verify(&info.id, &signature));
+
+        let bytes = service.download_thumbnail(&info.id).await?;
+        assert_eq!(sniff(&bytes), Some(mime::IMAGE_JPEG));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunked() -> crate::Result<()> {
+        let d = TempDir::new()?;
+        let service = service(&d).await?;
+        let owner = Uuid::new_v4();
+
+        let data = b"hello, jinshu";
+        let create = service
+            .create_upload(
+                APP,
+                owner,
+                CreateUploadParam {
+                    size: data.len() as u64,
+                    mime: mime::TEXT_PLAIN,
+                    sha256: None,
+                },
+            )
+            .await?;
+        assert!(create.file.is_none());
+        assert_eq!(create.chunk_count, 4);
+
+        let upload_id = create.upload_id;
+        assert!(service
+            .upload_status(APP, Uuid::new_v4(), upload_id)
+            .await
+            .is_err());
+
+        for (index, chunk) in data.chunks(4).enumerate().skip(1) {
+            service
+                .upload_chunk(
+                    APP,
+                    owner,
+                    upload_id,
+                    index as u64,
+                    Bytes::copy_from_slice(chunk),
+                )
+                .await?;
+        }
+
+        let status = service.upload_status(APP, owner, upload_id).await?;
+        assert_eq!(status.received, vec![1, 2, 3]);
+        assert!(service
+            .complete_upload(APP, owner, upload_id)
+            .await
+            .is_err());
+
+        assert!(service
+            .upload_chunk(APP, owner, upload_id, 0, Bytes::from_static(b"hel"))
+            .await
+            .is_err());
+        service
+            .upload_chunk(APP, owner, upload_id, 0, Bytes::from_static(b"hell"))
+            .await?;
+
+        let info = service.complete_upload(APP, owner, upload_id).await?;
+        assert_eq!(info.sha256, hex::encode(Sha256::digest(data)));
+        assert!(service.upload_status(APP, owner, upload_id).await.is_err());
+
+        let dedup = service
+            .create_upload(
+                APP,
+                owner,
+                CreateUploadParam {
+                    size: data.len() as u64,
+                    mime: mime::TEXT_PLAIN,
+                    sha256: Some(info.sha256.clone()),
+                },
+            )
+            .await?;
+        assert!(matches!(dedup.file, Some(file) if file.id == info.id));
+
+        // 只声明 SHA-256 不能获得其他用户的文件
+        let other = service
+            .create_upload(
+                APP,
+                Uuid::new_v4(),
+                CreateUploadParam {
+                    size: data.len() as u64,
+                    mime: mime::TEXT_PLAIN,
+                    sha256: Some(info.sha256.clone()),
+                },
+            )
+            .await?;
+        assert!(other.file.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expired_upload() -> crate::Result<()> {
+        let d = TempDir::new()?;
+        let service = service(&d).await?;
+        let owner = Uuid::new_v4();
+
+        let create = service
+            .create_upload(
+                APP,
+                owner,
+                CreateUploadParam {
+                    size: 8,
+                    mime: mime::TEXT_PLAIN,
+                    sha256: None,
+                },
+            )
+            .await?;
+        let upload_id = create.upload_id;
+        service
+            .upload_chunk(APP, owner, upload_id, 0, Bytes::from_static(b"1234"))
+            .await?;
+
+        // 遗留的分片
+        let store = LocalStore::new(LocalStorageConfig {
+            path: d.path().to_path_buf(),
+        })
+        .await?;
+        let orphan = format!("upload/{}/0", Uuid::new_v4().as_simple());
+        store.put(&orphan, Bytes::from_static(b"1234")).await?;
+
+        let now = current_second();
+        assert_eq!(service.remove_expired_uploads(now).await?, 1);
+        assert!(!store.exists(&orphan).await?);
+        assert!(service.upload_status(APP, owner, upload_id).await.is_ok());
+
+        // 有效期为 0 时任务立即过期
+        let expired = FileService::new(
+            &FileConfig {
+                chunk_size: 4,
+                upload_ttl_sec: 0,
+                ..Default::default()
+            },
+            Arc::new(store.clone()),
+        );
+        assert!(matches!(
+            expired
+                .upload_chunk(APP, owner, upload_id, 1, Bytes::from_static(b"5678"))
+                .await,
+            Err(Error::Expired(_))
+        ));
+        assert!(matches!(
+            expired.complete_upload(APP, owner, upload_id).await,
+            Err(Error::Expired(_))
+        ));
+
+        let ttl = FileConfig::default().upload_ttl_sec;
+        assert_eq!(service.remove_expired_uploads(now + ttl).await?, 1);
+        assert!(store.list("upload").await?.is_empty());
+        assert!(matches!(
+            service.upload_status(APP, owner, upload_id).await,
+            Err(Error::NotFound(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn isolated_apps() -> crate::Result<()> {
+        let d = TempDir::new()?;
+        let service = service(&d).await?;
+        let owner = Uuid::new_v4();
+
+        let data = Bytes::from_static(b"hello, jinshu");
+        let info = service
+            .upload(APP, owner, mime::TEXT_PLAIN, data.clone())
+            .await?;
+
+        // 其他应用的同名用户看不到该文件，也不能分享
+        assert!(matches!(
+            service.check_access("other", &info.id, owner).await,
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            service.share("other", owner, &info.id, &[owner]).await,
+            Err(Error::NotFound(_))
+        ));
+
+        // 去重不跨应用，相同内容在其他应用中单独存储
+        let dedup = service
+            .create_upload(
+                "other",
+                owner,
+                CreateUploadParam {
+                    size: data.len() as u64,
+                    mime: mime::TEXT_PLAIN,
+                    sha256: Some(info.sha256.clone()),
+                },
+            )
+            .await?;
+        assert!(dedup.file.is_none());
+        assert!(service
+            .upload_status(APP, owner, dedup.upload_id)
+            .await
+            .is_err());
+
+        let other = service
+            .upload("other", owner, mime::TEXT_PLAIN, data.clone())
+            .await?;
+        assert_ne!(other.id, info.id);
+        assert!(d
+            .path()
+            .join(format!("app/other/blob/{}", other.sha256))
+            .exists());
+        assert_eq!(service.download(&other.id).await?.1, data);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use crate::config::SignConfig;
+use hmac::{Hmac, Mac};
+use jinshu_utils::secret::Secret;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use url::Url;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 签名链接的查询参数
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Signature {
+    /// 过期时间（距离 1970-01-01 00:00:00 UTC 的秒数）
+    pub expires: u64,
+    /// 签名，十六进制字符串
+    pub signature: String,
+}
+
+/// 签名链接生成及校验器
+///
+/// 签名为 `HMAC-SHA256(secret, "{file_id}:{expires}")`
+///
+#[derive(Debug, Clone)]
+pub struct UrlSigner {
+    secret: Secret,
+    validity_sec: u64,
+}
+
+impl UrlSigner {
+    /// 使用配置构造
+    pub fn new(config: SignConfig) -> Self {
+        Self {
+            secret: config.secret,
+            validity_sec: config.validity_sec,
+        }
+    }
+
+    /// 签名链接有效期（秒）
+    pub fn validity_sec(&self) -> u64 {
+        self.validity_sec
+    }
+
+    fn mac(&self, file_id: &str, expires: u64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.secret.expose_bytes())
+            .expect("impossible: HMAC can take key of any size");
+        mac.update(file_id.as_bytes());
+        mac.update(b":");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+
+    /// 对文件 ID 签名，`now` 为当前时间（秒）
+    pub fn sign(&self, file_id: &str, now: u64) -> Signature {
+        let expires = now + self.validity_sec;
+        let signature = hex::encode(self.mac(file_id, expires).finalize().into_bytes());
+        Signature { expires, signature }
+    }
+
+    /// 校验签名，`now` 为当前时间（秒）
+    pub fn verify(&self, file_id: &str, signature: &Signature, now: u64) -> bool {
+        if signature.expires < now {
+            return false;
+        }
+
+        match hex::decode(&signature.signature) {
+            Ok(bytes) => self
+                .mac(file_id, signature.expires)
+                .verify_slice(&bytes)
+                .is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// 使用文件服务的外部 URL 生成文件的签名链接，可直接用于 `Content::link`
+    pub fn url(&self, base: &Url, file_id: &str, now: u64) -> crate::Result<(Url, Signature)> {
+        let signature = self.sign(file_id, now);
+        let mut url = base
+            .join(&format!("file/{}", file_id))
+            .map_err(|e| crate::Error::Other(e.to_string().into()))?;
+        url.query_pairs_mut()
+            .append_pair("expires", &signature.expires.to_string())
+            .append_pair("signature", &signature.signature);
+        Ok((url, signature))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Signature, UrlSigner};
+    use crate::config::SignConfig;
+    use jinshu_utils::current_second;
+
+    #[test]
+    fn sign() {
+        let signer = UrlSigner::new(SignConfig::default());
+        let now = current_second();
+
+        let signature = signer.sign("abc", now);
+        assert!(signer.verify("abc", &signature, now));
+        assert!(!signer.verify("abd", &signature, now));
+        assert!(!signer.verify("abc", &signature, now + signer.validity_sec() + 1));
+
+        let forged = Signature {
+            expires: signature.expires + 1,
+            signature: signature.signature.clone(),
+        };
+        assert!(!signer.verify("abc", &forged, now));
+
+        let invalid = Signature {
+            expires: signature.expires,
+            signature: "not hex".into(),
+        };
+        assert!(!signer.verify("abc", &invalid, now));
+    }
+
+    #[test]
+    fn url() -> crate::Result<()> {
+        let signer = UrlSigner::new(SignConfig::default());
+        let base = "http://localhost:9600/".parse().unwrap();
+        let (url, signature) = signer.url(&base, "abc", current_second())?;
+        assert_eq!(url.path(), "/file/abc");
+        assert!(url
+            .query_pairs()
+            .any(|(k, v)| k == "signature" && v == signature.signature));
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+mod local;
+mod s3;
+
+pub use self::s3::*;
+use crate::config::StorageConfig;
+use async_trait::async_trait;
+use bytes::Bytes;
+pub use local::*;
+use std::sync::Arc;
+
+/// 文件存储后端
+///
+/// 以键值的形式存取二进制数据，键使用 `/` 分隔层级
+///
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// 写入数据，键已存在时覆盖
+    async fn put(&self, key: &str, data: Bytes) -> crate::Result<()>;
+
+    /// 读取数据，键不存在时返回 `None`
+    async fn get(&self, key: &str) -> crate::Result<Option<Bytes>>;
+
+    /// 判断键是否存在
+    async fn exists(&self, key: &str) -> crate::Result<bool>;
+
+    /// 按顺序合并 `parts` 的数据写入 `key`，流式读写，不会把全部数据读入内存
+    async fn compose(&self, key: &str, parts: &[String]) -> crate::Result<()>;
+
+    /// 删除数据，键不存在时不报错
+    async fn delete(&self, key: &str) -> crate::Result<()>;
+
+    /// 列出层级 `prefix` 下的所有键，包括更深层级的键
+    async fn list(&self, prefix: &str) -> crate::Result<Vec<String>>;
+}
+
+impl StorageConfig {
+    /// 使用配置构造存储后端
+    pub async fn build(self) -> crate::Result<Arc<dyn BlobStore>> {
+        Ok(match self {
+            StorageConfig::Local(config) => Arc::new(LocalStore::new(config).await?),
+            StorageConfig::S3(config) => Arc::new(S3Store::new(config)?),
+        })
+    }
+}
+
+/// 检查存储键是否合法，防止访问存储根目录之外的路径
+pub(crate) fn check_key(key: &str) -> crate::Result<()> {
+    if key.is_empty()
+        || key.starts_with('/')
+        || key
+            .split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..")
+    {
+        return Err(crate::Error::BadRequest(
+            format!("Invalid storage key: {}", key).into(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_key;
+
+    #[test]
+    fn key() {
+        assert!(check_key("blob/abc").is_ok());
+        assert!(check_key("upload/abc/0").is_ok());
+        assert!(check_key("").is_err());
+        assert!(check_key("/etc/passwd").is_err());
+        assert!(check_key("blob/../../etc/passwd").is_err());
+        assert!(check_key("blob//abc").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::config::LocalStorageConfig;
+use crate::storage::{check_key, BlobStore};
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// 本地文件系统存储
+#[derive(Debug, Clone)]
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    /// 使用配置构造，根目录不存在时自动创建
+    pub async fn new(config: LocalStorageConfig) -> crate::Result<Self> {
+        tokio::fs::create_dir_all(&config.path).await?;
+        Ok(Self { root: config.path })
+    }
+
+    fn path(&self, key: &str) -> crate::Result<PathBuf> {
+        check_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalStore {
+    async fn put(&self, key: &str, data: Bytes) -> crate::Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // 先写入临时文件再重命名，避免读到写了一半的文件
+        let temp = path.with_extension(format!("{}.tmp", Uuid::new_v4().as_simple()));
+        tokio::fs::write(&temp, &data).await?;
+        tokio::fs::rename(&temp, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> crate::Result<Option<Bytes>> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data.into())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn exists(&self, key: &str) -> crate::Result<bool> {
+        match tokio::fs::metadata(self.path(key)?).await {
+            Ok(metadata) => Ok(metadata.is_file()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn compose(&self, key: &str, parts: &[String]) -> crate::Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let temp = path.with_extension(format!("{}.tmp", Uuid::new_v4().as_simple()));
+        let result = async {
+            let mut file = tokio::fs::File::create(&temp).await?;
+            for part in parts {
+                let mut source = tokio::fs::File::open(self.path(part)?).await?;
+                tokio::io::copy(&mut source, &mut file).await?;
+            }
+            file.sync_all().await?;
+            Ok::<_, crate::Error>(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => Ok(tokio::fs::rename(&temp, &path).await?),
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> crate::Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> crate::Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut dirs = vec![(self.path(prefix)?, prefix.to_string())];
+        while let Some((dir, prefix)) = dirs.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let key = format!("{}/{}", prefix, name);
+                if entry.file_type().await?.is_dir() {
+                    dirs.push((entry.path(), key));
+                } else if !name.ends_with(".tmp") {
+                    // 跳过正在写入的临时文件
+                    keys.push(key);
+                }
+            }
+        }
+
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LocalStore;
+    use crate::config::LocalStorageConfig;
+    use crate::storage::BlobStore;
+    use bytes::Bytes;
+    use temp_dir::TempDir;
+
+    #[tokio::test]
+    async fn local() -> crate::Result<()> {
+        let d = TempDir::new()?;
+        let store = LocalStore::new(LocalStorageConfig {
+            path: d.path().to_path_buf(),
+        })
+        .await?;
+
+        let data = Bytes::from_static(b"hello, jinshu");
+        assert!(!store.exists("blob/hello").await?);
+        assert!(store.get("blob/hello").await?.is_none());
+
+        store.put("blob/hello", data.clone()).await?;
+        assert!(store.exists("blob/hello").await?);
+        assert_eq!(store.get("blob/hello").await?, Some(data.clone()));
+
+        store.delete("blob/hello").await?;
+        store.delete("blob/hello").await?;
+        assert!(!store.exists("blob/hello").await?);
+
+        assert!(store.put("../hello", Bytes::new()).await.is_err());
+
+        store
+            .put("upload/0", Bytes::from_static(b"hello, "))
+            .await?;
+        store.put("upload/1", Bytes::from_static(b"jinshu")).await?;
+        let parts = ["upload/0".to_string(), "upload/1".to_string()];
+        store.compose("blob/composed", &parts).await?;
+        assert_eq!(store.get("blob/composed").await?, Some(data));
+
+        let missing = ["upload/0".to_string(), "upload/2".to_string()];
+        assert!(store.compose("blob/missing", &missing).await.is_err());
+        assert!(!store.exists("blob/missing").await?);
+
+        let mut keys = store.list("upload").await?;
+        keys.sort();
+        assert_eq!(keys, ["upload/0", "upload/1"]);
+        assert_eq!(store.list("blob").await?, ["blob/composed"]);
+        assert!(store.list("missing").await?.is_empty());
+
+        Ok(())
+    }
+}
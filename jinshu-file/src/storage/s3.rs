@@ -0,0 +1,130 @@
+use crate::config::S3StorageConfig;
+use crate::storage::{check_key, BlobStore};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::StreamExt;
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+use tokio_util::io::StreamReader;
+
+/// S3 兼容的对象存储
+#[derive(Debug, Clone)]
+pub struct S3Store {
+    bucket: Bucket,
+}
+
+impl S3Store {
+    /// 使用配置构造
+    pub fn new(config: S3StorageConfig) -> crate::Result<Self> {
+        let region = Region::Custom {
+            region: config.region,
+            endpoint: config.endpoint,
+        };
+        let credentials = Credentials::new(
+            Some(&config.access_key),
+            Some(config.secret_key.expose()),
+            None,
+            None,
+            None,
+        )?;
+
+        let mut bucket = Bucket::new(&config.bucket, region, credentials)?;
+        if config.path_style {
+            bucket = bucket.with_path_style();
+        }
+
+        Ok(Self { bucket })
+    }
+}
+
+fn check_status(code: u16) -> crate::Result<()> {
+    if (200..300).contains(&code) {
+        Ok(())
+    } else {
+        Err(crate::Error::Other(
+            format!("S3 responds with status code {}", code).into(),
+        ))
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3Store {
+    async fn put(&self, key: &str, data: Bytes) -> crate::Result<()> {
+        check_key(key)?;
+        let (_, code) = self.bucket.put_object(key, &data).await?;
+        check_status(code)
+    }
+
+    async fn get(&self, key: &str) -> crate::Result<Option<Bytes>> {
+        check_key(key)?;
+        let (data, code) = self.bucket.get_object(key).await?;
+        if code == 404 {
+            return Ok(None);
+        }
+
+        check_status(code)?;
+        Ok(Some(data.into()))
+    }
+
+    async fn exists(&self, key: &str) -> crate::Result<bool> {
+        check_key(key)?;
+        let (_, code) = self.bucket.head_object(key).await?;
+        if code == 404 {
+            return Ok(false);
+        }
+
+        check_status(code)?;
+        Ok(true)
+    }
+
+    async fn compose(&self, key: &str, parts: &[String]) -> crate::Result<()> {
+        check_key(key)?;
+        // 逐个读取分片，由 rust-s3 按块分段上传
+        let chunks = futures::stream::iter(parts).then(|part| async move {
+            match self.get(part).await {
+                Ok(Some(data)) => Ok(data),
+                Ok(None) => Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("{} not found", part),
+                )),
+                Err(e) => Err(std::io::Error::other(e.to_string())),
+            }
+        });
+        let mut reader = StreamReader::new(Box::pin(chunks));
+        let code = self.bucket.put_object_stream(&mut reader, key).await?;
+        check_status(code)
+    }
+
+    async fn delete(&self, key: &str) -> crate::Result<()> {
+        check_key(key)?;
+        let (_, code) = self.bucket.delete_object(key).await?;
+        if code == 404 {
+            return Ok(());
+        }
+
+        check_status(code)
+    }
+
+    async fn list(&self, prefix: &str) -> crate::Result<Vec<String>> {
+        check_key(prefix)?;
+        let results = self.bucket.list(format!("{}/", prefix), None).await?;
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .map(|object| object.key)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::S3Store;
+    use crate::config::S3StorageConfig;
+
+    #[test]
+    fn new() {
+        assert!(S3Store::new(S3StorageConfig::default()).is_ok());
+        assert!(super::check_status(200).is_ok());
+        assert!(super::check_status(500).is_err());
+    }
+}
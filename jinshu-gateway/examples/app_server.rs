@@ -9,17 +9,18 @@ use axum::http::StatusCode;
 use axum::routing::{delete, post};
 use axum::{Json, Router};
 use deadpool_redis::redis::AsyncCommands;
+use jinshu_authorizer::webhook::{WebhookRequest, WebhookResponse};
 use jinshu_common::Config;
 use jinshu_database::config::DatabaseConfig;
 use jinshu_gateway::{
-    route, CreateUserParam, CreateUserResult, SignInParam, SignInResult, SignOutParam,
+    auth, header, route, CreateUserParam, CreateUserResult, SignInParam, SignInResult, SignOutParam,
 };
 use jinshu_redis::config::RedisConfig;
 use jinshu_tracing::config::TracingConfig;
 use jinshu_utils::secret::Secret;
-use jinshu_utils::shutdown_signal;
+use jinshu_utils::{current_millisecond, shutdown_signal};
 use model::app_user;
-use reqwest::{Client, Url};
+use reqwest::{Client, Method, RequestBuilder, Url};
 use sea_orm::{ActiveModelTrait, JsonValue};
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use sea_orm::{Database, DatabaseConnection, Set};
@@ -34,6 +35,8 @@ struct AppServerConfig {
     port: u16,
     gateway_host: String,
     gateway_port: u16,
+    app_id: String,
+    app_secret: Secret,
 }
 
 impl Default for AppServerConfig {
@@ -43,6 +46,8 @@ impl Default for AppServerConfig {
             port: 8765,
             gateway_host: "127.0.0.1".into(),
             gateway_port: 9200,
+            app_id: "example".into(),
+            app_secret: Secret::new("example-app-secret"),
         }
     }
 }
@@ -59,6 +64,8 @@ struct Conf {
 struct Resources {
     base_url: Url,
     http: Client,
+    app_id: String,
+    app_secret: Secret,
     database: DatabaseConnection,
     redis: deadpool_redis::Pool,
 }
@@ -78,6 +85,8 @@ async fn main() -> anyhow::Result<()> {
                 port,
                 gateway_host,
                 gateway_port,
+                app_id,
+                app_secret,
             },
         ..
     } = conf;
@@ -96,6 +105,8 @@ async fn main() -> anyhow::Result<()> {
     let resources = Resources {
         base_url,
         http,
+        app_id,
+        app_secret,
         database,
         redis,
     };
@@ -104,6 +115,7 @@ async fn main() -> anyhow::Result<()> {
         .route("/sign_up", post(sign_up))
         .route("/sign_in", post(sign_in))
         .route("/sign_out", delete(sign_out))
+        .route("/jinshu/authorize", post(authorize))
         .layer(Extension(resources))
         .layer(tower_http::trace::TraceLayer::new_for_http());
 
@@ -115,9 +127,43 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-#[derive(Debug, serde_repr::Serialize_repr, serde_repr::Deserialize_repr)]
+impl Resources {
+    /// 构造带有应用签名的 Gateway 请求
+    fn gateway_request<T: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &T,
+    ) -> Result<RequestBuilder, (StatusCode, String)> {
+        let url = self.base_url.join(path).map_err(internal_error)?;
+        let body = serde_json::to_vec(body).map_err(internal_error)?;
+        let timestamp = current_millisecond();
+        let nonce = Uuid::new_v4().as_simple().to_string();
+        let signature = auth::signature(
+            self.app_secret.expose(),
+            method.as_str(),
+            path,
+            timestamp,
+            &nonce,
+            &body,
+        );
+
+        Ok(self
+            .http
+            .request(method, url)
+            .header(header::APP_ID, &self.app_id)
+            .header(header::TIMESTAMP, timestamp)
+            .header(header::NONCE, nonce)
+            .header(header::SIGNATURE, signature)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body))
+    }
+}
+
+#[derive(Debug, Default, serde_repr::Serialize_repr, serde_repr::Deserialize_repr)]
 #[repr(u8)]
 enum Gender {
+    #[default]
     Unknown = 0,
     Male = 1,
     Female = 2,
@@ -129,12 +175,6 @@ impl Display for Gender {
     }
 }
 
-impl Default for Gender {
-    fn default() -> Self {
-        Gender::Unknown
-    }
-}
-
 #[derive(Debug, Deserialize)]
 struct AppSignUpParam {
     username: String,
@@ -179,17 +219,15 @@ async fn sign_up(
         .await
         .map_err(internal_error)?;
 
-    let url = resources
-        .base_url
-        .join(route::USER)
-        .map_err(internal_error)?;
     let resp = resources
-        .http
-        .post(url)
-        .json(&CreateUserParam {
-            external_id: user_id.as_simple().to_string(),
-            extension: None,
-        })
+        .gateway_request(
+            Method::POST,
+            route::USER,
+            &CreateUserParam {
+                external_id: user_id.as_simple().to_string(),
+                extension: None,
+            },
+        )?
         .send()
         .await
         .map_err(internal_error)?;
@@ -263,17 +301,15 @@ async fn sign_in(
 
         if valid {
             if let Some(jinshu_id) = app_user.jinshu_id {
-                let url = resources
-                    .base_url
-                    .join(route::SIGN_IN)
-                    .map_err(internal_error)?;
                 let resp = resources
-                    .http
-                    .post(url)
-                    .json(&SignInParam {
-                        user_id: jinshu_id.parse().map_err(internal_error)?,
-                        extension: JsonValue::Null,
-                    })
+                    .gateway_request(
+                        Method::POST,
+                        route::SIGN_IN,
+                        &SignInParam {
+                            user_id: jinshu_id.parse().map_err(internal_error)?,
+                            extension: JsonValue::Null,
+                        },
+                    )?
                     .send()
                     .await
                     .map_err(internal_error)?;
@@ -286,7 +322,7 @@ async fn sign_in(
                     let mut conn = resources.redis.get().await.map_err(internal_error)?;
                     let _: String = conn
                         .set(
-                            get_app_sign_in_key(&app_user_id),
+                            get_app_sign_in_key(app_user_id),
                             token.as_simple().to_string(),
                         )
                         .await
@@ -338,7 +374,7 @@ async fn sign_out(
 
     if let Some(app_user) = query {
         let mut conn = resources.redis.get().await.map_err(internal_error)?;
-        let key = get_app_sign_in_key(&param.id);
+        let key = get_app_sign_in_key(param.id);
         let token: Option<String> = conn.get(&key).await.map_err(internal_error)?;
         if let Some(token) = token {
             let token: Uuid = token.parse().map_err(internal_error)?;
@@ -349,16 +385,14 @@ async fn sign_out(
             let _: i64 = conn.del(&key).await.map_err(internal_error)?;
 
             if let Some(jinshu_id) = app_user.jinshu_id {
-                let url = resources
-                    .base_url
-                    .join(route::SIGN_OUT)
-                    .map_err(internal_error)?;
                 let resp = resources
-                    .http
-                    .delete(url)
-                    .json(&SignOutParam {
-                        user_id: jinshu_id.parse().map_err(internal_error)?,
-                    })
+                    .gateway_request(
+                        Method::DELETE,
+                        route::SIGN_OUT,
+                        &SignOutParam {
+                            user_id: jinshu_id.parse().map_err(internal_error)?,
+                        },
+                    )?
                     .send()
                     .await
                     .map_err(internal_error)?;
@@ -382,6 +416,49 @@ fn internal_error<E: Display>(e: E) -> (StatusCode, String) {
     (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
 }
 
+/// 供 jinshu-authorizer 的 Webhook 调用，使用本应用的令牌登录锦书
+#[tracing::instrument(skip_all)]
+async fn authorize(
+    extract::Json(request): extract::Json<WebhookRequest>,
+    Extension(resources): Extension<Resources>,
+) -> Result<Json<WebhookResponse>, (StatusCode, String)> {
+    tracing::info!(%request.app_id, %request.user_id);
+    if request.app_id != resources.app_id {
+        return Err((StatusCode::NOT_FOUND, "Unknown app".into()));
+    }
+
+    let query: Option<app_user::Model> = app_user::Entity::find()
+        .filter(app_user::Column::JinshuId.eq(request.user_id.as_simple().to_string()))
+        .one(&resources.database)
+        .await
+        .map_err(internal_error)?;
+
+    let app_user = match query {
+        Some(app_user) => app_user,
+        None => return Err((StatusCode::NOT_FOUND, "Unknown user".into())),
+    };
+
+    let app_user_id: Uuid = app_user.id.parse().map_err(internal_error)?;
+    let mut conn = resources.redis.get().await.map_err(internal_error)?;
+    let token: Option<String> = conn
+        .get(get_app_sign_in_key(app_user_id))
+        .await
+        .map_err(internal_error)?;
+
+    let ok = match (token, request.token.parse::<Uuid>()) {
+        (Some(token), Ok(request_token)) => token.parse() == Ok(request_token),
+        _ => false,
+    };
+
+    Ok(Json(WebhookResponse {
+        ok,
+        extension: serde_json::json!({
+            "id": app_user_id,
+            "username": app_user.username,
+        }),
+    }))
+}
+
 pub fn get_app_sign_in_key<D: Display>(user_id: D) -> String {
     format!("app_user:sign_in:{}", user_id)
 }
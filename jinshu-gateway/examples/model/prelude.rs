@@ -1,3 +1,4 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.4.2
 
+#[allow(unused_imports)]
 pub use super::app_user::Entity as AppUser;
@@ -12,6 +12,21 @@ pub mod route {
     pub const SIGN_IN: &str = "/sign_in";
     /// 登出
     pub const SIGN_OUT: &str = "/sign_out";
+    /// 刷新令牌
+    pub const REFRESH: &str = "/refresh";
+    /// 用户的身份公钥查询及发布
+    pub const USER_KEYS: &str = "/user/:id/keys";
+    /// 删除用户的身份公钥
+    pub const USER_KEY: &str = "/user/:id/keys/:key_id";
+}
+
+/// 应用服务器鉴权使用的 HTTP 请求头
+pub mod header {
+    /// 应用 ID
+    pub const APP_ID: &str = "x-jinshu-app-id";
+    /// 应用密钥，与签名二选一
+    pub const APP_SECRET: &str = "x-jinshu-app-secret";
+    pub use jinshu_common::header::{NONCE, SIGNATURE, TIMESTAMP};
 }
 
 /// 注册/创建用户请求参数
@@ -43,14 +58,31 @@ pub struct SignInParam {
 /// 登录返回结果
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SignInResult {
+    /// 应用ID
+    pub app_id: String,
     /// 锦书用户ID
     pub user_id: Uuid,
     /// 用于登录的令牌
-    pub token: Uuid,
+    pub token: String,
     /// 扩展字段
     pub extension: JsonValue,
     /// 过期时间
     pub expire: u64,
+    /// 用于换取新令牌的刷新令牌
+    pub refresh_token: String,
+    /// 刷新令牌的过期时间
+    pub refresh_expire: u64,
+}
+
+/// 刷新令牌请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RefreshParam {
+    /// 应用ID
+    pub app_id: String,
+    /// 锦书用户ID
+    pub user_id: Uuid,
+    /// 刷新令牌
+    pub refresh_token: String,
 }
 
 /// 登出返回结果
@@ -59,3 +91,14 @@ pub struct SignOutParam {
     /// 锦书用户ID
     pub user_id: Uuid,
 }
+
+/// 发布身份公钥请求参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PublishKeyParam {
+    /// 公钥 ID，同一用户内唯一，已存在时覆盖
+    pub key_id: String,
+    /// 密钥算法标识，如 `x25519`
+    pub algorithm: String,
+    /// 公钥，服务端不解析，通常为 Base64 编码
+    pub public_key: String,
+}
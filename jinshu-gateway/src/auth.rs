@@ -0,0 +1,339 @@
+use crate::config::GatewayConfig;
+use crate::header;
+use axum::async_trait;
+use axum::body::{Body, Bytes, HttpBody};
+use axum::http::header::CONTENT_LENGTH;
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use deadpool_redis::{redis, Pool as RedisPool};
+use jinshu_database::prelude::App;
+use jinshu_redis::get_app_nonce_key;
+use jinshu_utils::current_millisecond;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use std::fmt::Display;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+
+pub use jinshu_common::signature::{signature, verify};
+
+/// 请求所属的应用 ID，由 [`app_auth`] 放入请求的扩展中
+#[derive(Debug, Clone)]
+pub struct AppId(pub String);
+
+/// 签名请求使用过的随机数
+#[async_trait]
+pub trait NonceStore: Send + Sync {
+    /// 记录应用 `app_id` 使用的随机数，保留 `ttl_ms` 毫秒；随机数已被使用过时返回 `false`
+    async fn claim(&self, app_id: &str, nonce: &str, ttl_ms: u64) -> anyhow::Result<bool>;
+}
+
+#[async_trait]
+impl NonceStore for RedisPool {
+    async fn claim(&self, app_id: &str, nonce: &str, ttl_ms: u64) -> anyhow::Result<bool> {
+        let mut conn = self.get().await?;
+        let fresh: Option<String> = redis::cmd("SET")
+            .arg(get_app_nonce_key(app_id, nonce))
+            .arg(1)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl_ms)
+            .query_async(&mut conn)
+            .await?;
+        Ok(fresh.is_some())
+    }
+}
+
+/// 读取请求体，超过 `limit` 字节时返回 413
+///
+/// 先检查 `Content-Length`，读取过程中累计长度超过上限时立即停止
+///
+pub async fn read_body<B>(
+    headers: &HeaderMap,
+    mut body: B,
+    limit: u64,
+) -> Result<Bytes, (StatusCode, String)>
+where
+    B: HttpBody<Data = Bytes> + Unpin,
+    B::Error: Display,
+{
+    let too_large = || {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Request body is larger than {} bytes", limit),
+        )
+    };
+
+    let length = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if length.is_some_and(|length| length > limit) {
+        return Err(too_large());
+    }
+
+    let mut data = Vec::with_capacity(length.unwrap_or_default() as usize);
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        if (data.len() + chunk.len()) as u64 > limit {
+            return Err(too_large());
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(data.into())
+}
+
+/// 校验签名请求：时间戳与 `now` 相差不能超过有效期，签名必须正确，随机数在有效期内只能使用一次
+#[allow(clippy::too_many_arguments)]
+pub async fn verify_request<N: NonceStore>(
+    config: &GatewayConfig,
+    nonces: &N,
+    app_id: &str,
+    secret: &str,
+    method: &Method,
+    path_and_query: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+    now: u64,
+) -> Result<(), (StatusCode, String)> {
+    let timestamp: u64 = header_value(headers, header::TIMESTAMP)?
+        .parse()
+        .map_err(|_| unauthorized("Invalid timestamp"))?;
+    if now.abs_diff(timestamp) > config.signature_validity_ms {
+        return Err(unauthorized("Request is expired"));
+    }
+    let nonce = header_value(headers, header::NONCE)?;
+    let signature = header_value(headers, header::SIGNATURE)?;
+
+    if !verify(
+        secret,
+        method.as_str(),
+        path_and_query,
+        timestamp,
+        nonce,
+        body,
+        signature,
+    ) {
+        return Err(unauthorized("Invalid signature"));
+    }
+
+    // 随机数的保留时间覆盖时间戳允许的整个范围
+    let fresh = nonces
+        .claim(app_id, nonce, config.signature_validity_ms * 2)
+        .await
+        .map_err(internal_error)?;
+    if !fresh {
+        return Err(unauthorized("Nonce is already used"));
+    }
+
+    Ok(())
+}
+
+/// 应用服务器鉴权中间件
+///
+/// 请求头中必须带有应用 ID，以及应用密钥或请求签名两者之一：
+/// 使用签名时需同时带有毫秒时间戳及随机数，时间戳与当前时间相差不能超过配置的有效期，
+/// 同一个随机数在有效期内只能使用一次。
+/// 鉴权通过后将 [`AppId`] 放入请求的扩展中，关闭鉴权时直接使用请求头中的应用 ID
+///
+pub async fn app_auth(
+    mut req: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response, (StatusCode, String)> {
+    let config = extension::<Arc<GatewayConfig>>(&req)?;
+    let app_id = header_value(req.headers(), header::APP_ID)?.to_string();
+    if !config.app_auth {
+        req.extensions_mut().insert(AppId(app_id));
+        return Ok(next.run(req).await);
+    }
+
+    let db = extension::<DatabaseConnection>(&req)?;
+    let redis = extension::<RedisPool>(&req)?;
+
+    let headers = req.headers();
+    let app = match App::find_by_id(app_id.clone())
+        .one(&db)
+        .await
+        .map_err(internal_error)?
+    {
+        Some(app) if app.enabled => app,
+        _ => return Err(unauthorized("Unknown or disabled app")),
+    };
+
+    if let Some(secret) = headers.get(header::APP_SECRET) {
+        if !config.allow_plain_secret {
+            return Err(unauthorized("Plain secret is not allowed"));
+        }
+        if !bool::from(secret.as_bytes().ct_eq(app.secret.as_bytes())) {
+            return Err(unauthorized("Invalid app secret"));
+        }
+        tracing::debug!(%app_id, "App authorized by secret");
+        req.extensions_mut().insert(AppId(app_id));
+        return Ok(next.run(req).await);
+    }
+
+    // 先检查签名所需的请求头，再读取请求体用于计算签名，校验后重新构造请求
+    for name in [header::TIMESTAMP, header::NONCE, header::SIGNATURE] {
+        header_value(headers, name)?;
+    }
+    let (mut parts, body) = req.into_parts();
+    let body = read_body(&parts.headers, body, config.max_body_size).await?;
+    let path_and_query = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or_else(|| parts.uri.path());
+
+    verify_request(
+        &config,
+        &redis,
+        &app_id,
+        &app.secret,
+        &parts.method,
+        path_and_query,
+        &parts.headers,
+        &body,
+        current_millisecond(),
+    )
+    .await?;
+
+    tracing::debug!(%app_id, "App authorized by signature");
+    parts.extensions.insert(AppId(app_id));
+    Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+}
+
+fn extension<T: Clone + Send + Sync + 'static>(
+    req: &Request<Body>,
+) -> Result<T, (StatusCode, String)> {
+    req.extensions().get::<T>().cloned().ok_or_else(|| {
+        internal_error(format!(
+            "Missing request extension: {}",
+            std::any::type_name::<T>()
+        ))
+    })
+}
+
+fn header_value<'h>(headers: &'h HeaderMap, name: &str) -> Result<&'h str, (StatusCode, String)> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| unauthorized(format!("Missing header: {}", name)))
+}
+
+fn unauthorized<D: Display>(message: D) -> (StatusCode, String) {
+    (StatusCode::UNAUTHORIZED, message.to_string())
+}
+
+fn internal_error<E: Display>(e: E) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_body, signature, verify_request, NonceStore};
+    use crate::config::GatewayConfig;
+    use crate::header;
+    use axum::async_trait;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_LENGTH;
+    use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    /// 内存中的随机数存储
+    #[derive(Default)]
+    struct MemoryNonceStore(Mutex<HashSet<(String, String)>>);
+
+    #[async_trait]
+    impl NonceStore for MemoryNonceStore {
+        async fn claim(&self, app_id: &str, nonce: &str, _ttl_ms: u64) -> anyhow::Result<bool> {
+            Ok(self
+                .0
+                .lock()
+                .unwrap()
+                .insert((app_id.to_string(), nonce.to_string())))
+        }
+    }
+
+    fn signed_headers(timestamp: u64, nonce: &str, signature: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::TIMESTAMP, HeaderValue::from(timestamp));
+        headers.insert(header::NONCE, HeaderValue::from_str(nonce).unwrap());
+        headers.insert(header::SIGNATURE, HeaderValue::from_str(signature).unwrap());
+        headers
+    }
+
+    async fn check(
+        config: &GatewayConfig,
+        nonces: &MemoryNonceStore,
+        timestamp: u64,
+        nonce: &str,
+        signature: &str,
+        now: u64,
+    ) -> Result<(), (StatusCode, String)> {
+        verify_request(
+            config,
+            nonces,
+            "app",
+            "secret",
+            &Method::POST,
+            "/sign_in",
+            &signed_headers(timestamp, nonce, signature),
+            b"{}",
+            now,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn signed_request() {
+        let config = GatewayConfig {
+            signature_validity_ms: 1000,
+            ..Default::default()
+        };
+        let nonces = MemoryNonceStore::default();
+        let now = 1_650_000_000_000;
+        let sign =
+            |timestamp, nonce| signature("secret", "POST", "/sign_in", timestamp, nonce, b"{}");
+
+        assert!(check(&config, &nonces, now, "a", &sign(now, "a"), now)
+            .await
+            .is_ok());
+
+        // 重放
+        let result = check(&config, &nonces, now, "a", &sign(now, "a"), now + 10).await;
+        assert!(matches!(result, Err((StatusCode::UNAUTHORIZED, m)) if m.contains("Nonce")));
+
+        // 过期及时钟偏差过大
+        let old = now - 1001;
+        let result = check(&config, &nonces, old, "b", &sign(old, "b"), now).await;
+        assert!(matches!(result, Err((StatusCode::UNAUTHORIZED, m)) if m.contains("expired")));
+        let future = now + 1001;
+        let result = check(&config, &nonces, future, "c", &sign(future, "c"), now).await;
+        assert!(matches!(result, Err((StatusCode::UNAUTHORIZED, m)) if m.contains("expired")));
+
+        // 错误的签名不消耗随机数
+        let result = check(&config, &nonces, now, "d", &sign(now, "x"), now).await;
+        assert!(matches!(result, Err((StatusCode::UNAUTHORIZED, m)) if m.contains("signature")));
+        assert!(check(&config, &nonces, now, "d", &sign(now, "d"), now)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn body_limit() {
+        let headers = HeaderMap::new();
+        let body = read_body(&headers, Body::from("hello"), 5).await.unwrap();
+        assert_eq!(body.as_ref(), b"hello");
+
+        let result = read_body(&headers, Body::from("hello"), 4).await;
+        assert!(matches!(result, Err((StatusCode::PAYLOAD_TOO_LARGE, _))));
+
+        // 声明的长度超过上限时不读取
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(1024));
+        let result = read_body(&headers, Body::empty(), 4).await;
+        assert!(matches!(result, Err((StatusCode::PAYLOAD_TOO_LARGE, _))));
+    }
+}
@@ -9,6 +9,30 @@ pub struct GatewayConfig {
 
     /// 监听的端口号
     pub port: u16,
+
+    /// 访问令牌的有效期（秒）
+    #[serde(default = "default_token_validity_sec")]
+    pub token_validity_sec: usize,
+
+    /// 刷新令牌的有效期（秒）
+    #[serde(default = "default_refresh_token_validity_sec")]
+    pub refresh_token_validity_sec: usize,
+
+    /// 是否要求应用服务器鉴权
+    #[serde(default = "default_app_auth")]
+    pub app_auth: bool,
+
+    /// 是否允许直接使用应用密钥鉴权，关闭后只接受签名请求
+    #[serde(default = "default_allow_plain_secret")]
+    pub allow_plain_secret: bool,
+
+    /// 请求签名的有效期（毫秒）
+    #[serde(default = "default_signature_validity_ms")]
+    pub signature_validity_ms: u64,
+
+    /// 签名请求的最大请求体长度（字节）
+    #[serde(default = "default_max_body_size")]
+    pub max_body_size: u64,
 }
 
 impl Default for GatewayConfig {
@@ -16,10 +40,40 @@ impl Default for GatewayConfig {
         Self {
             ip: [0u8, 0, 0, 0].into(),
             port: 9200,
+            token_validity_sec: default_token_validity_sec(),
+            refresh_token_validity_sec: default_refresh_token_validity_sec(),
+            app_auth: default_app_auth(),
+            allow_plain_secret: default_allow_plain_secret(),
+            signature_validity_ms: default_signature_validity_ms(),
+            max_body_size: default_max_body_size(),
         }
     }
 }
 
+fn default_token_validity_sec() -> usize {
+    300
+}
+
+fn default_refresh_token_validity_sec() -> usize {
+    7 * 24 * 3600
+}
+
+fn default_app_auth() -> bool {
+    true
+}
+
+fn default_allow_plain_secret() -> bool {
+    true
+}
+
+fn default_signature_validity_ms() -> u64 {
+    300_000
+}
+
+fn default_max_body_size() -> u64 {
+    1024 * 1024
+}
+
 #[cfg(test)]
 mod test {
     use super::GatewayConfig;
@@ -28,4 +82,20 @@ mod test {
     fn default() {
         GatewayConfig::default();
     }
+
+    #[test]
+    fn legacy() {
+        let config: GatewayConfig =
+            serde_json::from_str(r#"{"ip": "127.0.0.1", "port": 9200}"#).unwrap();
+        let default = GatewayConfig::default();
+        assert_eq!(config.token_validity_sec, default.token_validity_sec);
+        assert_eq!(
+            config.refresh_token_validity_sec,
+            default.refresh_token_validity_sec
+        );
+        assert!(config.app_auth);
+        assert!(config.allow_plain_secret);
+        assert_eq!(config.signature_validity_ms, default.signature_validity_ms);
+        assert_eq!(config.max_body_size, default.max_body_size);
+    }
 }
@@ -4,6 +4,8 @@
 //! 系统接入模块
 
 mod api;
+/// 应用服务器鉴权
+pub mod auth;
 /// 配置
 pub mod config;
 
@@ -2,23 +2,33 @@ use axum::extract::{Extension, Json, Path};
 use axum::http::StatusCode;
 use axum::routing::{delete, get, post};
 use axum::Router;
-use deadpool_redis::{redis::AsyncCommands, Pool as RedisPool};
+use deadpool_redis::redis::{AsyncCommands, Script};
+use deadpool_redis::Pool as RedisPool;
+use jinshu_common::token::{TokenConfig, TokenMode, TokenSigner};
 use jinshu_common::Config;
 use jinshu_database::config::DatabaseConfig;
+use jinshu_database::identity_key::Model as IdentityKeyModel;
 use jinshu_database::prelude::*;
 use jinshu_database::user::Model as UserModel;
+use jinshu_database::{identity_key, user};
 use jinshu_gateway::{
-    config::GatewayConfig, route, CreateUserParam, CreateUserResult, SignInParam, SignInResult,
-    SignOutParam,
+    auth::{app_auth, AppId},
+    config::GatewayConfig,
+    route, CreateUserParam, CreateUserResult, PublishKeyParam, RefreshParam, SignInParam,
+    SignInResult, SignOutParam,
 };
-use jinshu_redis::{config::RedisConfig, get_sign_in_key};
+use jinshu_redis::revocation::RevocationStore;
+use jinshu_redis::{config::RedisConfig, get_ban_key, get_refresh_key, get_sign_in_key};
 use jinshu_tracing::config::TracingConfig;
 use jinshu_utils::{current_millisecond, shutdown_signal};
-use sea_orm::{ActiveModelTrait, EntityTrait};
-use sea_orm::{Database, DatabaseConnection, Set};
-use serde::Deserialize;
+use sea_orm::prelude::TimeDateTimeWithTimeZone;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use sea_orm::{Database, DatabaseConnection, JsonValue, Set};
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
 use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
@@ -27,8 +37,25 @@ struct Conf {
     gateway: GatewayConfig,
     database: DatabaseConfig,
     redis: RedisConfig,
+    #[serde(default)]
+    token: TokenConfig,
 }
 
+/// 保存在 Redis 中的刷新令牌
+#[derive(Debug, Deserialize, Serialize)]
+struct RefreshRecord {
+    refresh_token: String,
+    extension: JsonValue,
+}
+
+/// 消费刷新令牌的脚本，记录未被修改时才删除，保证每个刷新令牌只能使用一次
+const CONSUME_REFRESH_SCRIPT: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('DEL', KEYS[1])
+end
+return 0
+";
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let conf = Conf::from_cli()?;
@@ -36,9 +63,10 @@ async fn main() -> anyhow::Result<()> {
     let _tracer = conf.tracing.init("gateway")?;
 
     let Conf {
-        gateway: GatewayConfig { ip, port },
+        gateway,
         database,
         redis,
+        token,
         ..
     } = conf;
 
@@ -49,18 +77,32 @@ async fn main() -> anyhow::Result<()> {
     let redis_config: deadpool_redis::Config = redis.into();
     let redis = redis_config.builder()?.build()?;
 
+    tracing::info!(mode = ?token.mode);
+    let signer = match token.mode {
+        TokenMode::Random => None,
+        TokenMode::Signed => Some(Arc::new(token.signer()?)),
+    };
+
+    let addr = SocketAddr::new(gateway.ip, gateway.port);
+
     let app = Router::new()
         .route(route::USER, post(create_user))
         .route(route::SIGN_UP, post(create_user)) // alias for create user
         .route(route::USER, get(retrieve_user))
         .route(route::SIGN_IN, post(sign_in))
         .route(route::SIGN_OUT, delete(sign_out))
+        .route(route::USER_KEYS, get(list_keys).put(publish_key))
+        .route(route::USER_KEY, delete(delete_key))
+        // 以上接口只允许应用服务器调用，刷新令牌由客户端直接调用
+        .route_layer(axum::middleware::from_fn(app_auth))
+        .route(route::REFRESH, post(refresh))
         .layer(Extension(database))
+        .layer(Extension(RevocationStore::from_pool(redis.clone())))
         .layer(Extension(redis))
+        .layer(Extension(signer))
+        .layer(Extension(Arc::new(gateway)))
         .layer(tower_http::trace::TraceLayer::new_for_http());
 
-    let addr = SocketAddr::new(ip, port);
-
     tracing::info!(%addr, "jinshu-gateway is started.");
 
     axum::Server::bind(&addr)
@@ -74,12 +116,14 @@ async fn main() -> anyhow::Result<()> {
 #[tracing::instrument(skip_all)]
 async fn create_user(
     Extension(db): Extension<DatabaseConnection>,
+    Extension(AppId(app_id)): Extension<AppId>,
     Json(create): Json<CreateUserParam>,
 ) -> Result<(StatusCode, axum::Json<CreateUserResult>), (StatusCode, String)> {
-    tracing::info!(?create);
+    tracing::info!(%app_id, ?create);
     let id = uuid::Uuid::new_v4();
-    let model = jinshu_database::user::ActiveModel {
+    let model = user::ActiveModel {
         id: Set(id.as_simple().to_string()),
+        app_id: Set(app_id),
         external_id: Set(create.external_id),
         extension: Set(create.extension),
         ..Default::default()
@@ -93,14 +137,11 @@ async fn create_user(
 #[tracing::instrument(skip_all)]
 async fn retrieve_user(
     Extension(db): Extension<DatabaseConnection>,
+    Extension(AppId(app_id)): Extension<AppId>,
     Path(user_id): Path<Uuid>,
 ) -> Result<axum::Json<UserModel>, (StatusCode, String)> {
-    tracing::info!(?user_id);
-    match User::find_by_id(user_id.as_simple().to_string())
-        .one(&db)
-        .await
-        .map_err(internal_error)?
-    {
+    tracing::info!(%app_id, ?user_id);
+    match find_user(&db, &app_id, user_id).await? {
         Some(user) => Ok(Json(user)),
         None => Err((StatusCode::NOT_FOUND, "".into())),
     }
@@ -110,40 +151,85 @@ async fn retrieve_user(
 async fn sign_in(
     Extension(db): Extension<DatabaseConnection>,
     Extension(redis): Extension<RedisPool>,
+    Extension(signer): Extension<Option<Arc<TokenSigner>>>,
+    Extension(config): Extension<Arc<GatewayConfig>>,
+    Extension(AppId(app_id)): Extension<AppId>,
     Json(param): Json<SignInParam>,
 ) -> Result<axum::Json<SignInResult>, (StatusCode, String)> {
-    tracing::info!(?param);
-    const TOKEN_VALIDITY_SEC: usize = 300;
+    tracing::info!(%app_id, ?param);
 
-    let user = match User::find_by_id(param.user_id.as_simple().to_string())
-        .one(&db)
-        .await
-        .map_err(internal_error)?
-    {
+    let user = match find_user(&db, &app_id, param.user_id).await? {
         Some(user) => user,
         None => return Err((StatusCode::NOT_FOUND, "".into())),
     };
 
-    let token = Uuid::new_v4();
-    let sign_in = SignInResult {
-        user_id: user.id.parse().map_err(internal_error)?,
-        token,
-        extension: param.extension,
-        expire: current_millisecond() + (TOKEN_VALIDITY_SEC as u64 * 1000),
-    };
+    let mut conn = redis.get().await.map_err(internal_error)?;
+    check_ban(&mut conn, &app_id, param.user_id).await?;
+
+    let user_id: Uuid = user.id.parse().map_err(internal_error)?;
+    let sign_in = issue(
+        &mut conn,
+        signer.as_deref(),
+        &config,
+        &app_id,
+        user_id,
+        param.extension,
+    )
+    .await?;
+
+    Ok(Json(sign_in))
+}
+
+#[tracing::instrument(skip_all)]
+async fn refresh(
+    Extension(redis): Extension<RedisPool>,
+    Extension(signer): Extension<Option<Arc<TokenSigner>>>,
+    Extension(config): Extension<Arc<GatewayConfig>>,
+    Json(param): Json<RefreshParam>,
+) -> Result<axum::Json<SignInResult>, (StatusCode, String)> {
+    tracing::info!(app_id = %param.app_id, user_id = %param.user_id);
 
     let mut conn = redis.get().await.map_err(internal_error)?;
+    check_ban(&mut conn, &param.app_id, param.user_id).await?;
 
-    let a: String = conn
-        .set_ex(
-            get_sign_in_key(sign_in.user_id.as_simple()),
-            serde_json::to_string(&sign_in).map_err(internal_error)?,
-            TOKEN_VALIDITY_SEC,
-        )
+    let key = get_refresh_key(&param.app_id, param.user_id.as_simple());
+    let value: String = conn
+        .get::<_, Option<String>>(&key)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Invalid refresh token".into()))?;
+    let record: RefreshRecord = serde_json::from_str(&value).map_err(internal_error)?;
+
+    if !bool::from(
+        record
+            .refresh_token
+            .as_bytes()
+            .ct_eq(param.refresh_token.as_bytes()),
+    ) {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid refresh token".into()));
+    }
+
+    // 并发使用同一个刷新令牌时只有一个请求能消费成功
+    let consumed: i32 = Script::new(CONSUME_REFRESH_SCRIPT)
+        .key(&key)
+        .arg(&value)
+        .invoke_async(&mut conn)
         .await
         .map_err(internal_error)?;
+    if consumed == 0 {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid refresh token".into()));
+    }
 
-    tracing::debug!("set result: {}", a);
+    // 每次刷新都签发新的刷新令牌，旧的刷新令牌随之失效
+    let sign_in = issue(
+        &mut conn,
+        signer.as_deref(),
+        &config,
+        &param.app_id,
+        param.user_id,
+        record.extension,
+    )
+    .await?;
 
     Ok(Json(sign_in))
 }
@@ -152,18 +238,225 @@ async fn sign_in(
 async fn sign_out(
     Json(param): Json<SignOutParam>,
     Extension(redis): Extension<deadpool_redis::Pool>,
+    Extension(signer): Extension<Option<Arc<TokenSigner>>>,
+    Extension(revocation): Extension<RevocationStore>,
+    Extension(config): Extension<Arc<GatewayConfig>>,
+    Extension(AppId(app_id)): Extension<AppId>,
 ) -> Result<(StatusCode, Json<()>), (StatusCode, String)> {
-    tracing::info!(?param);
+    tracing::info!(%app_id, ?param);
+
     let mut conn = redis.get().await.map_err(internal_error)?;
 
     let _: i64 = conn
-        .del(get_sign_in_key(param.user_id.as_simple()))
+        .del(&[
+            get_sign_in_key(&app_id, param.user_id.as_simple()),
+            get_refresh_key(&app_id, param.user_id.as_simple()),
+        ])
         .await
         .map_err(internal_error)?;
 
+    // 签名令牌无法删除，吊销该用户此前签发的所有令牌
+    if signer.is_some() {
+        let now = current_millisecond();
+        revocation
+            .revoke_user(
+                &app_id,
+                param.user_id,
+                now,
+                now + (config.token_validity_sec as u64 * 1000),
+            )
+            .await
+            .map_err(internal_error)?;
+    }
+
     Ok((StatusCode::OK, Json(())))
 }
 
+#[tracing::instrument(skip_all)]
+async fn list_keys(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(AppId(app_id)): Extension<AppId>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<Vec<IdentityKeyModel>>, (StatusCode, String)> {
+    tracing::info!(%app_id, %user_id);
+    let keys = IdentityKey::find()
+        .filter(identity_key::Column::UserId.eq(user_id.as_simple().to_string()))
+        .filter(identity_key::Column::AppId.eq(app_id))
+        .order_by_desc(identity_key::Column::CreateTime)
+        .all(&db)
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(keys))
+}
+
+/// 代用户发布身份公钥，公钥 ID 已存在时覆盖
+#[tracing::instrument(skip_all)]
+async fn publish_key(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(AppId(app_id)): Extension<AppId>,
+    Path(user_id): Path<Uuid>,
+    Json(param): Json<PublishKeyParam>,
+) -> Result<Json<IdentityKeyModel>, (StatusCode, String)> {
+    tracing::info!(%app_id, %user_id, key_id = %param.key_id, algorithm = %param.algorithm);
+    if param.key_id.is_empty() || param.algorithm.is_empty() || param.public_key.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Key id, algorithm and public key must not be empty".into(),
+        ));
+    }
+    if find_user(&db, &app_id, user_id).await?.is_none() {
+        return Err((StatusCode::NOT_FOUND, "".into()));
+    }
+
+    let user_id = user_id.as_simple().to_string();
+    let existing = IdentityKey::find_by_id((user_id.clone(), param.key_id.clone()))
+        .one(&db)
+        .await
+        .map_err(internal_error)?;
+    let model = match existing {
+        Some(model) => {
+            let mut model: identity_key::ActiveModel = model.into();
+            model.algorithm = Set(param.algorithm);
+            model.public_key = Set(param.public_key);
+            model.create_time = Set(TimeDateTimeWithTimeZone::now_utc());
+            model.update(&db).await
+        }
+        None => {
+            identity_key::ActiveModel {
+                user_id: Set(user_id),
+                key_id: Set(param.key_id),
+                app_id: Set(app_id),
+                algorithm: Set(param.algorithm),
+                public_key: Set(param.public_key),
+                create_time: Set(TimeDateTimeWithTimeZone::now_utc()),
+            }
+            .insert(&db)
+            .await
+        }
+    }
+    .map_err(internal_error)?;
+
+    Ok(Json(model))
+}
+
+#[tracing::instrument(skip_all)]
+async fn delete_key(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(AppId(app_id)): Extension<AppId>,
+    Path((user_id, key_id)): Path<(Uuid, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    tracing::info!(%app_id, %user_id, %key_id);
+    let result = IdentityKey::delete_many()
+        .filter(identity_key::Column::UserId.eq(user_id.as_simple().to_string()))
+        .filter(identity_key::Column::KeyId.eq(key_id))
+        .filter(identity_key::Column::AppId.eq(app_id))
+        .exec(&db)
+        .await
+        .map_err(internal_error)?;
+    if result.rows_affected == 0 {
+        return Err((StatusCode::NOT_FOUND, "".into()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 查询应用 `app_id` 的用户，不属于该应用的用户视为不存在
+async fn find_user(
+    db: &DatabaseConnection,
+    app_id: &str,
+    user_id: Uuid,
+) -> Result<Option<UserModel>, (StatusCode, String)> {
+    User::find_by_id(user_id.as_simple().to_string())
+        .filter(user::Column::AppId.eq(app_id))
+        .one(db)
+        .await
+        .map_err(internal_error)
+}
+
+/// 用户被封禁时返回 403
+async fn check_ban(
+    conn: &mut deadpool_redis::Connection,
+    app_id: &str,
+    user_id: Uuid,
+) -> Result<(), (StatusCode, String)> {
+    let banned: bool = conn
+        .exists(get_ban_key(app_id, user_id.as_simple()))
+        .await
+        .map_err(internal_error)?;
+    if banned {
+        return Err((StatusCode::FORBIDDEN, "User is banned".into()));
+    }
+    Ok(())
+}
+
+/// 签发登录令牌及刷新令牌
+///
+/// 签名令牌模式下不需要在 Redis 中保存登录信息
+///
+async fn issue(
+    conn: &mut deadpool_redis::Connection,
+    signer: Option<&TokenSigner>,
+    config: &GatewayConfig,
+    app_id: &str,
+    user_id: Uuid,
+    extension: JsonValue,
+) -> Result<SignInResult, (StatusCode, String)> {
+    let now = current_millisecond();
+
+    let (token, expire) = match signer {
+        Some(signer) => {
+            let (token, claims) = signer
+                .sign(
+                    app_id,
+                    user_id,
+                    extension.clone(),
+                    config.token_validity_sec as u64,
+                )
+                .map_err(internal_error)?;
+            (token, claims.exp * 1000)
+        }
+        None => (
+            Uuid::new_v4().as_simple().to_string(),
+            now + (config.token_validity_sec as u64 * 1000),
+        ),
+    };
+
+    let sign_in = SignInResult {
+        app_id: app_id.to_string(),
+        user_id,
+        token,
+        extension,
+        expire,
+        refresh_token: Uuid::new_v4().as_simple().to_string(),
+        refresh_expire: now + (config.refresh_token_validity_sec as u64 * 1000),
+    };
+
+    if signer.is_none() {
+        let _: () = conn
+            .set_ex(
+                get_sign_in_key(app_id, user_id.as_simple()),
+                serde_json::to_string(&sign_in).map_err(internal_error)?,
+                config.token_validity_sec,
+            )
+            .await
+            .map_err(internal_error)?;
+    }
+
+    let record = RefreshRecord {
+        refresh_token: sign_in.refresh_token.clone(),
+        extension: sign_in.extension.clone(),
+    };
+    let _: () = conn
+        .set_ex(
+            get_refresh_key(app_id, user_id.as_simple()),
+            serde_json::to_string(&record).map_err(internal_error)?,
+            config.refresh_token_validity_sec,
+        )
+        .await
+        .map_err(internal_error)?;
+
+    Ok(sign_in)
+}
+
 fn internal_error<E: Display>(e: E) -> (StatusCode, String) {
     (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
 }
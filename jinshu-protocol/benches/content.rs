@@ -0,0 +1,136 @@
+//! 大数据消息的编解码吞吐量，`cargo bench -p jinshu-protocol`
+//!
+//! 对比数据消息内容使用 `Bytes`（协商 `binary_data` 后序列化为二进制数据）与旧版本使用 `Vec<u8>`
+//! （序列化为整数序列）时，从接收报文到转换为 RPC 消息内容的吞吐量；
+//! Protobuf 解码时检查消息内容引用接收缓冲区，没有复制
+//!
+
+use bytes::BytesMut;
+use jinshu_protocol::{Codec, Content, Message, Pdu, PduCodec, Request, TransactionIdGenerator};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio_util::codec::{Decoder, Encoder};
+use uuid::Uuid;
+
+const SIZES: [usize; 3] = [64 * 1024, 256 * 1024, 1024 * 1024];
+
+/// 旧版本的数据消息内容
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum LegacyContent {
+    Data { mime: String, bytes: Vec<u8> },
+}
+
+fn payload(size: usize) -> Vec<u8> {
+    (0..size).map(|i| (i % 251) as u8).collect()
+}
+
+/// 运行 `f` 至少一秒，返回每秒处理的字节数（MB/s）
+fn throughput(size: usize, mut f: impl FnMut()) -> f64 {
+    let start = Instant::now();
+    let mut iterations = 0u64;
+    while start.elapsed() < Duration::from_secs(1) {
+        f();
+        iterations += 1;
+    }
+    (size as u64 * iterations) as f64 / start.elapsed().as_secs_f64() / (1024.0 * 1024.0)
+}
+
+fn current(codec: Codec, size: usize) -> f64 {
+    let mut id_gen = TransactionIdGenerator::default();
+    let mut pdu_codec = PduCodec::new(codec);
+    pdu_codec.set_binary_data(true);
+    let data = payload(size);
+    let mut frame = BytesMut::new();
+    pdu_codec
+        .encode(
+            Request::Send {
+                message: Message::new(
+                    Uuid::new_v4(),
+                    Uuid::new_v4(),
+                    Content::data(mime::APPLICATION_OCTET_STREAM, data),
+                ),
+            }
+            .to_pdu(id_gen.next_id()),
+            &mut frame,
+        )
+        .unwrap();
+
+    throughput(size, || {
+        let mut src = frame.clone();
+        let range = src.as_ptr_range();
+        match pdu_codec.decode(&mut src) {
+            Ok(Some(Pdu {
+                body: jinshu_protocol::Body::Req(Request::Send { message }),
+                ..
+            })) => {
+                if let (Codec::Protobuf, Content::Data { bytes, .. }) = (codec, &message.content) {
+                    assert!(
+                        range.contains(&bytes.as_ptr()),
+                        "protobuf content is copied"
+                    );
+                }
+                let content = Vec::<u8>::try_from(&message.content).unwrap();
+                assert!(content.len() >= size);
+            }
+            _ => unreachable!(),
+        }
+    })
+}
+
+fn legacy(codec: Codec, size: usize) -> f64 {
+    let content = LegacyContent::Data {
+        mime: mime::APPLICATION_OCTET_STREAM.to_string(),
+        bytes: payload(size),
+    };
+    let encode = |content: &LegacyContent| -> Vec<u8> {
+        match codec {
+            Codec::Json => serde_json::to_vec(content).unwrap(),
+            Codec::MsgPack => rmp_serde::to_vec_named(content).unwrap(),
+            Codec::Cbor => {
+                let mut wr = Vec::new();
+                ciborium::ser::into_writer(content, &mut wr).unwrap();
+                wr
+            }
+            Codec::FlexBuffers => flexbuffers::to_vec(content).unwrap(),
+            Codec::Protobuf => unreachable!(),
+        }
+    };
+    let frame = encode(&content);
+
+    throughput(size, || {
+        let decoded: LegacyContent = match codec {
+            Codec::Json => serde_json::from_slice(&frame).unwrap(),
+            Codec::MsgPack => rmp_serde::from_slice(&frame).unwrap(),
+            Codec::Cbor => ciborium::de::from_reader(frame.as_slice()).unwrap(),
+            Codec::FlexBuffers => flexbuffers::from_slice(&frame).unwrap(),
+            Codec::Protobuf => unreachable!(),
+        };
+        // 转换为 RPC 消息内容（CBOR）
+        let mut wr = Vec::with_capacity(128);
+        ciborium::ser::into_writer(&decoded, &mut wr).unwrap();
+        assert!(wr.len() >= size);
+    })
+}
+
+fn main() {
+    println!(
+        "{:<12} {:>10} {:>14} {:>14}",
+        "codec", "size", "legacy MB/s", "current MB/s"
+    );
+    for codec in Codec::ALL {
+        for size in SIZES {
+            let legacy = match codec {
+                Codec::Protobuf => "-".to_string(),
+                codec => format!("{:.1}", legacy(codec, size)),
+            };
+            println!(
+                "{:<12} {:>10} {:>14} {:>14.1}",
+                codec.to_string(),
+                size,
+                legacy,
+                current(codec, size)
+            );
+        }
+    }
+}
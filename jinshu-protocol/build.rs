@@ -0,0 +1,7 @@
+fn main() -> std::io::Result<()> {
+    println!("cargo:rerun-if-changed=proto/pdu.proto");
+    prost_build::Config::new()
+        // 数据消息的内容直接引用接收缓冲区
+        .bytes([".jinshu.pdu.Data.bytes"])
+        .compile_protos(&["proto/pdu.proto"], &["proto"])
+}
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+/// 当前的协议版本
+///
+/// 未带版本的旧客户端视为版本 0，只具有 [`Capabilities::legacy`] 中的能力
+///
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// Comet 接受的最低协议版本
+pub const MIN_PROTOCOL_VERSION: u16 = 0;
+
+/// 协议的可选能力，只有双方都支持时才会启用
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// 报文压缩，见 [`crate::Compression`]
+    Compression,
+    /// 发送回执，消息入队时返回 [`crate::Response::Queued`]
+    Receipt,
+    /// 限流响应 [`crate::Response::RateLimited`]，不支持时使用 [`crate::Response::Rejected`]
+    RateLimit,
+    /// 端到端加密消息 [`crate::Content::Encrypted`]，不支持时不会收到加密消息
+    Encryption,
+    /// [`crate::Content::Data`] 的数据在 MsgPack、CBOR 及 FlexBuffers 中编码为二进制数据，
+    /// 不支持时编码为旧版本使用的整数序列
+    BinaryData,
+    /// 对方支持而本端不认识的能力
+    #[serde(other)]
+    Unknown,
+}
+
+impl Capability {
+    /// 当前版本支持的所有能力
+    pub const ALL: [Capability; 5] = [
+        Capability::Compression,
+        Capability::Receipt,
+        Capability::RateLimit,
+        Capability::Encryption,
+        Capability::BinaryData,
+    ];
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Capability::Compression => write!(f, "compression"),
+            Capability::Receipt => write!(f, "receipt"),
+            Capability::RateLimit => write!(f, "rate_limit"),
+            Capability::Encryption => write!(f, "encryption"),
+            Capability::BinaryData => write!(f, "binary_data"),
+            Capability::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl FromStr for Capability {
+    type Err = Infallible;
+
+    /// 不认识的能力解析为 [`Capability::Unknown`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim() {
+            "compression" => Capability::Compression,
+            "receipt" => Capability::Receipt,
+            "rate_limit" => Capability::RateLimit,
+            "encryption" => Capability::Encryption,
+            "binary_data" => Capability::BinaryData,
+            _ => Capability::Unknown,
+        })
+    }
+}
+
+/// 能力集合
+///
+/// 不包含 [`Capability::Unknown`]，反序列化时丢弃本端不认识的能力
+///
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(from = "BTreeSet<Capability>", into = "BTreeSet<Capability>")]
+pub struct Capabilities(BTreeSet<Capability>);
+
+impl Capabilities {
+    /// 当前版本支持的所有能力
+    pub fn all() -> Self {
+        Capability::ALL.into_iter().collect()
+    }
+
+    /// 未带协议版本的旧客户端具有的能力
+    pub fn legacy() -> Self {
+        [Capability::Receipt].into_iter().collect()
+    }
+
+    /// 是否支持能力
+    pub fn contains(&self, capability: Capability) -> bool {
+        self.0.contains(&capability)
+    }
+
+    /// 添加能力
+    pub fn insert(&mut self, capability: Capability) {
+        if capability != Capability::Unknown {
+            self.0.insert(capability);
+        }
+    }
+
+    /// 删除能力
+    pub fn remove(&mut self, capability: Capability) {
+        self.0.remove(&capability);
+    }
+
+    /// 双方都支持的能力
+    pub fn intersection(&self, other: &Capabilities) -> Capabilities {
+        Capabilities(self.0.intersection(&other.0).copied().collect())
+    }
+
+    /// 遍历能力
+    pub fn iter(&self) -> impl Iterator<Item = Capability> + '_ {
+        self.0.iter().copied()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<BTreeSet<Capability>> for Capabilities {
+    fn from(capabilities: BTreeSet<Capability>) -> Self {
+        capabilities.into_iter().collect()
+    }
+}
+
+impl From<Capabilities> for BTreeSet<Capability> {
+    fn from(capabilities: Capabilities) -> Self {
+        capabilities.0
+    }
+}
+
+impl FromIterator<Capability> for Capabilities {
+    fn from_iter<T: IntoIterator<Item = Capability>>(iter: T) -> Self {
+        let mut capabilities = Capabilities::default();
+        for capability in iter {
+            capabilities.insert(capability);
+        }
+        capabilities
+    }
+}
+
+/// 协商的协议版本，为双方版本中较低的一个，低于 [`MIN_PROTOCOL_VERSION`] 时返回 `None`
+pub fn negotiate_version(client: u16) -> Option<u16> {
+    let version = client.min(PROTOCOL_VERSION);
+    (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION)
+        .contains(&version)
+        .then_some(version)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{negotiate_version, Capabilities, Capability, PROTOCOL_VERSION};
+
+    #[test]
+    fn intersection() {
+        let client: Capabilities = [Capability::Compression, Capability::Encryption]
+            .into_iter()
+            .collect();
+        let agreed = Capabilities::all().intersection(&client);
+        assert!(agreed.contains(Capability::Compression));
+        assert!(agreed.contains(Capability::Encryption));
+        assert!(!agreed.contains(Capability::RateLimit));
+        assert!(Capabilities::default()
+            .intersection(&Capabilities::all())
+            .is_empty());
+        assert_eq!(
+            Capabilities::legacy().iter().collect::<Vec<_>>(),
+            [Capability::Receipt]
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        let capabilities: Capabilities =
+            serde_json::from_str(r#"["compression", "from_the_future"]"#).unwrap();
+        // 不认识的能力在反序列化时丢弃
+        assert!(!capabilities.contains(Capability::Unknown));
+        assert_eq!(
+            capabilities.iter().collect::<Vec<_>>(),
+            [Capability::Compression]
+        );
+        let only_unknown: Capabilities = serde_json::from_str(r#"["from_the_future"]"#).unwrap();
+        assert!(only_unknown.is_empty());
+        assert_eq!(
+            Capabilities::all().intersection(&capabilities),
+            [Capability::Compression].into_iter().collect()
+        );
+        assert_eq!(
+            serde_json::to_string(&Capabilities::all()).unwrap(),
+            r#"["compression","receipt","rate_limit","encryption","binary_data"]"#
+        );
+
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&["receipt", "from_the_future"], &mut cbor).unwrap();
+        let capabilities: Capabilities = ciborium::de::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(capabilities, Capabilities::legacy());
+    }
+
+    #[test]
+    fn capability_str() {
+        for capability in Capability::ALL {
+            assert_eq!(capability.to_string().parse(), Ok(capability));
+        }
+        assert_eq!("from_the_future".parse(), Ok(Capability::Unknown));
+    }
+
+    #[test]
+    fn version() {
+        assert_eq!(negotiate_version(0), Some(0));
+        assert_eq!(negotiate_version(PROTOCOL_VERSION), Some(PROTOCOL_VERSION));
+        assert_eq!(negotiate_version(u16::MAX), Some(PROTOCOL_VERSION));
+    }
+}
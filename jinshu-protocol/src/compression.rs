@@ -0,0 +1,208 @@
+use crate::{Error, NoSuchCompressionError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+/// 报文压缩算法
+///
+/// 登录时由客户端在 [`crate::Request::SignIn`] 中按优先级列出支持的算法，
+/// Comet 在 [`crate::Response::SignedIn`] 中返回选中的算法，之后双方发送的报文使用该算法压缩
+///
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum Compression {
+    /// Zstandard
+    #[serde(rename = "zstd")]
+    Zstd = 1,
+    /// LZ4
+    #[serde(rename = "lz4")]
+    Lz4 = 2,
+    /// Deflate
+    #[serde(rename = "deflate")]
+    Deflate = 3,
+}
+
+impl Compression {
+    /// 当前平台支持的所有压缩算法
+    #[cfg(not(target_arch = "wasm32"))]
+    pub const ALL: [Compression; 3] = [Compression::Zstd, Compression::Lz4, Compression::Deflate];
+
+    /// 当前平台支持的所有压缩算法，wasm32 上只支持 Deflate
+    #[cfg(target_arch = "wasm32")]
+    pub const ALL: [Compression; 1] = [Compression::Deflate];
+
+    /// zstd 使用的压缩级别
+    #[cfg(not(target_arch = "wasm32"))]
+    const ZSTD_LEVEL: i32 = 3;
+
+    /// 当前平台不支持的压缩算法
+    #[cfg(target_arch = "wasm32")]
+    fn unsupported(&self) -> Error {
+        Error::Other(format!("Compression {} is not supported on wasm32", self).into())
+    }
+
+    /// 从 `preferred` 中选出第一个同时在 `supported` 中的算法
+    pub fn negotiate(preferred: &[Compression], supported: &[Compression]) -> Option<Compression> {
+        preferred.iter().find(|c| supported.contains(c)).copied()
+    }
+
+    /// 压缩数据
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Compression::Zstd => zstd::bulk::compress(data, Self::ZSTD_LEVEL)?,
+            #[cfg(not(target_arch = "wasm32"))]
+            Compression::Lz4 => lz4::block::compress(data, None, true)?,
+            #[cfg(target_arch = "wasm32")]
+            Compression::Zstd | Compression::Lz4 => return Err(self.unsupported()),
+            Compression::Deflate => {
+                let mut encoder =
+                    flate2::read::DeflateEncoder::new(data, flate2::Compression::fast());
+                let mut compressed = Vec::with_capacity(data.len() / 2);
+                encoder.read_to_end(&mut compressed)?;
+                compressed
+            }
+        })
+    }
+
+    /// 解压数据，解压后的长度超过 `max_len` 时返回错误
+    pub fn decompress(&self, data: &[u8], max_len: usize) -> Result<Vec<u8>, Error> {
+        let decompressed = match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Compression::Zstd => zstd::bulk::decompress(data, max_len)?,
+            #[cfg(not(target_arch = "wasm32"))]
+            Compression::Lz4 => {
+                // 数据前 4 个字节为小端序的原始长度，解压前先检查，避免分配过大的内存
+                let len = data
+                    .get(..4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+                    .ok_or_else(|| Error::Other("Invalid lz4 data".into()))?;
+                if len > max_len {
+                    return Err(Error::TooLongDecompressed(*self, max_len));
+                }
+                lz4::block::decompress(data, None)?
+            }
+            #[cfg(target_arch = "wasm32")]
+            Compression::Zstd | Compression::Lz4 => return Err(self.unsupported()),
+            Compression::Deflate => {
+                let mut decompressed = Vec::with_capacity(data.len() * 2);
+                flate2::read::DeflateDecoder::new(data)
+                    .take(max_len as u64 + 1)
+                    .read_to_end(&mut decompressed)?;
+                decompressed
+            }
+        };
+
+        if decompressed.len() > max_len {
+            return Err(Error::TooLongDecompressed(*self, max_len));
+        }
+        Ok(decompressed)
+    }
+}
+
+impl TryFrom<u8> for Compression {
+    type Error = NoSuchCompressionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            1 => Self::Zstd,
+            2 => Self::Lz4,
+            3 => Self::Deflate,
+            _ => return Err(NoSuchCompressionError),
+        })
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Zstd => write!(f, "zstd"),
+            Compression::Lz4 => write!(f, "lz4"),
+            Compression::Deflate => write!(f, "deflate"),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = NoSuchCompressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim() {
+            "zstd" | "1" => Self::Zstd,
+            "lz4" | "2" => Self::Lz4,
+            "deflate" | "3" => Self::Deflate,
+            _ => return Err(NoSuchCompressionError),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Compression;
+    use crate::{Error, NoSuchCompressionError};
+
+    #[test]
+    fn round_trip() {
+        let data = "jinshu ".repeat(1000).into_bytes();
+        for compression in Compression::ALL {
+            let compressed = compression.compress(&data).unwrap();
+            assert!(compressed.len() < data.len(), "{}", compression);
+            assert_eq!(
+                compression.decompress(&compressed, data.len()).unwrap(),
+                data
+            );
+            assert!(compression.decompress(&compressed, data.len() - 1).is_err());
+        }
+    }
+
+    #[test]
+    fn limit() {
+        let data = vec![0u8; 10000];
+        for compression in Compression::ALL {
+            let compressed = compression.compress(&data).unwrap();
+            let result = compression.decompress(&compressed, 100);
+            assert!(result.is_err(), "{}", compression);
+        }
+
+        let compressed = Compression::Deflate.compress(&data).unwrap();
+        assert!(matches!(
+            Compression::Deflate.decompress(&compressed, 100),
+            Err(Error::TooLongDecompressed(Compression::Deflate, 100))
+        ));
+    }
+
+    #[test]
+    fn negotiate() {
+        assert_eq!(
+            Compression::negotiate(&[Compression::Lz4, Compression::Zstd], &Compression::ALL),
+            Some(Compression::Lz4)
+        );
+        assert_eq!(
+            Compression::negotiate(&[Compression::Lz4], &[Compression::Zstd]),
+            None
+        );
+        assert_eq!(Compression::negotiate(&[], &Compression::ALL), None);
+    }
+
+    #[test]
+    fn compression_str() {
+        for compression in Compression::ALL {
+            assert_eq!(
+                compression.to_string().parse::<Compression>().unwrap(),
+                compression
+            );
+            assert_eq!(
+                Compression::try_from(compression as u8).unwrap(),
+                compression
+            );
+        }
+        assert!(matches!(
+            Compression::try_from(0),
+            Err(NoSuchCompressionError)
+        ));
+        assert!(matches!(
+            "gzip".parse::<Compression>(),
+            Err(NoSuchCompressionError)
+        ));
+    }
+}
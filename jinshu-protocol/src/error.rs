@@ -1,4 +1,4 @@
-use crate::Codec;
+use crate::{Codec, Compression};
 use std::borrow::Cow;
 use std::fmt;
 use std::fmt::Display;
@@ -20,6 +20,23 @@ impl fmt::Display for NoSuchCodecError {
 
 impl std::error::Error for NoSuchCodecError {}
 
+/// 没有这种压缩算法的错误
+#[derive(Debug)]
+pub struct NoSuchCompressionError;
+
+impl NoSuchCompressionError {
+    /// 没有这种压缩算法的错误信息
+    pub const MESSAGE: &'static str = "No such compression";
+}
+
+impl fmt::Display for NoSuchCompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Self::MESSAGE)
+    }
+}
+
+impl std::error::Error for NoSuchCompressionError {}
+
 /// 不合法的消息内容格式错误
 #[derive(Debug)]
 pub struct InvalidContentFormat(String);
@@ -48,6 +65,9 @@ pub enum Error {
     /// 不存在编码错误
     #[error(transparent)]
     InvalidCodec(#[from] NoSuchCodecError),
+    /// 不存在压缩算法错误
+    #[error(transparent)]
+    InvalidCompression(#[from] NoSuchCompressionError),
     /// 格式不合法错误
     #[error(transparent)]
     InvalidContent(#[from] InvalidContentFormat),
@@ -72,9 +92,24 @@ pub enum Error {
     /// FlexBuffers 解码错误
     #[error(transparent)]
     FlexBuffersDecode(#[from] flexbuffers::DeserializationError),
+    /// Protobuf 解码错误
+    #[error(transparent)]
+    ProtobufDecode(#[from] prost::DecodeError),
+    /// Protobuf 报文缺少必需的字段
+    #[error("Missing field in protobuf data: {0}")]
+    MissingField(&'static str),
+    /// 不合法的 UUID
+    #[error(transparent)]
+    Uuid(#[from] uuid::Error),
     /// 报文过长错误
     #[error("The length of {0} data ({1} bytes) exceeds the maximum length")]
     TooLong(Codec, usize),
+    /// 接收的报文超过最大长度
+    #[error("The length of received frame ({0} bytes) exceeds the limit ({1} bytes)")]
+    FrameTooLarge(usize, usize),
+    /// 解压后的报文过长错误
+    #[error("The length of {0} decompressed data exceeds the maximum length ({1} bytes)")]
+    TooLongDecompressed(Compression, usize),
     /// 其他错误
     #[error("{0}")]
     Other(Cow<'static, str>),
@@ -85,10 +120,18 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 #[cfg(test)]
 mod test {
-    use super::NoSuchCodecError;
+    use super::{NoSuchCodecError, NoSuchCompressionError};
 
     #[test]
     fn no_such_codec() {
         assert_eq!(NoSuchCodecError.to_string(), NoSuchCodecError::MESSAGE);
     }
+
+    #[test]
+    fn no_such_compression() {
+        assert_eq!(
+            NoSuchCompressionError.to_string(),
+            NoSuchCompressionError::MESSAGE
+        );
+    }
 }
@@ -4,8 +4,13 @@
 //! 协议模块，包括前后端共用的协议、接口等
 //!
 
+mod capability;
+mod compression;
 mod error;
+mod protobuf;
 mod protocol;
 
+pub use capability::*;
+pub use compression::*;
 pub use error::*;
 pub use protocol::*;
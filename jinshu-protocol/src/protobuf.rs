@@ -0,0 +1,362 @@
+//! `Codec::Protobuf` 编码，协议定义见 `proto/pdu.proto`
+
+use crate::{
+    Body, Capabilities, Codec, Compression, Content, Error, InvalidContentFormat, Message, Pdu,
+    Request, Response, TransactionId,
+};
+use bytes::Bytes;
+use pb::content::Type;
+use pb::pdu::Body as PbBody;
+use pb::request::Method;
+use pb::response::Status;
+use prost::Message as _;
+use uuid::Uuid;
+
+#[allow(missing_docs, clippy::all)]
+mod pb {
+    include!(concat!(env!("OUT_DIR"), "/jinshu.pdu.rs"));
+}
+
+/// 将协议数据单元编码为 protobuf 数据
+pub(crate) fn encode(pdu: Pdu) -> Vec<u8> {
+    pb::Pdu::from(pdu).encode_to_vec()
+}
+
+/// 从 protobuf 数据解码协议数据单元，数据消息的内容直接引用 `bytes`
+pub(crate) fn decode(bytes: Bytes) -> crate::Result<Pdu> {
+    Pdu::try_from(pb::Pdu::decode(bytes)?)
+}
+
+fn uuid(bytes: &[u8]) -> crate::Result<Uuid> {
+    Ok(Uuid::from_slice(bytes)?)
+}
+
+fn required<T>(field: Option<T>, name: &'static str) -> crate::Result<T> {
+    field.ok_or(Error::MissingField(name))
+}
+
+/// 忽略不认识的压缩算法，以兼容新版本的对端
+fn compressions(values: &[i32]) -> Vec<Compression> {
+    values
+        .iter()
+        .filter_map(|c| u8::try_from(*c).ok())
+        .filter_map(|c| Compression::try_from(c).ok())
+        .collect()
+}
+
+fn compression(value: i32) -> Option<Compression> {
+    u8::try_from(value)
+        .ok()
+        .and_then(|c| Compression::try_from(c).ok())
+}
+
+fn capabilities(values: Vec<String>) -> Capabilities {
+    values.iter().filter_map(|c| c.parse().ok()).collect()
+}
+
+impl From<Pdu> for pb::Pdu {
+    fn from(pdu: Pdu) -> Self {
+        pb::Pdu {
+            id: Some(pb::TransactionId {
+                time: pdu.id.time,
+                seq: pdu.id.seq,
+            }),
+            body: Some(match pdu.body {
+                Body::Req(request) => PbBody::Req(request.into()),
+                Body::Resp(response) => PbBody::Resp(response.into()),
+            }),
+        }
+    }
+}
+
+impl TryFrom<pb::Pdu> for Pdu {
+    type Error = Error;
+
+    fn try_from(pdu: pb::Pdu) -> Result<Self, Error> {
+        let id = required(pdu.id, "Pdu.id")?;
+        Ok(Pdu {
+            id: TransactionId {
+                time: id.time,
+                seq: id.seq,
+            },
+            body: match required(pdu.body, "Pdu.body")? {
+                PbBody::Req(request) => Body::Req(request.try_into()?),
+                PbBody::Resp(response) => Body::Resp(response.try_into()?),
+            },
+        })
+    }
+}
+
+impl From<Request> for pb::Request {
+    fn from(request: Request) -> Self {
+        let method = match request {
+            Request::SignIn {
+                app_id,
+                user_id,
+                token,
+                compressions,
+                version,
+                capabilities,
+            } => Method::SignIn(pb::SignIn {
+                app_id,
+                user_id: user_id.as_bytes().to_vec(),
+                token,
+                compressions: compressions.into_iter().map(|c| c as i32).collect(),
+                version: version as u32,
+                capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+            }),
+            Request::SignOut => Method::SignOut(pb::Empty {}),
+            Request::Ping => Method::Ping(pb::Empty {}),
+            Request::Send { message } => Method::Send(message.into()),
+            Request::Push { message } => Method::Push(message.into()),
+        };
+        pb::Request {
+            method: Some(method),
+        }
+    }
+}
+
+impl TryFrom<pb::Request> for Request {
+    type Error = Error;
+
+    fn try_from(request: pb::Request) -> Result<Self, Error> {
+        Ok(match required(request.method, "Request.method")? {
+            Method::SignIn(sign_in) => Request::SignIn {
+                user_id: uuid(&sign_in.user_id)?,
+                compressions: compressions(&sign_in.compressions),
+                version: u16::try_from(sign_in.version).unwrap_or(u16::MAX),
+                capabilities: capabilities(sign_in.capabilities),
+                app_id: sign_in.app_id,
+                token: sign_in.token,
+            },
+            Method::SignOut(_) => Request::SignOut,
+            Method::Ping(_) => Request::Ping,
+            Method::Send(message) => Request::Send {
+                message: message.try_into()?,
+            },
+            Method::Push(message) => Request::Push {
+                message: message.try_into()?,
+            },
+        })
+    }
+}
+
+impl From<Response> for pb::Response {
+    fn from(response: Response) -> Self {
+        let status = match response {
+            Response::Ok => Status::Ok(pb::Empty {}),
+            Response::SignedIn {
+                extension,
+                compression,
+                version,
+                capabilities,
+                codecs,
+                compressions,
+            } => Status::SignedIn(pb::SignedIn {
+                extension: extension.map(|e| e.to_string()).unwrap_or_default(),
+                compression: compression.map(|c| c as i32).unwrap_or_default(),
+                version: version as u32,
+                capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+                codecs: codecs.into_iter().map(|c| c as i32).collect(),
+                compressions: compressions.into_iter().map(|c| c as i32).collect(),
+            }),
+            Response::InvalidToken { user_id } => Status::InvalidToken(pb::InvalidToken {
+                user_id: user_id.as_bytes().to_vec(),
+            }),
+            Response::Pong => Status::Pong(pb::Empty {}),
+            Response::Queued { id } => Status::Queued(pb::Queued {
+                id: id.as_bytes().to_vec(),
+            }),
+            Response::Rejected { id, error } => Status::Rejected(pb::Rejected {
+                id: id.as_bytes().to_vec(),
+                error,
+            }),
+            Response::RateLimited { id, retry_after_ms } => Status::RateLimited(pb::RateLimited {
+                id: id.as_bytes().to_vec(),
+                retry_after_ms,
+            }),
+            Response::Error { cause } => Status::Error(pb::Error { cause }),
+            Response::UnsupportedVersion {
+                min_version,
+                max_version,
+            } => Status::UnsupportedVersion(pb::UnsupportedVersion {
+                min_version: min_version as u32,
+                max_version: max_version as u32,
+            }),
+        };
+        pb::Response {
+            status: Some(status),
+        }
+    }
+}
+
+impl TryFrom<pb::Response> for Response {
+    type Error = Error;
+
+    fn try_from(response: pb::Response) -> Result<Self, Error> {
+        Ok(match required(response.status, "Response.status")? {
+            Status::Ok(_) => Response::Ok,
+            Status::SignedIn(signed_in) => Response::SignedIn {
+                extension: if signed_in.extension.is_empty() {
+                    None
+                } else {
+                    Some(serde_json::from_str(&signed_in.extension)?)
+                },
+                compression: compression(signed_in.compression),
+                version: u16::try_from(signed_in.version).unwrap_or(u16::MAX),
+                codecs: signed_in
+                    .codecs
+                    .iter()
+                    .filter_map(|c| u8::try_from(*c).ok())
+                    .filter_map(|c| Codec::try_from(c).ok())
+                    .collect(),
+                compressions: compressions(&signed_in.compressions),
+                capabilities: capabilities(signed_in.capabilities),
+            },
+            Status::InvalidToken(invalid) => Response::InvalidToken {
+                user_id: uuid(&invalid.user_id)?,
+            },
+            Status::Pong(_) => Response::Pong,
+            Status::Queued(queued) => Response::Queued {
+                id: uuid(&queued.id)?,
+            },
+            Status::Rejected(rejected) => Response::Rejected {
+                id: uuid(&rejected.id)?,
+                error: rejected.error,
+            },
+            Status::RateLimited(limited) => Response::RateLimited {
+                id: uuid(&limited.id)?,
+                retry_after_ms: limited.retry_after_ms,
+            },
+            Status::Error(error) => Response::Error { cause: error.cause },
+            Status::UnsupportedVersion(unsupported) => Response::UnsupportedVersion {
+                min_version: u16::try_from(unsupported.min_version).unwrap_or(u16::MAX),
+                max_version: u16::try_from(unsupported.max_version).unwrap_or(u16::MAX),
+            },
+        })
+    }
+}
+
+impl From<Message> for pb::Message {
+    fn from(message: Message) -> Self {
+        pb::Message {
+            id: message.id.as_bytes().to_vec(),
+            timestamp: message.timestamp,
+            from: message.from.as_bytes().to_vec(),
+            to: message.to.as_bytes().to_vec(),
+            content: Some(message.content.into()),
+        }
+    }
+}
+
+impl TryFrom<pb::Message> for Message {
+    type Error = Error;
+
+    fn try_from(message: pb::Message) -> Result<Self, Error> {
+        Ok(Message {
+            id: uuid(&message.id)?,
+            timestamp: message.timestamp,
+            from: uuid(&message.from)?,
+            to: uuid(&message.to)?,
+            content: required(message.content, "Message.content")?.try_into()?,
+        })
+    }
+}
+
+impl From<Content> for pb::Content {
+    fn from(content: Content) -> Self {
+        let r#type = match content {
+            Content::Data { mime, bytes } => Type::Data(pb::Data {
+                mime: mime.to_string(),
+                bytes,
+            }),
+            Content::Link { url } => Type::Link(pb::Link { url: url.into() }),
+            Content::Encrypted {
+                algorithm,
+                sender_key_id,
+                recipient_key_id,
+                nonce,
+                ciphertext,
+            } => Type::Encrypted(pb::Encrypted {
+                algorithm,
+                sender_key_id,
+                recipient_key_id,
+                nonce,
+                ciphertext,
+            }),
+        };
+        pb::Content {
+            r#type: Some(r#type),
+        }
+    }
+}
+
+impl TryFrom<pb::Content> for Content {
+    type Error = Error;
+
+    fn try_from(content: pb::Content) -> Result<Self, Error> {
+        Ok(match required(content.r#type, "Content.type")? {
+            Type::Data(data) => Content::Data {
+                mime: data.mime.parse().map_err(InvalidContentFormat::new)?,
+                bytes: data.bytes,
+            },
+            Type::Link(link) => Content::Link {
+                url: link.url.parse().map_err(InvalidContentFormat::new)?,
+            },
+            Type::Encrypted(encrypted) => Content::Encrypted {
+                algorithm: encrypted.algorithm,
+                sender_key_id: encrypted.sender_key_id,
+                recipient_key_id: encrypted.recipient_key_id,
+                nonce: encrypted.nonce,
+                ciphertext: encrypted.ciphertext,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode, pb};
+    use crate::{Body, Error, Pdu, Request};
+    use bytes::Bytes;
+    use prost::Message as _;
+
+    #[test]
+    fn missing_field() {
+        let bytes = pb::Pdu {
+            id: None,
+            body: Some(pb::pdu::Body::Req(pb::Request::default())),
+        }
+        .encode_to_vec();
+        assert!(matches!(
+            decode(bytes.into()),
+            Err(Error::MissingField("Pdu.id"))
+        ));
+
+        let bytes = pb::Pdu {
+            id: Some(pb::TransactionId::default()),
+            body: Some(pb::pdu::Body::Req(pb::Request::default())),
+        }
+        .encode_to_vec();
+        assert!(matches!(
+            decode(bytes.into()),
+            Err(Error::MissingField("Request.method"))
+        ));
+
+        assert!(matches!(
+            decode(Bytes::from_static(&[0xff])),
+            Err(Error::ProtobufDecode(_))
+        ));
+    }
+
+    #[test]
+    fn ping() {
+        let mut id_gen = crate::TransactionIdGenerator::default();
+        let id = id_gen.next_id();
+        let bytes = encode(Request::Ping.to_pdu(id));
+        assert!(matches!(
+            decode(bytes.into()),
+            Ok(Pdu { id: decoded, body: Body::Req(Request::Ping) }) if decoded == id
+        ));
+    }
+}
@@ -1,5 +1,5 @@
-use crate::{Error, InvalidContentFormat, NoSuchCodecError};
-use bytes::{Buf, BufMut, BytesMut};
+use crate::{Capabilities, Compression, Error, InvalidContentFormat, NoSuchCodecError};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use jinshu_utils::{current_millisecond, current_second};
 use mime::{Mime, TEXT_PLAIN_UTF_8};
 use serde::{Deserialize, Serialize};
@@ -13,8 +13,8 @@ use uuid::Uuid;
 /// 事务 ID
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub struct TransactionId {
-    time: u32,
-    seq: u32,
+    pub(crate) time: u32,
+    pub(crate) seq: u32,
 }
 
 /// 事务 ID 生成器
@@ -93,6 +93,13 @@ pub enum Body {
     Resp(Response),
 }
 
+/// 默认应用 ID，不带应用 ID 的旧客户端登录时使用
+pub const DEFAULT_APP_ID: &str = "default";
+
+fn default_app_id() -> String {
+    DEFAULT_APP_ID.to_string()
+}
+
 /// 请求
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "method")]
@@ -101,8 +108,23 @@ pub enum Request {
     SignIn {
         /// 锦书用户 ID
         user_id: Uuid,
-        /// 登录令牌
-        token: Uuid,
+        /// 登录令牌，旧客户端以二进制数据发送的 UUID 令牌转换为不带连字符的字符串
+        #[serde(deserialize_with = "token_serde::deserialize")]
+        token: String,
+        /// 应用 ID，连接上收发的消息只在该应用内传递，旧客户端不带应用 ID 时为 [`DEFAULT_APP_ID`]
+        ///
+        /// 新增的字段都放在旧版本的字段之后，MsgPack 按位置编码字段，旧客户端的报文缺少这些字段时使用默认值
+        #[serde(default = "default_app_id")]
+        app_id: String,
+        /// 客户端支持的报文压缩算法，按优先级排列，为空时不压缩
+        #[serde(default)]
+        compressions: Vec<Compression>,
+        /// 客户端的协议版本，旧客户端为 0
+        #[serde(default)]
+        version: u16,
+        /// 客户端支持的能力
+        #[serde(default)]
+        capabilities: Capabilities,
     },
     /// 登出
     SignOut,
@@ -137,6 +159,28 @@ pub enum Response {
     SignedIn {
         /// 扩展字段
         extension: Option<serde_json::Value>,
+        /// 协商的报文压缩算法，为空时不压缩
+        #[serde(default)]
+        compression: Option<Compression>,
+        /// 协商的协议版本
+        #[serde(default)]
+        version: u16,
+        /// 协商的能力，即双方都支持的能力
+        #[serde(default)]
+        capabilities: Capabilities,
+        /// 服务端支持的编解码格式
+        #[serde(default)]
+        codecs: Vec<Codec>,
+        /// 服务端支持的报文压缩算法
+        #[serde(default)]
+        compressions: Vec<Compression>,
+    },
+    /// 不支持客户端的协议版本
+    UnsupportedVersion {
+        /// 服务端支持的最低协议版本
+        min_version: u16,
+        /// 服务端支持的最高协议版本
+        max_version: u16,
     },
     /// 非法的令牌
     InvalidToken {
@@ -157,6 +201,13 @@ pub enum Response {
         /// 错误信息
         error: String,
     },
+    /// 发送过于频繁，消息未入队
+    RateLimited {
+        /// 消息 ID
+        id: Uuid,
+        /// 建议在多少毫秒后重试
+        retry_after_ms: u64,
+    },
     /// 发生错误
     Error {
         /// 错误信息
@@ -172,7 +223,7 @@ impl Response {
 }
 
 /// 消息
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     /// 消息 ID
     pub id: Uuid,
@@ -199,8 +250,11 @@ impl Message {
     }
 }
 
+/// 过期通知的内容类型，数据为过期消息的 ID
+pub const EXPIRED_MIME: &str = "application/x-jinshu-expired";
+
 /// 消息内容
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Content {
     /// 数据消息，包括字符串、小图片等
@@ -208,14 +262,28 @@ pub enum Content {
         /// 数据类型
         #[serde(with = "serde_shims::mime")]
         mime: Mime,
-        /// 二进制数据
-        bytes: Vec<u8>,
+        /// 二进制数据，解码 protobuf 报文时直接引用接收缓冲区，不复制
+        #[serde(with = "bytes_serde")]
+        bytes: Bytes,
     },
     /// 链接消息，包括大图片、视频等
     Link {
         /// 链接地址
         url: Url,
     },
+    /// 端到端加密消息，服务端不解析其中的内容，只原样存储及转发
+    Encrypted {
+        /// 加密算法标识，由收发双方约定，如 `x25519-aes256gcm`
+        algorithm: String,
+        /// 发送方身份公钥的 ID
+        sender_key_id: String,
+        /// 接收方身份公钥的 ID
+        recipient_key_id: String,
+        /// 随机数等算法参数
+        nonce: Vec<u8>,
+        /// 密文
+        ciphertext: Vec<u8>,
+    },
 }
 
 impl Content {
@@ -225,7 +293,7 @@ impl Content {
     }
 
     /// 构造一个数据消息内容
-    pub fn data(mime: Mime, bytes: impl Into<Vec<u8>>) -> Self {
+    pub fn data(mime: Mime, bytes: impl Into<Bytes>) -> Self {
         Self::Data {
             mime,
             bytes: bytes.into(),
@@ -236,13 +304,58 @@ impl Content {
     pub fn link(url: impl Into<Url>) -> Self {
         Self::Link { url: url.into() }
     }
+
+    /// 构造一个端到端加密消息内容
+    pub fn encrypted(
+        algorithm: impl Into<String>,
+        sender_key_id: impl Into<String>,
+        recipient_key_id: impl Into<String>,
+        nonce: impl Into<Vec<u8>>,
+        ciphertext: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self::Encrypted {
+            algorithm: algorithm.into(),
+            sender_key_id: sender_key_id.into(),
+            recipient_key_id: recipient_key_id.into(),
+            nonce: nonce.into(),
+            ciphertext: ciphertext.into(),
+        }
+    }
+
+    /// 是否为端到端加密消息
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self, Self::Encrypted { .. })
+    }
+
+    /// 构造一个过期通知内容，通知接收方 ID 为 `id` 的消息已过期（阅后即焚）
+    pub fn expired(id: Uuid) -> Self {
+        Self::data(
+            EXPIRED_MIME.parse().expect("invalid expired mime"),
+            id.as_simple().to_string(),
+        )
+    }
+
+    /// 如果是过期通知，返回过期消息的 ID，客户端收到后应删除本地保存的该消息
+    pub fn expired_id(&self) -> Option<Uuid> {
+        match self {
+            Self::Data { mime, bytes } if mime.essence_str() == EXPIRED_MIME => {
+                std::str::from_utf8(bytes).ok()?.parse().ok()
+            }
+            _ => None,
+        }
+    }
 }
 
 impl TryFrom<&Content> for Vec<u8> {
     type Error = InvalidContentFormat;
 
     fn try_from(value: &Content) -> Result<Self, Self::Error> {
-        let mut wr = Vec::with_capacity(128);
+        let payload = match value {
+            Content::Data { bytes, .. } => bytes.len(),
+            Content::Link { .. } => 0,
+            Content::Encrypted { ciphertext, .. } => ciphertext.len(),
+        };
+        let mut wr = Vec::with_capacity(128 + payload);
         ciborium::ser::into_writer(value, &mut wr).map_err(InvalidContentFormat::new)?;
         Ok(wr)
     }
@@ -256,13 +369,136 @@ impl TryFrom<&[u8]> for Content {
     }
 }
 
+/// 登录令牌的反序列化
+///
+/// 旧版本的令牌为 UUID，JSON 中为字符串，MsgPack、CBOR 及 FlexBuffers 中为 16 字节的二进制数据，
+/// 二进制数据转换为 Gateway 签发时使用的不带连字符的格式，字符串原样保留
+mod token_serde {
+    use serde::de::{Error, Visitor};
+    use serde::Deserializer;
+    use std::fmt;
+    use uuid::Uuid;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        deserializer.deserialize_any(TokenVisitor)
+    }
+
+    struct TokenVisitor;
+
+    impl<'de> Visitor<'de> for TokenVisitor {
+        type Value = String;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a string or a UUID")
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(v.to_string())
+        }
+
+        fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            Uuid::from_slice(v)
+                .map(|uuid| uuid.as_simple().to_string())
+                .map_err(E::custom)
+        }
+    }
+}
+
+/// [`Bytes`] 的序列化
+///
+/// 默认序列化为与旧版本相同的整数序列，只有在 [`with_binary`] 中才序列化为二进制数据；
+/// 反序列化时同时接受二进制数据及整数序列
+mod bytes_serde {
+    use bytes::Bytes;
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::ser::SerializeSeq;
+    use serde::{Deserializer, Serializer};
+    use std::cell::Cell;
+    use std::fmt;
+
+    thread_local! {
+        static BINARY: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// 执行 `f`，`binary` 为 `true` 时其中序列化的数据编码为二进制数据
+    pub fn with_binary<T>(binary: bool, f: impl FnOnce() -> T) -> T {
+        let previous = BINARY.with(|b| b.replace(binary));
+        let result = f();
+        BINARY.with(|b| b.set(previous));
+        result
+    }
+
+    pub fn serialize<S: Serializer>(bytes: &Bytes, serializer: S) -> Result<S::Ok, S::Error> {
+        if BINARY.with(Cell::get) {
+            return serializer.serialize_bytes(bytes);
+        }
+
+        let mut seq = serializer.serialize_seq(Some(bytes.len()))?;
+        for b in bytes.iter() {
+            seq.serialize_element(b)?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+        deserializer.deserialize_any(BytesVisitor)
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Bytes;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "bytes or a sequence of u8")
+        }
+
+        fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            Ok(Bytes::copy_from_slice(v))
+        }
+
+        fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+            Ok(v.into())
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default().min(4096));
+            while let Some(b) = seq.next_element::<u8>()? {
+                bytes.push(b);
+            }
+            Ok(bytes.into())
+        }
+    }
+}
+
 /// 协议数据单元编解码器
-#[derive(Debug, Copy, Clone, Default)]
+///
+/// 编码时只压缩不小于阈值的报文；解码时根据报文头处理压缩，与是否设置压缩算法无关。
+/// 收发的报文长度（压缩前后）都不能超过最大长度
+///
+#[derive(Debug, Copy, Clone)]
 pub struct PduCodec {
     codec: Codec,
+    compression: Option<Compression>,
+    compress_threshold: usize,
+    max_frame_len: usize,
+    binary_data: bool,
     state: CodecState,
 }
 
+impl Default for PduCodec {
+    fn default() -> Self {
+        Self {
+            codec: Codec::default(),
+            compression: None,
+            compress_threshold: Self::DEFAULT_COMPRESS_THRESHOLD,
+            max_frame_len: Self::DEFAULT_MAX_FRAME_LEN,
+            binary_data: false,
+            state: CodecState::default(),
+        }
+    }
+}
+
 impl PduCodec {
     /// 构造编解码器
     pub fn new(codec: Codec) -> Self {
@@ -276,12 +512,46 @@ impl PduCodec {
     pub fn codec(&self) -> Codec {
         self.codec
     }
+
+    /// 获取编码时使用的压缩算法
+    pub fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+
+    /// 设置编码时使用的压缩算法，为空时不压缩
+    pub fn set_compression(&mut self, compression: Option<Compression>) {
+        self.compression = compression;
+    }
+
+    /// 设置压缩阈值，小于该长度（字节）的报文不压缩
+    pub fn set_compress_threshold(&mut self, threshold: usize) {
+        self.compress_threshold = threshold;
+    }
+
+    /// 设置编码时是否将 [`Content::Data`] 的数据编码为二进制数据，
+    /// 只有对方支持 [`crate::Capability::BinaryData`] 时才能启用
+    pub fn set_binary_data(&mut self, binary_data: bool) {
+        self.binary_data = binary_data;
+    }
+
+    /// 获取报文的最大长度（字节）
+    pub fn max_frame_len(&self) -> usize {
+        self.max_frame_len
+    }
+
+    /// 设置报文的最大长度（字节），不能超过 [`PduCodec::MAX_DATA_LEN`]
+    // usize 为 32 位时（如 wasm32）上限即为 usize::MAX
+    #[cfg_attr(target_pointer_width = "32", allow(clippy::unnecessary_min_or_max))]
+    pub fn set_max_frame_len(&mut self, max_frame_len: usize) {
+        self.max_frame_len = max_frame_len.min(Self::MAX_DATA_LEN);
+    }
 }
 
 /// 编解码格式
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
 pub enum Codec {
     /// JSON
+    #[default]
     #[serde(rename = "json")]
     Json = 0,
     /// MsgPack
@@ -293,24 +563,31 @@ pub enum Codec {
     /// FlexBuffers
     #[serde(rename = "flexbuffers")]
     FlexBuffers = 3,
+    /// Protobuf，协议定义见 `proto/pdu.proto`
+    #[serde(rename = "protobuf")]
+    Protobuf = 4,
 }
 
-impl Default for Codec {
-    fn default() -> Self {
-        Self::Json
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 enum CodecState {
+    #[default]
     Head,
-    Data { codec: Codec, length: usize },
+    Data {
+        codec: Codec,
+        compression: Option<Compression>,
+        length: usize,
+    },
 }
 
-impl Default for CodecState {
-    fn default() -> Self {
-        Self::Head
-    }
+impl Codec {
+    /// 所有支持的编解码格式
+    pub const ALL: [Codec; 5] = [
+        Codec::Json,
+        Codec::MsgPack,
+        Codec::Cbor,
+        Codec::FlexBuffers,
+        Codec::Protobuf,
+    ];
 }
 
 impl TryFrom<u8> for Codec {
@@ -322,6 +599,7 @@ impl TryFrom<u8> for Codec {
             1 => Self::MsgPack,
             2 => Self::Cbor,
             3 => Self::FlexBuffers,
+            4 => Self::Protobuf,
             _ => return Err(NoSuchCodecError),
         })
     }
@@ -334,6 +612,7 @@ impl fmt::Display for Codec {
             Codec::MsgPack => write!(f, "msgpack"),
             Codec::Cbor => write!(f, "cbor"),
             Codec::FlexBuffers => write!(f, "flexbuffers"),
+            Codec::Protobuf => write!(f, "protobuf"),
         }
     }
 }
@@ -347,6 +626,7 @@ impl FromStr for Codec {
             "msgpack" | "1" => Self::MsgPack,
             "cbor" | "2" => Self::Cbor,
             "flexbuffers" | "3" => Self::FlexBuffers,
+            "protobuf" | "4" => Self::Protobuf,
             _ => return Err(NoSuchCodecError),
         })
     }
@@ -357,25 +637,54 @@ impl Encoder<Pdu> for PduCodec {
 
     #[tracing::instrument(skip(dst))]
     fn encode(&mut self, item: Pdu, dst: &mut bytes::BytesMut) -> Result<(), Self::Error> {
-        let pdu = match self.codec {
-            Codec::Json => serde_json::to_vec(&item)?,
-            Codec::MsgPack => rmp_serde::to_vec(&item)?,
-            Codec::Cbor => {
-                let mut wr = Vec::with_capacity(128);
-                ciborium::ser::into_writer(&item, &mut wr)?;
-                wr
+        let pdu = bytes_serde::with_binary(self.binary_data, || -> Result<_, Self::Error> {
+            Ok(match self.codec {
+                Codec::Json => serde_json::to_vec(&item)?,
+                Codec::MsgPack => rmp_serde::to_vec(&item)?,
+                Codec::Cbor => {
+                    let mut wr = Vec::with_capacity(128);
+                    ciborium::ser::into_writer(&item, &mut wr)?;
+                    wr
+                }
+                Codec::FlexBuffers => flexbuffers::to_vec(item)?,
+                Codec::Protobuf => crate::protobuf::encode(item),
+            })
+        })?;
+
+        tracing::debug!("serialize pdu to {} bytes {} data", pdu.len(), self.codec);
+
+        let (compression, pdu) = match self.compression {
+            Some(compression) if pdu.len() >= self.compress_threshold => {
+                let compressed = compression.compress(&pdu)?;
+                if compressed.len() < pdu.len() {
+                    tracing::debug!(
+                        "compress {} bytes to {} bytes with {}",
+                        pdu.len(),
+                        compressed.len(),
+                        compression
+                    );
+                    (Some(compression), compressed)
+                } else {
+                    (None, pdu)
+                }
             }
-            Codec::FlexBuffers => flexbuffers::to_vec(item)?,
+            _ => (None, pdu),
         };
 
-        if pdu.len() > Self::MAX_DATA_LEN {
+        if pdu.len() > self.max_frame_len {
             return Err(Error::TooLong(self.codec, pdu.len()));
         }
 
-        tracing::debug!("serialize pdu to {} bytes {} data", pdu.len(), self.codec);
-
-        let head = ((self.codec as u32) << 24) | (pdu.len() as u32 & 0xffffff);
-        dst.put_u32(head);
+        let flags = compression.map(|c| c as u8).unwrap_or_default() << Self::COMPRESSION_SHIFT
+            | self.codec as u8;
+        if pdu.len() > Self::MAX_SHORT_DATA_LEN {
+            dst.reserve(Self::EXTENDED_HEAD_LEN + pdu.len());
+            dst.put_u32(((flags | Self::EXTENDED_FLAG) as u32) << 24);
+            dst.put_u32(pdu.len() as u32);
+        } else {
+            dst.reserve(Self::HEAD_LEN + pdu.len());
+            dst.put_u32(((flags as u32) << 24) | pdu.len() as u32);
+        }
         dst.put_slice(&pdu);
 
         Ok(())
@@ -384,45 +693,106 @@ impl Encoder<Pdu> for PduCodec {
 
 impl PduCodec {
     /// head is a u32
-    /// | codec: u8 | length: u24 |
+    /// | compression: u2 | extended: u1 | codec: u5 | length: u24 |
+    ///
+    /// 报文长度超过 24 位时使用扩展报文头，`extended` 位为 1，`length` 为 0，之后是 32 位的长度
+    /// | compression: u2 | extended: u1 | codec: u5 | 0: u24 | length: u32 |
     pub const HEAD_LEN: usize = size_of::<u32>();
 
-    /// Data length is a 24 bit unsigned integer
+    /// 扩展报文头的长度
+    pub const EXTENDED_HEAD_LEN: usize = Self::HEAD_LEN + size_of::<u32>();
+
+    /// 报文头第一个字节中压缩算法的偏移，0 表示未压缩
+    const COMPRESSION_SHIFT: u8 = 6;
+
+    /// 报文头第一个字节中的扩展报文头标志
+    const EXTENDED_FLAG: u8 = 1 << 5;
+
+    /// 报文头第一个字节中编码格式的掩码
+    const CODEC_MASK: u8 = Self::EXTENDED_FLAG - 1;
+
+    /// 默认的压缩阈值（字节）
+    pub const DEFAULT_COMPRESS_THRESHOLD: usize = 1024;
+
+    /// 普通报文头中的长度为 24 位无符号整数
     ///
     /// MAX: 16MB
-    pub const MAX_DATA_LEN: usize = (1 << 24) - 1;
+    pub const MAX_SHORT_DATA_LEN: usize = (1 << 24) - 1;
+
+    /// 扩展报文头中的长度为 32 位无符号整数
+    ///
+    /// MAX: 4GB
+    pub const MAX_DATA_LEN: usize = u32::MAX as usize;
 
-    fn decode_head(&mut self, src: &mut BytesMut) -> crate::error::Result<Option<(Codec, usize)>> {
+    /// 默认的报文最大长度，与只支持普通报文头的旧版本相同
+    pub const DEFAULT_MAX_FRAME_LEN: usize = Self::MAX_SHORT_DATA_LEN;
+
+    fn decode_head(&mut self, src: &mut BytesMut) -> crate::error::Result<Option<CodecState>> {
         if src.len() < Self::HEAD_LEN {
             return Ok(None);
         }
 
+        let flags = src[0];
+        let codec = Codec::try_from(flags & Self::CODEC_MASK)?;
+        let compression = match flags >> Self::COMPRESSION_SHIFT {
+            0 => None,
+            c => Some(Compression::try_from(c)?),
+        };
+        let extended = flags & Self::EXTENDED_FLAG != 0;
+        if extended && src.len() < Self::EXTENDED_HEAD_LEN {
+            return Ok(None);
+        }
+
         let head = src.get_u32();
-        let codec = Codec::try_from(((head & 0xff000000) >> 24) as u8)?;
-        let length = (head & 0xffffff) as usize;
-        src.reserve(length);
-        Ok(Some((codec, length)))
+        let length = if extended {
+            src.get_u32() as usize
+        } else {
+            (head & 0xffffff) as usize
+        };
+
+        // 在分配内存前检查长度
+        if length > self.max_frame_len {
+            return Err(Error::FrameTooLarge(length, self.max_frame_len));
+        }
+        // 只为尚未收到的部分预留空间，已收到完整报文时不重新分配，以免复制
+        src.reserve(length.saturating_sub(src.len()));
+        Ok(Some(CodecState::Data {
+            codec,
+            compression,
+            length,
+        }))
     }
 
     fn decode_data(
         &mut self,
         src: &mut BytesMut,
         codec: Codec,
+        compression: Option<Compression>,
         length: usize,
     ) -> crate::error::Result<Option<Pdu>> {
         if src.len() < length {
             return Ok(None);
         }
 
-        let bytes = src.split_to(length);
+        let mut bytes = src.split_to(length).freeze();
+        if let Some(compression) = compression {
+            bytes = compression.decompress(&bytes, self.max_frame_len)?.into();
+        }
+
         let pdu = match codec {
-            Codec::Json => serde_json::from_reader(bytes.reader())?,
-            Codec::MsgPack => rmp_serde::decode::from_read(bytes.reader())?,
-            Codec::Cbor => ciborium::de::from_reader(bytes.reader())?,
-            Codec::FlexBuffers => flexbuffers::from_slice(bytes.as_ref())?,
+            Codec::Json => serde_json::from_slice(&bytes)?,
+            Codec::MsgPack => rmp_serde::from_slice(&bytes)?,
+            Codec::Cbor => ciborium::de::from_reader(bytes.as_ref())?,
+            Codec::FlexBuffers => flexbuffers::from_slice(&bytes)?,
+            Codec::Protobuf => crate::protobuf::decode(bytes)?,
         };
 
-        tracing::debug!("deserialize {} bytes {} data to pdu", length, codec);
+        tracing::debug!(
+            "deserialize {} bytes {} data to pdu, compression: {:?}",
+            length,
+            codec,
+            compression
+        );
 
         Ok(Some(pdu))
     }
@@ -434,18 +804,27 @@ impl Decoder for PduCodec {
 
     #[tracing::instrument(skip(src))]
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        let (codec, length) = match self.state {
+        let state = match self.state {
             CodecState::Head => match self.decode_head(src)? {
-                Some((codec, length)) => {
-                    self.state = CodecState::Data { codec, length };
-                    (codec, length)
+                Some(state) => {
+                    self.state = state;
+                    state
                 }
                 None => return Ok(None),
             },
-            CodecState::Data { codec, length } => (codec, length),
+            state => state,
         };
 
-        match self.decode_data(src, codec, length)? {
+        let (codec, compression, length) = match state {
+            CodecState::Data {
+                codec,
+                compression,
+                length,
+            } => (codec, compression, length),
+            CodecState::Head => unreachable!("impossible: head state after decoding head"),
+        };
+
+        match self.decode_data(src, codec, compression, length)? {
             Some(pdu) => {
                 self.state = CodecState::Head;
                 src.reserve(Self::HEAD_LEN);
@@ -460,8 +839,8 @@ impl Decoder for PduCodec {
 mod test {
     use super::Codec;
     use super::{Content, Message, PduCodec, Response};
-    use super::{NoSuchCodecError, Pdu, Request};
-    use crate::{Body, TransactionIdGenerator};
+    use super::{NoSuchCodecError, Pdu, Request, DEFAULT_APP_ID};
+    use crate::{Body, Compression, TransactionIdGenerator};
     use bytes::{BufMut, BytesMut};
     use tokio_util::codec::{Decoder, Encoder};
     use url::Url;
@@ -491,7 +870,11 @@ mod test {
             Ok(Codec::FlexBuffers)
         ));
         assert!(matches!(
-            Codec::try_from(Codec::FlexBuffers as u8 + 1),
+            Codec::try_from(Codec::Protobuf as u8),
+            Ok(Codec::Protobuf)
+        ));
+        assert!(matches!(
+            Codec::try_from(Codec::Protobuf as u8 + 1),
             Err(NoSuchCodecError)
         ));
     }
@@ -502,11 +885,13 @@ mod test {
         assert_eq!(Codec::Cbor.to_string(), "cbor");
         assert_eq!(Codec::MsgPack.to_string(), "msgpack");
         assert_eq!(Codec::FlexBuffers.to_string(), "flexbuffers");
+        assert_eq!(Codec::Protobuf.to_string(), "protobuf");
 
         assert!(matches!("json".parse(), Ok(Codec::Json)));
         assert!(matches!("cbor".parse(), Ok(Codec::Cbor)));
         assert!(matches!("msgpack".parse(), Ok(Codec::MsgPack)));
         assert!(matches!("flexbuffers".parse(), Ok(Codec::FlexBuffers)));
+        assert!(matches!("protobuf".parse(), Ok(Codec::Protobuf)));
         assert!(matches!(
             Uuid::new_v4().to_string().parse::<Codec>(),
             Err(NoSuchCodecError)
@@ -528,6 +913,10 @@ mod test {
             (Codec::FlexBuffers as u8).to_string().parse(),
             Ok(Codec::FlexBuffers)
         ));
+        assert!(matches!(
+            (Codec::Protobuf as u8).to_string().parse(),
+            Ok(Codec::Protobuf)
+        ));
     }
 
     #[test]
@@ -536,6 +925,117 @@ mod test {
         pdu_codec(Codec::Cbor);
         pdu_codec(Codec::MsgPack);
         pdu_codec(Codec::FlexBuffers);
+        pdu_codec(Codec::Protobuf);
+    }
+
+    #[test]
+    fn pdu_round_trip_all() {
+        for codec in Codec::ALL {
+            pdu_round_trip(codec);
+        }
+    }
+
+    fn pdu_round_trip(codec: Codec) {
+        let mut id_gen = TransactionIdGenerator::default();
+        let mut codec = PduCodec::new(codec);
+        let message = |content: Content| Message::new(Uuid::new_v4(), Uuid::new_v4(), content);
+        let contents = || {
+            vec![
+                Content::string("hello"),
+                Content::data(mime::IMAGE_PNG, vec![0u8, 1, 2, 255]),
+                Content::link(Url::parse("https://jinshu.io/a.png").unwrap()),
+                Content::encrypted("x25519-aes256gcm", "a", "b", [7u8; 12], vec![1, 2, 3]),
+            ]
+        };
+
+        let mut pdus = vec![
+            Request::SignIn {
+                app_id: "app".into(),
+                user_id: Uuid::new_v4(),
+                token: "token".into(),
+                compressions: vec![Compression::Lz4, Compression::Zstd],
+                version: crate::PROTOCOL_VERSION,
+                capabilities: crate::Capabilities::all(),
+            }
+            .to_pdu(id_gen.next_id()),
+            Request::SignOut.to_pdu(id_gen.next_id()),
+            Request::Ping.to_pdu(id_gen.next_id()),
+            Response::Ok.to_pdu(id_gen.next_id()),
+            Response::SignedIn {
+                extension: Some(serde_json::json!({ "name": "jinshu" })),
+                compression: Some(Compression::Deflate),
+                version: crate::PROTOCOL_VERSION,
+                capabilities: crate::Capabilities::legacy(),
+                codecs: Codec::ALL.to_vec(),
+                compressions: Compression::ALL.to_vec(),
+            }
+            .to_pdu(id_gen.next_id()),
+            Response::SignedIn {
+                extension: None,
+                compression: None,
+                version: 0,
+                capabilities: Default::default(),
+                codecs: vec![],
+                compressions: vec![],
+            }
+            .to_pdu(id_gen.next_id()),
+            Response::InvalidToken {
+                user_id: Uuid::new_v4(),
+            }
+            .to_pdu(id_gen.next_id()),
+            Response::Pong.to_pdu(id_gen.next_id()),
+            Response::Queued { id: Uuid::new_v4() }.to_pdu(id_gen.next_id()),
+            Response::Rejected {
+                id: Uuid::new_v4(),
+                error: "error".into(),
+            }
+            .to_pdu(id_gen.next_id()),
+            Response::RateLimited {
+                id: Uuid::new_v4(),
+                retry_after_ms: 100,
+            }
+            .to_pdu(id_gen.next_id()),
+            Response::Error {
+                cause: "cause".into(),
+            }
+            .to_pdu(id_gen.next_id()),
+            Response::UnsupportedVersion {
+                min_version: 1,
+                max_version: 2,
+            }
+            .to_pdu(id_gen.next_id()),
+        ];
+        for content in contents() {
+            pdus.push(
+                Request::Send {
+                    message: message(content),
+                }
+                .to_pdu(id_gen.next_id()),
+            );
+        }
+        for content in contents() {
+            pdus.push(
+                Request::Push {
+                    message: message(content),
+                }
+                .to_pdu(id_gen.next_id()),
+            );
+        }
+
+        let mut bytes = BytesMut::new();
+        for pdu in pdus {
+            // 使用 JSON 比较解码前后的协议数据单元
+            let expected = serde_json::to_value(&pdu).unwrap();
+            codec.encode(pdu, &mut bytes).unwrap();
+            let decoded = codec.decode(&mut bytes).unwrap().unwrap();
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                expected,
+                "{}",
+                codec.codec()
+            );
+            assert!(bytes.is_empty());
+        }
     }
 
     fn pdu_codec(codec: Codec) {
@@ -546,8 +1046,12 @@ mod test {
         assert!(codec
             .encode(
                 Request::SignIn {
+                    app_id: "app".into(),
                     user_id: Uuid::new_v4(),
-                    token: Uuid::new_v4(),
+                    token: Uuid::new_v4().as_simple().to_string(),
+                    compressions: Compression::ALL.to_vec(),
+                    version: crate::PROTOCOL_VERSION,
+                    capabilities: crate::Capabilities::all(),
                 }
                 .to_pdu(id_gen.next_id()),
                 &mut bytes
@@ -606,6 +1110,425 @@ mod test {
         ));
     }
 
+    #[test]
+    fn legacy_sign_in() {
+        let json = format!(
+            r#"{{"method":"SignIn","app_id":"app","user_id":"{}","token":"t"}}"#,
+            Uuid::new_v4()
+        );
+        assert!(matches!(
+            serde_json::from_str(&json),
+            Ok(Request::SignIn { version: 0, capabilities, compressions, .. })
+                if capabilities.is_empty() && compressions.is_empty()
+        ));
+
+        // 不带应用 ID 的旧报文使用默认应用
+        let user_id = Uuid::new_v4();
+        let json = format!(
+            r#"{{"method":"SignIn","user_id":"{}","token":"t"}}"#,
+            user_id
+        );
+        assert!(matches!(
+            serde_json::from_str(&json),
+            Ok(Request::SignIn { app_id, user_id: id, version: 0, .. })
+                if app_id == DEFAULT_APP_ID && id == user_id
+        ));
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(
+            &serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            &mut cbor,
+        )
+        .unwrap();
+        assert!(matches!(
+            ciborium::de::from_reader(cbor.as_slice()),
+            Ok(Request::SignIn { app_id, version: 0, .. }) if app_id == DEFAULT_APP_ID
+        ));
+
+        // MsgPack 按位置编码字段
+        #[derive(serde::Serialize)]
+        #[serde(tag = "method")]
+        enum Legacy {
+            SignIn { user_id: Uuid, token: String },
+        }
+        let msgpack = rmp_serde::to_vec(&Legacy::SignIn {
+            user_id,
+            token: "t".into(),
+        })
+        .unwrap();
+        assert!(matches!(
+            rmp_serde::from_slice(&msgpack),
+            Ok(Request::SignIn { app_id, user_id: id, token, version: 0, .. })
+                if app_id == DEFAULT_APP_ID && id == user_id && token == "t"
+        ));
+
+        // 旧版本的令牌为 UUID
+        #[derive(serde::Serialize)]
+        #[serde(tag = "method")]
+        enum Baseline {
+            SignIn { user_id: Uuid, token: Uuid },
+        }
+        let token = Uuid::new_v4();
+        let baseline = Baseline::SignIn { user_id, token };
+        let is_token = |request: Request, expected: String| {
+            matches!(request, Request::SignIn { app_id, token, user_id: id, .. }
+                if app_id == DEFAULT_APP_ID && token == expected && id == user_id)
+        };
+        assert!(is_token(
+            serde_json::from_slice(&serde_json::to_vec(&baseline).unwrap()).unwrap(),
+            token.to_string()
+        ));
+        let simple = token.as_simple().to_string();
+        let msgpack = rmp_serde::to_vec(&baseline).unwrap();
+        assert!(is_token(
+            rmp_serde::from_slice(&msgpack).unwrap(),
+            simple.clone()
+        ));
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&baseline, &mut cbor).unwrap();
+        assert!(is_token(
+            ciborium::de::from_reader(cbor.as_slice()).unwrap(),
+            simple.clone()
+        ));
+        let flex = flexbuffers::to_vec(&baseline).unwrap();
+        assert!(is_token(flexbuffers::from_slice(&flex).unwrap(), simple));
+
+        assert!(matches!(
+            serde_json::from_str(r#"{"status":"SignedIn","extension":null}"#),
+            Ok(Response::SignedIn {
+                version: 0,
+                compression: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn pdu_codec_compression() {
+        let mut id_gen = TransactionIdGenerator::default();
+        let mut message = |text: &str| {
+            Request::Send {
+                message: Message::new(Uuid::new_v4(), Uuid::new_v4(), Content::string(text)),
+            }
+            .to_pdu(id_gen.next_id())
+        };
+        let long = "jinshu ".repeat(1000);
+
+        for compression in Compression::ALL {
+            let mut encoder = PduCodec::new(Codec::Cbor);
+            encoder.set_compression(Some(compression));
+            assert_eq!(encoder.compression(), Some(compression));
+            // 解码不依赖协商结果
+            let mut decoder = PduCodec::new(Codec::Json);
+
+            let mut bytes = BytesMut::new();
+            encoder.encode(message("hello"), &mut bytes).unwrap();
+            assert_eq!(bytes[0], Codec::Cbor as u8);
+            assert!(matches!(
+                decoder.decode(&mut bytes),
+                Ok(Some(Pdu {
+                    body: Body::Req(Request::Send { .. }),
+                    ..
+                }))
+            ));
+
+            encoder.encode(message(&long), &mut bytes).unwrap();
+            assert_eq!(bytes[0] >> 6, compression as u8);
+            assert!(bytes.len() < long.len());
+            match decoder.decode(&mut bytes) {
+                Ok(Some(Pdu {
+                    body:
+                        Body::Req(Request::Send {
+                            message:
+                                Message {
+                                    content: Content::Data { bytes, .. },
+                                    ..
+                                },
+                        }),
+                    ..
+                })) => assert_eq!(bytes, long.as_bytes()),
+                other => panic!("unexpected: {:?}", other),
+            }
+            assert!(bytes.is_empty());
+        }
+
+        let mut codec = PduCodec::new(Codec::Json);
+        codec.set_compression(Some(Compression::Zstd));
+        codec.set_compress_threshold(usize::MAX);
+        let mut bytes = BytesMut::new();
+        codec.encode(message(&long), &mut bytes).unwrap();
+        assert_eq!(bytes[0], Codec::Json as u8);
+    }
+
+    #[test]
+    fn protobuf_zero_copy() {
+        let mut id_gen = TransactionIdGenerator::default();
+        let mut codec = PduCodec::new(Codec::Protobuf);
+        let data = vec![b'J'; 64 * 1024];
+        let pdu = Request::Send {
+            message: Message::new(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Content::data(mime::APPLICATION_OCTET_STREAM, data.clone()),
+            ),
+        }
+        .to_pdu(id_gen.next_id());
+
+        let mut encoded = BytesMut::new();
+        codec.encode(pdu, &mut encoded).unwrap();
+        // 与从连接读到完整报文时相同，缓冲区没有多余的容量
+        let mut bytes = BytesMut::from(encoded.as_ref());
+        let range = bytes.as_ptr_range();
+        match codec.decode(&mut bytes) {
+            Ok(Some(Pdu {
+                body:
+                    Body::Req(Request::Send {
+                        message:
+                            Message {
+                                content: Content::Data { bytes, .. },
+                                ..
+                            },
+                    }),
+                ..
+            })) => {
+                assert_eq!(bytes, data);
+                // 消息内容引用接收缓冲区
+                assert!(range.contains(&bytes.as_ptr()));
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn legacy_data_bytes() {
+        // 旧版本的数据消息内容序列化为整数序列
+        #[derive(serde::Serialize)]
+        #[serde(tag = "type")]
+        enum Legacy {
+            Data {
+                #[serde(with = "serde_shims::mime")]
+                mime: mime::Mime,
+                bytes: Vec<u8>,
+            },
+        }
+        let legacy = Legacy::Data {
+            mime: mime::TEXT_PLAIN_UTF_8,
+            bytes: b"jinshu".to_vec(),
+        };
+
+        let is_jinshu = |content: Content| matches!(content, Content::Data { bytes, .. } if bytes == b"jinshu".as_ref());
+
+        let json = serde_json::to_vec(&legacy).unwrap();
+        assert!(is_jinshu(serde_json::from_slice(&json).unwrap()));
+        let msgpack = rmp_serde::to_vec_named(&legacy).unwrap();
+        assert!(is_jinshu(rmp_serde::from_slice(&msgpack).unwrap()));
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&legacy, &mut cbor).unwrap();
+        assert!(is_jinshu(Content::try_from(cbor.as_slice()).unwrap()));
+        let flex = flexbuffers::to_vec(&legacy).unwrap();
+        assert!(is_jinshu(flexbuffers::from_slice(&flex).unwrap()));
+    }
+
+    #[test]
+    fn baseline_decodes_data_bytes() {
+        // 旧版本的报文结构，数据消息内容为整数序列
+        #[derive(serde::Deserialize)]
+        struct BaselinePdu {
+            #[allow(dead_code)]
+            id: serde::de::IgnoredAny,
+            body: BaselineBody,
+        }
+        #[derive(serde::Deserialize)]
+        #[serde(tag = "type")]
+        enum BaselineBody {
+            Req(BaselineRequest),
+        }
+        #[derive(serde::Deserialize)]
+        #[serde(tag = "method")]
+        enum BaselineRequest {
+            Send { message: BaselineMessage },
+        }
+        #[allow(dead_code)]
+        #[derive(serde::Deserialize)]
+        struct BaselineMessage {
+            id: Uuid,
+            timestamp: u64,
+            from: Uuid,
+            to: Uuid,
+            content: BaselineContent,
+        }
+        #[derive(serde::Deserialize)]
+        #[serde(tag = "type")]
+        enum BaselineContent {
+            Data {
+                #[allow(dead_code)]
+                #[serde(with = "serde_shims::mime")]
+                mime: mime::Mime,
+                bytes: Vec<u8>,
+            },
+        }
+
+        fn decode(codec: Codec, payload: &[u8]) -> Option<Vec<u8>> {
+            let pdu: BaselinePdu = match codec {
+                Codec::MsgPack => rmp_serde::from_slice(payload).ok()?,
+                Codec::Cbor => ciborium::de::from_reader(payload).ok()?,
+                Codec::FlexBuffers => flexbuffers::from_slice(payload).ok()?,
+                _ => unreachable!(),
+            };
+            let BaselineBody::Req(BaselineRequest::Send { message }) = pdu.body;
+            let BaselineContent::Data { bytes, .. } = message.content;
+            Some(bytes)
+        }
+
+        let mut id_gen = TransactionIdGenerator::default();
+        for codec in [Codec::MsgPack, Codec::Cbor, Codec::FlexBuffers] {
+            let mut encode = |binary_data: bool| {
+                let mut encoder = PduCodec::new(codec);
+                encoder.set_binary_data(binary_data);
+                let pdu = Request::Send {
+                    message: Message::new(
+                        Uuid::new_v4(),
+                        Uuid::new_v4(),
+                        Content::data(mime::APPLICATION_OCTET_STREAM, vec![0u8, 1, 2, 255]),
+                    ),
+                }
+                .to_pdu(id_gen.next_id());
+                let mut bytes = BytesMut::new();
+                encoder.encode(pdu, &mut bytes).unwrap();
+                bytes
+            };
+
+            // 未协商时新版本编码的报文旧版本可以解码
+            let frame = encode(false);
+            assert_eq!(
+                decode(codec, &frame[PduCodec::HEAD_LEN..]),
+                Some(vec![0u8, 1, 2, 255]),
+                "{}",
+                codec
+            );
+
+            // 协商后编码为二进制数据，新版本可以解码
+            let mut frame = encode(true);
+            assert_eq!(
+                decode(codec, &frame[PduCodec::HEAD_LEN..]),
+                None,
+                "{}",
+                codec
+            );
+            assert!(matches!(
+                PduCodec::new(codec).decode(&mut frame),
+                Ok(Some(Pdu {
+                    body: Body::Req(Request::Send {
+                        message: Message {
+                            content: Content::Data { bytes, .. },
+                            ..
+                        },
+                    }),
+                    ..
+                })) if bytes == [0u8, 1, 2, 255].as_ref()
+            ));
+        }
+    }
+
+    #[test]
+    fn extended_head() {
+        let mut id_gen = TransactionIdGenerator::default();
+        let mut codec = PduCodec::new(Codec::MsgPack);
+        codec.set_max_frame_len(PduCodec::MAX_SHORT_DATA_LEN + 1024);
+        let send = |id_gen: &mut TransactionIdGenerator, len: usize| {
+            Request::Send {
+                message: Message::new(
+                    Uuid::new_v4(),
+                    Uuid::new_v4(),
+                    Content::data(mime::APPLICATION_OCTET_STREAM, vec![0u8; len]),
+                ),
+            }
+            .to_pdu(id_gen.next_id())
+        };
+
+        // 超过 24 位长度的报文使用扩展报文头
+        let mut bytes = BytesMut::new();
+        codec
+            .encode(send(&mut id_gen, PduCodec::MAX_SHORT_DATA_LEN), &mut bytes)
+            .unwrap();
+        assert_eq!(bytes[0], PduCodec::EXTENDED_FLAG | Codec::MsgPack as u8);
+        assert_eq!(&bytes[1..4], &[0, 0, 0]);
+        let total = bytes.len();
+
+        // 扩展长度不完整时等待
+        let mut head = bytes.split_to(PduCodec::HEAD_LEN + 2);
+        assert!(matches!(codec.decode(&mut head), Ok(None)));
+        assert_eq!(head.len(), PduCodec::HEAD_LEN + 2);
+        head.unsplit(bytes);
+        assert_eq!(head.len(), total);
+        assert!(matches!(
+            codec.decode(&mut head),
+            Ok(Some(Pdu {
+                body: Body::Req(Request::Send { .. }),
+                ..
+            }))
+        ));
+        assert!(head.is_empty());
+
+        // 普通报文仍使用旧的报文头
+        codec.encode(send(&mut id_gen, 16), &mut head).unwrap();
+        assert_eq!(head[0], Codec::MsgPack as u8);
+        assert_eq!(head.len(), PduCodec::HEAD_LEN + (head[3] as usize));
+
+        // 默认长度的解码器拒绝扩展报文
+        let mut bytes = BytesMut::new();
+        codec
+            .encode(send(&mut id_gen, PduCodec::MAX_SHORT_DATA_LEN), &mut bytes)
+            .unwrap();
+        assert!(matches!(
+            PduCodec::default().decode(&mut bytes),
+            Err(crate::Error::FrameTooLarge(
+                _,
+                PduCodec::DEFAULT_MAX_FRAME_LEN
+            ))
+        ));
+    }
+
+    #[test]
+    fn max_frame_len() {
+        let mut codec = PduCodec::new(Codec::Json);
+        codec.set_max_frame_len(1024);
+        assert_eq!(codec.max_frame_len(), 1024);
+
+        // 在分配内存前拒绝过长的报文
+        let mut bytes = BytesMut::new();
+        bytes.put_u32(PduCodec::MAX_SHORT_DATA_LEN as u32);
+        assert!(matches!(
+            codec.decode(&mut bytes),
+            Err(crate::Error::FrameTooLarge(len, 1024)) if len == PduCodec::MAX_SHORT_DATA_LEN
+        ));
+        assert!(bytes.capacity() < PduCodec::MAX_SHORT_DATA_LEN);
+
+        let mut bytes = BytesMut::new();
+        bytes.put_u32((PduCodec::EXTENDED_FLAG as u32) << 24);
+        bytes.put_u32(u32::MAX);
+        assert!(matches!(
+            codec.decode(&mut bytes),
+            Err(crate::Error::FrameTooLarge(len, 1024)) if len == PduCodec::MAX_DATA_LEN
+        ));
+
+        let mut id_gen = TransactionIdGenerator::default();
+        let pdu = Request::Send {
+            message: Message::new(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Content::string("J".repeat(1024)),
+            ),
+        }
+        .to_pdu(id_gen.next_id());
+        assert!(matches!(
+            codec.encode(pdu, &mut bytes),
+            Err(crate::Error::TooLong(Codec::Json, _))
+        ));
+
+        codec.set_max_frame_len(usize::MAX);
+        assert_eq!(codec.max_frame_len(), PduCodec::MAX_DATA_LEN);
+    }
+
     #[test]
     fn maximum() {
         let mut id_gen = TransactionIdGenerator::default();
@@ -620,7 +1543,7 @@ mod test {
                 to: Uuid::new_v4(),
                 content: Content::Data {
                     mime: mime::TEXT_PLAIN_UTF_8,
-                    bytes: vec![b'J'; PduCodec::MAX_DATA_LEN],
+                    bytes: vec![b'J'; PduCodec::DEFAULT_MAX_FRAME_LEN].into(),
                 },
             },
         }
@@ -630,7 +1553,7 @@ mod test {
 
         assert!(matches!(
             codec.encode(pdu, &mut bytes),
-            Err(crate::Error::TooLong(Codec::Json, len)) if len > PduCodec::MAX_DATA_LEN
+            Err(crate::Error::TooLong(Codec::Json, len)) if len > PduCodec::DEFAULT_MAX_FRAME_LEN
         ));
     }
 
@@ -648,6 +1571,23 @@ mod test {
 
         let link =
             Content::link(Url::parse("http://localhost:10000").expect("Failed to parse url"));
-        assert!(matches!(link, Content::Link { .. }))
+        assert!(matches!(link, Content::Link { .. }));
+
+        let encrypted = Content::encrypted("x25519-aes256gcm", "a", "b", [0u8; 12], vec![1, 2, 3]);
+        assert!(encrypted.is_encrypted());
+        let bytes = Vec::try_from(&encrypted).unwrap();
+        assert!(matches!(Content::try_from(bytes.as_slice()),
+                Ok(Content::Encrypted { algorithm, sender_key_id, recipient_key_id, nonce, ciphertext })
+                    if algorithm == "x25519-aes256gcm" && sender_key_id == "a" && recipient_key_id == "b"
+                        && nonce == [0u8; 12] && ciphertext == [1, 2, 3]));
+
+        let id = Uuid::new_v4();
+        let expired = Content::expired(id);
+        let bytes = Vec::try_from(&expired).unwrap();
+        assert_eq!(
+            Content::try_from(bytes.as_slice()).unwrap().expired_id(),
+            Some(id)
+        );
+        assert!(string.expired_id().is_none());
     }
 }
@@ -5,16 +5,25 @@ use serde::{Deserialize, Serialize};
 pub struct PusherConfig {
     /// Comet 服务名
     pub comet_name: String,
+
+    /// 消费的 Distributor 分发主题名，实际主题为 `<入队主题>.<topic_name>`，需与 Distributor 的 `pusher_name` 一致
+    #[serde(default = "default_topic_name")]
+    pub topic_name: String,
 }
 
 impl Default for PusherConfig {
     fn default() -> Self {
         Self {
             comet_name: "comet".into(),
+            topic_name: default_topic_name(),
         }
     }
 }
 
+fn default_topic_name() -> String {
+    "pusher".into()
+}
+
 #[cfg(test)]
 mod test {
     use crate::PusherConfig;
@@ -42,6 +42,9 @@ async fn main() -> anyhow::Result<()> {
     let etcd = EtcdRegistry::new(&etcd).await?;
     let session_store = SessionStore::new(redis).await?;
 
+    let queue = queue.distributed(&pusher.topic_name);
+    tracing::info!(topic = %queue.topic(), "Consume distributed messages");
+
     let pusher = Pusher::new(&pusher.comet_name, &etcd, session_store).await?;
 
     consume_with_handler(queue, pusher, shutdown_signal()).await?;
@@ -92,7 +92,7 @@ impl Pusher {
     /// 发送消息
     pub async fn send(&self, message: RpcMessage) -> anyhow::Result<()> {
         let user_id: Uuid = Uuid::from_slice(message.to.as_slice())?;
-        match self.session_store.load(user_id).await? {
+        match self.session_store.load(&message.app_id, user_id).await? {
             Some(uri) => {
                 if let Some(mut client) = self.clients.get_mut(&uri) {
                     client.push(Request::new(message)).await?;
@@ -103,7 +103,7 @@ impl Pusher {
                 }
             }
             None => {
-                tracing::info!(%user_id, "User is offline");
+                tracing::info!(app_id = %message.app_id, %user_id, "User is offline");
                 anyhow::bail!("User is offline")
             }
         }
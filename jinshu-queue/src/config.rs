@@ -1,6 +1,6 @@
-use crate::kafka::{KafkaConsumer, KafkaConsumerConfig};
-use crate::pulsar::{PulsarConsumer, PulsarConsumerConfig};
-use crate::QueuedMessageHandler;
+use crate::kafka::{KafkaConsumer, KafkaConsumerConfig, KafkaProducer, KafkaProducerConfig};
+use crate::pulsar::{PulsarConsumer, PulsarConsumerConfig, PulsarProducer, PulsarProducerConfig};
+use crate::{QueuedMessageHandler, QueuedMessageProducer};
 use serde::{Deserialize, Serialize};
 use std::future::Future;
 
@@ -22,6 +22,26 @@ pub enum QueueConfig<K, P> {
     ),
 }
 
+impl QueueConfig<KafkaConsumerConfig, PulsarConsumerConfig> {
+    /// 消费的主题
+    pub fn topic(&self) -> &str {
+        match self {
+            QueueConfig::Kafka(config) => &config.topic,
+            QueueConfig::Pulsar(config) => &config.topic,
+        }
+    }
+
+    /// 改为消费 Distributor 分发的主题 `<主题>.<name>`，见 [`crate::distributed_topic`]
+    pub fn distributed(mut self, name: &str) -> Self {
+        let topic = crate::distributed_topic(self.topic(), name);
+        match &mut self {
+            QueueConfig::Kafka(config) => config.topic = topic,
+            QueueConfig::Pulsar(config) => config.topic = topic,
+        }
+        self
+    }
+}
+
 /// 使用给定的消息处理器消费队列
 ///
 /// 使用消费者配置构造消费者对象，对消费的每个消息调用处理器，在 `signal` 触发时退出
@@ -47,3 +67,33 @@ pub async fn consume_with_handler<
 
     Ok(())
 }
+
+/// 使用生产者配置构造生产者
+pub async fn create_producer(
+    producer_config: &QueueConfig<KafkaProducerConfig, PulsarProducerConfig>,
+) -> crate::error::Result<Box<dyn QueuedMessageProducer>> {
+    Ok(match producer_config {
+        QueueConfig::Kafka(config) => Box::new(KafkaProducer::new(config)?),
+        QueueConfig::Pulsar(config) => Box::new(PulsarProducer::new(config).await?),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::QueueConfig;
+    use crate::kafka::KafkaConsumerConfig;
+    use crate::pulsar::PulsarConsumerConfig;
+
+    #[test]
+    fn distributed() {
+        let kafka: QueueConfig<KafkaConsumerConfig, PulsarConsumerConfig> =
+            QueueConfig::Kafka(Default::default());
+        assert_eq!(kafka.topic(), "jinshu.dev");
+        assert_eq!(kafka.distributed("pusher").topic(), "jinshu.dev.pusher");
+
+        let pulsar: QueueConfig<KafkaConsumerConfig, PulsarConsumerConfig> =
+            QueueConfig::Pulsar(Default::default());
+        let topic = format!("{}.storage", pulsar.topic());
+        assert_eq!(pulsar.distributed("storage").topic(), topic);
+    }
+}
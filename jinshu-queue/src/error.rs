@@ -7,8 +7,11 @@ pub enum ConvertError {
     #[error("Insufficient buffer ({0} bytes)")]
     InsufficientBuffer(u64),
     /// 内容长度不合法
-    #[error("Invalid content length: {0}, expected: {1}")]
+    #[error("Invalid content length: {0}, at most: {1}")]
     InvalidContentLength(u64, u64),
+    /// 应用 ID 不是合法的 UTF-8 字符串
+    #[error("Invalid app id: {0}")]
+    InvalidAppId(std::str::Utf8Error),
 }
 
 /// 队列消费错误
@@ -1,20 +1,24 @@
 mod config;
 mod consumer;
 mod error;
+mod producer;
 
 use crate::QueuedMessage;
+use bytes::Bytes;
 pub use config::*;
 pub use consumer::*;
 pub use error::*;
+pub use producer::*;
 use rdkafka::message::BorrowedMessage;
 use rdkafka::Message as _;
 
 impl<'a> TryFrom<&BorrowedMessage<'a>> for QueuedMessage {
     type Error = error::Error;
 
+    /// 借用的消息体属于 librdkafka，只在提交前有效，需要复制一次；之后消息内容引用复制的缓冲区
     fn try_from(value: &BorrowedMessage<'a>) -> error::Result<Self> {
         match value.payload() {
-            Some(bytes) => Ok(QueuedMessage::try_from(bytes)?),
+            Some(bytes) => Ok(QueuedMessage::try_from(Bytes::copy_from_slice(bytes))?),
             None => Err(error::Error::NoPayload),
         }
     }
@@ -0,0 +1,46 @@
+use crate::kafka::KafkaProducerConfig;
+use crate::{QueuedMessage, QueuedMessageProducer};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::ClientConfig;
+use std::time::Duration;
+
+/// Kafka 生产者
+#[derive(Clone)]
+pub struct KafkaProducer {
+    producer: FutureProducer,
+}
+
+impl KafkaProducer {
+    /// 使用配置构造
+    pub fn new(config: &KafkaProducerConfig) -> crate::kafka::Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", &config.servers)
+            .set(
+                "message.timeout.ms",
+                config.extension.message_timeout.to_string(),
+            )
+            .create()?;
+
+        Ok(Self { producer })
+    }
+}
+
+#[async_trait::async_trait]
+impl QueuedMessageProducer for KafkaProducer {
+    async fn produce(&self, topic: &str, message: &QueuedMessage) -> crate::error::Result<()> {
+        let payload = Vec::<u8>::from(message);
+        let (partition, offset) = self
+            .producer
+            .send(
+                FutureRecord::to(topic)
+                    .key(message.inner().id.as_slice())
+                    .payload(&payload),
+                Duration::from_secs(0),
+            )
+            .await
+            .map_err(|(e, _)| crate::kafka::Error::from(e))?;
+
+        tracing::debug!(%topic, partition, offset, "Message is produced.");
+        Ok(())
+    }
+}
@@ -4,8 +4,10 @@
 //! 消息队列消费相关
 //!
 
+use bytes::Bytes;
 use jinshu_rpc::domain::message::Message as RpcMessage;
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::mem::size_of;
 use uuid::Uuid;
@@ -54,6 +56,7 @@ impl QueuedMessage {
     }
 }
 
+// 格式：id + ts + from + to + content len + content + app id，app id 占用剩余的所有字节
 const CONTENT_LEN_OFFSET: u64 = size_of::<Uuid>() as u64 * 3 + 8; // id + ts + from + to
 const CONTENT_LEN_END: u64 = CONTENT_LEN_OFFSET + size_of::<u64>() as u64; // CONTENT_LEN_OFFSET + size of content len (u64)
 
@@ -61,6 +64,15 @@ impl TryFrom<&[u8]> for QueuedMessage {
     type Error = crate::error::ConvertError;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from(Bytes::copy_from_slice(value))
+    }
+}
+
+impl TryFrom<Bytes> for QueuedMessage {
+    type Error = crate::error::ConvertError;
+
+    /// 消息内容直接引用 `value`，不复制
+    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
         let len = value.len() as u64;
         if len < CONTENT_LEN_END {
             return Err(Self::Error::InsufficientBuffer(len));
@@ -71,11 +83,11 @@ impl TryFrom<&[u8]> for QueuedMessage {
             .unwrap();
         let content_len = u64::from_be_bytes(content_len);
 
-        let expected_content_len = len - CONTENT_LEN_END;
-        if expected_content_len != content_len {
+        let max_content_len = len - CONTENT_LEN_END;
+        if content_len > max_content_len {
             return Err(Self::Error::InvalidContentLength(
                 content_len,
-                expected_content_len,
+                max_content_len,
             ));
         }
 
@@ -93,7 +105,12 @@ impl TryFrom<&[u8]> for QueuedMessage {
         let to = Vec::from(&value[pos..pos + size_of::<Uuid>()]);
 
         pos += size_of::<Uuid>() + size_of::<u64>();
-        let content = Vec::from(&value[pos..]);
+        let content = value.slice(pos..pos + content_len as usize);
+
+        pos += content_len as usize;
+        let app_id = std::str::from_utf8(&value[pos..])
+            .map_err(Self::Error::InvalidAppId)?
+            .to_string();
 
         Ok(Self(RpcMessage {
             id,
@@ -101,23 +118,66 @@ impl TryFrom<&[u8]> for QueuedMessage {
             from,
             to,
             content,
+            app_id,
         }))
     }
 }
 
 impl From<&QueuedMessage> for Vec<u8> {
     fn from(QueuedMessage(msg): &QueuedMessage) -> Self {
-        let mut vec = Vec::with_capacity(128);
+        let mut vec =
+            Vec::with_capacity(CONTENT_LEN_END as usize + msg.content.len() + msg.app_id.len());
         vec.extend_from_slice(&msg.id);
         vec.extend_from_slice(&msg.timestamp.to_be_bytes()); // big-endian
         vec.extend_from_slice(&msg.from);
         vec.extend_from_slice(&msg.to);
         vec.extend_from_slice(&(msg.content.len() as u64).to_be_bytes());
         vec.extend_from_slice(&msg.content);
+        vec.extend_from_slice(msg.app_id.as_bytes());
         vec
     }
 }
 
+/// Distributor 分发给 Pusher、Storage 等下游模块的主题，`<入队主题>.<下游主题名>`
+pub fn distributed_topic(topic: &str, name: &str) -> String {
+    format!("{}.{}", topic, name)
+}
+
+/// 应用专用的主题，`<主题>.app.<应用 ID>`
+pub fn app_topic(topic: &str, app_id: &str) -> String {
+    format!("{}.app.{}", topic, app_id)
+}
+
+/// 按消息所属的应用选择入队主题
+///
+/// 隔离的应用使用专用主题（见 [`app_topic`]），需要为其单独部署消费该主题的服务；
+/// 其他应用共用同一个主题
+///
+#[derive(Debug, Clone)]
+pub struct TopicSelector {
+    topic: String,
+    isolated_apps: HashSet<String>,
+}
+
+impl TopicSelector {
+    /// 使用共用主题及隔离的应用构造
+    pub fn new(topic: impl Into<String>, isolated_apps: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            topic: topic.into(),
+            isolated_apps: isolated_apps.into_iter().collect(),
+        }
+    }
+
+    /// 选择应用 `app_id` 的消息使用的主题
+    pub fn select(&self, app_id: &str) -> Cow<'_, str> {
+        if self.isolated_apps.contains(app_id) {
+            Cow::Owned(app_topic(&self.topic, app_id))
+        } else {
+            Cow::Borrowed(&self.topic)
+        }
+    }
+}
+
 /// 队列消息处理器，在消费队列时使用
 #[async_trait::async_trait]
 pub trait QueuedMessageHandler {
@@ -125,9 +185,23 @@ pub trait QueuedMessageHandler {
     async fn handle(&self, topic: &str, message: &QueuedMessage) -> HandleResult;
 }
 
+/// 队列消息生产者，向指定的主题发送消息
+#[async_trait::async_trait]
+pub trait QueuedMessageProducer: Send + Sync {
+    /// 发送消息到 `topic`
+    async fn produce(&self, topic: &str, message: &QueuedMessage) -> crate::error::Result<()>;
+}
+
+#[async_trait::async_trait]
+impl<P: QueuedMessageProducer + ?Sized> QueuedMessageProducer for Box<P> {
+    async fn produce(&self, topic: &str, message: &QueuedMessage) -> crate::error::Result<()> {
+        (**self).produce(topic, message).await
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::QueuedMessage;
+    use crate::{QueuedMessage, TopicSelector};
     use jinshu_protocol::Content;
     use jinshu_rpc::domain::message::Message as RpcMessage;
     use jinshu_utils::current_millisecond;
@@ -148,7 +222,8 @@ mod test {
             timestamp: current_millisecond(),
             from: Uuid::new_v4().as_bytes().to_vec(),
             to: Uuid::new_v4().as_bytes().to_vec(),
-            content: convert.unwrap(),
+            content: convert.unwrap().into(),
+            app_id: "app".into(),
         };
 
         let qm = QueuedMessage::new(message);
@@ -158,7 +233,25 @@ mod test {
             QueuedMessage::try_from(vec.as_slice()),
             Ok(m) if m.0.id == qm.0.id && m.0.timestamp == qm.0.timestamp
                 && m.0.from == qm.0.from && m.0.to == qm.0.to
-                && m.0.content == qm.0.content
+                && m.0.content == qm.0.content && m.0.app_id == qm.0.app_id
         ));
+
+        // 从 `Bytes` 解码时消息内容引用原缓冲区
+        let buffer = bytes::Bytes::from(vec.clone());
+        let m = QueuedMessage::try_from(buffer.clone()).unwrap();
+        assert_eq!(m.0.content, qm.0.content);
+        let range = buffer.as_ptr_range();
+        assert!(range.contains(&m.0.content.as_ptr()));
+
+        let mut truncated = vec.clone();
+        truncated.truncate(vec.len() - "app".len() - 1);
+        assert!(QueuedMessage::try_from(truncated.as_slice()).is_err());
+    }
+
+    #[test]
+    fn topic_selector() {
+        let selector = TopicSelector::new("jinshu.dev", ["vip".to_string()]);
+        assert_eq!(selector.select("vip"), "jinshu.dev.app.vip");
+        assert_eq!(selector.select("other"), "jinshu.dev");
     }
 }
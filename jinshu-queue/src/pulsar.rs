@@ -1,16 +1,58 @@
 mod config;
 mod consumer;
 mod error;
+mod producer;
 
+use bytes::Bytes;
 pub use config::*;
 pub use consumer::*;
 pub use error::*;
+pub use producer::*;
 use pulsar::{DeserializeMessage, Payload};
 
 impl DeserializeMessage for crate::QueuedMessage {
     type Output = error::Result<Self>;
 
+    /// 只能借用消息体，需要复制，消费时使用 `take_message`
     fn deserialize_message(payload: &Payload) -> Self::Output {
         Ok(crate::QueuedMessage::try_from(payload.data.as_slice())?)
     }
 }
+
+/// 取出消息体并解码，消息内容直接引用消息体，不复制
+pub(crate) fn take_message(payload: &mut Payload) -> error::Result<crate::QueuedMessage> {
+    let data = Bytes::from(std::mem::take(&mut payload.data));
+    Ok(crate::QueuedMessage::try_from(data)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::take_message;
+    use crate::QueuedMessage;
+    use jinshu_rpc::domain::message::Message as RpcMessage;
+    use pulsar::Payload;
+    use uuid::Uuid;
+
+    #[test]
+    fn zero_copy() {
+        let message = QueuedMessage::new(RpcMessage {
+            id: Uuid::new_v4().as_bytes().to_vec(),
+            timestamp: 0,
+            from: Uuid::new_v4().as_bytes().to_vec(),
+            to: Uuid::new_v4().as_bytes().to_vec(),
+            content: vec![b'J'; 1024].into(),
+            app_id: "app".into(),
+        });
+        let mut payload = Payload {
+            metadata: Default::default(),
+            data: Vec::from(&message),
+        };
+        let range = payload.data.as_ptr_range();
+
+        let decoded = take_message(&mut payload).unwrap();
+        assert_eq!(decoded.inner().content, message.inner().content);
+        // 消息内容引用消费到的消息体
+        assert!(range.contains(&decoded.inner().content.as_ptr()));
+        assert!(payload.data.is_empty());
+    }
+}
@@ -75,7 +75,7 @@ mod test {
     #[test]
     fn default() {
         ConsumerConfig::default();
-        ProducerConfig::default();
+        let _ = ProducerConfig;
         PulsarConfig::<()>::default();
     }
 }
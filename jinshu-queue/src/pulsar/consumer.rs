@@ -1,4 +1,4 @@
-use crate::pulsar::PulsarConsumerConfig;
+use crate::pulsar::{take_message, PulsarConsumerConfig};
 use crate::{HandleResult, QueuedMessage, QueuedMessageHandler};
 use pulsar::consumer::InitialPosition;
 use pulsar::{Consumer, ConsumerOptions, Pulsar, SubType, TokioExecutor};
@@ -82,8 +82,8 @@ impl PulsarConsumer {
                 }
                 option = self.consumer.next() => {
                     if let Some(consume) = option {
-                        let pulsar_message: pulsar::consumer::Message<QueuedMessage> = consume?;
-                        let message = pulsar_message.deserialize()?;
+                        let mut pulsar_message: pulsar::consumer::Message<QueuedMessage> = consume?;
+                        let message = take_message(&mut pulsar_message.payload)?;
 
                         match handler.handle(&self.topic, &message).await {
                             HandleResult::Ok => {},
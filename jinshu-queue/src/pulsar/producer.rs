@@ -0,0 +1,62 @@
+use crate::pulsar::PulsarProducerConfig;
+use crate::{QueuedMessage, QueuedMessageProducer};
+use pulsar::producer::Message as PulsarMessage;
+use pulsar::{Producer, Pulsar, TokioExecutor};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// Pulsar 生产者，每个主题的生产者在首次发送时创建并缓存
+pub struct PulsarProducer {
+    pulsar: Pulsar<TokioExecutor>,
+    producers: Mutex<HashMap<String, Producer<TokioExecutor>>>,
+}
+
+impl PulsarProducer {
+    /// 使用配置构造
+    pub async fn new(config: &PulsarProducerConfig) -> crate::pulsar::Result<Self> {
+        let pulsar = Pulsar::builder(config.url.to_string(), TokioExecutor)
+            .build()
+            .await?;
+
+        Ok(Self {
+            pulsar,
+            producers: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl QueuedMessageProducer for PulsarProducer {
+    async fn produce(&self, topic: &str, message: &QueuedMessage) -> crate::error::Result<()> {
+        let mut producers = self.producers.lock().await;
+
+        let producer = match producers.get_mut(topic) {
+            Some(producer) => producer,
+            None => {
+                let producer = self
+                    .pulsar
+                    .producer()
+                    .with_topic(topic)
+                    .build()
+                    .await
+                    .map_err(crate::pulsar::Error::from)?;
+                producers.entry(topic.to_string()).or_insert(producer)
+            }
+        };
+
+        let pulsar_message = PulsarMessage {
+            payload: message.into(),
+            ..Default::default()
+        };
+
+        producer
+            .send(pulsar_message)
+            .await
+            .map_err(crate::pulsar::Error::from)?
+            .await
+            .map_err(crate::pulsar::Error::from)?;
+
+        tracing::debug!(%topic, "Message is produced.");
+        Ok(())
+    }
+}
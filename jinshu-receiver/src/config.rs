@@ -8,6 +8,10 @@ pub struct ReceiverConfig {
     /// 接收服务配置
     #[serde(flatten)]
     pub service: ServiceConfig,
+
+    /// 使用专用主题 `<主题>.app.<应用 ID>` 的应用，其他应用共用配置的主题
+    #[serde(default)]
+    pub isolated_apps: Vec<String>,
 }
 
 impl Default for ReceiverConfig {
@@ -19,6 +23,7 @@ impl Default for ReceiverConfig {
                 listen_ip: [0u8, 0, 0, 0].into(),
                 listen_port: 9100,
             },
+            isolated_apps: vec![],
         }
     }
 }
@@ -1,5 +1,5 @@
 use jinshu_queue::kafka::KafkaProducerConfig;
-use jinshu_queue::QueuedMessage;
+use jinshu_queue::{QueuedMessage, TopicSelector};
 use jinshu_rpc::domain;
 use jinshu_rpc::receiver::{self, receiver_server};
 use rdkafka::producer::{FutureProducer, FutureRecord};
@@ -7,13 +7,13 @@ use std::time::Duration;
 
 /// Kafka 接收器
 #[derive(Clone)]
-pub struct KafkaReceiver(FutureProducer, String);
+pub struct KafkaReceiver(FutureProducer, TopicSelector);
 
 // .\kafka-topics.sh --zookeeper localhost:2181 --create --topic jinshu.test --partitions 32 --replication-factor 1
 
 impl KafkaReceiver {
-    /// 使用 Kafka 的消费者配置构造
-    pub fn create(config: KafkaProducerConfig) -> anyhow::Result<Self> {
+    /// 使用 Kafka 的消费者配置及隔离的应用构造
+    pub fn create(config: KafkaProducerConfig, isolated_apps: Vec<String>) -> anyhow::Result<Self> {
         let cli = rdkafka::ClientConfig::new()
             .set("bootstrap.servers", config.servers)
             .set(
@@ -21,7 +21,7 @@ impl KafkaReceiver {
                 config.extension.message_timeout.to_string(),
             )
             .create()?;
-        Ok(Self(cli, config.topic))
+        Ok(Self(cli, TopicSelector::new(config.topic, isolated_apps)))
     }
 }
 
@@ -32,11 +32,15 @@ impl receiver_server::Receiver for KafkaReceiver {
         request: tonic::Request<domain::message::Message>,
     ) -> Result<tonic::Response<receiver::EnqueueResult>, tonic::Status> {
         let message = request.into_inner();
+        if message.app_id.is_empty() {
+            return Err(tonic::Status::invalid_argument("App id is empty"));
+        }
+        let topic = self.1.select(&message.app_id).into_owned();
         let message = QueuedMessage::new(message);
         match self
             .0
             .send(
-                FutureRecord::to(&self.1)
+                FutureRecord::to(&topic)
                     .key(message.inner().id.as_slice())
                     .payload(&Vec::<u8>::from(&message)),
                 Duration::from_secs(0),
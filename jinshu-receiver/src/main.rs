@@ -31,7 +31,10 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!(?conf);
 
     let Conf {
-        receiver: ReceiverConfig { service },
+        receiver: ReceiverConfig {
+            service,
+            isolated_apps,
+        },
         etcd,
         queue,
         ..
@@ -41,13 +44,13 @@ async fn main() -> anyhow::Result<()> {
 
     let (uri, handle) = match queue {
         QueueConfig::Kafka(config) => {
-            let kp = KafkaReceiver::create(config)?;
+            let kp = KafkaReceiver::create(config, isolated_apps)?;
             registry
                 .run_service(service, ReceiverServer::new(kp), shutdown_signal())
                 .await?
         }
         QueueConfig::Pulsar(config) => {
-            let pp = PulsarReceiver::create(config).await?;
+            let pp = PulsarReceiver::create(config, isolated_apps).await?;
             registry
                 .run_service(service, ReceiverServer::new(pp), shutdown_signal())
                 .await?
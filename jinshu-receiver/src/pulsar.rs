@@ -1,5 +1,5 @@
 use jinshu_queue::pulsar::PulsarProducerConfig;
-use jinshu_queue::QueuedMessage;
+use jinshu_queue::{QueuedMessage, TopicSelector};
 use jinshu_rpc::domain::message::Message;
 use jinshu_rpc::receiver::receiver_server::Receiver;
 use jinshu_rpc::receiver::EnqueueResult;
@@ -8,28 +8,40 @@ use pulsar::{Pulsar, TokioExecutor};
 use tonic::{Request, Response, Status};
 
 /// Pulsar 接收器
-pub struct PulsarReceiver(Pulsar<TokioExecutor>, PulsarProducerConfig);
+pub struct PulsarReceiver(Pulsar<TokioExecutor>, TopicSelector);
 
 impl PulsarReceiver {
-    /// 使用 Pulsar 消费者配置构造
-    pub async fn create(config: PulsarProducerConfig) -> anyhow::Result<Self> {
+    /// 使用 Pulsar 消费者配置及隔离的应用构造
+    pub async fn create(
+        config: PulsarProducerConfig,
+        isolated_apps: Vec<String>,
+    ) -> anyhow::Result<Self> {
         let pulsar = Pulsar::builder(config.url.to_string(), TokioExecutor)
             .build()
             .await?;
         //let producer = pulsar.producer();
-        Ok(Self(pulsar, config))
+        Ok(Self(
+            pulsar,
+            TopicSelector::new(config.topic, isolated_apps),
+        ))
     }
 }
 
 #[tonic::async_trait]
 impl Receiver for PulsarReceiver {
     async fn enqueue(&self, request: Request<Message>) -> Result<Response<EnqueueResult>, Status> {
-        let mut producer = match self.0.producer().with_topic(&self.1.topic).build().await {
+        let message = request.into_inner();
+        if message.app_id.is_empty() {
+            return Err(Status::invalid_argument("App id is empty"));
+        }
+
+        let topic = self.1.select(&message.app_id);
+        let mut producer = match self.0.producer().with_topic(topic.as_ref()).build().await {
             Ok(p) => p,
             Err(e) => return Err(Status::internal(e.to_string())),
         };
 
-        let message = QueuedMessage::new(request.into_inner());
+        let message = QueuedMessage::new(message);
 
         let pulsar_message = PulsarMessage {
             payload: (&message).into(),
@@ -15,6 +15,9 @@ pub enum Error {
     /// Redis 错误
     #[error(transparent)]
     Redis(#[from] redis::RedisError),
+    /// 序列化错误
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
 }
 
 /// Redis 相关结果
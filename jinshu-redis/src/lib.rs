@@ -8,24 +8,52 @@ use std::fmt::Display;
 /// 配置
 pub mod config;
 mod error;
+/// 内容审核标记的消息存储
+pub mod moderation;
+/// 签名令牌吊销列表
+pub mod revocation;
 /// 用户长链接会话存储
 pub mod session;
 
 pub use error::*;
 
-/// 构造存储登录信息时使用的键
-pub fn get_sign_in_key<D: Display>(user_id: D) -> String {
-    format!("user:sign_in:{}", user_id)
+/// 构造存储登录信息时使用的键，按应用隔离
+pub fn get_sign_in_key<A: Display, D: Display>(app_id: A, user_id: D) -> String {
+    format!("user:sign_in:{}:{}", app_id, user_id)
+}
+
+/// 构造存储刷新令牌时使用的键，按应用隔离
+pub fn get_refresh_key<A: Display, D: Display>(app_id: A, user_id: D) -> String {
+    format!("user:refresh:{}:{}", app_id, user_id)
+}
+
+/// 构造记录应用请求随机数时使用的键
+pub fn get_app_nonce_key<A: Display, N: Display>(app_id: A, nonce: N) -> String {
+    format!("app:nonce:{}:{}", app_id, nonce)
+}
+
+/// 构造存储用户封禁信息时使用的键，按应用隔离
+pub fn get_ban_key<A: Display, D: Display>(app_id: A, user_id: D) -> String {
+    format!("user:ban:{}:{}", app_id, user_id)
+}
+
+/// 构造存储用户发送消息令牌桶时使用的键，按应用隔离
+pub fn get_rate_limit_key<A: Display, D: Display>(app_id: A, user_id: D) -> String {
+    format!("user:rate_limit:{}:{}", app_id, user_id)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::get_sign_in_key;
+    use super::{get_ban_key, get_rate_limit_key, get_refresh_key, get_sign_in_key};
     use uuid::Uuid;
 
     #[test]
     fn simple() {
         let uuid = Uuid::new_v4().simple();
-        assert_eq!(get_sign_in_key(&uuid), get_sign_in_key(&uuid));
+        assert_eq!(get_sign_in_key("a", uuid), get_sign_in_key("a", uuid));
+        assert_ne!(get_sign_in_key("a", uuid), get_sign_in_key("b", uuid));
+        assert_ne!(get_sign_in_key("a", uuid), get_ban_key("a", uuid));
+        assert_ne!(get_sign_in_key("a", uuid), get_refresh_key("a", uuid));
+        assert_ne!(get_sign_in_key("a", uuid), get_rate_limit_key("a", uuid));
     }
 }
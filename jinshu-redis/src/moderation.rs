@@ -0,0 +1,101 @@
+use deadpool_redis::redis;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 标记消息内容使用的哈希表的键，字段为消息 ID
+const FLAGGED_KEY: &str = "moderation:flagged";
+
+/// 标记消息索引使用的有序集合的键，成员的分数为标记时间（毫秒）
+const FLAGGED_INDEX_KEY: &str = "moderation:flagged:index";
+
+/// 被标记为待复审的消息
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FlaggedMessage {
+    /// 消息 ID
+    pub id: Uuid,
+    /// 应用 ID
+    pub app_id: String,
+    /// 发送者 ID
+    pub from: Uuid,
+    /// 接收者 ID
+    pub to: Uuid,
+    /// 消息时间戳
+    pub timestamp: u64,
+    /// 审核前的消息内容，文本消息为文本，链接消息为链接地址，其他消息为数据类型及长度
+    pub content: String,
+    /// 标记的原因
+    pub reasons: Vec<String>,
+    /// 标记时间（毫秒）
+    pub flag_time: u64,
+}
+
+/// 被标记消息的存储，按标记时间倒序查询，复审后删除
+#[derive(Clone)]
+pub struct FlaggedStore {
+    redis: deadpool_redis::Pool,
+}
+
+impl FlaggedStore {
+    /// 使用现有的 Redis 连接池构造
+    pub fn from_pool(redis: deadpool_redis::Pool) -> Self {
+        Self { redis }
+    }
+
+    /// 保存被标记的消息
+    pub async fn flag(&self, message: &FlaggedMessage) -> crate::Result<()> {
+        let mut conn = self.redis.get().await?;
+        let id = message.id.as_simple().to_string();
+        let _: () = redis::pipe()
+            .atomic()
+            .hset(FLAGGED_KEY, &id, serde_json::to_string(message)?)
+            .ignore()
+            .zadd(FLAGGED_INDEX_KEY, &id, message.flag_time)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// 按标记时间倒序查询被标记的消息
+    pub async fn list(&self, offset: usize, limit: usize) -> crate::Result<Vec<FlaggedMessage>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.redis.get().await?;
+        let ids: Vec<String> = redis::cmd("ZREVRANGE")
+            .arg(FLAGGED_INDEX_KEY)
+            .arg(offset)
+            .arg(offset + limit - 1)
+            .query_async(&mut conn)
+            .await?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let values: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(FLAGGED_KEY)
+            .arg(&ids)
+            .query_async(&mut conn)
+            .await?;
+
+        let mut messages = Vec::with_capacity(values.len());
+        for value in values.into_iter().flatten() {
+            messages.push(serde_json::from_str(&value)?);
+        }
+        Ok(messages)
+    }
+
+    /// 删除被标记的消息，返回消息是否存在
+    pub async fn remove(&self, id: Uuid) -> crate::Result<bool> {
+        let mut conn = self.redis.get().await?;
+        let id = id.as_simple().to_string();
+        let (removed, _): (usize, usize) = redis::pipe()
+            .atomic()
+            .zrem(FLAGGED_INDEX_KEY, &id)
+            .hdel(FLAGGED_KEY, &id)
+            .query_async(&mut conn)
+            .await?;
+        Ok(removed > 0)
+    }
+}
@@ -0,0 +1,184 @@
+use deadpool_redis::redis::AsyncCommands;
+use jinshu_utils::current_millisecond;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// 吊销列表使用的键，成员的分数为该条记录的过期时间（毫秒）
+const REVOCATION_KEY: &str = "token:revoked";
+
+/// 吊销用户时记录的默认保留时间（毫秒），签名令牌的有效期不应超过该值
+pub const DEFAULT_RETENTION_MS: u64 = 24 * 3600 * 1000;
+
+/// 签名令牌吊销列表存储
+///
+/// 可以吊销单个令牌，或吊销某个用户在某一时刻之前签发的所有令牌；
+/// 记录过期后在读取或写入时清除
+///
+#[derive(Clone)]
+pub struct RevocationStore {
+    redis: deadpool_redis::Pool,
+}
+
+impl RevocationStore {
+    /// 使用现有的 Redis 连接池构造
+    pub fn from_pool(redis: deadpool_redis::Pool) -> Self {
+        Self { redis }
+    }
+
+    /// 吊销令牌 ID 为 `jti` 的令牌，`expire` 为该令牌的过期时间（毫秒）
+    pub async fn revoke_token(&self, jti: Uuid, expire: u64) -> crate::Result<()> {
+        self.add(format!("token:{}", jti.as_simple()), expire).await
+    }
+
+    /// 吊销应用 `app_id` 的用户在 `before`（毫秒）之前签发的所有令牌，该记录保留到 `expire`（毫秒）
+    ///
+    /// 在 `before` 及之后签发的令牌不受影响，因此用户登出后可以立即重新登录
+    ///
+    pub async fn revoke_user(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        before: u64,
+        expire: u64,
+    ) -> crate::Result<()> {
+        self.add(user_member(app_id, user_id, before), expire).await
+    }
+
+    /// 读取当前有效的吊销列表
+    pub async fn load(&self) -> crate::Result<RevocationList> {
+        let mut conn = self.redis.get().await?;
+        let _: () = conn
+            .zrembyscore(REVOCATION_KEY, "-inf", current_millisecond())
+            .await?;
+        let members: Vec<String> = conn.zrange(REVOCATION_KEY, 0, -1).await?;
+        Ok(members.iter().map(String::as_str).collect())
+    }
+
+    async fn add(&self, member: String, expire: u64) -> crate::Result<()> {
+        let mut conn = self.redis.get().await?;
+        let _: () = conn
+            .zrembyscore(REVOCATION_KEY, "-inf", current_millisecond())
+            .await?;
+        let _: () = conn.zadd(REVOCATION_KEY, member, expire).await?;
+        Ok(())
+    }
+}
+
+/// 吊销用户时的成员，格式为 `user:{app_id}:{user_id}:{before}`
+fn user_member(app_id: &str, user_id: Uuid, before: u64) -> String {
+    format!("user:{}:{}:{}", app_id, user_id.as_simple(), before)
+}
+
+/// 吊销列表
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RevocationList {
+    tokens: HashSet<Uuid>,
+    users: HashMap<(String, Uuid), u64>,
+    /// 旧版本写入的不区分应用的记录
+    legacy_users: HashMap<Uuid, u64>,
+}
+
+impl RevocationList {
+    /// 判断应用 `app_id` 的用户 `user_id` 在 `issued_at`（毫秒）签发的令牌 `jti` 是否被吊销
+    pub fn is_revoked(&self, app_id: &str, user_id: Uuid, jti: Uuid, issued_at: u64) -> bool {
+        let before = self
+            .users
+            .get(&(app_id.to_string(), user_id))
+            .into_iter()
+            .chain(self.legacy_users.get(&user_id))
+            .max();
+        self.tokens.contains(&jti) || before.map(|before| issued_at < *before).unwrap_or_default()
+    }
+
+    /// 吊销的令牌及用户数
+    pub fn len(&self) -> usize {
+        self.tokens.len() + self.users.len() + self.legacy_users.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn insert(&mut self, member: &str) -> Option<()> {
+        match member.split_once(':')? {
+            ("token", jti) => {
+                self.tokens.insert(jti.parse().ok()?);
+            }
+            ("user", rest) => {
+                let (rest, before) = rest.rsplit_once(':')?;
+                let before: u64 = before.parse().ok()?;
+                let entry = match rest.rsplit_once(':') {
+                    Some((app_id, user_id)) => self
+                        .users
+                        .entry((app_id.to_string(), user_id.parse().ok()?))
+                        .or_default(),
+                    None => self.legacy_users.entry(rest.parse().ok()?).or_default(),
+                };
+                *entry = before.max(*entry);
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+impl<'a> FromIterator<&'a str> for RevocationList {
+    fn from_iter<T: IntoIterator<Item = &'a str>>(iter: T) -> Self {
+        let mut list = RevocationList::default();
+        for member in iter {
+            if list.insert(member).is_none() {
+                tracing::warn!(%member, "Invalid revocation member");
+            }
+        }
+        list
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{user_member, RevocationList};
+    use uuid::Uuid;
+
+    #[test]
+    fn revocation_list() {
+        let (jti, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let token_member = format!("token:{}", jti.as_simple());
+        let user_members = [
+            user_member("app", user_id, 10_000),
+            user_member("app", user_id, 20_500),
+            user_member("a:b", user_id, 5_000),
+        ];
+
+        let list: RevocationList = [token_member.as_str(), "invalid", "user:app:bad:1"]
+            .into_iter()
+            .chain(user_members.iter().map(String::as_str))
+            .collect();
+        assert_eq!(list.len(), 3);
+
+        let other = Uuid::new_v4();
+        assert!(list.is_revoked("app", other, jti, 100));
+        assert!(!list.is_revoked("app", other, Uuid::new_v4(), 100));
+
+        assert!(list.is_revoked("app", user_id, Uuid::new_v4(), 20_499));
+        assert!(!list.is_revoked("app", user_id, Uuid::new_v4(), 20_500));
+
+        // 按应用隔离，应用 ID 可以包含冒号
+        assert!(list.is_revoked("a:b", user_id, Uuid::new_v4(), 4_999));
+        assert!(!list.is_revoked("a:b", user_id, Uuid::new_v4(), 5_000));
+        assert!(!list.is_revoked("other", user_id, Uuid::new_v4(), 100));
+    }
+
+    #[test]
+    fn legacy_member() {
+        let user_id = Uuid::new_v4();
+        let member = format!("user:{}:{}", user_id.as_simple(), 10_000);
+        let list: RevocationList = [member.as_str()].into_iter().collect();
+        assert_eq!(list.len(), 1);
+
+        // 旧版本的记录对所有应用生效
+        assert!(list.is_revoked("app", user_id, Uuid::new_v4(), 9_000));
+        assert!(list.is_revoked("other", user_id, Uuid::new_v4(), 9_000));
+        assert!(!list.is_revoked("app", user_id, Uuid::new_v4(), 10_000));
+    }
+}
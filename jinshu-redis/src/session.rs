@@ -1,17 +1,25 @@
 use crate::config::RedisConfig;
-use deadpool_redis::redis::AsyncCommands;
+use deadpool_redis::redis::{AsyncCommands, Script};
 use std::fmt::Display;
 use uuid::Uuid;
 
+/// 值相同时才删除键的脚本
+const REMOVE_IF_SCRIPT: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('DEL', KEYS[1])
+end
+return 0
+";
+
 /// 会话存储
 #[derive(Clone)]
 pub struct SessionStore {
     redis: deadpool_redis::Pool,
 }
 
-/// 获取用户会话的键
-fn get_user_session_key<D: Display>(user_id: D) -> String {
-    format!("user:session:{}", user_id)
+/// 获取用户会话的键，按应用隔离
+fn get_user_session_key<A: Display, D: Display>(app_id: A, user_id: D) -> String {
+    format!("user:session:{}:{}", app_id, user_id)
 }
 
 impl SessionStore {
@@ -29,24 +37,45 @@ impl SessionStore {
         Self { redis }
     }
 
-    /// 存储用户及其所在的 Comet 服务地址
-    pub async fn store(&self, user_id: Uuid, service_key: &str) -> crate::Result<()> {
+    /// 存储应用 `app_id` 的用户及其所在的 Comet 服务地址
+    pub async fn store(&self, app_id: &str, user_id: Uuid, service_key: &str) -> crate::Result<()> {
         let mut conn = self.redis.get().await?;
-        let _: () = conn.set(get_user_session_key(user_id), service_key).await?;
+        let _: () = conn
+            .set(get_user_session_key(app_id, user_id), service_key)
+            .await?;
         Ok(())
     }
 
-    /// 读取用户及其所在的 Comet 服务地址
-    pub async fn load(&self, user_id: Uuid) -> crate::Result<Option<String>> {
+    /// 读取应用 `app_id` 的用户及其所在的 Comet 服务地址
+    pub async fn load(&self, app_id: &str, user_id: Uuid) -> crate::Result<Option<String>> {
         let mut conn = self.redis.get().await?;
-        let endpoint: Option<String> = conn.get(get_user_session_key(user_id)).await?;
+        let endpoint: Option<String> = conn.get(get_user_session_key(app_id, user_id)).await?;
         Ok(endpoint)
     }
 
-    /// 删除用户及其所在的 Comet 服务地址
-    pub async fn remove(&self, user_id: Uuid) -> crate::Result<()> {
+    /// 删除应用 `app_id` 的用户及其所在的 Comet 服务地址
+    pub async fn remove(&self, app_id: &str, user_id: Uuid) -> crate::Result<()> {
         let mut conn = self.redis.get().await?;
-        let _: () = conn.del(get_user_session_key(user_id)).await?;
+        let _: () = conn.del(get_user_session_key(app_id, user_id)).await?;
         Ok(())
     }
+
+    /// 应用 `app_id` 的用户仍在 `service_key` 对应的 Comet 上时才删除其会话，返回是否删除
+    ///
+    /// 用户重连到其他 Comet 后，旧连接关闭时不会删除新的会话
+    ///
+    pub async fn remove_if(
+        &self,
+        app_id: &str,
+        user_id: Uuid,
+        service_key: &str,
+    ) -> crate::Result<bool> {
+        let mut conn = self.redis.get().await?;
+        let removed: i32 = Script::new(REMOVE_IF_SCRIPT)
+            .key(get_user_session_key(app_id, user_id))
+            .arg(service_key)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(removed > 0)
+    }
 }
@@ -21,8 +21,12 @@ fn build_dir<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
         .map(|a| a.path())
         .collect::<Vec<_>>();
 
+    let mut config = prost_build::Config::new();
+    // 消息内容使用 `Bytes`，在各服务间传递时避免复制
+    config.bytes([".domain.message.Message.content"]);
+
     Ok(tonic_build::configure()
         // .server_mod_attribute("attrs", "#[cfg(feature = \"server\")]")
         // .client_mod_attribute("attrs", "#[cfg(feature = \"client\")]")
-        .compile(&proto_files, &[path])?)
+        .compile_with_config(config, &proto_files, &[path])?)
 }
@@ -34,6 +34,16 @@ pub mod authorizer {
     tonic::include_proto!("authorizer");
 }
 
+#[allow(missing_docs)]
+pub mod moderator {
+    tonic::include_proto!("moderator");
+}
+
+#[allow(missing_docs)]
+pub mod timer {
+    tonic::include_proto!("timer");
+}
+
 #[allow(missing_docs)]
 #[cfg(test)]
 pub mod test {
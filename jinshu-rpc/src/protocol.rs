@@ -11,7 +11,9 @@ impl TryFrom<&Message> for RpcMessage {
             timestamp: message.timestamp,
             from: message.from.as_bytes().to_vec(),
             to: message.to.as_bytes().to_vec(),
-            content: Vec::<u8>::try_from(&message.content)?,
+            content: Vec::<u8>::try_from(&message.content)?.into(),
+            // 客户端消息不带应用 ID，由 Comet 按连接所属的应用设置
+            app_id: String::new(),
         })
     }
 }
@@ -25,7 +27,7 @@ impl TryFrom<&RpcMessage> for Message {
             timestamp: msg.timestamp,
             from: Uuid::from_slice(&msg.from)?,
             to: Uuid::from_slice(&msg.to)?,
-            content: Content::try_from(msg.content.as_slice())?,
+            content: Content::try_from(msg.content.as_ref())?,
         })
     }
 }
@@ -16,6 +16,7 @@ use tokio_stream::Stream;
 type Providers = Arc<RwLock<HashMap<String, Uri>>>;
 type Consumers = Arc<RwLock<HashMap<String, Sender<Change<String, Uri>>>>>;
 
+/// Mock 注册中心，仅用于测试
 #[derive(Clone, Default)]
 pub struct MockRegistry {
     providers: Providers,
@@ -98,6 +99,7 @@ impl Registry for MockRegistry {
     }
 }
 
+/// Mock 监听器
 pub struct MockWatcher {
     receiver: BroadcastStream<Change<String, Uri>>,
 }
@@ -1,7 +1,7 @@
 use futures::future::join_all;
 use jinshu_common::Config;
 use jinshu_protocol::{Content, Message};
-use jinshu_sdk::{Client, ClientConfig};
+use jinshu_sdk::{Client, ClientConfig, Credential};
 use jinshu_tracing::config::TracingConfig;
 use jinshu_utils::shutdown_signal;
 use serde::{Deserialize, Serialize};
@@ -81,6 +81,7 @@ async fn impersonate(
         .write(true)
         .read(true)
         .create(true)
+        .truncate(false)
         .open(&username)
         .await?;
 
@@ -144,9 +145,19 @@ async fn impersonate(
 
     tracing::info!(%username, "User signed in");
 
-    let mut ua = client.sign_in(jinshu.user_id, jinshu.token).await?;
+    let user_id = jinshu.user_id;
+    let mut ua = client.sign_in_with_credential(jinshu).await?;
 
-    users.write().await.insert(jinshu.user_id);
+    let mut state = ua.watch_state();
+    let name = username.clone();
+    tokio::spawn(async move {
+        while state.changed().await.is_ok() {
+            let state = *state.borrow();
+            tracing::info!(username = %name, ?state, "Connection state changed");
+        }
+    });
+
+    users.write().await.insert(user_id);
 
     let mut shutdown = Box::pin(shutdown_signal());
 
@@ -157,17 +168,22 @@ async fn impersonate(
                     .read()
                     .await
                     .iter()
-                    .filter(|u| jinshu.user_id.ne(*u))
+                    .filter(|u| user_id.ne(*u))
                     .map(|to| {
                         ua.send(Message::new(
-                            jinshu.user_id,
+                            user_id,
                             *to,
                             Content::string(format!("Hello, I'm {}", username)),
                         ))
                     })
                     .collect::<Vec<_>>();
 
-                join_all(targets).await;
+                for result in join_all(targets).await {
+                    match result {
+                        Ok(response) => tracing::debug!(%username, ?response, "Message is sent"),
+                        Err(error) => tracing::warn!(%username, %error, "Failed to send message"),
+                    }
+                }
             }
             receive = ua.receive() => {
                 match receive {
@@ -196,11 +212,5 @@ struct AppSignParam<'a> {
 
 #[derive(Debug, Deserialize)]
 struct AppSignInResult {
-    pub jinshu: JinshuResult,
-}
-
-#[derive(Debug, Deserialize)]
-struct JinshuResult {
-    pub user_id: Uuid,
-    pub token: Uuid,
+    pub jinshu: Credential,
 }
@@ -1,28 +1,131 @@
-use crate::LoginError;
-use dashmap::DashMap;
-use futures::stream::{SplitSink, SplitStream};
+use crate::rt;
+use crate::session::{ConnectionState, Outgoing, Session, SignedIn, TokenSource};
+use crate::{Connector, Credential, IdentityKey, LoginError, RttStats};
+use futures::future::AbortHandle;
 use futures::{SinkExt, StreamExt};
 use jinshu_protocol::{
-    Body, Message, Pdu, PduCodec, Request, Response, TransactionId, TransactionIdGenerator,
+    Body, Capabilities, Capability, Compression, Message, Pdu, PduCodec, Request, Response,
+    TransactionIdGenerator, PROTOCOL_VERSION,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use std::time::Instant;
-use tokio::net::TcpStream;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 use tokio::sync::mpsc::{Receiver, Sender};
-use tokio_util::codec::Framed;
+use tokio::sync::{oneshot, watch};
 use url::Url;
 use uuid::Uuid;
 
 /// Client 的配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClientConfig {
+    /// 应用 ID
+    pub app_id: String,
     /// Comet 的主机名
     pub comet_host: String,
     /// Comet 的端口
     pub comet_port: u16,
     /// Api 的 URL
     pub api_url: Url,
+    /// Gateway 的 URL，用于刷新令牌
+    pub gateway_url: Url,
+    /// 在令牌过期前多久刷新（秒）
+    pub refresh_ahead_sec: u64,
+    /// 支持的报文压缩算法，按优先级排列，为空时不压缩
+    #[serde(default = "default_compressions")]
+    pub compressions: Vec<Compression>,
+    /// 压缩阈值，小于该长度（字节）的报文不压缩
+    #[serde(default = "default_compress_threshold")]
+    pub compress_threshold: usize,
+    /// 等待发送响应的超时时间（毫秒）
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+    /// 断线重连配置
+    #[serde(default)]
+    pub reconnect: ReconnectConfig,
+    /// 心跳配置
+    #[serde(default)]
+    pub heartbeat: HeartbeatConfig,
+}
+
+/// 心跳配置，每隔 `interval_ms` 发送一次 Ping，超过 `timeout_ms` 未收到 Pong 时断开并重连
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeartbeatConfig {
+    /// 是否发送心跳
+    pub enabled: bool,
+    /// 发送 Ping 的间隔（毫秒）
+    pub interval_ms: u64,
+    /// 等待 Pong 的超时时间（毫秒）
+    pub timeout_ms: u64,
+}
+
+impl HeartbeatConfig {
+    /// 发送 Ping 的间隔
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+
+    /// 等待 Pong 的超时时间
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_ms: 30_000,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// 断线重连配置，重连间隔从 `initial_delay_ms` 开始每次加倍，不超过 `max_delay_ms`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconnectConfig {
+    /// 是否重连
+    pub enabled: bool,
+    /// 第一次重连前等待的时间（毫秒）
+    pub initial_delay_ms: u64,
+    /// 重连前等待的最长时间（毫秒）
+    pub max_delay_ms: u64,
+    /// 最多连续重连的次数，为 0 时不限制
+    pub max_attempts: u32,
+}
+
+impl ReconnectConfig {
+    /// 第 `attempt` 次（从 1 开始）重连前等待的时间
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .initial_delay_ms
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        Duration::from_millis(delay.min(self.max_delay_ms))
+    }
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+            max_attempts: 0,
+        }
+    }
+}
+
+fn default_compressions() -> Vec<Compression> {
+    Compression::ALL.to_vec()
+}
+
+fn default_compress_threshold() -> usize {
+    PduCodec::DEFAULT_COMPRESS_THRESHOLD
+}
+
+fn default_request_timeout_ms() -> u64 {
+    10_000
 }
 
 impl ClientConfig {
@@ -30,16 +133,36 @@ impl ClientConfig {
     pub fn comet_address(&self) -> String {
         format!("{}:{}", self.comet_host, self.comet_port)
     }
+
+    /// 在令牌过期前多久刷新
+    pub fn refresh_ahead(&self) -> Duration {
+        Duration::from_secs(self.refresh_ahead_sec)
+    }
+
+    /// 等待发送响应的超时时间
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_millis(self.request_timeout_ms)
+    }
 }
 
 impl Default for ClientConfig {
     fn default() -> Self {
         Self {
+            app_id: "example".into(),
             comet_host: "localhost".into(),
             comet_port: 9000,
             api_url: "http://localhost:9500"
                 .parse()
                 .expect("impossible: api_url parse error"),
+            gateway_url: "http://localhost:9200"
+                .parse()
+                .expect("impossible: gateway_url parse error"),
+            refresh_ahead_sec: 60,
+            compressions: default_compressions(),
+            compress_threshold: default_compress_threshold(),
+            request_timeout_ms: default_request_timeout_ms(),
+            reconnect: ReconnectConfig::default(),
+            heartbeat: HeartbeatConfig::default(),
         }
     }
 }
@@ -49,6 +172,9 @@ impl Default for ClientConfig {
 pub struct Client {
     config: ClientConfig,
     http: reqwest::Client,
+    connector: Arc<dyn Connector>,
+    #[cfg(feature = "local-store")]
+    store: Option<crate::LocalStore>,
 }
 
 impl Client {
@@ -59,151 +185,445 @@ impl Client {
             http: reqwest::ClientBuilder::new()
                 .user_agent(USER_AGENT)
                 .build()?,
+            #[cfg(not(target_arch = "wasm32"))]
+            connector: Arc::new(crate::TcpConnector),
+            #[cfg(target_arch = "wasm32")]
+            connector: Arc::new(crate::WebSocketConnector::default()),
+            #[cfg(feature = "local-store")]
+            store: None,
         })
     }
 
+    /// 使用指定的方式连接 Comet，默认通过 TCP 连接，wasm32 上默认通过 WebSocket 连接
+    pub fn with_connector(mut self, connector: impl Connector + 'static) -> Self {
+        self.connector = Arc::new(connector);
+        self
+    }
+
+    /// 使用本地存储保存收发的消息及会话状态，发送的消息在收到响应前保存在发件箱中，
+    /// 离线或超时未确认的消息在下次连接时重发
+    #[cfg(feature = "local-store")]
+    pub fn with_local_store(mut self, store: crate::LocalStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// 本地存储
+    #[cfg(feature = "local-store")]
+    pub fn local_store(&self) -> Option<&crate::LocalStore> {
+        self.store.as_ref()
+    }
+
+    /// 客户端配置
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
     /// 客户端内部使用的 HTTP 客户端
     pub fn http_client(&self) -> &reqwest::Client {
         &self.http
     }
 
-    /// 使用锦书用户 ID 及令牌登录
-    pub async fn sign_in(&self, user_id: Uuid, token: Uuid) -> Result<UserAgent, LoginError> {
-        let socket = TcpStream::connect(self.config.comet_address()).await?;
-        let mut framed = Framed::new(socket, PduCodec::default());
+    /// 使用刷新令牌换取新的登录凭证
+    pub async fn refresh(&self, credential: &Credential) -> Result<Credential, LoginError> {
+        #[derive(Serialize)]
+        struct RefreshParam<'a> {
+            app_id: &'a str,
+            user_id: Uuid,
+            refresh_token: &'a str,
+        }
+
+        let url = self.config.gateway_url.join(REFRESH_PATH).map_err(|e| {
+            LoginError::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+        })?;
+        let response = self
+            .http
+            .post(url)
+            .json(&RefreshParam {
+                app_id: &self.config.app_id,
+                user_id: credential.user_id,
+                refresh_token: &credential.refresh_token,
+            })
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_client_error() {
+            return Err(LoginError::RefreshRejected(status));
+        }
+
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    /// 构造带有用户鉴权信息的 Api 请求
+    fn api_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        credential: &Credential,
+    ) -> crate::Result<reqwest::RequestBuilder> {
+        let url = self
+            .config
+            .api_url
+            .join(path)
+            .map_err(|e| crate::Error::Other(e.to_string().into()))?;
+        Ok(self
+            .http
+            .request(method, url)
+            .header(APP_ID_HEADER, &self.config.app_id)
+            .header(USER_ID_HEADER, credential.user_id.as_simple().to_string())
+            .bearer_auth(&credential.token))
+    }
+
+    /// 发布当前用户的身份公钥，公钥 ID 已存在时覆盖
+    pub async fn publish_identity_key(
+        &self,
+        credential: &Credential,
+        key_id: &str,
+        algorithm: &str,
+        public_key: &str,
+    ) -> crate::Result<IdentityKey> {
+        #[derive(Serialize)]
+        struct PublishKeyParam<'a> {
+            key_id: &'a str,
+            algorithm: &'a str,
+            public_key: &'a str,
+        }
+
+        Ok(self
+            .api_request(reqwest::Method::PUT, KEYS_PATH, credential)?
+            .json(&PublishKeyParam {
+                key_id,
+                algorithm,
+                public_key,
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// 查询同一应用内用户 `user_id` 的身份公钥
+    pub async fn identity_keys(
+        &self,
+        credential: &Credential,
+        user_id: Uuid,
+    ) -> crate::Result<Vec<IdentityKey>> {
+        let path = format!("/users/{}/keys", user_id.as_simple());
+        Ok(self
+            .api_request(reqwest::Method::GET, &path, credential)?
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// 删除当前用户的身份公钥
+    pub async fn delete_identity_key(
+        &self,
+        credential: &Credential,
+        key_id: &str,
+    ) -> crate::Result<()> {
+        let path = format!("{}/{}", KEYS_PATH, key_id);
+        self.api_request(reqwest::Method::DELETE, &path, credential)?
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// 使用登录凭证登录
+    ///
+    /// 令牌即将过期时先刷新令牌，令牌被拒绝时刷新令牌后重试一次；
+    /// 登录成功后在后台持续刷新令牌，可通过 [`UserAgent::credential`] 获取最新的凭证
+    ///
+    pub async fn sign_in_with_credential(
+        &self,
+        mut credential: Credential,
+    ) -> Result<UserAgent, LoginError> {
+        if credential.expires_within(self.config.refresh_ahead()) {
+            credential = self.refresh(&credential).await?;
+        }
+
+        let signed_in = match self
+            .connect(credential.user_id, credential.token.clone())
+            .await
+        {
+            Err(LoginError::InvalidToken) => {
+                log::info!("Token is rejected, refresh and retry");
+                credential = self.refresh(&credential).await?;
+                self.connect(credential.user_id, credential.token.clone())
+                    .await?
+            }
+            result => result?,
+        };
+
+        let user_id = credential.user_id;
+        let credential = Arc::new(RwLock::new(credential));
+        let mut user_agent = self.start(
+            user_id,
+            TokenSource::Credential(credential.clone()),
+            signed_in,
+        );
+        user_agent.refresher = Some(spawn_refresher(self.clone(), credential.clone()));
+        user_agent.credential = Some(credential);
+
+        Ok(user_agent)
+    }
+
+    /// 使用锦书用户 ID 及令牌登录配置的应用
+    ///
+    /// 连接断开后按 [`ClientConfig::reconnect`] 重连并使用同一令牌重新登录，令牌被拒绝时不再重连
+    ///
+    pub async fn sign_in(
+        &self,
+        user_id: Uuid,
+        token: impl Into<String>,
+    ) -> Result<UserAgent, LoginError> {
+        let token = token.into();
+        let signed_in = self.connect(user_id, token.clone()).await?;
+        Ok(self.start(user_id, TokenSource::Static(token), signed_in))
+    }
+
+    /// 在已登录的连接上启动会话
+    fn start(&self, user_id: Uuid, token: TokenSource, signed_in: SignedIn) -> UserAgent {
+        let SignedIn {
+            transport,
+            version,
+            capabilities,
+        } = signed_in;
+
+        let (incoming, receiver) = tokio::sync::mpsc::channel(32);
+        let (sender, outgoing) = tokio::sync::mpsc::channel(32);
+        let (session, state) = Session::new(
+            self.clone(),
+            user_id,
+            token,
+            capabilities.clone(),
+            outgoing,
+            incoming,
+        );
+        let rtt = session.rtt();
+        rt::spawn(session.run(transport));
+
+        UserAgent {
+            user_id,
+            version,
+            sender: MessageSender {
+                encryption: capabilities.contains(Capability::Encryption),
+                sender: sender.clone(),
+                request_timeout: self.config.request_timeout(),
+                #[cfg(feature = "local-store")]
+                user_id,
+                #[cfg(feature = "local-store")]
+                store: self.store.clone(),
+            },
+            capabilities,
+            connection: Connection::new(receiver, sender),
+            state,
+            rtt,
+            credential: None,
+            refresher: None,
+        }
+    }
+
+    /// 连接 Comet 并登录
+    pub(crate) async fn connect(
+        &self,
+        user_id: Uuid,
+        token: String,
+    ) -> Result<SignedIn, LoginError> {
+        let mut framed = self.connector.connect(&self.config).await?;
         let mut trans_id_gen = TransactionIdGenerator::default();
 
-        let sign_in = Request::SignIn { user_id, token }.to_pdu(trans_id_gen.next_id());
+        let sign_in = Request::SignIn {
+            app_id: self.config.app_id.clone(),
+            user_id,
+            token,
+            compressions: self.config.compressions.clone(),
+            version: PROTOCOL_VERSION,
+            capabilities: Capabilities::all(),
+        }
+        .to_pdu(trans_id_gen.next_id());
 
         framed.send(sign_in).await?;
 
         // add timeout
         match framed.next().await {
             Some(Ok(Pdu {
-                body: Body::Resp(Response::SignedIn { extension }),
+                body:
+                    Body::Resp(Response::SignedIn {
+                        extension,
+                        compression,
+                        version,
+                        capabilities,
+                        ..
+                    }),
                 ..
             })) => {
-                log::info!("Sign in ok");
+                log::info!(
+                    "Sign in ok, version: {}, capabilities: {:?}, compression: {:?}",
+                    version,
+                    capabilities,
+                    compression
+                );
+                framed.set_compression(compression);
+                framed.set_binary_data(capabilities.contains(Capability::BinaryData));
                 if let Some(extension) = &extension {
                     log::info!("extension: {}", extension);
                 }
                 // TODO: extension
-                let (writer, reader) = framed.split();
-
-                let waiting = Arc::new(DashMap::new());
-                let (read_sender, receiver) = tokio::sync::mpsc::channel(32);
-                let w = waiting.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = read_loop(read_sender, w, reader).await {
-                        log::error!("Read loop exited with error: {}", e);
-                    }
-                });
-
-                let (sender, write_receiver) = tokio::sync::mpsc::channel(32);
-                tokio::spawn(async move {
-                    if let Err(e) = write_loop(write_receiver, waiting, writer).await {
-                        log::error!("Write loop exited with error: {}", e);
-                    }
-                });
-
-                Ok(UserAgent {
-                    user_id,
-                    connection: Connection::new(receiver, sender),
+                Ok(SignedIn {
+                    transport: framed,
+                    version,
+                    capabilities,
                 })
             }
             Some(Ok(Pdu {
                 body: Body::Resp(Response::InvalidToken { .. }),
                 ..
             })) => Err(crate::LoginError::InvalidToken),
-            Some(Ok(pdu)) => Err(crate::LoginError::UnexpectedResponse(pdu)),
+            Some(Ok(Pdu {
+                body:
+                    Body::Resp(Response::UnsupportedVersion {
+                        min_version,
+                        max_version,
+                    }),
+                ..
+            })) => Err(crate::LoginError::UnsupportedVersion {
+                min_version,
+                max_version,
+            }),
+            Some(Ok(pdu)) => Err(crate::LoginError::UnexpectedResponse(Box::new(pdu))),
             Some(Err(e)) => Err(crate::LoginError::DecodeError(e)),
             None => Err(crate::LoginError::ConnectionClosed),
         }
     }
 }
 
-async fn write_loop(
-    mut receiver: Receiver<Message>,
-    waiting: Arc<DashMap<TransactionId, Instant>>,
-    mut writer: SplitSink<Framed<TcpStream, PduCodec>, Pdu>,
-) -> anyhow::Result<()> {
-    let mut id_gen = TransactionIdGenerator::new();
+/// 刷新令牌的 Gateway 路由
+const REFRESH_PATH: &str = "/refresh";
 
-    while let Some(message) = receiver.recv().await {
-        let trans_id = id_gen.next_id();
-        let pdu = Request::Send { message }.to_pdu(trans_id);
+/// 身份公钥的 Api 路由
+const KEYS_PATH: &str = "/keys";
 
-        waiting.insert(trans_id, Instant::now());
+/// Api 鉴权使用的应用 ID 请求头
+const APP_ID_HEADER: &str = "x-jinshu-app-id";
 
-        writer.send(pdu).await?;
-    }
+/// Api 鉴权使用的用户 ID 请求头
+const USER_ID_HEADER: &str = "x-jinshu-user-id";
 
-    Ok(())
-}
+/// 刷新令牌失败后的重试间隔
+const REFRESH_RETRY_INTERVAL: Duration = Duration::from_secs(5);
 
-async fn read_loop(
-    sender: Sender<Message>,
-    waiting: Arc<DashMap<TransactionId, Instant>>,
-    mut reader: SplitStream<Framed<TcpStream, PduCodec>>,
-) -> anyhow::Result<()> {
-    while let Some(qr) = reader.next().await {
-        let pdu = qr?;
-        match pdu.body {
-            Body::Resp(response) => match waiting.remove(&pdu.id) {
-                Some((_, instant)) => match response {
-                    Response::Queued { id } => {
-                        log::info!(
-                            "Message {:?} is queued. ({}ms)",
-                            id,
-                            instant.elapsed().as_millis()
-                        );
-                    }
-                    Response::Rejected { id, error } => {
-                        log::error!(
-                            "Message {:?} is rejected: {}. ({}ms)",
-                            id,
-                            error,
-                            instant.elapsed().as_millis()
-                        )
+/// 在令牌过期前刷新令牌，刷新令牌被拒绝或过期后停止
+fn spawn_refresher(client: Client, credential: Arc<RwLock<Credential>>) -> AbortHandle {
+    rt::spawn(async move {
+        let ahead = client.config.refresh_ahead();
+        loop {
+            let current = match credential.read() {
+                Ok(current) => current.clone(),
+                Err(_) => break,
+            };
+
+            if current.is_refresh_expired() {
+                log::error!("Refresh token is expired");
+                break;
+            }
+
+            rt::sleep(current.refresh_delay(ahead)).await;
+
+            match client.refresh(&current).await {
+                Ok(refreshed) => {
+                    log::info!("Token is refreshed");
+                    match credential.write() {
+                        Ok(mut credential) => *credential = refreshed,
+                        Err(_) => break,
                     }
-                    resp => log::error!("Invalid response: {:?}", resp),
-                },
-                None => {
-                    log::error!(
-                        "Invalid transaction id: {:?}, waiting: {:?}",
-                        pdu.id,
-                        waiting
-                    );
+                }
+                Err(e @ LoginError::RefreshRejected(_)) => {
+                    log::error!("Failed to refresh token: {}", e);
                     break;
                 }
-            },
-            Body::Req(request) => match request {
-                Request::Push { message } => {
-                    log::info!("Received a message: {:?}", message);
-                    sender.send(message).await?;
+                Err(e) => {
+                    log::warn!("Failed to refresh token: {}, retry later", e);
+                    rt::sleep(REFRESH_RETRY_INTERVAL).await;
                 }
-                req => log::error!("Invalid request: {:?}", req),
-            },
+            }
         }
-    }
-
-    Ok(())
+    })
 }
 
 /// 客户端发送 HTTP 请求时的 User-Agent 字段
 pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
+/// 消息发送端，可以克隆后在多个任务中并发发送，见 [`UserAgent::sender`]
+#[derive(Debug, Clone)]
+pub struct MessageSender {
+    encryption: bool,
+    sender: Sender<Outgoing>,
+    request_timeout: Duration,
+    #[cfg(feature = "local-store")]
+    user_id: Uuid,
+    #[cfg(feature = "local-store")]
+    store: Option<crate::LocalStore>,
+}
+
+impl MessageSender {
+    /// 发送消息并等待 Comet 的响应（[`Response::Queued`]、[`Response::Rejected`] 等），
+    /// 超过 [`ClientConfig::request_timeout_ms`] 未收到响应时返回 [`crate::Error::Timeout`]
+    ///
+    /// 登录时未协商端到端加密时不能发送加密消息；配置了本地存储时，消息先放入发件箱，
+    /// 超时或连接关闭时仍保留在发件箱中，下次连接时重发
+    ///
+    pub async fn send(&self, message: Message) -> crate::Result<Response> {
+        if message.content.is_encrypted() && !self.encryption {
+            return Err(crate::Error::Unsupported(Capability::Encryption));
+        }
+
+        #[cfg(feature = "local-store")]
+        if let Some(store) = &self.store {
+            store.push_outbox(self.user_id, &message)?;
+        }
+
+        let (responder, response) = oneshot::channel();
+        rt::timeout(self.request_timeout, async move {
+            self.sender
+                .send((message, responder))
+                .await
+                .map_err(|_| crate::Error::ConnectionClosed)?;
+            response.await.map_err(|_| crate::Error::ConnectionClosed)
+        })
+        .await
+        .map_err(|_| crate::Error::Timeout)?
+    }
+}
+
 /// 用户代理
 #[derive(Debug)]
 pub struct UserAgent {
     user_id: Uuid,
-    connection: Connection<Message>,
+    version: u16,
+    capabilities: Capabilities,
+    sender: MessageSender,
+    connection: Connection<Message, Outgoing>,
+    state: watch::Receiver<ConnectionState>,
+    rtt: Arc<Mutex<RttStats>>,
+    credential: Option<Arc<RwLock<Credential>>>,
+    refresher: Option<AbortHandle>,
 }
 
 impl UserAgent {
-    /// 发送消息
-    pub async fn send(&self, message: Message) -> crate::Result<()> {
-        self.connection.send(message).await
+    /// 发送消息并等待 Comet 的响应，见 [`MessageSender::send`]
+    pub async fn send(&self, message: Message) -> crate::Result<Response> {
+        self.sender.send(message).await
+    }
+
+    /// 消息发送端，用于在接收消息的同时在其他任务中发送消息
+    pub fn sender(&self) -> MessageSender {
+        self.sender.clone()
     }
 
     /// 接收消息
@@ -215,23 +635,69 @@ impl UserAgent {
     pub async fn user_id(&self) -> &Uuid {
         &self.user_id
     }
+
+    /// 本地存储，可用于查询历史消息及会话
+    #[cfg(feature = "local-store")]
+    pub fn local_store(&self) -> Option<&crate::LocalStore> {
+        self.sender.store.as_ref()
+    }
+
+    /// 当前的连接状态
+    pub fn state(&self) -> ConnectionState {
+        *self.state.borrow()
+    }
+
+    /// 观察连接状态的变化，只保留最新的状态
+    pub fn watch_state(&self) -> watch::Receiver<ConnectionState> {
+        self.state.clone()
+    }
+
+    /// 心跳的往返时间统计
+    pub fn rtt(&self) -> RttStats {
+        self.rtt.lock().map(|rtt| *rtt).unwrap_or_default()
+    }
+
+    /// 协商的协议版本
+    pub fn protocol_version(&self) -> u16 {
+        self.version
+    }
+
+    /// 协商的能力
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// 最新的登录凭证，只有使用 [`Client::sign_in_with_credential`] 登录时存在
+    pub fn credential(&self) -> Option<Credential> {
+        self.credential
+            .as_ref()
+            .and_then(|c| c.read().ok().map(|c| c.clone()))
+    }
 }
 
-/// 用于收发 `T` 的连接
+impl Drop for UserAgent {
+    fn drop(&mut self) {
+        if let Some(refresher) = self.refresher.take() {
+            refresher.abort();
+        }
+    }
+}
+
+/// 用于接收 `T` 及发送 `S` 的连接
 #[derive(Debug)]
-pub struct Connection<T> {
+pub struct Connection<T, S = T> {
     receiver: Receiver<T>,
-    sender: Sender<T>,
+    sender: Sender<S>,
 }
 
-impl<T> Connection<T> {
+impl<T, S> Connection<T, S> {
     /// 构造连接
-    pub(crate) fn new(receiver: Receiver<T>, sender: Sender<T>) -> Self {
+    pub(crate) fn new(receiver: Receiver<T>, sender: Sender<S>) -> Self {
         Self { receiver, sender }
     }
 
-    /// 发送 `T`
-    pub async fn send(&self, message: T) -> crate::Result<()> {
+    /// 发送 `S`
+    pub async fn send(&self, message: S) -> crate::Result<()> {
         if self.sender.send(message).await.is_err() {
             return Err(crate::Error::ConnectionClosed);
         }
@@ -249,7 +715,23 @@ impl<T> Connection<T> {
 
 #[cfg(test)]
 mod test {
-    use crate::Connection;
+    use crate::{ClientConfig, Connection, HeartbeatConfig, ReconnectConfig};
+    use std::time::Duration;
+
+    #[test]
+    fn default() {
+        ClientConfig::default();
+        HeartbeatConfig::default();
+    }
+
+    #[test]
+    fn reconnect_delay() {
+        let config = ReconnectConfig::default();
+        assert_eq!(config.delay(1), Duration::from_millis(500));
+        assert_eq!(config.delay(2), Duration::from_secs(1));
+        assert_eq!(config.delay(3), Duration::from_secs(2));
+        assert_eq!(config.delay(100), Duration::from_secs(30));
+    }
 
     #[tokio::test]
     async fn connection() {
@@ -0,0 +1,71 @@
+use jinshu_utils::current_millisecond;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// 登录凭证
+///
+/// 与 Gateway 登录及刷新令牌接口的返回结果兼容，应用服务器可以将其原样返回给客户端
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credential {
+    /// 锦书用户 ID
+    pub user_id: Uuid,
+    /// 登录令牌
+    pub token: String,
+    /// 登录令牌的过期时间（毫秒）
+    pub expire: u64,
+    /// 刷新令牌
+    pub refresh_token: String,
+    /// 刷新令牌的过期时间（毫秒）
+    pub refresh_expire: u64,
+}
+
+impl Credential {
+    /// 登录令牌是否会在 `ahead` 之内过期
+    pub fn expires_within(&self, ahead: Duration) -> bool {
+        self.refresh_delay(ahead).is_zero()
+    }
+
+    /// 刷新令牌是否已过期
+    pub fn is_refresh_expired(&self) -> bool {
+        current_millisecond() >= self.refresh_expire
+    }
+
+    /// 距离需要刷新（过期前 `ahead`）的时长
+    pub fn refresh_delay(&self, ahead: Duration) -> Duration {
+        let refresh_at = self.expire.saturating_sub(ahead.as_millis() as u64);
+        Duration::from_millis(refresh_at.saturating_sub(current_millisecond()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Credential;
+    use jinshu_utils::current_millisecond;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    #[test]
+    fn credential() {
+        let user_id = Uuid::new_v4();
+        let now = current_millisecond();
+        let json = serde_json::json!({
+            "user_id": user_id,
+            "token": "token",
+            "extension": { "device": "ios" },
+            "expire": now + 300_000,
+            "refresh_token": "refresh",
+            "refresh_expire": now + 600_000,
+        });
+
+        let credential: Credential = serde_json::from_value(json).unwrap();
+        assert_eq!(credential.user_id, user_id);
+        assert!(!credential.is_refresh_expired());
+        assert!(!credential.expires_within(Duration::from_secs(60)));
+        assert!(credential.expires_within(Duration::from_secs(300)));
+
+        let delay = credential.refresh_delay(Duration::from_secs(60));
+        assert!(delay <= Duration::from_secs(240) && delay > Duration::from_secs(230));
+    }
+}
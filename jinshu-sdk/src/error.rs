@@ -1,4 +1,4 @@
-use jinshu_protocol::Pdu;
+use jinshu_protocol::{Capability, Pdu};
 use std::borrow::Cow;
 
 /// SDK 错误
@@ -16,6 +16,19 @@ pub enum Error {
     /// 连接关闭
     #[error("Connection closed")]
     ConnectionClosed,
+    /// 等待响应超时
+    #[error("Request timed out")]
+    Timeout,
+    /// 登录时未协商该能力
+    #[error("Capability is not negotiated: {0}")]
+    Unsupported(Capability),
+    /// 本地存储错误
+    #[cfg(feature = "local-store")]
+    #[error(transparent)]
+    Store(#[from] rusqlite::Error),
+    /// 消息内容格式错误
+    #[error(transparent)]
+    InvalidContent(#[from] jinshu_protocol::InvalidContentFormat),
     /// 其他错误
     #[error("Other error: {}", .0)]
     Other(Cow<'static, str>),
@@ -33,13 +46,27 @@ pub enum LoginError {
     /// 非法令牌
     #[error("Invalid token")]
     InvalidToken,
+    /// 服务端不支持客户端的协议版本
+    #[error("Unsupported protocol version, server supports {min_version} to {max_version}")]
+    UnsupportedVersion {
+        /// 服务端支持的最低协议版本
+        min_version: u16,
+        /// 服务端支持的最高协议版本
+        max_version: u16,
+    },
     /// 异常响应
     #[error("Unexpected response: {:?}", .0)]
-    UnexpectedResponse(Pdu),
+    UnexpectedResponse(Box<Pdu>),
     /// 解码错误
     #[error("Invalid pdu: {}", .0)]
     DecodeError(#[from] jinshu_protocol::Error),
     /// 连接关闭
     #[error("Connection is closed")]
     ConnectionClosed,
+    /// 刷新令牌请求错误
+    #[error(transparent)]
+    Refresh(#[from] reqwest::Error),
+    /// 刷新令牌被拒绝，需要重新登录
+    #[error("Refresh token is rejected: {0}")]
+    RefreshRejected(reqwest::StatusCode),
 }
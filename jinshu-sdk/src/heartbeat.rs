@@ -0,0 +1,171 @@
+use crate::rt::Instant;
+use crate::HeartbeatConfig;
+use jinshu_protocol::TransactionId;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 心跳的往返时间统计，可通过 [`crate::UserAgent::rtt`] 获取
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RttStats {
+    /// 最近一次的往返时间
+    pub last: Option<Duration>,
+    /// 最短的往返时间
+    pub min: Option<Duration>,
+    /// 最长的往返时间
+    pub max: Option<Duration>,
+    /// 平滑的往返时间，计算方式同 TCP 的 SRTT（RFC 6298）
+    pub smoothed: Option<Duration>,
+    /// 收到 Pong 的次数
+    pub samples: u64,
+    /// 未按时收到 Pong 的次数
+    pub timeouts: u64,
+}
+
+impl RttStats {
+    /// 记录一次往返时间
+    fn record(&mut self, rtt: Duration) {
+        self.last = Some(rtt);
+        self.min = Some(self.min.map_or(rtt, |min| min.min(rtt)));
+        self.max = Some(self.max.map_or(rtt, |max| max.max(rtt)));
+        self.smoothed = Some(match self.smoothed {
+            Some(smoothed) => (smoothed * 7 + rtt) / 8,
+            None => rtt,
+        });
+        self.samples += 1;
+    }
+}
+
+/// 跟踪已发送但未收到 Pong 的 Ping，同一时间最多一个
+#[derive(Debug)]
+pub(crate) struct Heartbeat {
+    interval: Option<Duration>,
+    timeout: Duration,
+    outstanding: Mutex<Option<(TransactionId, Instant)>>,
+    stats: Arc<Mutex<RttStats>>,
+}
+
+impl Heartbeat {
+    /// 使用配置构造
+    pub(crate) fn new(config: &HeartbeatConfig) -> Self {
+        Self {
+            interval: (config.enabled && config.interval_ms > 0).then(|| config.interval()),
+            timeout: config.timeout(),
+            outstanding: Default::default(),
+            stats: Default::default(),
+        }
+    }
+
+    /// 发送 Ping 的间隔，未启用心跳时为 `None`
+    pub(crate) fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// 共享的往返时间统计
+    pub(crate) fn stats(&self) -> Arc<Mutex<RttStats>> {
+        self.stats.clone()
+    }
+
+    /// 记录发送的 Ping
+    pub(crate) fn ping(&self, id: TransactionId) {
+        if let Ok(mut outstanding) = self.outstanding.lock() {
+            *outstanding = Some((id, Instant::now()));
+        }
+    }
+
+    /// 收到 Pong 时记录往返时间，不是最近一次 Ping 的 Pong 时返回 `None`
+    pub(crate) fn pong(&self, id: TransactionId) -> Option<Duration> {
+        let mut outstanding = self.outstanding.lock().ok()?;
+        match *outstanding {
+            Some((ping, sent_at)) if ping == id => {
+                *outstanding = None;
+                let rtt = sent_at.elapsed();
+                if let Ok(mut stats) = self.stats.lock() {
+                    stats.record(rtt);
+                }
+                Some(rtt)
+            }
+            _ => None,
+        }
+    }
+
+    /// 未收到 Pong 的 Ping 的超时时刻
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.outstanding
+            .lock()
+            .ok()?
+            .map(|(_, sent_at)| sent_at + self.timeout)
+    }
+
+    /// 是否有未收到 Pong 的 Ping
+    pub(crate) fn is_outstanding(&self) -> bool {
+        self.outstanding
+            .lock()
+            .map(|outstanding| outstanding.is_some())
+            .unwrap_or(false)
+    }
+
+    /// 记录一次超时，并清除未收到 Pong 的 Ping
+    pub(crate) fn timeout(&self) {
+        if let Ok(mut outstanding) = self.outstanding.lock() {
+            *outstanding = None;
+        }
+        if let Ok(mut stats) = self.stats.lock() {
+            stats.timeouts += 1;
+        }
+    }
+
+    /// 重连后清除上一个连接未收到 Pong 的 Ping
+    pub(crate) fn reset(&self) {
+        if let Ok(mut outstanding) = self.outstanding.lock() {
+            *outstanding = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Heartbeat, RttStats};
+    use crate::HeartbeatConfig;
+    use jinshu_protocol::TransactionIdGenerator;
+    use std::time::Duration;
+
+    #[test]
+    fn record() {
+        let mut stats = RttStats::default();
+        stats.record(Duration::from_millis(80));
+        assert_eq!(stats.smoothed, Some(Duration::from_millis(80)));
+        stats.record(Duration::from_millis(160));
+        assert_eq!(stats.last, Some(Duration::from_millis(160)));
+        assert_eq!(stats.min, Some(Duration::from_millis(80)));
+        assert_eq!(stats.max, Some(Duration::from_millis(160)));
+        assert_eq!(stats.smoothed, Some(Duration::from_millis(90)));
+        assert_eq!(stats.samples, 2);
+    }
+
+    #[test]
+    fn ping_pong() {
+        let mut id_gen = TransactionIdGenerator::default();
+        let heartbeat = Heartbeat::new(&HeartbeatConfig::default());
+        assert_eq!(heartbeat.interval(), Some(Duration::from_secs(30)));
+        assert!(heartbeat.deadline().is_none());
+
+        let ping = id_gen.next_id();
+        heartbeat.ping(ping);
+        assert!(heartbeat.is_outstanding());
+        assert!(heartbeat.deadline().is_some());
+
+        // 其他事务的 Pong 不影响统计
+        assert!(heartbeat.pong(id_gen.next_id()).is_none());
+        assert!(heartbeat.pong(ping).is_some());
+        assert!(!heartbeat.is_outstanding());
+        assert!(heartbeat.pong(ping).is_none());
+
+        heartbeat.ping(id_gen.next_id());
+        heartbeat.timeout();
+        assert!(!heartbeat.is_outstanding());
+
+        let stats = *heartbeat.stats().lock().unwrap();
+        assert_eq!(stats.samples, 1);
+        assert_eq!(stats.timeouts, 1);
+    }
+}
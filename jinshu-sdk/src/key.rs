@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 用户的身份公钥，用于协商端到端加密消息的密钥
+///
+/// 公钥由客户端生成并发布，服务端不解析其内容
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityKey {
+    /// 锦书用户 ID
+    pub user_id: Uuid,
+    /// 公钥 ID，同一用户内唯一
+    pub key_id: String,
+    /// 密钥算法标识
+    pub algorithm: String,
+    /// 公钥，通常为 Base64 编码
+    pub public_key: String,
+}
@@ -5,7 +5,31 @@
 //!
 
 mod client;
+mod credential;
 mod error;
+mod heartbeat;
+mod key;
+/// 异步运行时，原生平台上使用 tokio，wasm32 上使用浏览器的事件循环及定时器
+mod rt;
+mod session;
+#[cfg(feature = "local-store")]
+mod store;
+mod transport;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+#[cfg(target_arch = "wasm32")]
+mod websocket;
 
 pub use client::*;
+pub use credential::*;
 pub use error::*;
+pub use heartbeat::RttStats;
+pub use key::*;
+pub use session::ConnectionState;
+#[cfg(feature = "local-store")]
+pub use store::*;
+pub use transport::*;
+#[cfg(target_arch = "wasm32")]
+pub use wasm::*;
+#[cfg(target_arch = "wasm32")]
+pub use websocket::*;
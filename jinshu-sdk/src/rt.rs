@@ -0,0 +1,135 @@
+use futures::future::{AbortHandle, Abortable};
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::Instant;
+
+/// 在后台运行任务，通过返回的句柄取消
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn spawn<F>(future: F) -> AbortHandle
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (handle, registration) = AbortHandle::new_pair();
+    tokio::spawn(Abortable::new(future, registration));
+    handle
+}
+
+/// 在后台运行任务，通过返回的句柄取消
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn spawn<F>(future: F) -> AbortHandle
+where
+    F: Future<Output = ()> + 'static,
+{
+    let (handle, registration) = AbortHandle::new_pair();
+    let future = Abortable::new(future, registration);
+    wasm_bindgen_futures::spawn_local(async move {
+        let _ = future.await;
+    });
+    handle
+}
+
+/// 等待 `duration`
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+/// 等待 `duration`
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await
+}
+
+/// 等待到 `deadline`，已经过去时立即返回
+pub(crate) async fn sleep_until(deadline: Instant) {
+    sleep(deadline.saturating_duration_since(Instant::now())).await
+}
+
+/// 等待超时
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct Elapsed;
+
+/// 在 `duration` 内等待 `future` 完成，超时时返回 [`Elapsed`]
+pub(crate) async fn timeout<F: Future>(
+    duration: Duration,
+    future: F,
+) -> Result<F::Output, Elapsed> {
+    futures::pin_mut!(future);
+    let sleep = sleep(duration);
+    futures::pin_mut!(sleep);
+    match futures::future::select(future, sleep).await {
+        futures::future::Either::Left((output, _)) => Ok(output),
+        futures::future::Either::Right(_) => Err(Elapsed),
+    }
+}
+
+/// 定时器，每隔 `period` 触发一次，错过的触发不补发，从本次触发起重新计时
+#[derive(Debug)]
+pub(crate) struct Interval {
+    period: Duration,
+    /// 下一次触发的时间，超出时钟范围时不再触发
+    next: Option<Instant>,
+}
+
+impl Interval {
+    /// 构造定时器，第一次在 `period` 后触发
+    pub(crate) fn new(period: Duration) -> Self {
+        Self {
+            period,
+            next: Instant::now().checked_add(period),
+        }
+    }
+
+    /// 等待下一次触发
+    pub(crate) async fn tick(&mut self) {
+        match self.next {
+            Some(next) => sleep_until(next).await,
+            None => futures::future::pending().await,
+        }
+        self.next = Instant::now().checked_add(self.period);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{spawn, timeout, Elapsed, Instant, Interval};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn timer() {
+        assert_eq!(timeout(Duration::from_millis(50), async { 1 }).await, Ok(1));
+        assert_eq!(
+            timeout(Duration::from_millis(10), futures::future::pending::<()>()).await,
+            Err(Elapsed)
+        );
+
+        let start = Instant::now();
+        let mut interval = Interval::new(Duration::from_millis(10));
+        interval.tick().await;
+        interval.tick().await;
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        // 超出时钟范围的定时器不会触发
+        let mut never = Interval::new(Duration::MAX);
+        assert_eq!(
+            timeout(Duration::from_millis(10), never.tick()).await,
+            Err(Elapsed)
+        );
+    }
+
+    #[tokio::test]
+    async fn abort() {
+        let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+        let handle = spawn(async move {
+            let _sender = sender;
+            futures::future::pending::<()>().await;
+        });
+        handle.abort();
+        // 任务被取消后发送端被丢弃
+        assert!(receiver.await.is_err());
+    }
+}
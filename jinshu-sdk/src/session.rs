@@ -0,0 +1,832 @@
+use crate::heartbeat::Heartbeat;
+use crate::rt::{self, Instant, Interval};
+use crate::{BoxTransport, Client, Credential, LoginError, RttStats};
+use dashmap::DashMap;
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use jinshu_protocol::{
+    Body, Capabilities, Message, Pdu, Request, Response, TransactionId, TransactionIdGenerator,
+};
+use jinshu_utils::current_millisecond;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::{oneshot, watch};
+use uuid::Uuid;
+
+/// 连接状态，可通过 [`crate::UserAgent::watch_state`] 观察变化
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ConnectionState {
+    /// 正在连接并登录
+    Connecting,
+    /// 已登录
+    Connected,
+    /// 连接断开，等待 `delay` 后进行第 `attempt` 次重连
+    Reconnecting {
+        /// 重连次数，从 1 开始
+        attempt: u32,
+        /// 重连前等待的时间
+        delay: Duration,
+    },
+    /// 连接关闭，不再重连
+    Closed,
+}
+
+/// 已登录的连接
+#[derive(Debug)]
+pub(crate) struct SignedIn {
+    pub(crate) transport: BoxTransport,
+    pub(crate) version: u16,
+    pub(crate) capabilities: Capabilities,
+}
+
+/// 重新登录时使用的令牌
+#[derive(Debug)]
+pub(crate) enum TokenSource {
+    /// 固定的令牌，被拒绝时不再重连
+    Static(String),
+    /// 自动刷新的登录凭证，被拒绝时刷新后重试
+    Credential(Arc<RwLock<Credential>>),
+}
+
+/// 等待响应的发送请求，连接断开时保留，重连后重发
+#[derive(Debug)]
+pub(crate) struct Pending {
+    sent_at: Instant,
+    message: Message,
+    /// 从发件箱重发的消息没有等待响应的调用者
+    responder: Option<oneshot::Sender<Response>>,
+}
+
+impl Pending {
+    /// 调用者是否已放弃等待
+    fn is_abandoned(&self) -> bool {
+        self.responder
+            .as_ref()
+            .is_some_and(|responder| responder.is_closed())
+    }
+}
+
+/// 在本地存储中记录收发的消息，未配置本地存储时不记录
+#[derive(Debug)]
+struct Archive {
+    #[cfg(feature = "local-store")]
+    owner: Uuid,
+    #[cfg(feature = "local-store")]
+    store: Option<crate::LocalStore>,
+}
+
+impl Archive {
+    #[cfg_attr(not(feature = "local-store"), allow(unused_variables))]
+    fn new(owner: Uuid, client: &Client) -> Self {
+        Self {
+            #[cfg(feature = "local-store")]
+            owner,
+            #[cfg(feature = "local-store")]
+            store: client.local_store().cloned(),
+        }
+    }
+
+    /// 记录收到的消息
+    #[cfg_attr(not(feature = "local-store"), allow(unused_variables))]
+    fn received(&self, message: &Message) {
+        #[cfg(feature = "local-store")]
+        if let Some(store) = &self.store {
+            if let Err(e) = store.save_received(self.owner, message) {
+                log::error!("Failed to save received message {}: {}", message.id, e);
+            }
+        }
+    }
+
+    /// 收到发送响应后将消息移出发件箱，入队的消息记录为已发送
+    #[cfg_attr(not(feature = "local-store"), allow(unused_variables))]
+    fn acknowledged(&self, message: &Message, response: &Response) {
+        #[cfg(feature = "local-store")]
+        if let Some(store) = &self.store {
+            let result = match response {
+                Response::Queued { .. } => store.save_sent(self.owner, message),
+                _ => Ok(()),
+            }
+            .and_then(|_| store.remove_outbox(self.owner, message.id));
+            if let Err(e) = result {
+                log::error!("Failed to archive sent message {}: {}", message.id, e);
+            }
+        }
+    }
+
+    /// 发件箱中的消息
+    fn outbox(&self) -> Vec<Message> {
+        #[cfg(feature = "local-store")]
+        if let Some(store) = &self.store {
+            return store.outbox(self.owner).unwrap_or_else(|e| {
+                log::error!("Failed to load outbox: {}", e);
+                Vec::new()
+            });
+        }
+        Vec::new()
+    }
+}
+
+/// 按事务 ID 索引的等待响应的发送请求
+type Waiting = Arc<DashMap<TransactionId, Pending>>;
+
+/// 待发送的消息及接收响应的通道
+pub(crate) type Outgoing = (Message, oneshot::Sender<Response>);
+
+/// 清理超时或调用者已放弃等待的发送请求
+fn collect_expired(waiting: &DashMap<TransactionId, Pending>, timeout: Duration) {
+    waiting.retain(|_, pending| !pending.is_abandoned() && pending.sent_at.elapsed() < timeout);
+}
+
+/// 定期清理等待响应的发送请求，会话结束后停止
+fn spawn_collector(waiting: Weak<DashMap<TransactionId, Pending>>, timeout: Duration) {
+    rt::spawn(async move {
+        let mut interval = Interval::new(timeout);
+        loop {
+            interval.tick().await;
+            match waiting.upgrade() {
+                Some(waiting) => collect_expired(&waiting, timeout),
+                None => break,
+            }
+        }
+    });
+}
+
+/// 用户会话，负责收发报文，连接断开后按配置重连、重新登录并重发未确认的消息
+pub(crate) struct Session {
+    client: Client,
+    user_id: Uuid,
+    token: TokenSource,
+    capabilities: Capabilities,
+    id_gen: TransactionIdGenerator,
+    waiting: Waiting,
+    archive: Archive,
+    /// 被 Comet 限流后可以恢复发送的毫秒时间戳
+    resume_at: AtomicU64,
+    heartbeat: Heartbeat,
+    outgoing: Receiver<Outgoing>,
+    incoming: Sender<Message>,
+    state: watch::Sender<ConnectionState>,
+}
+
+impl Session {
+    /// 构造会话，`outgoing` 为待发送的消息，收到的消息发送到 `incoming`
+    pub(crate) fn new(
+        client: Client,
+        user_id: Uuid,
+        token: TokenSource,
+        capabilities: Capabilities,
+        outgoing: Receiver<Outgoing>,
+        incoming: Sender<Message>,
+    ) -> (Self, watch::Receiver<ConnectionState>) {
+        let waiting = Arc::new(DashMap::new());
+        spawn_collector(Arc::downgrade(&waiting), client.config().request_timeout());
+        let (state, state_receiver) = watch::channel(ConnectionState::Connected);
+        let heartbeat = Heartbeat::new(&client.config().heartbeat);
+        let archive = Archive::new(user_id, &client);
+        (
+            Self {
+                client,
+                user_id,
+                token,
+                capabilities,
+                id_gen: TransactionIdGenerator::new(),
+                waiting,
+                archive,
+                resume_at: AtomicU64::new(0),
+                heartbeat,
+                outgoing,
+                incoming,
+                state,
+            },
+            state_receiver,
+        )
+    }
+
+    /// 心跳的往返时间统计
+    pub(crate) fn rtt(&self) -> Arc<Mutex<RttStats>> {
+        self.heartbeat.stats()
+    }
+
+    /// 运行会话直到用户代理被丢弃或无法重连
+    pub(crate) async fn run(mut self, mut transport: BoxTransport) {
+        loop {
+            self.state.send_replace(ConnectionState::Connected);
+            match self.serve(transport).await {
+                Ok(()) => break,
+                Err(e) => log::warn!("Connection is lost: {}", e),
+            }
+
+            match self.reconnect().await {
+                Some(reconnected) => transport = reconnected,
+                None => break,
+            }
+        }
+
+        self.state.send_replace(ConnectionState::Closed);
+        // 丢弃等待中的请求，调用者会收到连接关闭错误
+        self.waiting.clear();
+    }
+
+    /// 在一个连接上收发报文，用户代理被丢弃时返回 `Ok`，连接断开时返回错误
+    async fn serve(&mut self, transport: BoxTransport) -> anyhow::Result<()> {
+        let (mut writer, reader) = transport.split();
+        self.heartbeat.reset();
+        self.resend(&mut writer).await?;
+
+        tokio::select! {
+            result = write_loop(
+                &mut self.outgoing,
+                &self.waiting,
+                &self.resume_at,
+                &self.heartbeat,
+                &mut self.id_gen,
+                &mut writer,
+            ) => result,
+            result = read_loop(
+                &self.incoming,
+                &self.waiting,
+                &self.archive,
+                &self.resume_at,
+                &self.heartbeat,
+                reader,
+            ) => result,
+        }
+    }
+
+    /// 使用新的事务 ID 按发送顺序重发未确认的消息，然后发送发件箱中的其他消息
+    async fn resend(&mut self, writer: &mut SplitSink<BoxTransport, Pdu>) -> anyhow::Result<()> {
+        let ids: Vec<TransactionId> = self.waiting.iter().map(|entry| *entry.key()).collect();
+        let mut pending: Vec<Pending> = ids
+            .into_iter()
+            .filter_map(|id| self.waiting.remove(&id))
+            .map(|(_, pending)| pending)
+            .filter(|pending| !pending.is_abandoned())
+            .collect();
+        pending.sort_by_key(|pending| pending.sent_at);
+
+        let outbox: Vec<Pending> = self
+            .archive
+            .outbox()
+            .into_iter()
+            .filter(|message| {
+                pending
+                    .iter()
+                    .all(|pending| pending.message.id != message.id)
+            })
+            .map(|message| Pending {
+                sent_at: Instant::now(),
+                message,
+                responder: None,
+            })
+            .collect();
+        if !outbox.is_empty() {
+            log::info!("Flush {} messages in the outbox", outbox.len());
+        }
+        pending.extend(outbox);
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+        log::info!("Resend {} unacknowledged messages", pending.len());
+
+        // 先全部记录再发送，重发过程中断开时仍可在下次重连后重发
+        let mut pdus = Vec::with_capacity(pending.len());
+        for pending in pending {
+            let trans_id = self.id_gen.next_id();
+            pdus.push(
+                Request::Send {
+                    message: pending.message.clone(),
+                }
+                .to_pdu(trans_id),
+            );
+            self.waiting.insert(trans_id, pending);
+        }
+
+        for pdu in pdus {
+            writer.feed(pdu).await?;
+        }
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// 按指数退避重连并重新登录，不再重连时返回 `None`
+    async fn reconnect(&mut self) -> Option<BoxTransport> {
+        let config = self.client.config().reconnect.clone();
+        if !config.enabled {
+            return None;
+        }
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            if config.max_attempts > 0 && attempt > config.max_attempts {
+                log::error!("Failed to reconnect after {} attempts", config.max_attempts);
+                return None;
+            }
+
+            let delay = config.delay(attempt);
+            self.state
+                .send_replace(ConnectionState::Reconnecting { attempt, delay });
+
+            let signed_in = tokio::select! {
+                signed_in = self.try_sign_in(delay) => signed_in,
+                // 用户代理已被丢弃
+                _ = self.incoming.closed() => return None,
+            };
+
+            match signed_in {
+                Ok(signed_in) => {
+                    log::info!("Reconnected after {} attempts", attempt);
+                    if signed_in.capabilities != self.capabilities {
+                        log::warn!(
+                            "Capabilities changed after reconnecting: {:?}",
+                            signed_in.capabilities
+                        );
+                    }
+                    return Some(signed_in.transport);
+                }
+                Err(LoginError::InvalidToken) => match &self.token {
+                    TokenSource::Static(_) => {
+                        log::error!("Token is rejected, stop reconnecting");
+                        return None;
+                    }
+                    TokenSource::Credential(credential) => {
+                        if let Err(e) = refresh(&self.client, credential).await {
+                            log::error!("Failed to refresh token: {}, stop reconnecting", e);
+                            return None;
+                        }
+                    }
+                },
+                Err(e @ LoginError::UnsupportedVersion { .. }) => {
+                    log::error!("{}, stop reconnecting", e);
+                    return None;
+                }
+                Err(e) => log::warn!("Failed to reconnect: {}", e),
+            }
+        }
+    }
+
+    /// 等待 `delay` 后连接并登录
+    async fn try_sign_in(&self, delay: Duration) -> Result<SignedIn, LoginError> {
+        rt::sleep(delay).await;
+        self.state.send_replace(ConnectionState::Connecting);
+
+        let token = match &self.token {
+            TokenSource::Static(token) => token.clone(),
+            TokenSource::Credential(credential) => credential
+                .read()
+                .map(|credential| credential.token.clone())
+                .map_err(|_| LoginError::ConnectionClosed)?,
+        };
+        self.client.connect(self.user_id, token).await
+    }
+}
+
+/// 刷新登录凭证
+async fn refresh(client: &Client, credential: &RwLock<Credential>) -> Result<(), LoginError> {
+    let current = credential
+        .read()
+        .map(|credential| credential.clone())
+        .map_err(|_| LoginError::ConnectionClosed)?;
+    let refreshed = client.refresh(&current).await?;
+    if let Ok(mut credential) = credential.write() {
+        *credential = refreshed;
+    }
+    Ok(())
+}
+
+/// 被限流后最多等待的时间，避免异常的 `retry_after_ms` 使发送长期停止
+const MAX_RETRY_AFTER_MS: u64 = 60_000;
+
+/// 发送消息，并按配置发送心跳，未按时收到 Pong 时返回错误
+///
+/// 被限流时暂存待发送的报文并停止读取发件队列，等待期间心跳照常进行
+async fn write_loop(
+    outgoing: &mut Receiver<Outgoing>,
+    waiting: &DashMap<TransactionId, Pending>,
+    resume_at: &AtomicU64,
+    heartbeat: &Heartbeat,
+    id_gen: &mut TransactionIdGenerator,
+    writer: &mut SplitSink<BoxTransport, Pdu>,
+) -> anyhow::Result<()> {
+    let interval = heartbeat.interval();
+    let mut ping = Interval::new(interval.unwrap_or(Duration::MAX));
+    // 因限流暂缓发送的报文，已记录在 `waiting` 中
+    let mut held: Option<Pdu> = None;
+
+    loop {
+        let wait = resume_at
+            .load(Ordering::Acquire)
+            .saturating_sub(current_millisecond())
+            .min(MAX_RETRY_AFTER_MS);
+        if wait == 0 {
+            if let Some(pdu) = held.take() {
+                writer.send(pdu).await?;
+            }
+        }
+
+        let deadline = heartbeat.deadline();
+        tokio::select! {
+            outgoing = outgoing.recv(), if held.is_none() => {
+                let (message, responder) = match outgoing {
+                    Some(outgoing) => outgoing,
+                    None => return Ok(()),
+                };
+                if responder.is_closed() {
+                    continue;
+                }
+
+                // 先记录再发送，发送前连接断开时重连后重发
+                let trans_id = id_gen.next_id();
+                let pdu = Request::Send {
+                    message: message.clone(),
+                }
+                .to_pdu(trans_id);
+                waiting.insert(
+                    trans_id,
+                    Pending {
+                        sent_at: Instant::now(),
+                        message,
+                        responder: Some(responder),
+                    },
+                );
+                if wait > 0 {
+                    log::debug!("Rate limited, wait {}ms", wait);
+                }
+                held = Some(pdu);
+            }
+            _ = rt::sleep(Duration::from_millis(wait)), if held.is_some() => {}
+            _ = ping.tick(), if interval.is_some() => {
+                if !heartbeat.is_outstanding() {
+                    let trans_id = id_gen.next_id();
+                    heartbeat.ping(trans_id);
+                    writer.send(Request::Ping.to_pdu(trans_id)).await?;
+                }
+            }
+            _ = rt::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                // 等待期间可能已经收到 Pong
+                if heartbeat.deadline().is_some_and(|deadline| deadline <= Instant::now()) {
+                    heartbeat.timeout();
+                    anyhow::bail!("No pong received in time");
+                }
+            }
+        }
+    }
+}
+
+async fn read_loop(
+    incoming: &Sender<Message>,
+    waiting: &DashMap<TransactionId, Pending>,
+    archive: &Archive,
+    resume_at: &AtomicU64,
+    heartbeat: &Heartbeat,
+    mut reader: SplitStream<BoxTransport>,
+) -> anyhow::Result<()> {
+    while let Some(qr) = reader.next().await {
+        let pdu = qr?;
+        match pdu.body {
+            Body::Resp(Response::Pong) => match heartbeat.pong(pdu.id) {
+                Some(rtt) => log::debug!("Pong received. ({}ms)", rtt.as_millis()),
+                None => log::warn!("Pong of unknown transaction {:?}", pdu.id),
+            },
+            Body::Resp(response) => {
+                if let Response::RateLimited { retry_after_ms, .. } = &response {
+                    resume_at.fetch_max(
+                        current_millisecond()
+                            .saturating_add((*retry_after_ms).min(MAX_RETRY_AFTER_MS)),
+                        Ordering::AcqRel,
+                    );
+                }
+
+                match waiting.remove(&pdu.id) {
+                    Some((_, pending)) => {
+                        log::debug!(
+                            "Response of {:?}: {:?}. ({}ms)",
+                            pdu.id,
+                            response,
+                            pending.sent_at.elapsed().as_millis()
+                        );
+                        archive.acknowledged(&pending.message, &response);
+                        // 调用者已放弃等待时忽略响应
+                        if let Some(responder) = pending.responder {
+                            let _ = responder.send(response);
+                        }
+                    }
+                    // 已超时被清理的请求
+                    None => log::warn!(
+                        "Response of unknown transaction {:?}: {:?}",
+                        pdu.id,
+                        response
+                    ),
+                }
+            }
+            Body::Req(request) => match request {
+                Request::Push { message } => {
+                    log::info!("Received a message: {:?}", message);
+                    archive.received(&message);
+                    if incoming.send(message).await.is_err() {
+                        // 用户代理已被丢弃
+                        return Ok(());
+                    }
+                }
+                req => log::error!("Invalid request: {:?}", req),
+            },
+        }
+    }
+
+    Err(crate::Error::ConnectionClosed.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{collect_expired, Pending};
+    use crate::{Client, ClientConfig, ConnectionState, HeartbeatConfig, ReconnectConfig};
+    use dashmap::DashMap;
+    use futures::{SinkExt, StreamExt};
+    use jinshu_protocol::{
+        Body, Capabilities, Content, Message, PduCodec, Request, Response, TransactionId,
+        TransactionIdGenerator, PROTOCOL_VERSION,
+    };
+    use std::time::{Duration, Instant};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::oneshot;
+    use tokio_util::codec::Framed;
+    use uuid::Uuid;
+
+    #[test]
+    fn collect() {
+        let mut id_gen = TransactionIdGenerator::default();
+        let waiting = DashMap::new();
+        let timeout = Duration::from_secs(10);
+        let pending = |sent_at, responder| Pending {
+            sent_at,
+            message: Message::new(Uuid::new_v4(), Uuid::new_v4(), Content::string("")),
+            responder: Some(responder),
+        };
+
+        let (responder, _alive) = oneshot::channel();
+        let alive = id_gen.next_id();
+        waiting.insert(alive, pending(Instant::now(), responder));
+
+        let (responder, _expired) = oneshot::channel();
+        waiting.insert(
+            id_gen.next_id(),
+            pending(Instant::now() - timeout, responder),
+        );
+
+        let (responder, abandoned) = oneshot::channel();
+        drop(abandoned);
+        waiting.insert(id_gen.next_id(), pending(Instant::now(), responder));
+
+        collect_expired(&waiting, timeout);
+        assert_eq!(waiting.len(), 1);
+        assert!(waiting.contains_key(&alive));
+    }
+
+    /// 模拟 Comet 接受一个连接并登录
+    async fn accept(listener: &TcpListener) -> Framed<TcpStream, PduCodec> {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut transport = Framed::new(socket, PduCodec::default());
+        let pdu = transport.next().await.unwrap().unwrap();
+        assert!(matches!(pdu.body, Body::Req(Request::SignIn { .. })));
+        transport
+            .send(
+                Response::SignedIn {
+                    extension: None,
+                    compression: None,
+                    version: PROTOCOL_VERSION,
+                    capabilities: Capabilities::all(),
+                    codecs: vec![],
+                    compressions: vec![],
+                }
+                .to_pdu(pdu.id),
+            )
+            .await
+            .unwrap();
+        transport
+    }
+
+    #[tokio::test]
+    async fn reconnect_and_resend() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = Client::new(ClientConfig {
+            comet_host: "127.0.0.1".into(),
+            comet_port: listener.local_addr().unwrap().port(),
+            reconnect: ReconnectConfig {
+                initial_delay_ms: 10,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+        let user_id = Uuid::new_v4();
+        let (user_agent, transport) =
+            tokio::join!(client.sign_in(user_id, "token"), accept(&listener));
+        let user_agent = user_agent.unwrap();
+        assert_eq!(user_agent.state(), ConnectionState::Connected);
+
+        let message = Message::new(user_id, Uuid::new_v4(), Content::string("hello"));
+        let id = message.id;
+        let comet = async move {
+            // 收到消息后不响应直接断开
+            let mut transport = transport;
+            let pdu = transport.next().await.unwrap().unwrap();
+            assert!(matches!(pdu.body, Body::Req(Request::Send { .. })));
+            drop(transport);
+
+            let mut transport = accept(&listener).await;
+            let pdu = transport.next().await.unwrap().unwrap();
+            match pdu.body {
+                Body::Req(Request::Send { message }) => assert_eq!(message.id, id),
+                body => panic!("unexpected body: {:?}", body),
+            }
+            transport
+                .send(Response::Queued { id }.to_pdu(pdu.id))
+                .await
+                .unwrap();
+            transport
+        };
+
+        let (response, _transport) = tokio::join!(user_agent.send(message), comet);
+        assert!(matches!(response, Ok(Response::Queued { id: queued }) if queued == id));
+        assert_eq!(user_agent.state(), ConnectionState::Connected);
+    }
+
+    #[tokio::test]
+    async fn heartbeat() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = Client::new(ClientConfig {
+            comet_host: "127.0.0.1".into(),
+            comet_port: listener.local_addr().unwrap().port(),
+            reconnect: ReconnectConfig {
+                initial_delay_ms: 10,
+                ..Default::default()
+            },
+            heartbeat: HeartbeatConfig {
+                enabled: true,
+                interval_ms: 20,
+                timeout_ms: 50,
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+        let (user_agent, transport) =
+            tokio::join!(client.sign_in(Uuid::new_v4(), "token"), accept(&listener));
+        let user_agent = user_agent.unwrap();
+
+        // 不响应 Ping，超时后重连
+        let mut transport = transport;
+        let pdu = transport.next().await.unwrap().unwrap();
+        assert!(matches!(pdu.body, Body::Req(Request::Ping)));
+        let mut transport = accept(&listener).await;
+        assert_eq!(user_agent.rtt().timeouts, 1);
+
+        let pdu = transport.next().await.unwrap().unwrap();
+        assert!(matches!(pdu.body, Body::Req(Request::Ping)));
+        transport.send(Response::Pong.to_pdu(pdu.id)).await.unwrap();
+
+        // 收到下一个 Ping 时上一个 Pong 已经处理
+        let pdu = transport.next().await.unwrap().unwrap();
+        assert!(matches!(pdu.body, Body::Req(Request::Ping)));
+        let rtt = user_agent.rtt();
+        assert_eq!(rtt.samples, 1);
+        assert!(rtt.last.is_some());
+        assert_eq!(user_agent.state(), ConnectionState::Connected);
+    }
+
+    #[tokio::test]
+    async fn rate_limited() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = Client::new(ClientConfig {
+            comet_host: "127.0.0.1".into(),
+            comet_port: listener.local_addr().unwrap().port(),
+            heartbeat: HeartbeatConfig {
+                enabled: true,
+                interval_ms: 20,
+                timeout_ms: 100,
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+        let user_id = Uuid::new_v4();
+        let (user_agent, transport) =
+            tokio::join!(client.sign_in(user_id, "token"), accept(&listener));
+        let user_agent = user_agent.unwrap();
+        let mut transport = transport;
+
+        // 响应 Ping，直到收到消息
+        async fn next_send(
+            transport: &mut Framed<TcpStream, PduCodec>,
+        ) -> (usize, Uuid, TransactionId) {
+            let mut pings = 0;
+            loop {
+                let pdu = transport.next().await.unwrap().unwrap();
+                match pdu.body {
+                    Body::Req(Request::Ping) => {
+                        pings += 1;
+                        transport.send(Response::Pong.to_pdu(pdu.id)).await.unwrap();
+                    }
+                    Body::Req(Request::Send { message }) => return (pings, message.id, pdu.id),
+                    body => panic!("unexpected body: {:?}", body),
+                }
+            }
+        }
+
+        let limited = Message::new(user_id, Uuid::new_v4(), Content::string("limited"));
+        let comet = async {
+            let (_, id, trans_id) = next_send(&mut transport).await;
+            transport
+                .send(
+                    Response::RateLimited {
+                        id,
+                        retry_after_ms: 300,
+                    }
+                    .to_pdu(trans_id),
+                )
+                .await
+                .unwrap();
+        };
+        let (response, ()) = tokio::join!(user_agent.send(limited), comet);
+        assert!(matches!(response, Ok(Response::RateLimited { .. })));
+
+        // 等待限流结束期间心跳照常进行
+        let message = Message::new(user_id, Uuid::new_v4(), Content::string("hello"));
+        let id = message.id;
+        let started = Instant::now();
+        let comet = async {
+            let (pings, sent, trans_id) = next_send(&mut transport).await;
+            assert_eq!(sent, id);
+            assert!(pings > 0);
+            assert!(started.elapsed() >= Duration::from_millis(250));
+            transport
+                .send(Response::Queued { id }.to_pdu(trans_id))
+                .await
+                .unwrap();
+        };
+        let (response, ()) = tokio::join!(user_agent.send(message), comet);
+        assert!(matches!(response, Ok(Response::Queued { id: queued }) if queued == id));
+        assert_eq!(user_agent.rtt().timeouts, 0);
+        assert_eq!(user_agent.state(), ConnectionState::Connected);
+    }
+
+    #[cfg(feature = "local-store")]
+    #[tokio::test]
+    async fn outbox() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let store = crate::LocalStore::memory().unwrap();
+        let client = Client::new(ClientConfig {
+            comet_host: "127.0.0.1".into(),
+            comet_port: listener.local_addr().unwrap().port(),
+            ..Default::default()
+        })
+        .unwrap()
+        .with_local_store(store.clone());
+
+        // 上次离线时未发送的消息
+        let (user_id, peer) = (Uuid::new_v4(), Uuid::new_v4());
+        let offline = Message::new(user_id, peer, Content::string("offline"));
+        store.push_outbox(user_id, &offline).unwrap();
+
+        let (user_agent, transport) =
+            tokio::join!(client.sign_in(user_id, "token"), accept(&listener));
+        let mut user_agent = user_agent.unwrap();
+        let mut transport = transport;
+
+        let pdu = transport.next().await.unwrap().unwrap();
+        match pdu.body {
+            Body::Req(Request::Send { message }) => assert_eq!(message.id, offline.id),
+            body => panic!("unexpected body: {:?}", body),
+        }
+        transport
+            .send(Response::Queued { id: offline.id }.to_pdu(pdu.id))
+            .await
+            .unwrap();
+
+        let mut id_gen = TransactionIdGenerator::default();
+        let pushed = Message::new(peer, user_id, Content::string("online"));
+        transport
+            .send(
+                Request::Push {
+                    message: pushed.clone(),
+                }
+                .to_pdu(id_gen.next_id()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(user_agent.receive().await.unwrap().id, pushed.id);
+
+        // 收到推送时发送响应已经处理
+        assert!(store.outbox(user_id).unwrap().is_empty());
+        let history = store.messages(user_id, peer, None, 10).unwrap();
+        assert_eq!(
+            history.iter().map(|m| m.id).collect::<Vec<_>>(),
+            [offline.id, pushed.id]
+        );
+        assert_eq!(store.conversations(user_id).unwrap()[0].unread, 1);
+    }
+}
@@ -0,0 +1,340 @@
+use jinshu_protocol::{Content, Message};
+use rusqlite::{params, Connection, Row};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+/// 建表语句
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS message (
+    owner TEXT NOT NULL,
+    id TEXT NOT NULL,
+    peer TEXT NOT NULL,
+    "from" TEXT NOT NULL,
+    "to" TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    content BLOB NOT NULL,
+    PRIMARY KEY (owner, id)
+);
+CREATE INDEX IF NOT EXISTS message_peer ON message (owner, peer, timestamp);
+CREATE TABLE IF NOT EXISTS conversation (
+    owner TEXT NOT NULL,
+    peer TEXT NOT NULL,
+    last_message_id TEXT NOT NULL,
+    last_timestamp INTEGER NOT NULL,
+    unread INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (owner, peer)
+);
+CREATE TABLE IF NOT EXISTS outbox (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    owner TEXT NOT NULL,
+    id TEXT NOT NULL,
+    "from" TEXT NOT NULL,
+    "to" TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    content BLOB NOT NULL,
+    UNIQUE (owner, id)
+);
+"#;
+
+/// 会话，即与一个对端用户之间的消息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conversation {
+    /// 对端用户 ID
+    pub peer: Uuid,
+    /// 最后一条消息的 ID
+    pub last_message_id: Uuid,
+    /// 最后一条消息的时间戳
+    pub last_timestamp: u64,
+    /// 未读消息数
+    pub unread: u64,
+}
+
+/// 基于 SQLite 的本地存储，保存收发的消息、会话状态及待发送的消息（发件箱）
+///
+/// 多个用户可以共用同一个存储，数据按登录用户隔离
+///
+#[derive(Debug, Clone)]
+pub struct LocalStore {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl LocalStore {
+    /// 打开 `path` 处的数据库，不存在时创建
+    pub fn open(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// 打开内存数据库，进程退出后数据丢失
+    pub fn memory() -> crate::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> crate::Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    fn lock(&self) -> crate::Result<MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|_| crate::Error::Other("Local store is poisoned".into()))
+    }
+
+    /// 保存用户 `owner` 收到的消息，未读消息数加一，重复的消息忽略
+    pub fn save_received(&self, owner: Uuid, message: &Message) -> crate::Result<()> {
+        self.save(owner, message, true)
+    }
+
+    /// 保存用户 `owner` 已发送（Comet 已入队）的消息
+    pub fn save_sent(&self, owner: Uuid, message: &Message) -> crate::Result<()> {
+        self.save(owner, message, false)
+    }
+
+    fn save(&self, owner: Uuid, message: &Message, unread: bool) -> crate::Result<()> {
+        let peer = peer_of(owner, message);
+        let content = Vec::try_from(&message.content)?;
+        let mut connection = self.lock()?;
+        let tx = connection.transaction()?;
+        let inserted = tx.execute(
+            r#"INSERT OR IGNORE INTO message (owner, id, peer, "from", "to", timestamp, content)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+            params![
+                owner.to_string(),
+                message.id.to_string(),
+                peer.to_string(),
+                message.from.to_string(),
+                message.to.to_string(),
+                message.timestamp as i64,
+                content
+            ],
+        )?;
+        if inserted > 0 {
+            tx.execute(
+                "INSERT INTO conversation (owner, peer, last_message_id, last_timestamp, unread)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT (owner, peer) DO UPDATE SET
+                    last_message_id = CASE WHEN excluded.last_timestamp >= last_timestamp
+                        THEN excluded.last_message_id ELSE last_message_id END,
+                    last_timestamp = MAX(last_timestamp, excluded.last_timestamp),
+                    unread = unread + excluded.unread",
+                params![
+                    owner.to_string(),
+                    peer.to_string(),
+                    message.id.to_string(),
+                    message.timestamp as i64,
+                    unread as i64
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 用户 `owner` 与 `peer` 之间时间戳早于 `before` 的最近 `limit` 条消息，按时间升序排列
+    pub fn messages(
+        &self,
+        owner: Uuid,
+        peer: Uuid,
+        before: Option<u64>,
+        limit: u32,
+    ) -> crate::Result<Vec<Message>> {
+        let connection = self.lock()?;
+        let mut statement = connection.prepare(
+            r#"SELECT id, "from", "to", timestamp, content FROM message
+            WHERE owner = ?1 AND peer = ?2 AND timestamp < ?3
+            ORDER BY timestamp DESC, rowid DESC LIMIT ?4"#,
+        )?;
+        let mut messages = statement
+            .query_map(
+                params![
+                    owner.to_string(),
+                    peer.to_string(),
+                    before.map_or(i64::MAX, |before| before as i64),
+                    limit
+                ],
+                message_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        messages.reverse();
+        Ok(messages)
+    }
+
+    /// 用户 `owner` 的所有会话，最近有消息的排在前面
+    pub fn conversations(&self, owner: Uuid) -> crate::Result<Vec<Conversation>> {
+        let connection = self.lock()?;
+        let mut statement = connection.prepare(
+            "SELECT peer, last_message_id, last_timestamp, unread FROM conversation
+            WHERE owner = ?1 ORDER BY last_timestamp DESC",
+        )?;
+        let conversations = statement
+            .query_map(params![owner.to_string()], |row| {
+                Ok(Conversation {
+                    peer: uuid_column(row, 0)?,
+                    last_message_id: uuid_column(row, 1)?,
+                    last_timestamp: row.get::<_, i64>(2)? as u64,
+                    unread: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(conversations)
+    }
+
+    /// 将用户 `owner` 与 `peer` 的会话标记为已读
+    pub fn mark_read(&self, owner: Uuid, peer: Uuid) -> crate::Result<()> {
+        self.lock()?.execute(
+            "UPDATE conversation SET unread = 0 WHERE owner = ?1 AND peer = ?2",
+            params![owner.to_string(), peer.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// 将用户 `owner` 待发送的消息放入发件箱，已存在时忽略
+    pub fn push_outbox(&self, owner: Uuid, message: &Message) -> crate::Result<()> {
+        let content = Vec::try_from(&message.content)?;
+        self.lock()?.execute(
+            r#"INSERT OR IGNORE INTO outbox (owner, id, "from", "to", timestamp, content)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+            params![
+                owner.to_string(),
+                message.id.to_string(),
+                message.from.to_string(),
+                message.to.to_string(),
+                message.timestamp as i64,
+                content
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 用户 `owner` 发件箱中的消息，按放入的顺序排列
+    pub fn outbox(&self, owner: Uuid) -> crate::Result<Vec<Message>> {
+        let connection = self.lock()?;
+        let mut statement = connection.prepare(
+            r#"SELECT id, "from", "to", timestamp, content FROM outbox
+            WHERE owner = ?1 ORDER BY seq"#,
+        )?;
+        let messages = statement
+            .query_map(params![owner.to_string()], message_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(messages)
+    }
+
+    /// 从用户 `owner` 的发件箱中删除消息
+    pub fn remove_outbox(&self, owner: Uuid, id: Uuid) -> crate::Result<()> {
+        self.lock()?.execute(
+            "DELETE FROM outbox WHERE owner = ?1 AND id = ?2",
+            params![owner.to_string(), id.to_string()],
+        )?;
+        Ok(())
+    }
+}
+
+/// 消息所属会话的对端用户
+fn peer_of(owner: Uuid, message: &Message) -> Uuid {
+    if message.from == owner {
+        message.to
+    } else {
+        message.from
+    }
+}
+
+fn uuid_column(row: &Row, index: usize) -> rusqlite::Result<Uuid> {
+    row.get::<_, String>(index)?.parse().map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn message_from_row(row: &Row) -> rusqlite::Result<Message> {
+    let content: Vec<u8> = row.get(4)?;
+    Ok(Message {
+        id: uuid_column(row, 0)?,
+        from: uuid_column(row, 1)?,
+        to: uuid_column(row, 2)?,
+        timestamp: row.get::<_, i64>(3)? as u64,
+        content: Content::try_from(content.as_slice()).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Blob, Box::new(e))
+        })?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::LocalStore;
+    use jinshu_protocol::{Content, Message};
+    use rusqlite::{params, OptionalExtension};
+    use uuid::Uuid;
+
+    /// 消息是否在用户 `owner` 的发件箱中
+    fn in_outbox(store: &LocalStore, owner: Uuid, id: Uuid) -> bool {
+        store
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT 1 FROM outbox WHERE owner = ?1 AND id = ?2",
+                params![owner.to_string(), id.to_string()],
+                |_| Ok(()),
+            )
+            .optional()
+            .unwrap()
+            .is_some()
+    }
+
+    #[test]
+    fn messages() {
+        let store = LocalStore::memory().unwrap();
+        let (me, peer) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let mut received = Message::new(peer, me, Content::string("hello"));
+        received.timestamp = 1;
+        let mut sent = Message::new(me, peer, Content::string("hi"));
+        sent.timestamp = 2;
+        store.save_received(me, &received).unwrap();
+        store.save_received(me, &received).unwrap();
+        store.save_sent(me, &sent).unwrap();
+
+        let messages = store.messages(me, peer, None, 10).unwrap();
+        assert_eq!(
+            messages.iter().map(|m| m.id).collect::<Vec<_>>(),
+            [received.id, sent.id]
+        );
+        assert!(
+            matches!(&messages[0].content, Content::Data { bytes, .. } if bytes.as_ref() == b"hello")
+        );
+        assert_eq!(store.messages(me, peer, Some(2), 10).unwrap().len(), 1);
+        assert!(store.messages(peer, me, None, 10).unwrap().is_empty());
+
+        let conversations = store.conversations(me).unwrap();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].peer, peer);
+        assert_eq!(conversations[0].last_message_id, sent.id);
+        assert_eq!(conversations[0].unread, 1);
+
+        store.mark_read(me, peer).unwrap();
+        assert_eq!(store.conversations(me).unwrap()[0].unread, 0);
+    }
+
+    #[test]
+    fn outbox() {
+        let store = LocalStore::memory().unwrap();
+        let me = Uuid::new_v4();
+        let first = Message::new(me, Uuid::new_v4(), Content::string("1"));
+        let second = Message::new(me, Uuid::new_v4(), Content::string("2"));
+        store.push_outbox(me, &first).unwrap();
+        store.push_outbox(me, &second).unwrap();
+        store.push_outbox(me, &first).unwrap();
+        assert!(store.outbox(Uuid::new_v4()).unwrap().is_empty());
+
+        let outbox = store.outbox(me).unwrap();
+        assert_eq!(
+            outbox.iter().map(|m| m.id).collect::<Vec<_>>(),
+            [first.id, second.id]
+        );
+
+        store.remove_outbox(me, first.id).unwrap();
+        assert!(!in_outbox(&store, me, first.id));
+        assert!(in_outbox(&store, me, second.id));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::ClientConfig;
+use futures::future::BoxFuture;
+use futures::{Sink, Stream};
+use jinshu_protocol::{Compression, Pdu, PduCodec};
+use std::fmt::Debug;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::Framed;
+
+/// 与 Comet 之间的报文传输，连接由 [`Connector`] 建立
+pub trait Transport:
+    Stream<Item = Result<Pdu, jinshu_protocol::Error>>
+    + Sink<Pdu, Error = jinshu_protocol::Error>
+    + Debug
+    + Send
+    + Unpin
+{
+    /// 设置登录时协商的压缩算法，之后发送的报文使用该算法压缩
+    fn set_compression(&mut self, compression: Option<Compression>);
+
+    /// 设置是否将数据消息的内容编码为二进制数据，双方都支持 [`jinshu_protocol::Capability::BinaryData`] 时启用
+    fn set_binary_data(&mut self, binary_data: bool);
+}
+
+/// 装箱的报文传输
+pub type BoxTransport = Box<dyn Transport>;
+
+impl<T> Transport for Framed<T, PduCodec>
+where
+    T: AsyncRead + AsyncWrite + Debug + Send + Unpin,
+{
+    fn set_compression(&mut self, compression: Option<Compression>) {
+        self.codec_mut().set_compression(compression);
+    }
+
+    fn set_binary_data(&mut self, binary_data: bool) {
+        self.codec_mut().set_binary_data(binary_data);
+    }
+}
+
+/// 建立与 Comet 之间的报文传输，默认使用 `TcpConnector`，wasm32 上默认使用 `WebSocketConnector`，
+/// 可通过 [`crate::Client::with_connector`] 替换为其他传输方式
+pub trait Connector: Debug + Send + Sync {
+    /// 按配置连接 Comet
+    fn connect<'a>(
+        &'a self,
+        config: &'a ClientConfig,
+    ) -> BoxFuture<'a, std::io::Result<BoxTransport>>;
+}
+
+/// 通过 TCP 连接 Comet
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TcpConnector;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Connector for TcpConnector {
+    fn connect<'a>(
+        &'a self,
+        config: &'a ClientConfig,
+    ) -> BoxFuture<'a, std::io::Result<BoxTransport>> {
+        Box::pin(async move {
+            let socket = tokio::net::TcpStream::connect(config.comet_address()).await?;
+            let mut codec = PduCodec::default();
+            codec.set_compress_threshold(config.compress_threshold);
+            Ok(Box::new(Framed::new(socket, codec)) as BoxTransport)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BoxTransport, Connector};
+    use crate::{Client, ClientConfig, ConnectionState};
+    use futures::future::BoxFuture;
+    use futures::{SinkExt, StreamExt};
+    use jinshu_protocol::{
+        Body, Capabilities, Content, Message, PduCodec, Request, Response, PROTOCOL_VERSION,
+    };
+    use std::sync::Mutex;
+    use tokio::io::DuplexStream;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+    use tokio_util::codec::Framed;
+    use uuid::Uuid;
+
+    /// 在内存中建立连接，另一端交给模拟的 Comet
+    #[derive(Debug)]
+    struct MemoryConnector(Mutex<UnboundedSender<DuplexStream>>);
+
+    impl MemoryConnector {
+        fn new() -> (Self, UnboundedReceiver<DuplexStream>) {
+            let (sender, receiver) = unbounded_channel();
+            (Self(Mutex::new(sender)), receiver)
+        }
+    }
+
+    impl Connector for MemoryConnector {
+        fn connect<'a>(
+            &'a self,
+            _config: &'a ClientConfig,
+        ) -> BoxFuture<'a, std::io::Result<BoxTransport>> {
+            let (client, server) = tokio::io::duplex(4096);
+            let accepted = self.0.lock().unwrap().send(server);
+            Box::pin(async move {
+                accepted.map_err(|_| std::io::ErrorKind::ConnectionRefused)?;
+                Ok(Box::new(Framed::new(client, PduCodec::default())) as BoxTransport)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_connector() {
+        let (connector, mut accepted) = MemoryConnector::new();
+        let client = Client::new(ClientConfig::default())
+            .unwrap()
+            .with_connector(connector);
+
+        let comet = async move {
+            let mut transport = Framed::new(accepted.recv().await.unwrap(), PduCodec::default());
+            let pdu = transport.next().await.unwrap().unwrap();
+            assert!(matches!(pdu.body, Body::Req(Request::SignIn { .. })));
+            transport
+                .send(
+                    Response::SignedIn {
+                        extension: None,
+                        compression: None,
+                        version: PROTOCOL_VERSION,
+                        capabilities: Capabilities::all(),
+                        codecs: vec![],
+                        compressions: vec![],
+                    }
+                    .to_pdu(pdu.id),
+                )
+                .await
+                .unwrap();
+            transport
+        };
+
+        let user_id = Uuid::new_v4();
+        let (user_agent, mut transport) = tokio::join!(client.sign_in(user_id, "token"), comet);
+        let user_agent = user_agent.unwrap();
+        assert_eq!(user_agent.state(), ConnectionState::Connected);
+
+        let message = Message::new(user_id, Uuid::new_v4(), Content::string("hello"));
+        let id = message.id;
+        let comet = async move {
+            let pdu = transport.next().await.unwrap().unwrap();
+            assert!(matches!(pdu.body, Body::Req(Request::Send { .. })));
+            transport
+                .send(Response::Queued { id }.to_pdu(pdu.id))
+                .await
+                .unwrap();
+            transport
+        };
+
+        let (response, _transport) = tokio::join!(user_agent.send(message), comet);
+        assert!(matches!(response, Ok(Response::Queued { id: queued }) if queued == id));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{Client, ClientConfig, ConnectionState, Credential, MessageSender, UserAgent};
+use crate::{LoginError, WebSocketConnector};
+use futures::future::AbortHandle;
+use jinshu_protocol::{Content, Message};
+use js_sys::{Function, Promise};
+use std::fmt::Display;
+use url::Url;
+use uuid::Uuid;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+fn js_error(e: impl Display) -> JsValue {
+    JsError::new(&e.to_string()).into()
+}
+
+/// JavaScript 中使用的客户端，见 [`Client`]
+///
+/// ```js
+/// const client = new Client(JSON.stringify(config), "wss://comet.example.com/ws");
+/// const userAgent = await client.signIn(userId, token, message => console.log(JSON.parse(message)));
+/// const response = await userAgent.send(peerId, "text/plain", new TextEncoder().encode("hi"));
+/// userAgent.free();
+/// ```
+///
+#[wasm_bindgen(js_name = Client)]
+pub struct JsClient {
+    client: Client,
+}
+
+#[wasm_bindgen(js_class = Client)]
+impl JsClient {
+    /// 使用 JSON 格式的配置（见 [`ClientConfig`]）构造，
+    /// `websocket_url` 为 Comet 的 WebSocket 地址，未指定时连接 `ws://{comet_host}:{comet_port}`
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: &str, websocket_url: Option<String>) -> Result<JsClient, JsValue> {
+        let config: ClientConfig = serde_json::from_str(config)
+            .map_err(|e| js_error(format_args!("Invalid config: {}", e)))?;
+        let connector = match websocket_url {
+            Some(url) => WebSocketConnector::new(
+                Url::parse(&url).map_err(|e| js_error(format_args!("Invalid url: {}", e)))?,
+            ),
+            None => WebSocketConnector::default(),
+        };
+        let client = Client::new(config)
+            .map_err(js_error)?
+            .with_connector(connector);
+        Ok(Self { client })
+    }
+
+    /// 使用锦书用户 ID 及令牌登录，返回 `Promise<UserAgent>`
+    ///
+    /// 收到的消息以 JSON 字符串回调 `on_message`，连接状态变化时回调 `on_state(state, attempt)`，
+    /// `state` 为 `connecting`、`connected`、`reconnecting` 或 `closed`
+    ///
+    #[wasm_bindgen(js_name = signIn)]
+    pub fn sign_in(
+        &self,
+        user_id: &str,
+        token: String,
+        on_message: Function,
+        on_state: Option<Function>,
+    ) -> Result<Promise, JsValue> {
+        let user_id: Uuid = user_id
+            .parse()
+            .map_err(|e| js_error(format_args!("Invalid user id: {}", e)))?;
+        let client = self.client.clone();
+        Ok(future_to_promise(async move {
+            let user_agent = client.sign_in(user_id, token).await.map_err(js_error)?;
+            Ok(JsUserAgent::start(user_id, user_agent, on_message, on_state).into())
+        }))
+    }
+
+    /// 使用 JSON 格式的登录凭证（见 [`Credential`]）登录，返回 `Promise<UserAgent>`，登录后自动刷新令牌
+    ///
+    /// 回调同 [`JsClient::sign_in`]
+    ///
+    #[wasm_bindgen(js_name = signInWithCredential)]
+    pub fn sign_in_with_credential(
+        &self,
+        credential: &str,
+        on_message: Function,
+        on_state: Option<Function>,
+    ) -> Result<Promise, JsValue> {
+        let credential: Credential = serde_json::from_str(credential)
+            .map_err(|e| js_error(format_args!("Invalid credential: {}", e)))?;
+        let client = self.client.clone();
+        Ok(future_to_promise(async move {
+            let user_id = credential.user_id;
+            let user_agent = client
+                .sign_in_with_credential(credential)
+                .await
+                .map_err(|e: LoginError| js_error(e))?;
+            Ok(JsUserAgent::start(user_id, user_agent, on_message, on_state).into())
+        }))
+    }
+}
+
+/// JavaScript 中使用的用户代理，见 [`UserAgent`]，调用 `free()` 后关闭连接
+#[wasm_bindgen(js_name = UserAgent)]
+pub struct JsUserAgent {
+    user_id: Uuid,
+    sender: MessageSender,
+    events: AbortHandle,
+}
+
+#[wasm_bindgen(js_class = UserAgent)]
+impl JsUserAgent {
+    /// 在后台分发用户代理的事件
+    fn start(
+        user_id: Uuid,
+        user_agent: UserAgent,
+        on_message: Function,
+        on_state: Option<Function>,
+    ) -> Self {
+        let sender = user_agent.sender();
+        let events = crate::rt::spawn(dispatch(user_agent, on_message, on_state));
+        Self {
+            user_id,
+            sender,
+            events,
+        }
+    }
+
+    /// 锦书用户 ID
+    #[wasm_bindgen(getter, js_name = userId)]
+    pub fn user_id(&self) -> String {
+        self.user_id.to_string()
+    }
+
+    /// 发送消息给用户 `to`，`mime` 为内容的 MIME 类型，如 `text/plain`，
+    /// 返回 `Promise<string>`，结果为 JSON 格式的 Comet 响应
+    pub fn send(&self, to: &str, mime: &str, data: Vec<u8>) -> Result<Promise, JsValue> {
+        let to: Uuid = to
+            .parse()
+            .map_err(|e| js_error(format_args!("Invalid user id: {}", e)))?;
+        let mime: mime::Mime = mime
+            .parse()
+            .map_err(|e| js_error(format_args!("Invalid mime: {}", e)))?;
+
+        let message = Message::new(self.user_id, to, Content::data(mime, data));
+        let sender = self.sender.clone();
+        Ok(future_to_promise(async move {
+            let response = sender.send(message).await.map_err(js_error)?;
+            let response = serde_json::to_string(&response).map_err(js_error)?;
+            Ok(response.into())
+        }))
+    }
+}
+
+impl Drop for JsUserAgent {
+    fn drop(&mut self) {
+        self.events.abort();
+    }
+}
+
+/// 连接状态的名称及重连次数
+fn state_args(state: ConnectionState) -> (&'static str, u32) {
+    match state {
+        ConnectionState::Connecting => ("connecting", 0),
+        ConnectionState::Connected => ("connected", 0),
+        ConnectionState::Reconnecting { attempt, .. } => ("reconnecting", attempt),
+        ConnectionState::Closed => ("closed", 0),
+    }
+}
+
+/// 回调收到的消息及连接状态的变化，连接关闭后结束
+async fn dispatch(mut user_agent: UserAgent, on_message: Function, on_state: Option<Function>) {
+    let notify = |state: ConnectionState| {
+        if let Some(on_state) = &on_state {
+            let (state, attempt) = state_args(state);
+            if let Err(e) = on_state.call2(&JsValue::NULL, &state.into(), &attempt.into()) {
+                log::error!("State callback failed: {:?}", e);
+            }
+        }
+    };
+
+    let mut state = user_agent.watch_state();
+    loop {
+        tokio::select! {
+            message = user_agent.receive() => match message {
+                Ok(message) => match serde_json::to_string(&message) {
+                    Ok(message) => {
+                        if let Err(e) = on_message.call1(&JsValue::NULL, &message.into()) {
+                            log::error!("Message callback failed: {:?}", e);
+                        }
+                    }
+                    Err(e) => log::error!("Failed to serialize message: {}", e),
+                },
+                Err(_) => break,
+            },
+            changed = state.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let current = *state.borrow_and_update();
+                notify(current);
+                if current == ConnectionState::Closed {
+                    return;
+                }
+            }
+        }
+    }
+    notify(ConnectionState::Closed);
+}
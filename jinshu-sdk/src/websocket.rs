@@ -0,0 +1,229 @@
+use crate::{BoxTransport, ClientConfig, Connector, Transport};
+use bytes::BytesMut;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::channel::oneshot;
+use futures::future::BoxFuture;
+use futures::{Sink, Stream, StreamExt};
+use jinshu_protocol::{Compression, Pdu, PduCodec};
+use std::io::ErrorKind;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio_util::codec::{Decoder, Encoder};
+use url::Url;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{BinaryType, MessageEvent, WebSocket};
+
+/// 通过浏览器的 WebSocket 连接 Comet，每个二进制消息承载一个编码后的报文
+///
+/// 未指定地址时连接 `ws://{comet_host}:{comet_port}`
+///
+#[derive(Debug, Default, Clone)]
+pub struct WebSocketConnector {
+    url: Option<Url>,
+}
+
+impl WebSocketConnector {
+    /// 连接指定的地址，如 `wss://comet.example.com/ws`
+    pub fn new(url: Url) -> Self {
+        Self { url: Some(url) }
+    }
+
+    fn url(&self, config: &ClientConfig) -> String {
+        match &self.url {
+            Some(url) => url.to_string(),
+            None => format!("ws://{}", config.comet_address()),
+        }
+    }
+}
+
+impl Connector for WebSocketConnector {
+    fn connect<'a>(
+        &'a self,
+        config: &'a ClientConfig,
+    ) -> BoxFuture<'a, std::io::Result<BoxTransport>> {
+        let mut codec = PduCodec::default();
+        codec.set_compress_threshold(config.compress_threshold);
+
+        // 浏览器的 WebSocket 只能在当前线程使用，由后台任务持有，通过通道收发数据
+        let (opened, open) = oneshot::channel();
+        let (incoming, receiver) = unbounded();
+        let (sender, outgoing) = unbounded();
+        crate::rt::spawn(run(self.url(config), opened, incoming, outgoing));
+
+        Box::pin(async move {
+            open.await
+                .map_err(|_| std::io::Error::from(ErrorKind::ConnectionAborted))??;
+            Ok(Box::new(WebSocketTransport {
+                codec,
+                receiver,
+                sender,
+            }) as BoxTransport)
+        })
+    }
+}
+
+/// WebSocket 的事件
+enum Event {
+    Open,
+    Message(Vec<u8>),
+    Closed,
+}
+
+fn js_error(value: JsValue) -> std::io::Error {
+    std::io::Error::other(format!("{:?}", value))
+}
+
+/// 持有 WebSocket，转发收发的数据，连接关闭或报文传输被丢弃后结束
+async fn run(
+    url: String,
+    opened: oneshot::Sender<std::io::Result<()>>,
+    incoming: UnboundedSender<std::io::Result<Vec<u8>>>,
+    mut outgoing: UnboundedReceiver<Vec<u8>>,
+) {
+    let socket = match WebSocket::new(&url) {
+        Ok(socket) => socket,
+        Err(e) => {
+            let _ = opened.send(Err(js_error(e)));
+            return;
+        }
+    };
+    socket.set_binary_type(BinaryType::Arraybuffer);
+
+    let (events, mut received) = unbounded();
+    let on_open = {
+        let events = events.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            let _ = events.unbounded_send(Event::Open);
+        })
+    };
+    let on_message = {
+        let events = events.clone();
+        Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            // 只接受二进制消息
+            if let Ok(buffer) = event.data().dyn_into::<js_sys::ArrayBuffer>() {
+                let data = js_sys::Uint8Array::new(&buffer).to_vec();
+                let _ = events.unbounded_send(Event::Message(data));
+            }
+        })
+    };
+    // 出错后浏览器会关闭连接，两者都视为连接关闭
+    let on_close = Closure::<dyn FnMut()>::new(move || {
+        let _ = events.unbounded_send(Event::Closed);
+    });
+    socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+    socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    socket.set_onerror(Some(on_close.as_ref().unchecked_ref()));
+    socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+    let mut opened = Some(opened);
+    loop {
+        tokio::select! {
+            event = received.next() => match event {
+                Some(Event::Open) => {
+                    if let Some(opened) = opened.take() {
+                        let _ = opened.send(Ok(()));
+                    }
+                }
+                Some(Event::Message(data)) => {
+                    if incoming.unbounded_send(Ok(data)).is_err() {
+                        break;
+                    }
+                }
+                Some(Event::Closed) | None => {
+                    match opened.take() {
+                        Some(opened) => {
+                            let _ = opened.send(Err(ErrorKind::ConnectionRefused.into()));
+                        }
+                        None => {
+                            let _ = incoming.unbounded_send(Err(ErrorKind::ConnectionReset.into()));
+                        }
+                    }
+                    break;
+                }
+            },
+            data = outgoing.next() => match data {
+                Some(data) => {
+                    if let Err(e) = socket.send_with_u8_array(&data) {
+                        let _ = incoming.unbounded_send(Err(js_error(e)));
+                        break;
+                    }
+                }
+                // 报文传输已被丢弃
+                None => break,
+            },
+        }
+    }
+
+    socket.set_onopen(None);
+    socket.set_onmessage(None);
+    socket.set_onerror(None);
+    socket.set_onclose(None);
+    let _ = socket.close();
+}
+
+/// 通过通道与持有 WebSocket 的后台任务交换数据的报文传输
+#[derive(Debug)]
+struct WebSocketTransport {
+    codec: PduCodec,
+    receiver: UnboundedReceiver<std::io::Result<Vec<u8>>>,
+    sender: UnboundedSender<Vec<u8>>,
+}
+
+impl Stream for WebSocketTransport {
+    type Item = Result<Pdu, jinshu_protocol::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        Poll::Ready(match futures::ready!(this.receiver.poll_next_unpin(cx)) {
+            Some(Ok(data)) => {
+                let mut buffer = BytesMut::from(data.as_slice());
+                match this.codec.decode(&mut buffer) {
+                    Ok(Some(pdu)) if buffer.is_empty() => Some(Ok(pdu)),
+                    Ok(_) => Some(Err(jinshu_protocol::Error::Other(
+                        "A WebSocket message must contain exactly one pdu".into(),
+                    ))),
+                    Err(e) => Some(Err(e)),
+                }
+            }
+            Some(Err(e)) => Some(Err(e.into())),
+            None => None,
+        })
+    }
+}
+
+impl Sink<Pdu> for WebSocketTransport {
+    type Error = jinshu_protocol::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Pdu) -> Result<(), Self::Error> {
+        let this = &mut *self;
+        let mut buffer = BytesMut::new();
+        this.codec.encode(item, &mut buffer)?;
+        this.sender
+            .unbounded_send(buffer.to_vec())
+            .map_err(|_| std::io::Error::from(ErrorKind::BrokenPipe).into())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.sender.close_channel();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn set_compression(&mut self, compression: Option<Compression>) {
+        self.codec.set_compression(compression);
+    }
+
+    fn set_binary_data(&mut self, binary_data: bool) {
+        self.codec.set_binary_data(binary_data);
+    }
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Storage 的配置
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StorageConfig {
+    /// 消费的 Distributor 分发主题名，实际主题为 `<入队主题>.<topic_name>`，需与 Distributor 的 `storage_name` 一致
+    pub topic_name: String,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            topic_name: "storage".into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::StorageConfig;
+
+    #[test]
+    fn default() {
+        StorageConfig::default();
+    }
+}
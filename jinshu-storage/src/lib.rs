@@ -3,6 +3,8 @@
 //!
 //! 消费队列消息并持久化
 
+mod config;
 mod storage;
 
+pub use config::*;
 pub use storage::*;
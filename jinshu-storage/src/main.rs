@@ -7,13 +7,14 @@ use jinshu_tracing::config::TracingConfig;
 use jinshu_utils::shutdown_signal;
 use sea_orm::Database;
 
-use jinshu_storage::Storage;
+use jinshu_storage::{Storage, StorageConfig};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
 struct Conf {
     tracing: TracingConfig,
     database: DatabaseConfig,
+    storage: StorageConfig,
 
     /// 使用的消息队列中间件
     #[serde(flatten)]
@@ -29,9 +30,15 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!(?conf);
 
     let Conf {
-        database, queue, ..
+        database,
+        storage,
+        queue,
+        ..
     } = conf;
 
+    let queue = queue.distributed(&storage.topic_name);
+    tracing::info!(topic = %queue.topic(), "Consume distributed messages");
+
     tracing::info!(?database);
     let database = Database::connect(database).await?;
 
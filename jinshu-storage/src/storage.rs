@@ -22,6 +22,7 @@ impl<B> Storage<B> {
 #[async_trait]
 impl QueuedMessageHandler for Storage<DatabaseConnection> {
     async fn handle(&self, _topic: &str, message: &QueuedMessage) -> HandleResult {
+        let app_id = message.inner().app_id.clone();
         let message = match Message::try_from(message.inner()) {
             Ok(message) => message,
             Err(e) => return HandleResult::Failure(e.to_string().into()),
@@ -36,6 +37,7 @@ impl QueuedMessageHandler for Storage<DatabaseConnection> {
         let nsecs = (message.timestamp as i64 - (secs * 1000)) as u64 * 1_000_000;
         let model = jinshu_database::message::ActiveModel {
             id: Set(message.id.as_simple().to_string()),
+            app_id: Set(app_id),
             timestamp: Set(
                 TimeDateTimeWithTimeZone::from_unix_timestamp(secs) + Duration::from_nanos(nsecs)
             ),
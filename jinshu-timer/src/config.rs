@@ -0,0 +1,61 @@
+use jinshu_rpc::config::ServiceConfig;
+use serde::{Deserialize, Serialize};
+
+/// Timer 的配置
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TimerConfig {
+    /// 定时服务配置
+    #[serde(flatten)]
+    pub service: ServiceConfig,
+
+    /// 要消费的 Receiver 服务名
+    pub receiver_name: String,
+
+    /// 时间轮的刻度（毫秒）
+    pub tick_ms: u64,
+
+    /// 时间轮的槽数
+    pub slots: usize,
+
+    /// 从存储中加载临近到期任务的间隔（毫秒）
+    pub load_interval_ms: u64,
+
+    /// 任务被某个实例领取后的租期（毫秒），超过租期未完成的任务会被重新投递
+    pub lease_ms: u64,
+
+    /// 投递失败后首次重试的间隔（毫秒），之后每次翻倍
+    pub retry_interval_ms: u64,
+
+    /// 最多投递次数
+    pub max_attempts: u32,
+}
+
+impl Default for TimerConfig {
+    fn default() -> Self {
+        Self {
+            service: ServiceConfig {
+                service_name: "timer".into(),
+                public_host: "0.0.0.0".into(),
+                listen_ip: [0u8, 0, 0, 0].into(),
+                listen_port: 9700,
+            },
+            receiver_name: "receiver".into(),
+            tick_ms: 100,
+            slots: 600,
+            load_interval_ms: 5000,
+            lease_ms: 30000,
+            retry_interval_ms: 1000,
+            max_attempts: 5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TimerConfig;
+
+    #[test]
+    fn default() {
+        TimerConfig::default();
+    }
+}
@@ -0,0 +1,45 @@
+use std::borrow::Cow;
+
+/// 定时服务错误
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Redis 错误
+    #[error(transparent)]
+    Redis(#[from] jinshu_redis::Error),
+    /// 任务解码错误
+    #[error(transparent)]
+    Decode(#[from] prost::DecodeError),
+    /// 数据库错误
+    #[error(transparent)]
+    Database(#[from] sea_orm::DbErr),
+    /// 消息内容编码错误
+    #[error(transparent)]
+    Content(#[from] jinshu_protocol::InvalidContentFormat),
+    /// 消息入队失败
+    #[error(transparent)]
+    Enqueue(Box<tonic::Status>),
+    /// 其他错误
+    #[error("{0}")]
+    Other(Cow<'static, str>),
+}
+
+impl From<tonic::Status> for Error {
+    fn from(status: tonic::Status) -> Self {
+        Error::Enqueue(Box::new(status))
+    }
+}
+
+impl From<deadpool_redis::PoolError> for Error {
+    fn from(e: deadpool_redis::PoolError) -> Self {
+        Error::Redis(e.into())
+    }
+}
+
+impl From<deadpool_redis::redis::RedisError> for Error {
+    fn from(e: deadpool_redis::redis::RedisError) -> Self {
+        Error::Redis(e.into())
+    }
+}
+
+/// 定时服务结果
+pub type Result<T> = std::result::Result<T, Error>;
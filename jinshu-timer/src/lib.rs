@@ -1 +1,22 @@
+#![deny(missing_docs, unsafe_code)]
+//! # Timer
+//!
+//! 定时服务，支持定时发送、延迟重试及过期（阅后即焚）消息
+//!
+//! 待执行的任务持久化在 Redis 中，临近到期的任务加载到内存中的时间轮，到期后通过 Receiver 入队；
+//! 过期任务到期时从数据库中删除该消息，再通过 Receiver 向接收方投递过期通知
+//!
 
+/// 配置
+pub mod config;
+mod error;
+/// 定时任务调度
+pub mod scheduler;
+/// gRPC 服务实现
+pub mod service;
+/// 定时任务存储
+pub mod store;
+/// 时间轮
+pub mod wheel;
+
+pub use error::*;
@@ -1,3 +1,79 @@
-fn main() {
-    println!("Hello, world!");
+use jinshu_common::Config;
+use jinshu_database::config::DatabaseConfig;
+use jinshu_redis::config::RedisConfig;
+use jinshu_rpc::receiver::receiver_client::ReceiverClient;
+use jinshu_rpc::registry::etcd::{EtcdConfig, EtcdRegistry};
+use jinshu_rpc::registry::Registry;
+use jinshu_rpc::timer::timer_server::TimerServer;
+use jinshu_timer::config::TimerConfig;
+use jinshu_timer::scheduler::Scheduler;
+use jinshu_timer::service::TimerService;
+use jinshu_timer::store::RedisJobStore;
+use jinshu_tracing::config::TracingConfig;
+use jinshu_utils::shutdown_signal;
+use sea_orm::Database;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Conf {
+    tracing: TracingConfig,
+    etcd: EtcdConfig,
+    redis: RedisConfig,
+    database: DatabaseConfig,
+    timer: TimerConfig,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let conf = Conf::from_cli()?;
+
+    let _tracer = conf.tracing.init("timer")?;
+
+    let Conf {
+        etcd,
+        redis,
+        database,
+        timer,
+        ..
+    } = conf;
+
+    tracing::info!(?etcd);
+    let registry = EtcdRegistry::new(&etcd).await?;
+
+    let (receiver_channel, rk) = registry.discover_channel(&timer.receiver_name).await?;
+    let receiver = ReceiverClient::new(receiver_channel);
+
+    tracing::info!(?redis);
+    let redis_config: deadpool_redis::Config = redis.into();
+    let redis = redis_config.builder()?.build()?;
+
+    tracing::info!(?database);
+    let database = Database::connect(database).await?;
+
+    let scheduler = Arc::new(Scheduler::new(
+        &timer,
+        RedisJobStore::from_pool(redis),
+        receiver,
+        database,
+    ));
+
+    let scheduler_handle = tokio::spawn(scheduler.clone().run(shutdown_signal()));
+
+    let (uri, handle) = registry
+        .run_service(
+            timer.service,
+            TimerServer::new(TimerService::new(scheduler)),
+            shutdown_signal(),
+        )
+        .await?;
+
+    tracing::info!(%uri, "Timer service is running.");
+    handle.await?;
+    scheduler_handle.await?;
+
+    rk.close().await??;
+    tracing::info!("Service keeper closed.");
+
+    Ok(())
 }
@@ -0,0 +1,498 @@
+use crate::config::TimerConfig;
+use crate::store::{JobId, JobStore};
+use crate::wheel::TimerWheel;
+use crate::Error;
+use async_trait::async_trait;
+use jinshu_database::message;
+use jinshu_protocol::Content;
+use jinshu_rpc::domain::message::Message;
+use jinshu_rpc::receiver::enqueue_result;
+use jinshu_rpc::receiver::receiver_client::ReceiverClient;
+use jinshu_rpc::timer::{Job, JobKind};
+use jinshu_utils::current_millisecond;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tonic::transport::Channel;
+use uuid::Uuid;
+
+/// 每次从存储中加载的最大任务数
+const LOAD_BATCH: usize = 10000;
+
+/// 到期消息的投递目标
+#[async_trait]
+pub trait MessageSink: Send + Sync + 'static {
+    /// 投递消息
+    async fn deliver(&self, message: Message) -> crate::Result<()>;
+}
+
+#[async_trait]
+impl MessageSink for ReceiverClient<Channel> {
+    async fn deliver(&self, message: Message) -> crate::Result<()> {
+        let result = self.clone().enqueue(message).await?.into_inner();
+        if result.ok {
+            Ok(())
+        } else {
+            Err(match result.result {
+                Some(enqueue_result::Result::Error(e)) => Error::Other(e.into()),
+                _ => Error::Other("Failed to enqueue message".into()),
+            })
+        }
+    }
+}
+
+/// 已存储的消息，过期（阅后即焚）任务到期时从中删除消息
+#[async_trait]
+pub trait MessageStore: Send + Sync + 'static {
+    /// 删除应用 `app_id` 中 ID 为 `id` 的消息，返回消息是否存在
+    async fn delete(&self, app_id: &str, id: Uuid) -> crate::Result<bool>;
+}
+
+#[async_trait]
+impl MessageStore for DatabaseConnection {
+    async fn delete(&self, app_id: &str, id: Uuid) -> crate::Result<bool> {
+        let result = message::Entity::delete_many()
+            .filter(message::Column::Id.eq(id.as_simple().to_string()))
+            .filter(message::Column::AppId.eq(app_id))
+            .exec(self)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+/// 定时任务调度器
+///
+/// 任务先持久化到存储中，投递时间在加载窗口内的任务同时放入时间轮；
+/// 调度器定期从存储中加载临近到期的任务，因此重启或多实例部署时任务不会丢失
+///
+/// 过期任务到期时从消息存储中删除该消息，再向接收方投递过期通知（[`Content::expired`]）
+///
+pub struct Scheduler<S, D, M> {
+    store: S,
+    sink: D,
+    messages: M,
+    wheel: Mutex<TimerWheel<JobId>>,
+    tick_ms: u64,
+    load_interval_ms: u64,
+    lease_ms: u64,
+    retry_interval_ms: u64,
+    max_attempts: u32,
+}
+
+impl<S: JobStore, D: MessageSink, M: MessageStore> Scheduler<S, D, M> {
+    /// 使用配置、任务存储、投递目标及消息存储构造
+    pub fn new(config: &TimerConfig, store: S, sink: D, messages: M) -> Self {
+        Self {
+            store,
+            sink,
+            messages,
+            wheel: Mutex::new(TimerWheel::new(
+                config.tick_ms,
+                config.slots,
+                current_millisecond(),
+            )),
+            tick_ms: config.tick_ms.max(1),
+            load_interval_ms: config.load_interval_ms.max(1),
+            lease_ms: config.lease_ms,
+            retry_interval_ms: config.retry_interval_ms,
+            max_attempts: config.max_attempts.max(1),
+        }
+    }
+
+    /// 加载窗口（毫秒），投递时间在该窗口内的任务会放入时间轮
+    fn horizon_ms(&self) -> u64 {
+        self.load_interval_ms * 2
+    }
+
+    fn wheel(&self) -> std::sync::MutexGuard<'_, TimerWheel<JobId>> {
+        self.wheel.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 投递时间在加载窗口内的任务放入时间轮，其余的等待加载
+    fn arm(&self, id: JobId, deliver_at: u64, now_ms: u64) {
+        if deliver_at <= now_ms + self.horizon_ms() {
+            self.wheel().insert(id, deliver_at);
+        }
+    }
+
+    /// 添加定时任务，任务归属于消息所属的应用，返回任务 ID
+    pub async fn schedule(
+        &self,
+        kind: JobKind,
+        deliver_at: u64,
+        message: Message,
+    ) -> crate::Result<JobId> {
+        let id = JobId::new(message.app_id.clone(), Uuid::new_v4());
+        let job = Job {
+            id: id.id.as_bytes().to_vec(),
+            kind: kind as i32,
+            deliver_at,
+            message: Some(message),
+            attempts: 0,
+        };
+
+        self.store.save(&id, &job).await?;
+        self.arm(id.clone(), deliver_at, current_millisecond());
+
+        tracing::info!(%id, ?kind, deliver_at, "Job scheduled");
+        Ok(id)
+    }
+
+    /// 取消定时任务，返回任务是否存在
+    pub async fn cancel(&self, id: &JobId) -> crate::Result<bool> {
+        self.wheel().remove(id);
+        let removed = self.store.remove(id).await?;
+        tracing::info!(%id, removed, "Job canceled");
+        Ok(removed)
+    }
+
+    /// 从存储中加载临近到期的任务到时间轮，返回加载的任务数
+    pub async fn load(&self, now_ms: u64) -> crate::Result<usize> {
+        let jobs = self
+            .store
+            .due_before(now_ms + self.horizon_ms(), LOAD_BATCH)
+            .await?;
+
+        let count = jobs.len();
+        let mut wheel = self.wheel();
+        for (id, deliver_at) in jobs {
+            wheel.insert(id, deliver_at);
+        }
+
+        Ok(count)
+    }
+
+    /// 推进时间轮，返回到期的任务
+    pub fn advance(&self, now_ms: u64) -> Vec<JobId> {
+        self.wheel().advance(now_ms)
+    }
+
+    /// 删除过期的消息并通知接收方，通知使用任务 ID 作为消息 ID，重试时不会重复
+    async fn expire(&self, id: &JobId, message: Message, now_ms: u64) -> crate::Result<()> {
+        let message_id = Uuid::from_slice(&message.id)
+            .map_err(|e| Error::Other(format!("Invalid message id: {}", e).into()))?;
+        let deleted = self.messages.delete(&message.app_id, message_id).await?;
+        tracing::info!(%id, %message_id, deleted, "Message expired");
+
+        let notification = Message {
+            id: id.id.as_bytes().to_vec(),
+            timestamp: now_ms,
+            from: message.from,
+            to: message.to,
+            content: Vec::<u8>::try_from(&Content::expired(message_id))?.into(),
+            app_id: message.app_id,
+        };
+        self.sink.deliver(notification).await
+    }
+
+    /// 投递到期的任务，投递失败时按指数退避重试，超过最大投递次数后丢弃
+    pub async fn fire(&self, id: &JobId, now_ms: u64) -> crate::Result<()> {
+        // 已被取消或已被其他实例领取
+        if !self.store.claim(id, now_ms, self.lease_ms).await? {
+            return Ok(());
+        }
+
+        let mut job = match self.store.load(id).await? {
+            Some(job) => job,
+            None => return Ok(()),
+        };
+
+        let message = match job.message.clone() {
+            Some(message) => message,
+            None => {
+                tracing::warn!(%id, "Job without message, discarded");
+                self.store.remove(id).await?;
+                return Ok(());
+            }
+        };
+
+        let result = match JobKind::from_i32(job.kind) {
+            Some(JobKind::Expire) => self.expire(id, message, now_ms).await,
+            _ => self.sink.deliver(message).await,
+        };
+
+        match result {
+            Ok(()) => {
+                self.store.remove(id).await?;
+                tracing::info!(%id, kind = job.kind, "Job done");
+            }
+            Err(e) => {
+                job.attempts += 1;
+                if job.attempts >= self.max_attempts {
+                    tracing::error!(%id, %e, attempts = job.attempts, "Job discarded");
+                    self.store.remove(id).await?;
+                } else {
+                    let backoff = self
+                        .retry_interval_ms
+                        .saturating_mul(1 << (job.attempts - 1).min(16));
+                    job.deliver_at = now_ms + backoff;
+                    tracing::warn!(%id, %e, attempts = job.attempts, deliver_at = job.deliver_at, "Failed to deliver job, retry later");
+                    self.store.save(id, &job).await?;
+                    self.arm(id.clone(), job.deliver_at, now_ms);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 运行调度循环，直到 `signal` 完成
+    pub async fn run<F: Future<Output = ()>>(self: Arc<Self>, signal: F) {
+        let mut tick = tokio::time::interval(Duration::from_millis(self.tick_ms));
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut load = tokio::time::interval(Duration::from_millis(self.load_interval_ms));
+        load.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        tokio::pin!(signal);
+
+        loop {
+            tokio::select! {
+                _ = load.tick() => {
+                    match self.load(current_millisecond()).await {
+                        Ok(count) => tracing::debug!(count, "Jobs loaded"),
+                        Err(e) => tracing::error!(%e, "Failed to load jobs"),
+                    }
+                }
+                _ = tick.tick() => {
+                    let now = current_millisecond();
+                    for id in self.advance(now) {
+                        let scheduler = self.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = scheduler.fire(&id, now).await {
+                                tracing::error!(%id, %e, "Failed to fire job");
+                            }
+                        });
+                    }
+                }
+                _ = &mut signal => {
+                    tracing::info!("Scheduler stopped.");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MessageSink, MessageStore, Scheduler};
+    use crate::config::TimerConfig;
+    use crate::store::test::MemoryJobStore;
+    use crate::store::{JobId, JobStore};
+    use crate::Error;
+    use async_trait::async_trait;
+    use jinshu_protocol::Content;
+    use jinshu_rpc::domain::message::Message;
+    use jinshu_rpc::timer::JobKind;
+    use jinshu_utils::current_millisecond;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    /// 前 `fail` 次投递失败
+    #[derive(Default)]
+    struct TestSink {
+        fail: AtomicUsize,
+        delivered: Mutex<Vec<Message>>,
+    }
+
+    #[async_trait]
+    impl MessageSink for TestSink {
+        async fn deliver(&self, message: Message) -> crate::Result<()> {
+            if self
+                .fail
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err(Error::Other("test".into()));
+            }
+
+            self.delivered.lock().unwrap().push(message);
+            Ok(())
+        }
+    }
+
+    /// 内存中的消息存储
+    #[derive(Default)]
+    struct TestMessages(Mutex<HashSet<(String, Uuid)>>);
+
+    #[async_trait]
+    impl MessageStore for TestMessages {
+        async fn delete(&self, app_id: &str, id: Uuid) -> crate::Result<bool> {
+            Ok(self.0.lock().unwrap().remove(&(app_id.to_string(), id)))
+        }
+    }
+
+    fn scheduler(sink: TestSink) -> Scheduler<MemoryJobStore, TestSink, TestMessages> {
+        Scheduler::new(
+            &config(),
+            MemoryJobStore::default(),
+            sink,
+            TestMessages::default(),
+        )
+    }
+
+    fn config() -> TimerConfig {
+        TimerConfig {
+            tick_ms: 10,
+            slots: 10,
+            load_interval_ms: 100,
+            lease_ms: 1000,
+            retry_interval_ms: 50,
+            max_attempts: 3,
+            ..Default::default()
+        }
+    }
+
+    fn message(timestamp: u64) -> Message {
+        Message {
+            timestamp,
+            app_id: "app".into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn schedule() -> crate::Result<()> {
+        let scheduler = scheduler(TestSink::default());
+        let now = current_millisecond();
+
+        let id = scheduler
+            .schedule(JobKind::Scheduled, now + 50, message(1))
+            .await?;
+        let far = scheduler
+            .schedule(JobKind::Scheduled, now + 10_000, message(2))
+            .await?;
+        let canceled = scheduler
+            .schedule(JobKind::Scheduled, now + 50, message(3))
+            .await?;
+        // 只能取消所属应用的任务
+        assert!(!scheduler.cancel(&JobId::new("other", canceled.id)).await?);
+        assert!(scheduler.cancel(&canceled).await?);
+        assert!(!scheduler.cancel(&canceled).await?);
+
+        assert!(scheduler.advance(now + 40).is_empty());
+        let expired = scheduler.advance(now + 50);
+        assert_eq!(expired, vec![id.clone()]);
+
+        scheduler.fire(&id, now + 50).await?;
+        assert_eq!(scheduler.sink.delivered.lock().unwrap().len(), 1);
+        assert!(scheduler.store.load(&id).await?.is_none());
+
+        // 加载窗口之外的任务需要等待加载
+        assert!(scheduler.advance(now + 10_000).is_empty());
+        assert_eq!(scheduler.load(now + 9_900).await?, 1);
+        // 已推进过的刻度不再处理，过期的任务在下一个刻度到期
+        assert_eq!(scheduler.advance(now + 10_010), vec![far.clone()]);
+        scheduler.fire(&far, now + 10_010).await?;
+
+        let delivered = scheduler.sink.delivered.lock().unwrap();
+        assert_eq!(
+            delivered.iter().map(|m| m.timestamp).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn retry() -> crate::Result<()> {
+        let sink = TestSink {
+            fail: AtomicUsize::new(1),
+            ..Default::default()
+        };
+        let scheduler = scheduler(sink);
+        let now = current_millisecond();
+
+        let id = scheduler
+            .schedule(JobKind::Scheduled, now, message(1))
+            .await?;
+
+        scheduler.fire(&id, now).await?;
+        assert!(scheduler.sink.delivered.lock().unwrap().is_empty());
+        let job = scheduler.store.load(&id).await?.expect("job removed");
+        assert_eq!(job.attempts, 1);
+        assert_eq!(job.deliver_at, now + 50);
+
+        // 未到期不能领取
+        scheduler.fire(&id, now + 10).await?;
+        assert!(scheduler.sink.delivered.lock().unwrap().is_empty());
+
+        scheduler.fire(&id, now + 50).await?;
+        assert_eq!(scheduler.sink.delivered.lock().unwrap().len(), 1);
+        assert!(scheduler.store.load(&id).await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn discard() -> crate::Result<()> {
+        let sink = TestSink {
+            fail: AtomicUsize::new(usize::MAX),
+            ..Default::default()
+        };
+        let scheduler = scheduler(sink);
+        let now = current_millisecond();
+
+        let id = scheduler.schedule(JobKind::Retry, now, message(1)).await?;
+
+        scheduler.fire(&id, now).await?;
+        scheduler.fire(&id, now + 50).await?;
+        assert!(scheduler.store.load(&id).await?.is_some());
+        scheduler.fire(&id, now + 150).await?;
+        assert!(scheduler.store.load(&id).await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expire() -> crate::Result<()> {
+        let sink = TestSink {
+            fail: AtomicUsize::new(1),
+            ..Default::default()
+        };
+        let scheduler = scheduler(sink);
+        let now = current_millisecond();
+
+        let (message_id, from, to) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        scheduler
+            .messages
+            .0
+            .lock()
+            .unwrap()
+            .insert(("app".to_string(), message_id));
+        let message = Message {
+            id: message_id.as_bytes().to_vec(),
+            timestamp: 1,
+            from: from.as_bytes().to_vec(),
+            to: to.as_bytes().to_vec(),
+            app_id: "app".into(),
+            ..Default::default()
+        };
+
+        let id = scheduler.schedule(JobKind::Expire, now, message).await?;
+
+        // 删除消息后通知失败，重试时只重新通知
+        scheduler.fire(&id, now).await?;
+        assert!(scheduler.messages.0.lock().unwrap().is_empty());
+        assert!(scheduler.sink.delivered.lock().unwrap().is_empty());
+        assert!(scheduler.store.load(&id).await?.is_some());
+
+        scheduler.fire(&id, now + 50).await?;
+        assert!(scheduler.store.load(&id).await?.is_none());
+
+        let delivered = scheduler.sink.delivered.lock().unwrap();
+        assert_eq!(delivered.len(), 1);
+        let notification = &delivered[0];
+        assert_eq!(notification.id, id.id.as_bytes().to_vec());
+        assert_eq!(notification.from, from.as_bytes().to_vec());
+        assert_eq!(notification.to, to.as_bytes().to_vec());
+        assert_eq!(notification.app_id, "app");
+        let content = Content::try_from(notification.content.as_ref()).unwrap();
+        assert_eq!(content.expired_id(), Some(message_id));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use crate::scheduler::{MessageSink, MessageStore, Scheduler};
+use crate::store::{JobId, JobStore};
+use jinshu_rpc::timer::{
+    timer_server, CancelRequest, CancelResult, JobKind, ScheduleRequest, ScheduleResult,
+};
+use jinshu_rpc::{internal, invalid_argument};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+/// 定时服务
+pub struct TimerService<S, D, M> {
+    scheduler: Arc<Scheduler<S, D, M>>,
+}
+
+impl<S, D, M> TimerService<S, D, M> {
+    /// 使用调度器构造
+    pub fn new(scheduler: Arc<Scheduler<S, D, M>>) -> Self {
+        Self { scheduler }
+    }
+}
+
+#[tonic::async_trait]
+impl<S: JobStore, D: MessageSink, M: MessageStore> timer_server::Timer for TimerService<S, D, M> {
+    #[tracing::instrument(skip_all)]
+    async fn schedule(
+        &self,
+        request: Request<ScheduleRequest>,
+    ) -> Result<Response<ScheduleResult>, Status> {
+        let ScheduleRequest {
+            kind,
+            deliver_at,
+            message,
+        } = request.into_inner();
+
+        let kind = JobKind::from_i32(kind)
+            .ok_or_else(|| invalid_argument(format!("Invalid job kind: {}", kind)))?;
+        let message = message.ok_or_else(|| invalid_argument("Message is required"))?;
+
+        let id = self
+            .scheduler
+            .schedule(kind, deliver_at, message)
+            .await
+            .map_err(internal)?;
+
+        Ok(Response::new(ScheduleResult {
+            job_id: id.id.as_bytes().to_vec(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn cancel(
+        &self,
+        request: Request<CancelRequest>,
+    ) -> Result<Response<CancelResult>, Status> {
+        let CancelRequest { job_id, app_id } = request.into_inner();
+        let id = JobId::new(app_id, Uuid::from_slice(&job_id).map_err(invalid_argument)?);
+
+        let ok = self.scheduler.cancel(&id).await.map_err(internal)?;
+
+        Ok(Response::new(CancelResult { ok }))
+    }
+}
@@ -0,0 +1,310 @@
+use async_trait::async_trait;
+use deadpool_redis::redis::{self, AsyncCommands, Script};
+use jinshu_rpc::timer::Job;
+use prost::Message;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// 定时任务 ID，任务按应用隔离，不同应用的任务互不可见
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JobId {
+    /// 任务消息所属的应用 ID
+    pub app_id: String,
+    /// 任务 ID
+    pub id: Uuid,
+}
+
+impl JobId {
+    /// 使用应用 ID 及任务 ID 构造
+    pub fn new(app_id: impl Into<String>, id: Uuid) -> Self {
+        Self {
+            app_id: app_id.into(),
+            id,
+        }
+    }
+}
+
+/// 格式为 `{app_id}:{id}`
+impl Display for JobId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.app_id, self.id.as_simple())
+    }
+}
+
+impl FromStr for JobId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // 任务 ID 中不含 `:`，应用 ID 中可能含有
+        let (app_id, id) = s.rsplit_once(':').unwrap_or(("", s));
+        Ok(Self::new(app_id, id.parse()?))
+    }
+}
+
+/// 定时任务存储
+///
+/// 任务按投递时间排序，多个 Timer 实例共享同一个存储时，通过 [`JobStore::claim`] 保证同一时刻只有一个实例投递任务
+///
+#[async_trait]
+pub trait JobStore: Send + Sync + 'static {
+    /// 保存任务，任务已存在时覆盖
+    async fn save(&self, id: &JobId, job: &Job) -> crate::Result<()>;
+
+    /// 读取任务
+    async fn load(&self, id: &JobId) -> crate::Result<Option<Job>>;
+
+    /// 删除任务，返回任务是否存在
+    async fn remove(&self, id: &JobId) -> crate::Result<bool>;
+
+    /// 按投递时间顺序获取投递时间不晚于 `time_ms` 的任务 ID 及投递时间，最多 `limit` 个
+    async fn due_before(&self, time_ms: u64, limit: usize) -> crate::Result<Vec<(JobId, u64)>>;
+
+    /// 领取已到期的任务，领取成功后任务的投递时间推迟 `lease_ms`，以便实例崩溃后任务能被重新投递
+    ///
+    /// 任务不存在、未到期或已被其他实例领取时返回 `false`
+    ///
+    async fn claim(&self, id: &JobId, now_ms: u64, lease_ms: u64) -> crate::Result<bool>;
+}
+
+/// 投递时间有序集合的键
+const JOB_QUEUE_KEY: &str = "timer:jobs";
+
+/// 获取存储任务内容的键
+fn get_job_key(id: &JobId) -> String {
+    format!("timer:job:{}", id)
+}
+
+/// 领取任务的脚本，保证判断与推迟投递时间的原子性
+const CLAIM_SCRIPT: &str = r"
+local score = redis.call('ZSCORE', KEYS[1], ARGV[1])
+if score and tonumber(score) <= tonumber(ARGV[2]) then
+  redis.call('ZADD', KEYS[1], ARGV[3], ARGV[1])
+  return 1
+end
+return 0
+";
+
+/// 基于 Redis 的任务存储
+///
+/// 投递时间存储在有序集合 `timer:jobs` 中，成员为 `{app_id}:{id}`；
+/// 任务内容以 Protobuf 编码存储在 `timer:job:{app_id}:{id}` 中
+///
+#[derive(Clone)]
+pub struct RedisJobStore {
+    redis: deadpool_redis::Pool,
+    claim: Script,
+}
+
+impl RedisJobStore {
+    /// 使用现有的 Redis 连接池构造
+    pub fn from_pool(redis: deadpool_redis::Pool) -> Self {
+        Self {
+            redis,
+            claim: Script::new(CLAIM_SCRIPT),
+        }
+    }
+}
+
+#[async_trait]
+impl JobStore for RedisJobStore {
+    async fn save(&self, id: &JobId, job: &Job) -> crate::Result<()> {
+        let mut conn = self.redis.get().await?;
+        let _: () = redis::pipe()
+            .atomic()
+            .set(get_job_key(id), job.encode_to_vec())
+            .ignore()
+            .zadd(JOB_QUEUE_KEY, id.to_string(), job.deliver_at)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn load(&self, id: &JobId) -> crate::Result<Option<Job>> {
+        let mut conn = self.redis.get().await?;
+        let bytes: Option<Vec<u8>> = conn.get(get_job_key(id)).await?;
+        match bytes {
+            Some(bytes) => Ok(Some(Job::decode(bytes.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn remove(&self, id: &JobId) -> crate::Result<bool> {
+        let mut conn = self.redis.get().await?;
+        let (removed, _): (usize, usize) = redis::pipe()
+            .atomic()
+            .zrem(JOB_QUEUE_KEY, id.to_string())
+            .del(get_job_key(id))
+            .query_async(&mut conn)
+            .await?;
+        Ok(removed > 0)
+    }
+
+    async fn due_before(&self, time_ms: u64, limit: usize) -> crate::Result<Vec<(JobId, u64)>> {
+        let mut conn = self.redis.get().await?;
+        let jobs: Vec<(String, u64)> = conn
+            .zrangebyscore_limit_withscores(JOB_QUEUE_KEY, "-inf", time_ms, 0, limit as isize)
+            .await?;
+
+        Ok(jobs
+            .into_iter()
+            .filter_map(|(id, due)| match id.parse() {
+                Ok(id) => Some((id, due)),
+                Err(e) => {
+                    tracing::warn!(%id, %e, "Invalid job id");
+                    None
+                }
+            })
+            .collect())
+    }
+
+    async fn claim(&self, id: &JobId, now_ms: u64, lease_ms: u64) -> crate::Result<bool> {
+        let mut conn = self.redis.get().await?;
+        let claimed: i32 = self
+            .claim
+            .key(JOB_QUEUE_KEY)
+            .arg(id.to_string())
+            .arg(now_ms)
+            .arg(now_ms + lease_ms)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(claimed == 1)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{JobId, JobStore, RedisJobStore};
+    use async_trait::async_trait;
+    use jinshu_rpc::timer::Job;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    /// 内存中的任务存储，用于测试
+    #[derive(Default)]
+    pub(crate) struct MemoryJobStore(Mutex<HashMap<JobId, Job>>);
+
+    #[async_trait]
+    impl JobStore for MemoryJobStore {
+        async fn save(&self, id: &JobId, job: &Job) -> crate::Result<()> {
+            self.0.lock().unwrap().insert(id.clone(), job.clone());
+            Ok(())
+        }
+
+        async fn load(&self, id: &JobId) -> crate::Result<Option<Job>> {
+            Ok(self.0.lock().unwrap().get(id).cloned())
+        }
+
+        async fn remove(&self, id: &JobId) -> crate::Result<bool> {
+            Ok(self.0.lock().unwrap().remove(id).is_some())
+        }
+
+        async fn due_before(&self, time_ms: u64, limit: usize) -> crate::Result<Vec<(JobId, u64)>> {
+            let mut jobs: Vec<_> = self
+                .0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, job)| job.deliver_at <= time_ms)
+                .map(|(id, job)| (id.clone(), job.deliver_at))
+                .collect();
+            jobs.sort_by_key(|(_, due)| *due);
+            jobs.truncate(limit);
+            Ok(jobs)
+        }
+
+        async fn claim(&self, id: &JobId, now_ms: u64, lease_ms: u64) -> crate::Result<bool> {
+            match self.0.lock().unwrap().get_mut(id) {
+                Some(job) if job.deliver_at <= now_ms => {
+                    job.deliver_at = now_ms + lease_ms;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+    }
+
+    /// 连接环境变量 `REDIS_URL` 指定的 Redis，默认为本机
+    fn redis_store() -> RedisJobStore {
+        let url =
+            std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let pool = deadpool_redis::Config::from_url(url)
+            .builder()
+            .expect("Invalid redis config")
+            .build()
+            .expect("Failed to build redis pool");
+        RedisJobStore::from_pool(pool)
+    }
+
+    #[test]
+    fn job_id() {
+        let id = JobId::new("app:1", Uuid::new_v4());
+        assert_eq!(id.to_string(), format!("app:1:{}", id.id.as_simple()));
+        assert_eq!(id.to_string().parse::<JobId>().unwrap(), id);
+        assert!("app:invalid".parse::<JobId>().is_err());
+    }
+
+    async fn store(store: impl JobStore) -> crate::Result<()> {
+        let id = JobId::new("app", Uuid::new_v4());
+        // 其他应用中相同 ID 的任务
+        let other = JobId::new("other", id.id);
+        let target = id.id;
+        // 共享的存储中可能有其他任务，只检查本次保存的任务
+        let due_before = |time_ms| {
+            let store = &store;
+            async move {
+                Ok::<_, crate::Error>(
+                    store
+                        .due_before(time_ms, usize::MAX)
+                        .await?
+                        .into_iter()
+                        .filter(|(due, _)| due.id == target)
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+        let job = Job {
+            id: id.id.as_bytes().to_vec(),
+            deliver_at: 100,
+            ..Default::default()
+        };
+
+        store.save(&id, &job).await?;
+        assert_eq!(due_before(99).await?, vec![]);
+        assert_eq!(due_before(100).await?, vec![(id.clone(), 100)]);
+        assert_eq!(store.load(&id).await?, Some(job));
+        assert!(store.load(&other).await?.is_none());
+        assert!(!store.claim(&other, 100, 1000).await?);
+        assert!(!store.remove(&other).await?);
+
+        assert!(!store.claim(&id, 99, 1000).await?);
+        assert!(store.claim(&id, 100, 1000).await?);
+        assert!(!store.claim(&id, 100, 1000).await?);
+        assert_eq!(due_before(1100).await?, vec![(id.clone(), 1100)]);
+        assert!(
+            !store
+                .claim(&JobId::new("app", Uuid::new_v4()), 100, 1000)
+                .await?
+        );
+
+        assert!(store.remove(&id).await?);
+        assert!(!store.remove(&id).await?);
+        assert!(store.load(&id).await?.is_none());
+        assert_eq!(due_before(u64::MAX).await?, vec![]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memory() -> crate::Result<()> {
+        store(MemoryJobStore::default()).await
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a running Redis, set REDIS_URL to override the default local one"]
+    async fn redis() -> crate::Result<()> {
+        store(redis_store()).await
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug, Clone)]
+struct Entry<K> {
+    id: K,
+    due_ms: u64,
+}
+
+/// 哈希时间轮
+///
+/// 每个槽对应一个刻度，到期时间超过一圈的任务放在对应的槽中等待后续轮次，
+/// 取消任务时只从索引中删除，槽中的条目在推进时惰性清理
+///
+#[derive(Debug)]
+pub struct TimerWheel<K> {
+    tick_ms: u64,
+    slots: Vec<Vec<Entry<K>>>,
+    /// 下一个要处理的刻度
+    current_tick: u64,
+    index: HashMap<K, u64>,
+}
+
+impl<K: Clone + Eq + Hash> TimerWheel<K> {
+    /// 使用刻度（毫秒）、槽数及当前时间（毫秒）构造
+    pub fn new(tick_ms: u64, slots: usize, now_ms: u64) -> Self {
+        let tick_ms = tick_ms.max(1);
+        Self {
+            tick_ms,
+            slots: vec![Vec::new(); slots.max(1)],
+            current_tick: now_ms / tick_ms,
+            index: HashMap::new(),
+        }
+    }
+
+    /// 时间轮转一圈的时长（毫秒）
+    pub fn span_ms(&self) -> u64 {
+        self.tick_ms * self.slots.len() as u64
+    }
+
+    /// 时间轮中的任务个数
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// 时间轮是否为空
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// 获取任务的到期时间（毫秒）
+    pub fn get(&self, id: &K) -> Option<u64> {
+        self.index.get(id).copied()
+    }
+
+    /// 插入任务，任务已存在时更新到期时间，已过期的任务会在下一次推进时到期
+    pub fn insert(&mut self, id: K, due_ms: u64) {
+        if self.index.insert(id.clone(), due_ms) == Some(due_ms) {
+            return;
+        }
+
+        let tick = (due_ms / self.tick_ms).max(self.current_tick);
+        let slot = (tick % self.slots.len() as u64) as usize;
+        self.slots[slot].push(Entry { id, due_ms });
+    }
+
+    /// 删除任务，返回任务是否存在
+    pub fn remove(&mut self, id: &K) -> bool {
+        self.index.remove(id).is_some()
+    }
+
+    /// 推进到当前时间（毫秒），返回到期的任务
+    pub fn advance(&mut self, now_ms: u64) -> Vec<K> {
+        let target = now_ms / self.tick_ms;
+        if target < self.current_tick {
+            return Vec::new();
+        }
+
+        // 跨度超过一圈时，每个槽只需处理一次
+        let count = (target - self.current_tick + 1).min(self.slots.len() as u64);
+
+        let tick_ms = self.tick_ms;
+        let mut expired = Vec::new();
+        for tick in self.current_tick..self.current_tick + count {
+            let slot = (tick % self.slots.len() as u64) as usize;
+            let index = &mut self.index;
+            self.slots[slot].retain(|entry| {
+                match index.get(&entry.id) {
+                    // 已取消或已被重新插入到其他位置
+                    Some(due_ms) if *due_ms != entry.due_ms => false,
+                    None => false,
+                    Some(_) if entry.due_ms / tick_ms <= target => {
+                        index.remove(&entry.id);
+                        expired.push(entry.id.clone());
+                        false
+                    }
+                    Some(_) => true,
+                }
+            });
+        }
+
+        self.current_tick = target + 1;
+        expired
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TimerWheel;
+    use uuid::Uuid;
+
+    #[test]
+    fn advance() {
+        let mut wheel = TimerWheel::new(100, 10, 0);
+        assert_eq!(wheel.span_ms(), 1000);
+
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        let c = Uuid::new_v4();
+        wheel.insert(a, 250);
+        wheel.insert(b, 950);
+        // 超过一圈
+        wheel.insert(c, 2550);
+        assert_eq!(wheel.len(), 3);
+
+        assert!(wheel.advance(199).is_empty());
+        assert_eq!(wheel.advance(299), vec![a]);
+        assert!(wheel.advance(599).is_empty());
+        assert_eq!(wheel.advance(1500), vec![b]);
+        assert!(wheel.advance(2499).is_empty());
+        assert_eq!(wheel.advance(2500), vec![c]);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn expired() {
+        let mut wheel = TimerWheel::new(100, 10, 1000);
+
+        let a = Uuid::new_v4();
+        wheel.insert(a, 0);
+        assert_eq!(wheel.advance(1000), vec![a]);
+
+        // 系统时间回拨
+        let b = Uuid::new_v4();
+        wheel.insert(b, 1000);
+        assert!(wheel.advance(500).is_empty());
+        assert_eq!(wheel.advance(1100), vec![b]);
+    }
+
+    #[test]
+    fn jump() {
+        let mut wheel = TimerWheel::new(100, 10, 0);
+
+        let ids: Vec<_> = (0..30u64)
+            .map(|i| {
+                let id = Uuid::new_v4();
+                wheel.insert(id, i * 100);
+                id
+            })
+            .collect();
+
+        let mut expired = wheel.advance(10_000);
+        expired.sort();
+        let mut ids = ids;
+        ids.sort();
+        assert_eq!(expired, ids);
+    }
+
+    #[test]
+    fn remove_and_reschedule() {
+        let mut wheel = TimerWheel::new(100, 10, 0);
+
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        wheel.insert(a, 300);
+        wheel.insert(b, 300);
+        assert!(wheel.remove(&a));
+        assert!(!wheel.remove(&a));
+
+        wheel.insert(b, 700);
+        assert_eq!(wheel.get(&b), Some(700));
+        assert!(wheel.advance(500).is_empty());
+        assert_eq!(wheel.advance(700), vec![b]);
+    }
+}
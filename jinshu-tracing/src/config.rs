@@ -78,7 +78,7 @@ impl TracingConfig {
         std::fs::create_dir_all(&self.path)?;
 
         let file_appender = rolling_file::BasicRollingFileAppender::new(
-            &self.path.join(format!("{}.log", service)),
+            self.path.join(format!("{}.log", service)),
             RollingConditionBasic::new().max_size(self.trigger_size.get_bytes()),
             self.archived_count,
         )?;
@@ -4,6 +4,7 @@ use std::borrow::Cow;
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     /// Join 错误
+    #[cfg(not(target_arch = "wasm32"))]
     #[error(transparent)]
     Join(#[from] tokio::task::JoinError),
     /// 其他错误
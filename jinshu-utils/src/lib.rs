@@ -9,11 +9,18 @@ mod error;
 pub mod secret;
 
 pub use error::*;
+#[cfg(not(target_arch = "wasm32"))]
 use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
 use std::net::IpAddr;
+#[cfg(not(target_arch = "wasm32"))]
 use std::time::SystemTime;
+#[cfg(not(target_arch = "wasm32"))]
 use tokio::sync::oneshot;
+#[cfg(not(target_arch = "wasm32"))]
 use tokio::task::JoinHandle;
+#[cfg(target_arch = "wasm32")]
+use web_time::SystemTime;
 
 /// 获取当前时间距离 1970-01-01 00:00:00 UTC 的毫秒数
 ///
@@ -45,6 +52,7 @@ pub fn current_second() -> u64 {
 
 /// 获取本机所有网卡的非回环 ip 地址
 ///
+#[cfg(not(target_arch = "wasm32"))]
 pub fn get_all_ip_addr() -> std::io::Result<Vec<IpAddr>> {
     Ok(if_addrs::get_if_addrs()?
         .iter()
@@ -60,6 +68,7 @@ pub fn get_all_ip_addr() -> std::io::Result<Vec<IpAddr>> {
 }
 
 /// 监听 SIGINT（Ctrl-C） / SIGTERM
+#[cfg(not(target_arch = "wasm32"))]
 pub async fn shutdown_signal() {
     let sig_int = async {
         tokio::signal::ctrl_c()
@@ -115,11 +124,13 @@ pub async fn shutdown_signal() {
 ///
 /// ```
 ///
+#[cfg(not(target_arch = "wasm32"))]
 pub struct Keeper<R> {
     closer: oneshot::Sender<()>,
     result_handle: JoinHandle<R>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl<R> Keeper<R>
 where
     R: Send + 'static,
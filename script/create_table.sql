@@ -3,6 +3,7 @@ create table "user"
     id          text                    not null
         constraint user_pk
             primary key,
+    app_id      text                    not null,
     external_id text                    not null,
     extension   json,
     create_time timestamptz default now() not null
@@ -11,8 +12,8 @@ create table "user"
 alter table "user"
     owner to jinshu;
 
-create unique index user_external_id_uindex
-    on "user" (external_id);
+create unique index user_app_id_external_id_uindex
+    on "user" (app_id, external_id);
 
 create table friend
 (
@@ -44,6 +45,7 @@ create table message
     id         text                    not null
         constraint message_pk
             primary key,
+    app_id     text                    not null,
     timestamp  timestamptz               not null,
     "from"     text                    not null,
     "to"       text                    not null,
@@ -54,11 +56,15 @@ create table message
 alter table message
     owner to jinshu;
 
+create index message_app_id_index
+    on message (app_id);
+
 create table "group"
 (
     id          text                    not null
         constraint group_pk
             primary key,
+    app_id      text                    not null,
     name        text                    not null,
     create_time timestamptz default now() not null
 );
@@ -66,8 +72,8 @@ create table "group"
 alter table "group"
     owner to jinshu;
 
-create unique index group_name_uindex
-    on "group" (name);
+create unique index group_app_id_name_uindex
+    on "group" (app_id, name);
 
 create table group_member
 (
@@ -82,6 +88,82 @@ create table group_member
 alter table group_member
     owner to jinshu;
 
+create table admin
+(
+    id          text                    not null
+        constraint admin_pk
+            primary key,
+    username    text                    not null,
+    password    text                    not null,
+    role        text                    not null,
+    create_time timestamptz default now() not null
+);
+
+alter table admin
+    owner to jinshu;
+
+create unique index admin_username_uindex
+    on admin (username);
+
+create table app
+(
+    id          text                    not null
+        constraint app_pk
+            primary key,
+    name        text                    not null,
+    secret      text                    not null,
+    enabled     boolean     default true  not null,
+    create_time timestamptz default now() not null
+);
+
+alter table app
+    owner to jinshu;
+
+create table conversation
+(
+    user_id     text                    not null,
+    peer_id     text                    not null,
+    read_time   timestamptz,
+    muted       boolean     default false not null,
+    pinned      boolean     default false not null,
+    update_time timestamptz default now() not null,
+    constraint conversation_pk
+        primary key (user_id, peer_id)
+);
+
+alter table conversation
+    owner to jinshu;
+
+create table user_setting
+(
+    user_id     text                    not null
+        constraint user_setting_pk
+            primary key,
+    setting     json                    not null,
+    update_time timestamptz default now() not null
+);
+
+alter table user_setting
+    owner to jinshu;
+
+create table identity_key
+(
+    user_id     text                    not null,
+    key_id      text                    not null,
+    app_id      text                    not null,
+    algorithm   text                    not null,
+    public_key  text                    not null,
+    create_time timestamptz default now() not null,
+    constraint identity_key_pk
+        primary key (user_id, key_id)
+);
+
+alter table identity_key
+    owner to jinshu;
+
+create index identity_key_app_id_index
+    on identity_key (app_id);
+
 -- example
 
 create table app_user